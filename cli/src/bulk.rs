@@ -0,0 +1,495 @@
+//! Bulk deployment of vested treasuries from a CSV file
+//!
+//! Every grant is created and funded in a single transaction. Progress is recorded in a
+//! journal before anything is sent, so an interrupted run can be resumed: the journal
+//! stores the keypair of every treasury account, which makes re-sending a grant
+//! idempotent since the same treasury address can't be created twice.
+
+use std::{
+    error::Error,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use treasury::{account::VestedTreasury, instruction};
+use treasury_client::error::ClientError;
+
+use crate::command::{create_fund, CommandResult, Config};
+
+/// A row of the grant spreadsheet
+#[derive(Debug, Deserialize)]
+struct GrantRecord {
+    beneficiary: String,
+    amount: u64,
+    start: Option<i64>,
+    cliff: Option<u64>,
+    period: u64,
+    percentage: u16,
+}
+
+/// A validated grant
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grant {
+    /// The line of the grant in the CSV file, starting at 1 for the first grant
+    pub row: usize,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub period: u64,
+    pub percentage: u16,
+}
+
+/// Read and validate all grants. Every invalid row is reported, not just the first.
+pub fn read_grants<R: Read>(reader: R) -> Result<Vec<Grant>, Box<dyn Error>> {
+    let mut grants = vec![];
+    let mut errors = vec![];
+
+    for (i, record) in csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(reader)
+        .deserialize::<GrantRecord>()
+        .enumerate()
+    {
+        let row = i + 1;
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(format!("row {}: {}", row, e));
+                continue;
+            }
+        };
+
+        let beneficiary = match record.beneficiary.parse::<Pubkey>() {
+            Ok(beneficiary) => beneficiary,
+            Err(_) => {
+                errors.push(format!("row {}: invalid beneficiary", row));
+                continue;
+            }
+        };
+        // the program starts vesting when the treasury is created and has no cliff
+        if record.start.unwrap_or(0) != 0 {
+            errors.push(format!(
+                "row {}: custom start times are not supported, vesting starts at creation",
+                row
+            ));
+            continue;
+        }
+        if record.cliff.unwrap_or(0) != 0 {
+            errors.push(format!("row {}: cliffs are not supported", row));
+            continue;
+        }
+        if let Err(e) =
+            VestedTreasury::verify_parameters(record.amount, record.period, record.percentage)
+        {
+            errors.push(format!("row {}: {}", row, e));
+            continue;
+        }
+
+        grants.push(Grant {
+            row,
+            beneficiary,
+            amount: record.amount,
+            period: record.period,
+            percentage: record.percentage,
+        });
+    }
+
+    if !errors.is_empty() {
+        return Err(errors.join("\n").into());
+    }
+    Ok(grants)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum JournalStatus {
+    /// The treasury keypair was generated but the transaction was not confirmed
+    Pending,
+    /// The treasury was created and funded
+    Confirmed,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub row: usize,
+    pub beneficiary: String,
+    pub amount: u64,
+    pub treasury: String,
+    /// The base58 encoded keypair of the treasury account. It has no authority
+    /// after the treasury is created.
+    pub treasury_keypair: String,
+    pub status: JournalStatus,
+    pub signature: String,
+}
+
+impl JournalEntry {
+    fn matches(&self, grant: &Grant) -> bool {
+        self.row == grant.row
+            && self.beneficiary == grant.beneficiary.to_string()
+            && self.amount == grant.amount
+    }
+}
+
+/// The progress of a bulk deployment, stored as a CSV file
+pub struct Journal {
+    /// `None` for journals that are never written, like in dry-run mode
+    path: Option<PathBuf>,
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    /// Open a journal, starting an empty one if the file does not exist
+    pub fn open(path: &Path, persist: bool) -> Result<Journal, Box<dyn Error>> {
+        let entries = match File::open(path) {
+            Ok(file) => csv::Reader::from_reader(file)
+                .deserialize()
+                .collect::<Result<Vec<JournalEntry>, _>>()?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Journal {
+            path: if persist {
+                Some(path.to_path_buf())
+            } else {
+                None
+            },
+            entries,
+        })
+    }
+
+    pub fn get(&self, row: usize) -> Option<&JournalEntry> {
+        self.entries.iter().find(|entry| entry.row == row)
+    }
+
+    /// Insert or replace the entry for a row and write the journal to disk
+    pub fn record(&mut self, entry: JournalEntry) -> Result<(), Box<dyn Error>> {
+        match self.entries.iter_mut().find(|e| e.row == entry.row) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        // write to a temporary file first so a crash never leaves a truncated journal
+        let tmp = path.with_extension("tmp");
+        {
+            let mut writer = csv::Writer::from_path(&tmp)?;
+            for entry in &self.entries {
+                writer.serialize(entry)?;
+            }
+            writer.flush()?;
+        }
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// Create and fund a vested treasury for every grant that hasn't been deployed yet
+pub fn deploy(
+    config: &Config,
+    mint: Pubkey,
+    source: Pubkey,
+    grants: &[Grant],
+    journal: &mut Journal,
+) -> CommandResult {
+    let payer = config.payer()?.pubkey();
+
+    // make sure the journal belongs to this spreadsheet before touching anything
+    let mut outstanding = 0u64;
+    for grant in grants {
+        match journal.get(grant.row) {
+            Some(entry) if !entry.matches(grant) => {
+                return Err(format!("row {}: journal does not match the csv", grant.row).into())
+            }
+            Some(entry) if entry.status == JournalStatus::Confirmed => {}
+            _ => outstanding += grant.amount,
+        }
+    }
+    let balance = config.client.fund_balance(&source)?;
+    if balance < outstanding {
+        return Err(format!(
+            "source {} has {} tokens but {} are needed",
+            source, balance, outstanding
+        )
+        .into());
+    }
+
+    for grant in grants {
+        let entry = match journal.get(grant.row) {
+            Some(entry) if entry.status == JournalStatus::Confirmed => {
+                println!("row {}: already deployed to {}", grant.row, entry.treasury);
+                continue;
+            }
+            Some(entry) => entry.clone(),
+            None => {
+                let keypair = Keypair::new();
+                let entry = JournalEntry {
+                    row: grant.row,
+                    beneficiary: grant.beneficiary.to_string(),
+                    amount: grant.amount,
+                    treasury: keypair.pubkey().to_string(),
+                    treasury_keypair: keypair.to_base58_string(),
+                    status: JournalStatus::Pending,
+                    signature: String::new(),
+                };
+                journal.record(entry.clone())?;
+                entry
+            }
+        };
+        let treasury = Keypair::from_base58_string(&entry.treasury_keypair);
+
+        // a previous run may have sent the transaction without recording the result
+        match config.client.get_vested_treasury(&treasury.pubkey()) {
+            Ok(_) => {
+                println!("row {}: found {} on chain", grant.row, entry.treasury);
+                journal.record(JournalEntry {
+                    status: JournalStatus::Confirmed,
+                    ..entry
+                })?;
+                continue;
+            }
+            Err(ClientError::AccountNotFound(_)) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let (fund_authority, _) =
+            VestedTreasury::fund_authority_address(&treasury.pubkey(), config.client.program_id());
+        let fund =
+            VestedTreasury::fund_address(&treasury.pubkey(), &mint, config.client.program_id());
+
+        let signature = config.send_transaction(
+            &[
+                create_fund(&payer, &fund_authority, &mint),
+                instruction::create_vested_treasury(
+                    config.client.program_id(),
+                    &payer,
+                    &grant.beneficiary,
+                    &payer,
+                    &treasury.pubkey(),
+                    &mint,
+                    grant.amount,
+                    grant.period,
+                    grant.percentage,
+                ),
+                spl_token::instruction::transfer(
+                    &spl_token::id(),
+                    &source,
+                    &fund,
+                    &payer,
+                    &[],
+                    grant.amount,
+                )?,
+            ],
+            &[&treasury],
+        )?;
+
+        if let Some(signature) = signature {
+            println!(
+                "row {}: deployed {} ({})",
+                grant.row, entry.treasury, signature
+            );
+            journal.record(JournalEntry {
+                status: JournalStatus::Confirmed,
+                signature: signature.to_string(),
+                ..entry
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct ReportRecord {
+    row: usize,
+    beneficiary: String,
+    treasury: String,
+    status: &'static str,
+    expected_amount: u64,
+    initial_amount: Option<u64>,
+    fund_balance: Option<u64>,
+    withdrawn: Option<u64>,
+    issues: String,
+}
+
+/// Compare every grant with the state of its treasury on chain. Returns the number of
+/// grants that are not deployed correctly.
+pub fn reconcile<W: Write>(
+    config: &Config,
+    mint: Pubkey,
+    grants: &[Grant],
+    journal: &Journal,
+    out: W,
+) -> Result<usize, Box<dyn Error>> {
+    let mut writer = csv::Writer::from_writer(out);
+    let mut failures = 0;
+
+    for grant in grants {
+        let mut record = ReportRecord {
+            row: grant.row,
+            beneficiary: grant.beneficiary.to_string(),
+            treasury: String::new(),
+            status: "missing",
+            expected_amount: grant.amount,
+            initial_amount: None,
+            fund_balance: None,
+            withdrawn: None,
+            issues: String::new(),
+        };
+
+        let entry = journal.get(grant.row).filter(|entry| entry.matches(grant));
+        if let Some(entry) = entry {
+            record.treasury = entry.treasury.clone();
+            let key: Pubkey = entry.treasury.parse()?;
+
+            match config.client.get_vested_treasury(&key) {
+                Ok(treasury) => {
+                    let fund =
+                        VestedTreasury::fund_address(&key, &mint, config.client.program_id());
+                    let fund_balance = config.client.fund_balance(&fund)?;
+
+                    let mut issues = vec![];
+                    if treasury.mint != mint {
+                        issues.push("wrong mint");
+                    }
+                    if treasury.authority != grant.beneficiary {
+                        issues.push("wrong authority");
+                    }
+                    if treasury.initial_amount != grant.amount {
+                        issues.push("wrong amount");
+                    }
+                    if treasury.vestment_period != grant.period {
+                        issues.push("wrong period");
+                    }
+                    if treasury.vestment_percentage != grant.percentage {
+                        issues.push("wrong percentage");
+                    }
+                    if fund_balance + treasury.withdrawn < grant.amount {
+                        issues.push("underfunded");
+                    }
+
+                    record.status = if issues.is_empty() { "ok" } else { "mismatch" };
+                    record.initial_amount = Some(treasury.initial_amount);
+                    record.fund_balance = Some(fund_balance);
+                    record.withdrawn = Some(treasury.withdrawn);
+                    record.issues = issues.join("; ");
+                }
+                Err(ClientError::AccountNotFound(_)) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        if record.status != "ok" {
+            failures += 1;
+        }
+        writer.serialize(record)?;
+    }
+    writer.flush()?;
+
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_read_grants() {
+        let beneficiary = Pubkey::new_unique();
+        let csv = format!(
+            "beneficiary,amount,start,cliff,period,percentage\n\
+             {0},100000,,,2592000,1000\n\
+             {0}, 500 ,0,0,60,10000\n",
+            beneficiary
+        );
+
+        assert_eq!(
+            read_grants(csv.as_bytes()).unwrap(),
+            vec![
+                Grant {
+                    row: 1,
+                    beneficiary,
+                    amount: 100_000,
+                    period: 2_592_000,
+                    percentage: 1_000,
+                },
+                Grant {
+                    row: 2,
+                    beneficiary,
+                    amount: 500,
+                    period: 60,
+                    percentage: 10_000,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_read_grants_invalid() {
+        let beneficiary = Pubkey::new_unique();
+        let csv = format!(
+            "beneficiary,amount,start,cliff,period,percentage\n\
+             nope,100,,,60,100\n\
+             {0},0,,,60,100\n\
+             {0},100,,,0,100\n\
+             {0},100,,,60,0\n\
+             {0},100,,,60,10001\n\
+             {0},100,1700000000,,60,100\n\
+             {0},100,,60,60,100\n\
+             {0},100,,,60,100\n",
+            beneficiary
+        );
+
+        let errors = read_grants(csv.as_bytes()).unwrap_err().to_string();
+        let errors: Vec<&str> = errors.lines().collect();
+        assert_eq!(errors.len(), 7);
+        assert!(errors[0].starts_with("row 1: invalid beneficiary"));
+        assert!(errors[1].starts_with("row 2: Invalid Vestment Amount"));
+        assert!(errors[2].starts_with("row 3: Invalid Vestment"));
+        assert!(errors[3].starts_with("row 4: Invalid Vestment Percentage"));
+        assert!(errors[4].starts_with("row 5: Invalid Vestment Percentage"));
+        assert!(errors[5].starts_with("row 6: custom start"));
+        assert!(errors[6].starts_with("row 7: cliffs"));
+    }
+
+    #[test]
+    pub fn test_journal() {
+        let path = std::env::temp_dir().join(format!("journal-{}.csv", Pubkey::new_unique()));
+        let keypair = Keypair::new();
+        let entry = JournalEntry {
+            row: 1,
+            beneficiary: Pubkey::new_unique().to_string(),
+            amount: 100,
+            treasury: keypair.pubkey().to_string(),
+            treasury_keypair: keypair.to_base58_string(),
+            status: JournalStatus::Pending,
+            signature: String::new(),
+        };
+
+        let mut journal = Journal::open(&path, true).unwrap();
+        assert_eq!(journal.get(1), None);
+        journal.record(entry.clone()).unwrap();
+
+        let confirmed = JournalEntry {
+            status: JournalStatus::Confirmed,
+            signature: "sig".to_string(),
+            ..entry
+        };
+        journal.record(confirmed.clone()).unwrap();
+
+        let journal = Journal::open(&path, true).unwrap();
+        assert_eq!(journal.entries, vec![confirmed]);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,508 @@
+use std::error::Error;
+
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use treasury::{
+    account::{Acceleration, SimpleTreasury, SimpleTreasuryMode, VestedTreasury},
+    instruction,
+};
+use treasury_client::{Treasury, TreasuryClient, TreasuryFilter, VestingProgress};
+
+pub type CommandResult = Result<(), Box<dyn Error>>;
+
+pub struct Config {
+    pub client: TreasuryClient,
+    /// The fee payer, only needed for commands that send transactions
+    pub payer: Result<Keypair, String>,
+    pub dry_run: bool,
+}
+
+impl Config {
+    pub fn payer(&self) -> Result<&Keypair, Box<dyn Error>> {
+        self.payer.as_ref().map_err(|e| e.clone().into())
+    }
+
+    fn rpc(&self) -> &RpcClient {
+        self.client.rpc()
+    }
+
+    fn program_id(&self) -> &Pubkey {
+        self.client.program_id()
+    }
+
+    /// Sign and send a transaction paid for by the payer. In dry-run mode, the signed
+    /// transaction is printed instead.
+    pub fn send(&self, instructions: &[Instruction], signers: &[&Keypair]) -> CommandResult {
+        if let Some(signature) = self.send_transaction(instructions, signers)? {
+            println!("Signature: {}", signature);
+        }
+        Ok(())
+    }
+
+    /// Same as `send` but returns the signature, which is `None` in dry-run mode.
+    pub fn send_transaction(
+        &self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Option<Signature>, Box<dyn Error>> {
+        let payer = self.payer()?;
+        let mut all_signers: Vec<&Keypair> = vec![payer];
+        for signer in signers {
+            if signer.pubkey() != payer.pubkey() {
+                all_signers.push(signer);
+            }
+        }
+
+        let blockhash = self.rpc().get_latest_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+
+        if self.dry_run {
+            println!("{}", base64::encode(bincode::serialize(&transaction)?));
+            Ok(None)
+        } else {
+            Ok(Some(
+                self.rpc()
+                    .send_and_confirm_transaction_with_spinner(&transaction)?,
+            ))
+        }
+    }
+}
+
+/// The fund authority and fund addresses of a treasury
+pub fn fund_addresses(
+    treasury: &Pubkey,
+    mint: &Pubkey,
+    vested: bool,
+    program_id: &Pubkey,
+) -> (Pubkey, Pubkey) {
+    if vested {
+        (
+            VestedTreasury::fund_authority_address(treasury, program_id).0,
+            VestedTreasury::fund_address(treasury, mint, program_id),
+        )
+    } else {
+        (
+            SimpleTreasury::fund_authority_address(treasury, program_id).0,
+            SimpleTreasury::fund_address(treasury, mint, program_id),
+        )
+    }
+}
+
+/// Instruction that creates the fund account of a treasury if it doesn't exist
+pub fn create_fund(payer: &Pubkey, fund_authority: &Pubkey, mint: &Pubkey) -> Instruction {
+    create_associated_token_account_idempotent(payer, fund_authority, mint, &spl_token::id())
+}
+
+pub fn create_simple(
+    config: &Config,
+    mint: Pubkey,
+    authority: &Keypair,
+    mode: SimpleTreasuryMode,
+    treasury: Keypair,
+) -> CommandResult {
+    let payer = config.payer()?.pubkey();
+    let (fund_authority, _) = fund_addresses(&treasury.pubkey(), &mint, false, config.program_id());
+
+    println!("Treasury: {}", treasury.pubkey());
+    config.send(
+        &[
+            create_fund(&payer, &fund_authority, &mint),
+            instruction::create_simple_treasury(
+                config.program_id(),
+                &payer,
+                &authority.pubkey(),
+                &treasury.pubkey(),
+                &mint,
+                mode,
+            ),
+        ],
+        &[authority, &treasury],
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn create_vested(
+    config: &Config,
+    mint: Pubkey,
+    authority: Pubkey,
+    grantor: Pubkey,
+    amount: u64,
+    period: u64,
+    percentage: u16,
+    treasury: Keypair,
+) -> CommandResult {
+    let payer = config.payer()?.pubkey();
+    let (fund_authority, _) = fund_addresses(&treasury.pubkey(), &mint, true, config.program_id());
+
+    println!("Treasury: {}", treasury.pubkey());
+    config.send(
+        &[
+            create_fund(&payer, &fund_authority, &mint),
+            instruction::create_vested_treasury(
+                config.program_id(),
+                &payer,
+                &authority,
+                &grantor,
+                &treasury.pubkey(),
+                &mint,
+                amount,
+                period,
+                percentage,
+            ),
+        ],
+        &[&treasury],
+    )
+}
+
+pub fn fund(
+    config: &Config,
+    treasury_id: Pubkey,
+    amount: u64,
+    source: Option<Pubkey>,
+) -> CommandResult {
+    let payer = config.payer()?.pubkey();
+    let treasury = config.client.get_treasury(&treasury_id)?;
+    let mint = treasury.mint();
+    let (_, fund) = fund_addresses(
+        &treasury_id,
+        &mint,
+        matches!(treasury, Treasury::Vested(_)),
+        config.program_id(),
+    );
+    let source = source.unwrap_or_else(|| get_associated_token_address(&payer, &mint));
+
+    config.send(
+        &[spl_token::instruction::transfer(
+            &spl_token::id(),
+            &source,
+            &fund,
+            &payer,
+            &[],
+            amount,
+        )?],
+        &[],
+    )
+}
+
+pub fn withdraw(
+    config: &Config,
+    treasury_id: Pubkey,
+    amount: Option<u64>,
+    authority: &Keypair,
+    recipient: Option<Pubkey>,
+) -> CommandResult {
+    let payer = config.payer()?.pubkey();
+    let treasury = config.client.get_treasury(&treasury_id)?;
+    let mint = treasury.mint();
+
+    let mut instructions = vec![];
+    let recipient = match recipient {
+        Some(recipient) => recipient,
+        None => {
+            instructions.push(create_associated_token_account_idempotent(
+                &payer,
+                &treasury.authority(),
+                &mint,
+                &spl_token::id(),
+            ));
+            get_associated_token_address(&treasury.authority(), &mint)
+        }
+    };
+
+    instructions.push(match treasury {
+        Treasury::Simple(_) => instruction::withdraw_simple(
+            config.program_id(),
+            &payer,
+            &authority.pubkey(),
+            &recipient,
+            &treasury_id,
+            &mint,
+            amount.ok_or("simple treasuries need an amount to withdraw")?,
+        ),
+        Treasury::Vested(_) => instruction::withdraw_vested(
+            config.program_id(),
+            &payer,
+            &authority.pubkey(),
+            &recipient,
+            &treasury_id,
+            &mint,
+            amount,
+        ),
+    });
+
+    config.send(&instructions, &[authority])
+}
+
+/// Pause or resume the vesting of a vested treasury
+pub fn set_paused(
+    config: &Config,
+    treasury_id: Pubkey,
+    grantor: &Keypair,
+    paused: bool,
+) -> CommandResult {
+    let instruction = if paused {
+        instruction::pause_vesting(config.program_id(), &grantor.pubkey(), &treasury_id)
+    } else {
+        instruction::resume_vesting(config.program_id(), &grantor.pubkey(), &treasury_id)
+    };
+    config.send(&[instruction], &[grantor])
+}
+
+/// Vest part of the unvested amount of a vested treasury immediately
+pub fn accelerate(
+    config: &Config,
+    treasury_id: Pubkey,
+    grantor: &Keypair,
+    acceleration: Acceleration,
+) -> CommandResult {
+    config.send(
+        &[instruction::accelerate_vesting(
+            config.program_id(),
+            &grantor.pubkey(),
+            &treasury_id,
+            acceleration,
+        )],
+        &[grantor],
+    )
+}
+
+/// Let a delegate withdraw from a vested treasury on behalf of its authority, or
+/// revoke the delegate with `None`
+pub fn set_delegate(
+    config: &Config,
+    treasury_id: Pubkey,
+    authority: &Keypair,
+    delegate: Option<Pubkey>,
+) -> CommandResult {
+    config.send(
+        &[instruction::set_vested_delegate(
+            config.program_id(),
+            &authority.pubkey(),
+            &treasury_id,
+            delegate,
+        )],
+        &[authority],
+    )
+}
+
+/// Lock a vote escrow treasury until `duration` seconds from now
+pub fn lock(
+    config: &Config,
+    treasury_id: Pubkey,
+    authority: &Keypair,
+    duration: u32,
+) -> CommandResult {
+    let mint = match config.client.get_treasury(&treasury_id)? {
+        Treasury::Simple(simple) if simple.mode == SimpleTreasuryMode::VoteEscrow => simple.mint,
+        _ => return Err("only vote escrow treasuries can be locked".into()),
+    };
+    let lock_end = config.client.now()? + duration as i64;
+    config.send(
+        &[instruction::extend_lock(
+            config.program_id(),
+            &authority.pubkey(),
+            &treasury_id,
+            &mint,
+            lock_end,
+        )],
+        &[authority],
+    )
+}
+
+/// Move part of a vested treasury into a new vested treasury with the same schedule
+pub fn split(
+    config: &Config,
+    treasury_id: Pubkey,
+    amount: u64,
+    authority: &Keypair,
+    new_authority: Pubkey,
+    new_treasury: Keypair,
+) -> CommandResult {
+    let payer = config.payer()?.pubkey();
+    let mint = match config.client.get_treasury(&treasury_id)? {
+        Treasury::Vested(vested) => vested.mint,
+        Treasury::Simple(_) => return Err("only vested treasuries can be split".into()),
+    };
+    let (fund_authority, _) =
+        fund_addresses(&new_treasury.pubkey(), &mint, true, config.program_id());
+
+    println!("Treasury: {}", new_treasury.pubkey());
+    config.send(
+        &[
+            create_fund(&payer, &fund_authority, &mint),
+            instruction::split_vested(
+                config.program_id(),
+                &payer,
+                &authority.pubkey(),
+                &treasury_id,
+                &new_treasury.pubkey(),
+                &new_authority,
+                &mint,
+                amount,
+            ),
+        ],
+        &[authority, &new_treasury],
+    )
+}
+
+/// Merge vested treasuries into one, closing them and returning their SOL to the payer
+pub fn merge(
+    config: &Config,
+    treasury_id: Pubkey,
+    sources: &[Pubkey],
+    authority: &Keypair,
+) -> CommandResult {
+    let payer = config.payer()?.pubkey();
+    let mint = match config.client.get_treasury(&treasury_id)? {
+        Treasury::Vested(vested) => vested.mint,
+        Treasury::Simple(_) => return Err("only vested treasuries can be merged".into()),
+    };
+
+    let instructions: Vec<Instruction> = sources
+        .iter()
+        .map(|source| {
+            instruction::merge_vested(
+                config.program_id(),
+                &authority.pubkey(),
+                &treasury_id,
+                source,
+                &payer,
+                &mint,
+            )
+        })
+        .collect();
+    config.send(&instructions, &[authority])
+}
+
+fn print_progress(progress: &VestingProgress) {
+    println!("  Fund Balance:   {}", progress.fund_balance);
+    println!(
+        "  Vested:         {} ({:.2}%)",
+        progress.query.vested,
+        progress.vested_ratio() * 100f64
+    );
+    println!("  Unvested:       {}", progress.query.unvested);
+    println!("  Claimable:      {}", progress.query.claimable);
+    println!("  Locked in Fund: {}", progress.query.locked);
+    match progress.next_vesting {
+        Some(next) => println!("  Next Vesting:   {}", next),
+        None => println!("  Next Vesting:   fully vested"),
+    }
+}
+
+/// Migrate a treasury to the current layout, the payer pays the additional rent
+pub fn migrate(config: &Config, treasury_id: Pubkey) -> CommandResult {
+    let payer = config.payer()?.pubkey();
+    config.send(
+        &[instruction::migrate_treasury(
+            config.program_id(),
+            &payer,
+            &treasury_id,
+        )],
+        &[],
+    )
+}
+
+pub fn show(config: &Config, treasury_id: Pubkey) -> CommandResult {
+    match config.client.get_treasury(&treasury_id)? {
+        Treasury::Simple(simple) => {
+            let (fund_authority, fund) =
+                fund_addresses(&treasury_id, &simple.mint, false, config.program_id());
+            println!("Simple Treasury {}", treasury_id);
+            println!("  Mint:           {}", simple.mint);
+            println!("  Authority:      {}", simple.authority);
+            println!("  Mode:           {:?}", simple.mode);
+            if simple.mode == SimpleTreasuryMode::VoteEscrow {
+                println!("  Lock End:       {}", { simple.lock_end });
+                let (weight, updated_at) = (simple.weight, simple.weight_updated_at);
+                println!("  Weight:         {} (at {})", weight, updated_at);
+            }
+            println!("  Fund Authority: {}", fund_authority);
+            println!("  Fund:           {}", fund);
+            println!("  Fund Balance:   {}", config.client.fund_balance(&fund)?);
+        }
+        Treasury::Vested(vested) => {
+            let (fund_authority, fund) =
+                fund_addresses(&treasury_id, &vested.mint, true, config.program_id());
+            println!("Vested Treasury {}", treasury_id);
+            println!("  Mint:           {}", vested.mint);
+            println!("  Authority:      {}", vested.authority);
+            if vested.delegate != Pubkey::default() {
+                println!("  Delegate:       {}", vested.delegate);
+            }
+            println!("  Initial Amount: {}", { vested.initial_amount });
+            println!("  Start:          {}", { vested.start });
+            println!("  Period:         {}s", { vested.vestment_period });
+            println!(
+                "  Percentage:     {:.2}%",
+                vested.vestment_percentage as f64 / 100f64
+            );
+            println!("  Withdrawn:      {}", { vested.withdrawn });
+            println!("  Accelerated:    {}", { vested.accelerated });
+            println!("  Grantor:        {}", vested.grantor);
+            if vested.is_paused() {
+                println!("  Paused Since:   {}", { vested.paused_at });
+            }
+            println!("  Fund Authority: {}", fund_authority);
+            println!("  Fund:           {}", fund);
+            print_progress(&config.client.vesting_progress(&treasury_id)?);
+        }
+    }
+    Ok(())
+}
+
+pub fn list(config: &Config, filter: TreasuryFilter) -> CommandResult {
+    for (key, simple) in config.client.simple_treasuries(filter)? {
+        println!(
+            "{} simple mint={} authority={} mode={:?}",
+            key, simple.mint, simple.authority, simple.mode
+        );
+    }
+    for (key, vested) in config.client.vested_treasuries(filter)? {
+        println!(
+            "{} vested mint={} authority={} initial_amount={} withdrawn={}",
+            key,
+            vested.mint,
+            vested.authority,
+            { vested.initial_amount },
+            { vested.withdrawn }
+        );
+    }
+    Ok(())
+}
+
+pub fn addresses(
+    config: &Config,
+    treasury_id: Pubkey,
+    mint: Option<Pubkey>,
+    vested: Option<bool>,
+) -> CommandResult {
+    let (mint, vested) = match (mint, vested) {
+        (Some(mint), Some(vested)) => (mint, vested),
+        _ => {
+            let treasury = config.client.get_treasury(&treasury_id)?;
+            (
+                mint.unwrap_or_else(|| treasury.mint()),
+                vested.unwrap_or(matches!(treasury, Treasury::Vested(_))),
+            )
+        }
+    };
+
+    let (fund_authority, fund) = fund_addresses(&treasury_id, &mint, vested, config.program_id());
+    println!("Fund Authority: {}", fund_authority);
+    println!("Fund:           {}", fund);
+    Ok(())
+}
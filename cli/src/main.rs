@@ -0,0 +1,747 @@
+//! Command-line tool for operating treasuries
+
+mod bulk;
+mod command;
+
+use clap::{
+    crate_description, crate_name, crate_version, App, AppSettings, Arg, ArgGroup, ArgMatches,
+    SubCommand,
+};
+use command::{CommandResult, Config};
+use solana_clap_utils::{
+    input_parsers::{pubkey_of, pubkeys_of, value_of},
+    input_validators::{is_keypair, is_parsable, is_url, is_valid_pubkey},
+};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    signature::{read_keypair_file, Keypair, Signer},
+};
+use spl_associated_token_account::get_associated_token_address;
+use std::{fs::File, io, path::Path};
+use treasury::account::{Acceleration, SimpleTreasuryMode, VestedTreasury};
+use treasury_client::{TreasuryClient, TreasuryFilter};
+
+fn treasury_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("treasury")
+        .value_name("TREASURY_ADDRESS")
+        .validator(is_valid_pubkey)
+        .takes_value(true)
+        .required(true)
+        .index(1)
+        .help("The treasury account")
+}
+
+fn treasury_keypair_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("treasury_keypair")
+        .long("treasury-keypair")
+        .value_name("KEYPAIR")
+        .validator(is_keypair)
+        .takes_value(true)
+        .help("Keypair of the new treasury account [default: randomly generated]")
+}
+
+fn mint_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("mint")
+        .value_name("MINT_ADDRESS")
+        .validator(is_valid_pubkey)
+        .takes_value(true)
+        .required(true)
+        .index(1)
+        .help("The SPL Token mint of the treasury")
+}
+
+fn authority_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("authority")
+        .long("authority")
+        .value_name("AUTHORITY_ADDRESS")
+        .validator(is_valid_pubkey)
+        .takes_value(true)
+        .help("The authority that controls the treasury [default: fee payer]")
+}
+
+fn grantor_keypair_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("grantor_keypair")
+        .long("grantor-keypair")
+        .value_name("KEYPAIR")
+        .validator(is_keypair)
+        .takes_value(true)
+        .help("Keypair of the treasury's grantor [default: fee payer]")
+}
+
+fn grants_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("grants")
+        .value_name("CSV")
+        .takes_value(true)
+        .required(true)
+        .index(1)
+        .help("CSV file with the columns beneficiary, amount, start, cliff, period, percentage")
+}
+
+fn bulk_mint_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("mint")
+        .long("mint")
+        .value_name("MINT_ADDRESS")
+        .validator(is_valid_pubkey)
+        .takes_value(true)
+        .required(true)
+        .help("The SPL Token mint of the grants")
+}
+
+fn journal_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("journal")
+        .long("journal")
+        .value_name("PATH")
+        .takes_value(true)
+        .required(true)
+        .help("File recording the progress of the deployment, used to resume interrupted runs")
+}
+
+fn report_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("report")
+        .long("report")
+        .value_name("PATH")
+        .takes_value(true)
+        .help("Where to write the reconciliation report [default: stdout]")
+}
+
+fn bulk_report(
+    config: &Config,
+    arg_matches: &ArgMatches,
+    grants: &[bulk::Grant],
+    journal: &bulk::Journal,
+) -> CommandResult {
+    let mint = pubkey_of(arg_matches, "mint").unwrap();
+    let failures = match arg_matches.value_of("report") {
+        Some(path) => bulk::reconcile(config, mint, grants, journal, File::create(path)?)?,
+        None => bulk::reconcile(config, mint, grants, journal, io::stdout())?,
+    };
+    if failures > 0 {
+        return Err(format!(
+            "{} of {} grants are not deployed correctly",
+            failures,
+            grants.len()
+        )
+        .into());
+    }
+    eprintln!("all {} grants are deployed correctly", grants.len());
+    Ok(())
+}
+
+fn keypair_or_new(matches: &ArgMatches, name: &str) -> Result<Keypair, String> {
+    match matches.value_of(name) {
+        Some(path) => read_keypair_file(path).map_err(|e| format!("{}: {}", path, e)),
+        None => Ok(Keypair::new()),
+    }
+}
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new(crate_name!())
+        .about(crate_description!())
+        .version(crate_version!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("config_file")
+                .short("C")
+                .long("config")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Solana CLI configuration file"),
+        )
+        .arg(
+            Arg::with_name("json_rpc_url")
+                .short("u")
+                .long("url")
+                .value_name("URL")
+                .takes_value(true)
+                .global(true)
+                .validator(is_url)
+                .help("JSON RPC URL for the cluster [default: value from configuration file]"),
+        )
+        .arg(
+            Arg::with_name("keypair")
+                .short("k")
+                .long("keypair")
+                .value_name("KEYPAIR")
+                .takes_value(true)
+                .global(true)
+                .help("Fee payer keypair [default: value from configuration file]"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("PROGRAM_ID")
+                .takes_value(true)
+                .global(true)
+                .validator(is_valid_pubkey)
+                .help("The address of the treasury program"),
+        )
+        .arg(
+            Arg::with_name("dry_run")
+                .long("dry-run")
+                .global(true)
+                .help("Print the signed transaction as base64 instead of sending it"),
+        )
+        .subcommand(
+            SubCommand::with_name("create-simple")
+                .about("Create a simple treasury and its fund account")
+                .arg(mint_arg())
+                .arg(
+                    Arg::with_name("authority_keypair")
+                        .long("authority-keypair")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Keypair of the authority that controls the treasury [default: fee payer]"),
+                )
+                .arg(
+                    Arg::with_name("mode")
+                        .long("mode")
+                        .value_name("MODE")
+                        .takes_value(true)
+                        .possible_values(&["locked", "unlocked", "vote-escrow"])
+                        .default_value("locked")
+                        .help("The mode of the simple treasury"),
+                )
+                .arg(treasury_keypair_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("create-vested")
+                .about("Create a vested treasury and its fund account")
+                .arg(mint_arg())
+                .arg(authority_arg())
+                .arg(
+                    Arg::with_name("grantor")
+                        .long("grantor")
+                        .value_name("GRANTOR_ADDRESS")
+                        .validator(is_valid_pubkey)
+                        .takes_value(true)
+                        .help("The key that can pause and resume vesting [default: fee payer]"),
+                )
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_parsable::<u64>)
+                        .help("The total amount of tokens that vest, in base units"),
+                )
+                .arg(
+                    Arg::with_name("period")
+                        .long("period")
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_parsable::<u64>)
+                        .help("The length of a single vesting period in seconds"),
+                )
+                .arg(
+                    Arg::with_name("percentage")
+                        .long("percentage")
+                        .value_name("BASIS_POINTS")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(is_parsable::<u16>)
+                        .help("The share of the amount released every period, in basis points"),
+                )
+                .arg(treasury_keypair_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("fund")
+                .about("Transfer tokens into a treasury's fund account")
+                .arg(treasury_arg())
+                .arg(
+                    Arg::with_name("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .index(2)
+                        .validator(is_parsable::<u64>)
+                        .help("The amount of tokens to transfer, in base units"),
+                )
+                .arg(
+                    Arg::with_name("source")
+                        .long("source")
+                        .value_name("TOKEN_ACCOUNT")
+                        .takes_value(true)
+                        .validator(is_valid_pubkey)
+                        .help("The token account to transfer from [default: fee payer's associated token account]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("withdraw")
+                .about("Withdraw from a treasury to a token account of its authority")
+                .arg(treasury_arg())
+                .arg(
+                    Arg::with_name("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .index(2)
+                        .validator(is_parsable::<u64>)
+                        .help(
+                            "The amount to withdraw, in base units. Vested treasuries withdraw \
+                             everything available without it",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("authority_keypair")
+                        .long("authority-keypair")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Keypair of the treasury's authority or its delegate [default: fee payer]"),
+                )
+                .arg(
+                    Arg::with_name("recipient")
+                        .long("recipient")
+                        .value_name("TOKEN_ACCOUNT")
+                        .takes_value(true)
+                        .validator(is_valid_pubkey)
+                        .help("The token account receiving the funds [default: authority's associated token account]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("pause")
+                .about("Stop the vesting clock of a vested treasury")
+                .arg(treasury_arg())
+                .arg(grantor_keypair_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("resume")
+                .about("Restart the vesting clock of a paused vested treasury")
+                .arg(treasury_arg())
+                .arg(grantor_keypair_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("accelerate")
+                .about("Vest part of the unvested amount of a vested treasury immediately")
+                .arg(treasury_arg())
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .validator(is_parsable::<u64>)
+                        .help("The amount to vest, in base units"),
+                )
+                .arg(
+                    Arg::with_name("percentage")
+                        .long("percentage")
+                        .value_name("BASIS_POINTS")
+                        .takes_value(true)
+                        .validator(is_parsable::<u16>)
+                        .help("The share of the unvested amount to vest, in basis points"),
+                )
+                .group(
+                    ArgGroup::with_name("acceleration")
+                        .args(&["amount", "percentage"])
+                        .required(true),
+                )
+                .arg(grantor_keypair_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("delegate")
+                .about("Let another key withdraw from a vested treasury to its authority")
+                .arg(treasury_arg())
+                .arg(
+                    Arg::with_name("delegate")
+                        .value_name("DELEGATE_ADDRESS")
+                        .takes_value(true)
+                        .index(2)
+                        .validator(is_valid_pubkey)
+                        .help("The key that can withdraw on behalf of the authority"),
+                )
+                .arg(
+                    Arg::with_name("revoke")
+                        .long("revoke")
+                        .takes_value(false)
+                        .help("Remove the current delegate"),
+                )
+                .group(
+                    ArgGroup::with_name("delegation")
+                        .args(&["delegate", "revoke"])
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("authority_keypair")
+                        .long("authority-keypair")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Keypair of the treasury's authority [default: fee payer]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("lock")
+                .about("Lock a vote escrow treasury, or extend its lock")
+                .arg(treasury_arg())
+                .arg(
+                    Arg::with_name("duration")
+                        .value_name("SECONDS")
+                        .takes_value(true)
+                        .required(true)
+                        .index(2)
+                        .validator(is_parsable::<u32>)
+                        .help("How long from now the fund stays locked"),
+                )
+                .arg(
+                    Arg::with_name("authority_keypair")
+                        .long("authority-keypair")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Keypair of the treasury's authority [default: fee payer]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("split")
+                .about("Move part of a vested treasury into a new one that vests in lockstep")
+                .arg(treasury_arg())
+                .arg(
+                    Arg::with_name("amount")
+                        .value_name("AMOUNT")
+                        .takes_value(true)
+                        .required(true)
+                        .index(2)
+                        .validator(is_parsable::<u64>)
+                        .help("The part of the initial amount to move, in base units"),
+                )
+                .arg(
+                    Arg::with_name("new_authority")
+                        .value_name("NEW_AUTHORITY_ADDRESS")
+                        .takes_value(true)
+                        .required(true)
+                        .index(3)
+                        .validator(is_valid_pubkey)
+                        .help("The authority of the new treasury"),
+                )
+                .arg(
+                    Arg::with_name("authority_keypair")
+                        .long("authority-keypair")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Keypair of the treasury's authority [default: fee payer]"),
+                )
+                .arg(treasury_keypair_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("merge")
+                .about("Merge vested treasuries with the same terms into one and close them")
+                .arg(treasury_arg())
+                .arg(
+                    Arg::with_name("sources")
+                        .value_name("SOURCE_ADDRESS")
+                        .takes_value(true)
+                        .multiple(true)
+                        .required(true)
+                        .index(2)
+                        .validator(is_valid_pubkey)
+                        .help("The treasuries to merge into the first one"),
+                )
+                .arg(
+                    Arg::with_name("authority_keypair")
+                        .long("authority-keypair")
+                        .value_name("KEYPAIR")
+                        .takes_value(true)
+                        .validator(is_keypair)
+                        .help("Keypair of the treasuries' authority [default: fee payer]"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("migrate")
+                .about("Grow a treasury stored with an earlier layout to the current one")
+                .arg(treasury_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("show")
+                .about("Show the state of a treasury and its vesting progress")
+                .arg(treasury_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("list")
+                .about("List all treasuries of an authority or mint")
+                .arg(
+                    Arg::with_name("authority")
+                        .long("authority")
+                        .value_name("AUTHORITY_ADDRESS")
+                        .takes_value(true)
+                        .validator(is_valid_pubkey),
+                )
+                .arg(
+                    Arg::with_name("mint")
+                        .long("mint")
+                        .value_name("MINT_ADDRESS")
+                        .takes_value(true)
+                        .validator(is_valid_pubkey),
+                )
+                .group(
+                    ArgGroup::with_name("filter")
+                        .args(&["authority", "mint"])
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("bulk-deploy")
+                .about("Create and fund vested treasuries for every grant in a CSV file")
+                .arg(grants_arg())
+                .arg(bulk_mint_arg())
+                .arg(journal_arg())
+                .arg(
+                    Arg::with_name("source")
+                        .long("source")
+                        .value_name("TOKEN_ACCOUNT")
+                        .takes_value(true)
+                        .validator(is_valid_pubkey)
+                        .help("The token account funding the grants [default: fee payer's associated token account]"),
+                )
+                .arg(report_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("bulk-report")
+                .about("Reconcile the grants in a CSV file with the treasuries on chain")
+                .arg(grants_arg())
+                .arg(bulk_mint_arg())
+                .arg(journal_arg())
+                .arg(report_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("address")
+                .about("Print the derived fund authority and fund addresses of a treasury")
+                .arg(treasury_arg())
+                .arg(
+                    Arg::with_name("mint")
+                        .long("mint")
+                        .value_name("MINT_ADDRESS")
+                        .takes_value(true)
+                        .validator(is_valid_pubkey)
+                        .help("The mint of the treasury [default: read from the treasury]"),
+                )
+                .arg(
+                    Arg::with_name("kind")
+                        .long("kind")
+                        .value_name("KIND")
+                        .takes_value(true)
+                        .possible_values(&["simple", "vested"])
+                        .help("The kind of treasury [default: read from the treasury]"),
+                ),
+        )
+}
+
+fn run(matches: ArgMatches) -> CommandResult {
+    let cli_config = match matches.value_of("config_file") {
+        Some(path) => solana_cli_config::Config::load(path)?,
+        None => match solana_cli_config::CONFIG_FILE.as_ref() {
+            Some(path) => solana_cli_config::Config::load(path).unwrap_or_default(),
+            None => solana_cli_config::Config::default(),
+        },
+    };
+
+    let url = matches
+        .value_of("json_rpc_url")
+        .map(String::from)
+        .unwrap_or(cli_config.json_rpc_url);
+    let keypair_path = matches
+        .value_of("keypair")
+        .map(String::from)
+        .unwrap_or(cli_config.keypair_path);
+    let payer = read_keypair_file(&keypair_path).map_err(|e| format!("{}: {}", keypair_path, e));
+
+    let program_id = pubkey_of(&matches, "program_id").ok_or("--program-id is required")?;
+
+    let config = Config {
+        client: TreasuryClient::new(
+            RpcClient::new_with_commitment(url, CommitmentConfig::confirmed()),
+            program_id,
+        ),
+        payer,
+        dry_run: matches.is_present("dry_run"),
+    };
+
+    match matches.subcommand() {
+        ("create-simple", Some(arg_matches)) => {
+            let mode = match arg_matches.value_of("mode") {
+                Some("unlocked") => SimpleTreasuryMode::Unlocked,
+                Some("vote-escrow") => SimpleTreasuryMode::VoteEscrow,
+                _ => SimpleTreasuryMode::Locked,
+            };
+            let authority = match arg_matches.value_of("authority_keypair") {
+                Some(path) => read_keypair_file(path).map_err(|e| format!("{}: {}", path, e))?,
+                None => config.payer()?.insecure_clone(),
+            };
+            command::create_simple(
+                &config,
+                pubkey_of(arg_matches, "mint").unwrap(),
+                &authority,
+                mode,
+                keypair_or_new(arg_matches, "treasury_keypair")?,
+            )
+        }
+        ("create-vested", Some(arg_matches)) => {
+            let amount = value_of(arg_matches, "amount").unwrap();
+            let period = value_of(arg_matches, "period").unwrap();
+            let percentage = value_of(arg_matches, "percentage").unwrap();
+            VestedTreasury::verify_parameters(amount, period, percentage)?;
+            command::create_vested(
+                &config,
+                pubkey_of(arg_matches, "mint").unwrap(),
+                pubkey_of(arg_matches, "authority").unwrap_or(config.payer()?.pubkey()),
+                pubkey_of(arg_matches, "grantor").unwrap_or(config.payer()?.pubkey()),
+                amount,
+                period,
+                percentage,
+                keypair_or_new(arg_matches, "treasury_keypair")?,
+            )
+        }
+        ("fund", Some(arg_matches)) => command::fund(
+            &config,
+            pubkey_of(arg_matches, "treasury").unwrap(),
+            value_of(arg_matches, "amount").unwrap(),
+            pubkey_of(arg_matches, "source"),
+        ),
+        ("withdraw", Some(arg_matches)) => {
+            let authority = match arg_matches.value_of("authority_keypair") {
+                Some(path) => read_keypair_file(path).map_err(|e| format!("{}: {}", path, e))?,
+                None => config.payer()?.insecure_clone(),
+            };
+            command::withdraw(
+                &config,
+                pubkey_of(arg_matches, "treasury").unwrap(),
+                value_of(arg_matches, "amount"),
+                &authority,
+                pubkey_of(arg_matches, "recipient"),
+            )
+        }
+        ("pause", Some(arg_matches)) | ("resume", Some(arg_matches)) => {
+            let grantor = match arg_matches.value_of("grantor_keypair") {
+                Some(path) => read_keypair_file(path).map_err(|e| format!("{}: {}", path, e))?,
+                None => config.payer()?.insecure_clone(),
+            };
+            command::set_paused(
+                &config,
+                pubkey_of(arg_matches, "treasury").unwrap(),
+                &grantor,
+                matches.subcommand_name() == Some("pause"),
+            )
+        }
+        ("accelerate", Some(arg_matches)) => {
+            let grantor = match arg_matches.value_of("grantor_keypair") {
+                Some(path) => read_keypair_file(path).map_err(|e| format!("{}: {}", path, e))?,
+                None => config.payer()?.insecure_clone(),
+            };
+            let acceleration = match value_of(arg_matches, "amount") {
+                Some(amount) => Acceleration::Amount(amount),
+                None => Acceleration::Percentage(value_of(arg_matches, "percentage").unwrap()),
+            };
+            command::accelerate(
+                &config,
+                pubkey_of(arg_matches, "treasury").unwrap(),
+                &grantor,
+                acceleration,
+            )
+        }
+        ("delegate", Some(arg_matches)) => {
+            let authority = match arg_matches.value_of("authority_keypair") {
+                Some(path) => read_keypair_file(path).map_err(|e| format!("{}: {}", path, e))?,
+                None => config.payer()?.insecure_clone(),
+            };
+            command::set_delegate(
+                &config,
+                pubkey_of(arg_matches, "treasury").unwrap(),
+                &authority,
+                pubkey_of(arg_matches, "delegate"),
+            )
+        }
+        ("lock", Some(arg_matches)) => {
+            let authority = match arg_matches.value_of("authority_keypair") {
+                Some(path) => read_keypair_file(path).map_err(|e| format!("{}: {}", path, e))?,
+                None => config.payer()?.insecure_clone(),
+            };
+            command::lock(
+                &config,
+                pubkey_of(arg_matches, "treasury").unwrap(),
+                &authority,
+                value_of::<u32>(arg_matches, "duration").unwrap(),
+            )
+        }
+        ("split", Some(arg_matches)) => {
+            let authority = match arg_matches.value_of("authority_keypair") {
+                Some(path) => read_keypair_file(path).map_err(|e| format!("{}: {}", path, e))?,
+                None => config.payer()?.insecure_clone(),
+            };
+            command::split(
+                &config,
+                pubkey_of(arg_matches, "treasury").unwrap(),
+                value_of(arg_matches, "amount").unwrap(),
+                &authority,
+                pubkey_of(arg_matches, "new_authority").unwrap(),
+                keypair_or_new(arg_matches, "treasury_keypair")?,
+            )
+        }
+        ("merge", Some(arg_matches)) => {
+            let authority = match arg_matches.value_of("authority_keypair") {
+                Some(path) => read_keypair_file(path).map_err(|e| format!("{}: {}", path, e))?,
+                None => config.payer()?.insecure_clone(),
+            };
+            command::merge(
+                &config,
+                pubkey_of(arg_matches, "treasury").unwrap(),
+                &pubkeys_of(arg_matches, "sources").unwrap(),
+                &authority,
+            )
+        }
+        ("migrate", Some(arg_matches)) => {
+            command::migrate(&config, pubkey_of(arg_matches, "treasury").unwrap())
+        }
+        ("show", Some(arg_matches)) => {
+            command::show(&config, pubkey_of(arg_matches, "treasury").unwrap())
+        }
+        ("list", Some(arg_matches)) => {
+            let filter = match pubkey_of(arg_matches, "authority") {
+                Some(authority) => TreasuryFilter::Authority(authority),
+                None => TreasuryFilter::Mint(pubkey_of(arg_matches, "mint").unwrap()),
+            };
+            command::list(&config, filter)
+        }
+        ("bulk-deploy", Some(arg_matches)) => {
+            let grants = bulk::read_grants(File::open(arg_matches.value_of("grants").unwrap())?)?;
+            let mut journal = bulk::Journal::open(
+                Path::new(arg_matches.value_of("journal").unwrap()),
+                !config.dry_run,
+            )?;
+            let mint = pubkey_of(arg_matches, "mint").unwrap();
+            let source = match pubkey_of(arg_matches, "source") {
+                Some(source) => source,
+                None => get_associated_token_address(&config.payer()?.pubkey(), &mint),
+            };
+
+            bulk::deploy(&config, mint, source, &grants, &mut journal)?;
+            if config.dry_run {
+                return Ok(());
+            }
+            bulk_report(&config, arg_matches, &grants, &journal)
+        }
+        ("bulk-report", Some(arg_matches)) => {
+            let grants = bulk::read_grants(File::open(arg_matches.value_of("grants").unwrap())?)?;
+            let journal =
+                bulk::Journal::open(Path::new(arg_matches.value_of("journal").unwrap()), false)?;
+            bulk_report(&config, arg_matches, &grants, &journal)
+        }
+        ("address", Some(arg_matches)) => command::addresses(
+            &config,
+            pubkey_of(arg_matches, "treasury").unwrap(),
+            pubkey_of(arg_matches, "mint"),
+            arg_matches.value_of("kind").map(|kind| kind == "vested"),
+        ),
+        _ => unreachable!(),
+    }
+}
+
+fn main() {
+    if let Err(e) = run(app().get_matches()) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
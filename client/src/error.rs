@@ -0,0 +1,29 @@
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// The RPC request failed
+    #[error("RPC error: {0}")]
+    Rpc(Box<solana_client::client_error::ClientError>),
+
+    /// The account does not exist
+    #[error("Account {0} not found")]
+    AccountNotFound(Pubkey),
+
+    /// The account is not owned by the treasury program
+    #[error("Account {0} is not owned by the treasury program")]
+    InvalidOwner(Pubkey),
+
+    /// The account data could not be decoded
+    #[error("Account {0} has invalid data")]
+    InvalidAccountData(Pubkey),
+}
+
+impl From<solana_client::client_error::ClientError> for ClientError {
+    fn from(e: solana_client::client_error::ClientError) -> Self {
+        ClientError::Rpc(Box::new(e))
+    }
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
@@ -0,0 +1,465 @@
+//! Off-chain client for reading treasuries over RPC
+
+pub mod error;
+
+use borsh::BorshDeserialize;
+use error::{ClientError, ClientResult};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{
+    account::{from_account, Account},
+    clock::{Clock, UnixTimestamp},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    sysvar,
+};
+use treasury::{
+    account::{
+        EscrowTreasury, HurdleTreasury, MilestoneTreasury, SimpleTreasury, SplitterTreasury,
+        VestedQuery, VestedTreasury,
+    },
+    config::Config,
+    recovery::{Recovery, SocialRecovery},
+};
+
+/// A treasury of any kind
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Treasury {
+    Simple(SimpleTreasury),
+    Vested(VestedTreasury),
+}
+
+impl Treasury {
+    /// Decode the account data of a treasury, using the size to tell the kinds apart
+    pub fn decode(data: &[u8]) -> Option<Treasury> {
+        decode_simple(data)
+            .map(Treasury::Simple)
+            .or_else(|| decode_vested(data).map(Treasury::Vested))
+    }
+
+    pub fn mint(&self) -> Pubkey {
+        match self {
+            Treasury::Simple(simple) => simple.mint,
+            Treasury::Vested(vested) => vested.mint,
+        }
+    }
+
+    pub fn authority(&self) -> Pubkey {
+        match self {
+            Treasury::Simple(simple) => simple.authority,
+            Treasury::Vested(vested) => vested.authority,
+        }
+    }
+}
+
+/// Decode a treasury stored with the current layout, or with an earlier layout that
+/// hasn't been grown by `MigrateTreasury` yet. Fields were only ever appended, so an
+/// earlier layout reads like the current one with the added fields zeroed, which is
+/// what the migration writes.
+fn decode_layout<T: BorshDeserialize>(data: &[u8], len: usize, legacy_lens: &[usize]) -> Option<T> {
+    if data.len() == len {
+        T::try_from_slice(data).ok()
+    } else if legacy_lens.contains(&data.len()) {
+        let mut extended = data.to_vec();
+        extended.resize(len, 0);
+        T::try_from_slice(&extended).ok()
+    } else {
+        None
+    }
+}
+
+/// Decode a simple treasury with any of its layouts
+pub fn decode_simple(data: &[u8]) -> Option<SimpleTreasury> {
+    decode_layout(data, SimpleTreasury::LEN, &SimpleTreasury::LEGACY_LENS)
+}
+
+/// Decode a vested treasury with any of its layouts
+pub fn decode_vested(data: &[u8]) -> Option<VestedTreasury> {
+    decode_layout(data, VestedTreasury::LEN, &VestedTreasury::LEGACY_LENS)
+}
+
+/// The state of a vested treasury at a specific point in time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct VestingProgress {
+    pub treasury: VestedTreasury,
+    pub now: UnixTimestamp,
+    pub fund_balance: u64,
+    pub query: VestedQuery,
+    /// When the next vesting period elapses, `None` if everything has vested or
+    /// vesting is paused
+    pub next_vesting: Option<UnixTimestamp>,
+}
+
+impl VestingProgress {
+    pub fn new(treasury: VestedTreasury, now: UnixTimestamp, fund_balance: u64) -> Self {
+        let query = treasury.query(now, fund_balance);
+
+        let next_vesting = if query.unvested == 0 || treasury.is_paused() {
+            None
+        } else if now < treasury.start {
+            Some(treasury.start + treasury.vestment_period as UnixTimestamp)
+        } else {
+            let ticks = (now - treasury.start) as u64 / treasury.vestment_period;
+            Some(treasury.start + ((ticks + 1) * treasury.vestment_period) as UnixTimestamp)
+        };
+
+        VestingProgress {
+            treasury,
+            now,
+            fund_balance,
+            query,
+            next_vesting,
+        }
+    }
+
+    /// The share of the initial amount that has vested, between 0 and 1
+    pub fn vested_ratio(&self) -> f64 {
+        self.query.vested as f64 / self.treasury.initial_amount as f64
+    }
+}
+
+/// Which field of a treasury to filter by when listing
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TreasuryFilter {
+    Authority(Pubkey),
+    Mint(Pubkey),
+}
+
+impl TreasuryFilter {
+    /// `getProgramAccounts` filters that match simple treasuries, one set for every
+    /// layout since the size filter can only match one
+    pub fn simple_filters(&self) -> Vec<Vec<RpcFilterType>> {
+        let (offset, key) = match self {
+            TreasuryFilter::Authority(key) => (SimpleTreasury::AUTHORITY_OFFSET, key),
+            TreasuryFilter::Mint(key) => (SimpleTreasury::MINT_OFFSET, key),
+        };
+        Self::filters(
+            SimpleTreasury::LEN,
+            &SimpleTreasury::LEGACY_LENS,
+            offset,
+            key,
+        )
+    }
+
+    /// `getProgramAccounts` filters that match vested treasuries, one set for every
+    /// layout since the size filter can only match one
+    pub fn vested_filters(&self) -> Vec<Vec<RpcFilterType>> {
+        let (offset, key) = match self {
+            TreasuryFilter::Authority(key) => (VestedTreasury::AUTHORITY_OFFSET, key),
+            TreasuryFilter::Mint(key) => (VestedTreasury::MINT_OFFSET, key),
+        };
+        Self::filters(
+            VestedTreasury::LEN,
+            &VestedTreasury::LEGACY_LENS,
+            offset,
+            key,
+        )
+    }
+
+    // the filtered fields are at the same offsets in every layout
+    fn filters(
+        len: usize,
+        legacy_lens: &[usize],
+        offset: usize,
+        key: &Pubkey,
+    ) -> Vec<Vec<RpcFilterType>> {
+        std::iter::once(&len)
+            .chain(legacy_lens)
+            .map(|len| {
+                vec![
+                    RpcFilterType::DataSize(*len as u64),
+                    RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset, key.to_bytes().to_vec())),
+                ]
+            })
+            .collect()
+    }
+}
+
+pub struct TreasuryClient {
+    rpc: RpcClient,
+    program_id: Pubkey,
+}
+
+impl TreasuryClient {
+    pub fn new(rpc: RpcClient, program_id: Pubkey) -> Self {
+        TreasuryClient { rpc, program_id }
+    }
+
+    pub fn rpc(&self) -> &RpcClient {
+        &self.rpc
+    }
+
+    pub fn program_id(&self) -> &Pubkey {
+        &self.program_id
+    }
+
+    fn get_program_account(&self, key: &Pubkey) -> ClientResult<Account> {
+        let account = self
+            .rpc
+            .get_account_with_commitment(key, self.rpc.commitment())?
+            .value
+            .ok_or(ClientError::AccountNotFound(*key))?;
+        if account.owner != self.program_id {
+            return Err(ClientError::InvalidOwner(*key));
+        }
+        Ok(account)
+    }
+
+    /// Fetch a treasury of any kind
+    pub fn get_treasury(&self, key: &Pubkey) -> ClientResult<Treasury> {
+        let account = self.get_program_account(key)?;
+        Treasury::decode(&account.data).ok_or(ClientError::InvalidAccountData(*key))
+    }
+
+    /// Fetch a simple treasury, which may still have an earlier layout
+    pub fn get_simple_treasury(&self, key: &Pubkey) -> ClientResult<SimpleTreasury> {
+        let account = self.get_program_account(key)?;
+        decode_simple(&account.data).ok_or(ClientError::InvalidAccountData(*key))
+    }
+
+    /// Fetch a vested treasury, which may still have an earlier layout
+    pub fn get_vested_treasury(&self, key: &Pubkey) -> ClientResult<VestedTreasury> {
+        let account = self.get_program_account(key)?;
+        decode_vested(&account.data).ok_or(ClientError::InvalidAccountData(*key))
+    }
+
+    pub fn get_splitter_treasury(&self, key: &Pubkey) -> ClientResult<SplitterTreasury> {
+        let account = self.get_program_account(key)?;
+        SplitterTreasury::try_from_slice(&account.data)
+            .map_err(|_| ClientError::InvalidAccountData(*key))
+    }
+
+    pub fn get_escrow_treasury(&self, key: &Pubkey) -> ClientResult<EscrowTreasury> {
+        let account = self.get_program_account(key)?;
+        EscrowTreasury::try_from_slice(&account.data)
+            .map_err(|_| ClientError::InvalidAccountData(*key))
+    }
+
+    pub fn get_milestone_treasury(&self, key: &Pubkey) -> ClientResult<MilestoneTreasury> {
+        let account = self.get_program_account(key)?;
+        MilestoneTreasury::try_from_slice(&account.data)
+            .map_err(|_| ClientError::InvalidAccountData(*key))
+    }
+
+    pub fn get_hurdle_treasury(&self, key: &Pubkey) -> ClientResult<HurdleTreasury> {
+        let account = self.get_program_account(key)?;
+        HurdleTreasury::try_from_slice(&account.data)
+            .map_err(|_| ClientError::InvalidAccountData(*key))
+    }
+
+    /// Fetch the program config, `AccountNotFound` until it's initialized
+    pub fn get_config(&self) -> ClientResult<Config> {
+        let (key, _) = Config::address(&self.program_id);
+        let account = self.get_program_account(&key)?;
+        Config::try_from_slice(&account.data).map_err(|_| ClientError::InvalidAccountData(key))
+    }
+
+    /// Fetch the recovery of a simple or vested treasury, `AccountNotFound` if none was
+    /// set up
+    pub fn get_recovery(&self, treasury: &Pubkey) -> ClientResult<Recovery> {
+        let (key, _) = Recovery::address(treasury, &self.program_id);
+        let account = self.get_program_account(&key)?;
+        Recovery::try_from_slice(&account.data).map_err(|_| ClientError::InvalidAccountData(key))
+    }
+
+    /// Fetch the guardians of a simple or vested treasury, `AccountNotFound` if none were
+    /// named
+    pub fn get_social_recovery(&self, treasury: &Pubkey) -> ClientResult<SocialRecovery> {
+        let (key, _) = SocialRecovery::address(treasury, &self.program_id);
+        let account = self.get_program_account(&key)?;
+        SocialRecovery::try_from_slice(&account.data)
+            .map_err(|_| ClientError::InvalidAccountData(key))
+    }
+
+    /// Run one query per set of filters and decode everything they found
+    fn get_program_accounts<T>(
+        &self,
+        filter_sets: Vec<Vec<RpcFilterType>>,
+        decode: fn(&[u8]) -> Option<T>,
+    ) -> ClientResult<Vec<(Pubkey, T)>> {
+        let mut treasuries = vec![];
+        for filters in filter_sets {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(filters),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(self.rpc.commitment()),
+                    ..RpcAccountInfoConfig::default()
+                },
+                ..RpcProgramAccountsConfig::default()
+            };
+
+            for (key, account) in self
+                .rpc
+                .get_program_accounts_with_config(&self.program_id, config)?
+            {
+                let treasury = decode(&account.data).ok_or(ClientError::InvalidAccountData(key))?;
+                treasuries.push((key, treasury));
+            }
+        }
+        Ok(treasuries)
+    }
+
+    /// List all simple treasuries matching the filter, including the ones that still
+    /// have an earlier layout
+    pub fn simple_treasuries(
+        &self,
+        filter: TreasuryFilter,
+    ) -> ClientResult<Vec<(Pubkey, SimpleTreasury)>> {
+        self.get_program_accounts(filter.simple_filters(), decode_simple)
+    }
+
+    /// List all vested treasuries matching the filter, including the ones that still
+    /// have an earlier layout
+    pub fn vested_treasuries(
+        &self,
+        filter: TreasuryFilter,
+    ) -> ClientResult<Vec<(Pubkey, VestedTreasury)>> {
+        self.get_program_accounts(filter.vested_filters(), decode_vested)
+    }
+
+    /// The cluster's current unix timestamp, taken from the clock sysvar
+    pub fn now(&self) -> ClientResult<UnixTimestamp> {
+        let account = self.rpc.get_account(&sysvar::clock::id())?;
+        let clock: Clock = from_account(&account)
+            .ok_or_else(|| ClientError::InvalidAccountData(sysvar::clock::id()))?;
+        Ok(clock.unix_timestamp)
+    }
+
+    /// The token balance of a fund account. Funds that don't exist yet are empty.
+    pub fn fund_balance(&self, fund: &Pubkey) -> ClientResult<u64> {
+        match self
+            .rpc
+            .get_account_with_commitment(fund, self.rpc.commitment())?
+            .value
+        {
+            Some(account) => spl_token::state::Account::unpack(&account.data)
+                .map(|token| token.amount)
+                .map_err(|_| ClientError::InvalidAccountData(*fund)),
+            None => Ok(0),
+        }
+    }
+
+    /// Fetch a vested treasury and its fund to report its progress
+    pub fn vesting_progress(&self, key: &Pubkey) -> ClientResult<VestingProgress> {
+        let treasury = self.get_vested_treasury(key)?;
+        let fund = VestedTreasury::fund_address(key, &treasury.mint, &self.program_id);
+        let fund_balance = self.fund_balance(&fund)?;
+        let now = self.now()?;
+        Ok(VestingProgress::new(treasury, now, fund_balance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshSerialize;
+    use treasury::account::SimpleTreasuryMode;
+
+    fn vested() -> VestedTreasury {
+        VestedTreasury {
+            mint: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            initial_amount: 100_000,
+            start: 1_000,
+            vestment_period: 60,
+            vestment_percentage: 2_500, // 25%
+            withdrawn: 0,
+            grantor: Pubkey::new_unique(),
+            paused_at: 0,
+            accelerated: 0,
+            delegate: Pubkey::default(),
+        }
+    }
+
+    #[test]
+    pub fn test_decode() {
+        let simple = SimpleTreasury {
+            mint: Pubkey::new_unique(),
+            mode: SimpleTreasuryMode::Locked,
+            authority: Pubkey::new_unique(),
+            lock_end: 0,
+            weight: 0,
+            weight_updated_at: 0,
+        };
+        let vested = vested();
+
+        assert_eq!(
+            Treasury::decode(&simple.try_to_vec().unwrap()),
+            Some(Treasury::Simple(simple))
+        );
+        assert_eq!(
+            Treasury::decode(&vested.try_to_vec().unwrap()),
+            Some(Treasury::Vested(vested))
+        );
+        assert_eq!(Treasury::decode(&[0; 10]), None);
+
+        // earlier layouts read with the added fields zeroed
+        let data = simple.try_to_vec().unwrap();
+        assert_eq!(
+            Treasury::decode(&data[..SimpleTreasury::LEGACY_LENS[0]]),
+            Some(Treasury::Simple(SimpleTreasury {
+                lock_end: 0,
+                weight: 0,
+                weight_updated_at: 0,
+                ..simple
+            }))
+        );
+        let data = vested.try_to_vec().unwrap();
+        assert_eq!(
+            Treasury::decode(&data[..VestedTreasury::LEGACY_LENS[0]]),
+            Some(Treasury::Vested(VestedTreasury {
+                grantor: Pubkey::default(),
+                ..vested
+            }))
+        );
+    }
+
+    #[test]
+    pub fn test_vesting_progress() {
+        let vested = vested();
+
+        let progress = VestingProgress::new(vested, 0, 0);
+        assert_eq!(progress.next_vesting, Some(1_060));
+
+        let progress = VestingProgress::new(vested, 1_130, 100_000);
+        assert_eq!(progress.query.vested, 50_000);
+        assert_eq!(progress.next_vesting, Some(1_180));
+        assert_eq!(progress.vested_ratio(), 0.5);
+
+        let progress = VestingProgress::new(vested, 1_240, 100_000);
+        assert_eq!(progress.query.vested, 100_000);
+        assert_eq!(progress.next_vesting, None);
+    }
+
+    #[test]
+    pub fn test_filters() {
+        let key = Pubkey::new_unique();
+        let memcmp =
+            |offset| RpcFilterType::Memcmp(Memcmp::new_raw_bytes(offset, key.to_bytes().to_vec()));
+        assert_eq!(
+            TreasuryFilter::Authority(key).vested_filters(),
+            vec![
+                vec![
+                    RpcFilterType::DataSize(VestedTreasury::LEN as u64),
+                    memcmp(32)
+                ],
+                vec![RpcFilterType::DataSize(98), memcmp(32)],
+            ]
+        );
+        assert_eq!(
+            TreasuryFilter::Mint(key).simple_filters(),
+            vec![
+                vec![
+                    RpcFilterType::DataSize(SimpleTreasury::LEN as u64),
+                    memcmp(0)
+                ],
+                vec![RpcFilterType::DataSize(65), memcmp(0)],
+            ]
+        );
+    }
+}
@@ -5,22 +5,60 @@ import './extendBorsh';
 
 export enum SimpleTreasuryMode {
     Locked,
-    Unlocked
+    Unlocked,
+    VoteEscrow
 }
 
+/** The longest a vote escrow treasury can be locked for, in seconds */
+export const MAX_LOCK_DURATION = 4 * 365 * 24 * 60 * 60;
+
 export class SimpleTreasury {
     public mint: PublicKey;
     public mode: SimpleTreasuryMode;
     public authority: PublicKey;
+    /** When a vote escrow treasury unlocks, null if it was never locked */
+    public lockEnd: Date | null;
+    /** The vote escrow weight as of `weightUpdatedAt` */
+    public weight: BN;
+    public weightUpdatedAt: Date;
 
     constructor(params: {
         mint: PublicKey;
         mode: SimpleTreasuryMode;
         authority: PublicKey;
+        lockEnd: BN;
+        weight: BN;
+        weightUpdatedAt: BN;
     }) {
         this.mint = params.mint;
         this.mode = params.mode;
         this.authority = params.authority;
+        this.lockEnd = params.lockEnd.isZero()
+            ? null
+            : new Date(params.lockEnd.toNumber() * 1000);
+        this.weight = params.weight;
+        this.weightUpdatedAt = new Date(
+            params.weightUpdatedAt.toNumber() * 1000
+        );
+    }
+
+    /** The vote escrow weight of the fund at a specific time */
+    public voteEscrowWeight(now: Date, fundBalance: BN): BN {
+        if (
+            this.mode !== SimpleTreasuryMode.VoteEscrow ||
+            this.lockEnd === null
+        ) {
+            return new BN(0);
+        }
+        const remaining = Math.min(
+            Math.max(
+                Math.floor(this.lockEnd.getTime() / 1000) -
+                    Math.floor(now.getTime() / 1000),
+                0
+            ),
+            MAX_LOCK_DURATION
+        );
+        return fundBalance.muln(remaining).divn(MAX_LOCK_DURATION);
     }
 }
 
@@ -32,6 +70,13 @@ export class VestedTreasury {
     public vestmentPeriod: BN;
     public vestmentPercentage: number;
     public withdrawn: BN;
+    public grantor: PublicKey;
+    /** When vesting was paused, null while vesting runs */
+    public pausedAt: Date | null;
+    /** The amount the grantor vested ahead of the schedule */
+    public accelerated: BN;
+    /** The key that can withdraw on behalf of the authority, null if none */
+    public delegate: PublicKey | null;
 
     constructor(params: {
         mint: PublicKey;
@@ -41,6 +86,10 @@ export class VestedTreasury {
         vestmentPeriod: BN;
         vestmentPercentage: number;
         withdrawn: BN;
+        grantor: PublicKey;
+        pausedAt: BN;
+        accelerated: BN;
+        delegate: PublicKey;
     }) {
         this.mint = params.mint;
         this.authority = params.authority;
@@ -49,19 +98,33 @@ export class VestedTreasury {
         this.vestmentPeriod = params.vestmentPeriod;
         this.vestmentPercentage = params.vestmentPercentage;
         this.withdrawn = params.withdrawn;
+        this.grantor = params.grantor;
+        this.pausedAt = params.pausedAt.isZero()
+            ? null
+            : new Date(params.pausedAt.toNumber() * 1000);
+        this.accelerated = params.accelerated;
+        this.delegate = params.delegate.equals(PublicKey.default)
+            ? null
+            : params.delegate;
     }
 
     public maximum_available(now: Date): BN {
+        // nothing vests while paused
+        if (this.pausedAt !== null && this.pausedAt < now) {
+            now = this.pausedAt;
+        }
+
         const period =
             Math.floor(now.getTime() / 1000) -
             Math.floor(this.start.getTime() / 1000);
-        if (period <= 0) {
-            return new BN(0);
-        }
 
-        const ticks = new BN(period).div(this.vestmentPeriod);
+        const ticks =
+            period > 0 ? new BN(period).div(this.vestmentPeriod) : new BN(0);
         const percentage = this.vestmentPercentage / 10000;
-        const amount = this.initialAmount.muln(percentage).mul(ticks);
+        const amount = this.initialAmount
+            .muln(percentage)
+            .mul(ticks)
+            .add(this.accelerated);
         return amount.gt(this.initialAmount) ? this.initialAmount : amount;
     }
 
@@ -71,6 +134,337 @@ export class VestedTreasury {
     }
 }
 
+export class Shareholder {
+    public recipient: PublicKey;
+    /** The share of everything deposited, in basis points */
+    public share: number;
+    /** The amount the recipient has claimed so far */
+    public released: BN;
+
+    constructor(params: { recipient: PublicKey; share: number; released: BN }) {
+        this.recipient = params.recipient;
+        this.share = params.share;
+        this.released = params.released;
+    }
+}
+
+export class SplitterTreasury {
+    public mint: PublicKey;
+    public totalReleased: BN;
+    public shareholders: Shareholder[];
+
+    constructor(params: {
+        mint: PublicKey;
+        totalReleased: BN;
+        shareholders: Shareholder[];
+    }) {
+        this.mint = params.mint;
+        this.totalReleased = params.totalReleased;
+        // unused slots have no share
+        this.shareholders = params.shareholders.filter(
+            (holder) => holder.share > 0
+        );
+    }
+
+    /** The amount a recipient can claim, null if it isn't a shareholder */
+    public claimable(recipient: PublicKey, fundBalance: BN): BN | null {
+        const holder = this.shareholders.find((holder) =>
+            holder.recipient.equals(recipient)
+        );
+        if (holder === undefined) {
+            return null;
+        }
+        const entitled = fundBalance
+            .add(this.totalReleased)
+            .muln(holder.share)
+            .divn(10000);
+        const claimable = entitled.sub(holder.released);
+        return claimable.ltn(0) ? new BN(0) : claimable;
+    }
+}
+
+export class EscrowTreasury {
+    public mint: PublicKey;
+    public depositor: PublicKey;
+    public payee: PublicKey;
+    public arbiter: PublicKey;
+    /** After this time the fund can only be refunded */
+    public expiry: Date;
+
+    constructor(params: {
+        mint: PublicKey;
+        depositor: PublicKey;
+        payee: PublicKey;
+        arbiter: PublicKey;
+        expiry: BN;
+    }) {
+        this.mint = params.mint;
+        this.depositor = params.depositor;
+        this.payee = params.payee;
+        this.arbiter = params.arbiter;
+        this.expiry = new Date(params.expiry.toNumber() * 1000);
+    }
+
+    public isExpired(now: Date): boolean {
+        return now >= this.expiry;
+    }
+}
+
+export class Milestone {
+    public amount: BN;
+    public attestor: PublicKey;
+    public confirmed: boolean;
+
+    constructor(params: {
+        amount: BN;
+        attestor: PublicKey;
+        confirmed: number;
+    }) {
+        this.amount = params.amount;
+        this.attestor = params.attestor;
+        this.confirmed = params.confirmed !== 0;
+    }
+}
+
+export class MilestoneTreasury {
+    public mint: PublicKey;
+    public authority: PublicKey;
+    public withdrawn: BN;
+    public milestones: Milestone[];
+
+    constructor(params: {
+        mint: PublicKey;
+        authority: PublicKey;
+        withdrawn: BN;
+        milestones: Milestone[];
+    }) {
+        this.mint = params.mint;
+        this.authority = params.authority;
+        this.withdrawn = params.withdrawn;
+        // unused slots have no amount
+        this.milestones = params.milestones.filter(
+            (milestone) => !milestone.amount.isZero()
+        );
+    }
+
+    /** The message an attestor signs off-chain to confirm a milestone */
+    public static attestation(treasury: PublicKey, index: number): Buffer {
+        return Buffer.concat([
+            Buffer.from('confirm milestone'),
+            treasury.toBuffer(),
+            Buffer.from([index])
+        ]);
+    }
+
+    /** The total amount of the confirmed milestones */
+    public unlocked(): BN {
+        return this.milestones
+            .filter((milestone) => milestone.confirmed)
+            .reduce(
+                (total, milestone) => total.add(milestone.amount),
+                new BN(0)
+            );
+    }
+
+    public available(fundBalance: BN): BN {
+        return BN.min(this.unlocked().sub(this.withdrawn), fundBalance);
+    }
+}
+
+export class Tranche {
+    public amount: BN;
+    /** The target price, in the unit of the price feed */
+    public price: BN;
+    /** Since when the price is at or above the target, null while it's below */
+    public metSince: Date | null;
+    public unlocked: boolean;
+
+    constructor(params: {
+        amount: BN;
+        price: BN;
+        metSince: BN;
+        unlocked: number;
+    }) {
+        this.amount = params.amount;
+        this.price = params.price;
+        this.metSince = params.metSince.isZero()
+            ? null
+            : new Date(params.metSince.toNumber() * 1000);
+        this.unlocked = params.unlocked !== 0;
+    }
+}
+
+export class HurdleTreasury {
+    public mint: PublicKey;
+    public authority: PublicKey;
+    /** The price feed the tranches are measured against */
+    public oracle: PublicKey;
+    /** How long a target has to be met, in seconds */
+    public duration: BN;
+    public withdrawn: BN;
+    public tranches: Tranche[];
+
+    constructor(params: {
+        mint: PublicKey;
+        authority: PublicKey;
+        oracle: PublicKey;
+        duration: BN;
+        withdrawn: BN;
+        tranches: Tranche[];
+    }) {
+        this.mint = params.mint;
+        this.authority = params.authority;
+        this.oracle = params.oracle;
+        this.duration = params.duration;
+        this.withdrawn = params.withdrawn;
+        // unused slots have no amount
+        this.tranches = params.tranches.filter(
+            (tranche) => !tranche.amount.isZero()
+        );
+    }
+
+    /** The total amount of the unlocked tranches */
+    public unlocked(): BN {
+        return this.tranches
+            .filter((tranche) => tranche.unlocked)
+            .reduce((total, tranche) => total.add(tranche.amount), new BN(0));
+    }
+
+    public available(fundBalance: BN): BN {
+        return BN.min(this.unlocked().sub(this.withdrawn), fundBalance);
+    }
+}
+
+export class Config {
+    public admin: PublicKey;
+    public feeReceiver: PublicKey;
+    /** Lamports charged for every new treasury */
+    public creationFee: BN;
+    /** The share of every withdrawal taken as a fee, in basis points */
+    public withdrawalFee: number;
+    /** The key that can pause the program */
+    public guardian: PublicKey;
+    public paused: boolean;
+
+    constructor(params: {
+        admin: PublicKey;
+        feeReceiver: PublicKey;
+        creationFee: BN;
+        withdrawalFee: number;
+        guardian: PublicKey;
+        paused: number;
+    }) {
+        this.admin = params.admin;
+        this.feeReceiver = params.feeReceiver;
+        this.creationFee = params.creationFee;
+        this.withdrawalFee = params.withdrawalFee;
+        this.guardian = params.guardian;
+        this.paused = params.paused !== 0;
+    }
+
+    /** The fee taken out of a withdrawal of `amount` */
+    public feeOn(amount: BN): BN {
+        return amount.muln(this.withdrawalFee).divn(10000);
+    }
+}
+
+export class Recovery {
+    public treasury: PublicKey;
+    /** The key that can claim the treasury once the authority is inactive */
+    public backup: PublicKey;
+    /** How long the authority has to be inactive, in seconds */
+    public inactivityPeriod: BN;
+    public lastActive: Date;
+
+    constructor(params: {
+        treasury: PublicKey;
+        backup: PublicKey;
+        inactivityPeriod: BN;
+        lastActive: BN;
+    }) {
+        this.treasury = params.treasury;
+        this.backup = params.backup;
+        this.inactivityPeriod = params.inactivityPeriod;
+        this.lastActive = new Date(params.lastActive.toNumber() * 1000);
+    }
+
+    /** When the backup can claim the treasury */
+    public claimableAt(): Date {
+        return new Date(
+            this.lastActive.getTime() + this.inactivityPeriod.toNumber() * 1000
+        );
+    }
+}
+
+export class SocialRecovery {
+    public treasury: PublicKey;
+    /** Unused slots hold the default key */
+    public guardians: PublicKey[];
+    /** How many guardians have to approve a rotation */
+    public threshold: number;
+    /** How long the authority has to cancel a rotation, in seconds */
+    public challengeDelay: BN;
+    /** The authority guardian `n` approved, the default key if none */
+    public approvals: PublicKey[];
+    /**
+     * The authority the guardians are rotating to, the default key until
+     * enough guardians approved it
+     */
+    public newAuthority: PublicKey;
+    /** When the rotation can be executed, null until the threshold is met */
+    public effectiveAt: Date | null;
+
+    constructor(params: {
+        treasury: PublicKey;
+        guardians: PublicKey[];
+        threshold: number;
+        challengeDelay: BN;
+        approvals: PublicKey[];
+        newAuthority: PublicKey;
+        effectiveAt: BN;
+    }) {
+        this.treasury = params.treasury;
+        this.guardians = params.guardians;
+        this.threshold = params.threshold;
+        this.challengeDelay = params.challengeDelay;
+        this.approvals = params.approvals;
+        this.newAuthority = params.newAuthority;
+        this.effectiveAt = params.effectiveAt.isZero()
+            ? null
+            : new Date(params.effectiveAt.toNumber() * 1000);
+    }
+
+    /** The authority a guardian approved, null if none */
+    public approvalOf(guardian: PublicKey): PublicKey | null {
+        const index = this.guardians.findIndex(
+            (key) => !key.equals(PublicKey.default) && key.equals(guardian)
+        );
+        if (index < 0 || this.approvals[index].equals(PublicKey.default)) {
+            return null;
+        }
+        return this.approvals[index];
+    }
+}
+
+export class VestedQuery {
+    public claimable: BN;
+    public vested: BN;
+    public unvested: BN;
+    public locked: BN;
+
+    constructor(params: {
+        claimable: BN;
+        vested: BN;
+        unvested: BN;
+        locked: BN;
+    }) {
+        this.claimable = params.claimable;
+        this.vested = params.vested;
+        this.unvested = params.unvested;
+        this.locked = params.locked;
+    }
+}
+
 export const ACCOUNT_SCHEMA: borsh.Schema = new Map<any, any>([
     [
         SimpleTreasury,
@@ -79,7 +473,10 @@ export const ACCOUNT_SCHEMA: borsh.Schema = new Map<any, any>([
             fields: [
                 ['mint', 'PublicKey'],
                 ['mode', 'SimpleTreasuryMode'],
-                ['authority', 'PublicKey']
+                ['authority', 'PublicKey'],
+                ['lockEnd', 'u64'],
+                ['weight', 'u64'],
+                ['weightUpdatedAt', 'u64']
             ]
         }
     ],
@@ -94,7 +491,148 @@ export const ACCOUNT_SCHEMA: borsh.Schema = new Map<any, any>([
                 ['start', 'u64'],
                 ['vestmentPeriod', 'u64'],
                 ['vestmentPercentage', 'u16'],
-                ['withdrawn', 'u64']
+                ['withdrawn', 'u64'],
+                ['grantor', 'PublicKey'],
+                ['pausedAt', 'u64'],
+                ['accelerated', 'u64'],
+                ['delegate', 'PublicKey']
+            ]
+        }
+    ],
+    [
+        Shareholder,
+        {
+            kind: 'struct',
+            fields: [
+                ['recipient', 'PublicKey'],
+                ['share', 'u16'],
+                ['released', 'u64']
+            ]
+        }
+    ],
+    [
+        SplitterTreasury,
+        {
+            kind: 'struct',
+            fields: [
+                ['mint', 'PublicKey'],
+                ['totalReleased', 'u64'],
+                ['shareholders', [Shareholder, 10]]
+            ]
+        }
+    ],
+    [
+        EscrowTreasury,
+        {
+            kind: 'struct',
+            fields: [
+                ['mint', 'PublicKey'],
+                ['depositor', 'PublicKey'],
+                ['payee', 'PublicKey'],
+                ['arbiter', 'PublicKey'],
+                ['expiry', 'u64']
+            ]
+        }
+    ],
+    [
+        Milestone,
+        {
+            kind: 'struct',
+            fields: [
+                ['amount', 'u64'],
+                ['attestor', 'PublicKey'],
+                ['confirmed', 'u8']
+            ]
+        }
+    ],
+    [
+        MilestoneTreasury,
+        {
+            kind: 'struct',
+            fields: [
+                ['mint', 'PublicKey'],
+                ['authority', 'PublicKey'],
+                ['withdrawn', 'u64'],
+                ['milestones', [Milestone, 8]]
+            ]
+        }
+    ],
+    [
+        Tranche,
+        {
+            kind: 'struct',
+            fields: [
+                ['amount', 'u64'],
+                ['price', 'u64'],
+                ['metSince', 'u64'],
+                ['unlocked', 'u8']
+            ]
+        }
+    ],
+    [
+        HurdleTreasury,
+        {
+            kind: 'struct',
+            fields: [
+                ['mint', 'PublicKey'],
+                ['authority', 'PublicKey'],
+                ['oracle', 'PublicKey'],
+                ['duration', 'u64'],
+                ['withdrawn', 'u64'],
+                ['tranches', [Tranche, 8]]
+            ]
+        }
+    ],
+    [
+        Config,
+        {
+            kind: 'struct',
+            fields: [
+                ['admin', 'PublicKey'],
+                ['feeReceiver', 'PublicKey'],
+                ['creationFee', 'u64'],
+                ['withdrawalFee', 'u16'],
+                ['guardian', 'PublicKey'],
+                ['paused', 'u8']
+            ]
+        }
+    ],
+    [
+        SocialRecovery,
+        {
+            kind: 'struct',
+            fields: [
+                ['treasury', 'PublicKey'],
+                ['guardians', ['PublicKey', 8]],
+                ['threshold', 'u8'],
+                ['challengeDelay', 'u64'],
+                ['approvals', ['PublicKey', 8]],
+                ['newAuthority', 'PublicKey'],
+                ['effectiveAt', 'u64']
+            ]
+        }
+    ],
+    [
+        Recovery,
+        {
+            kind: 'struct',
+            fields: [
+                ['treasury', 'PublicKey'],
+                ['backup', 'PublicKey'],
+                ['inactivityPeriod', 'u64'],
+                ['lastActive', 'u64']
+            ]
+        }
+    ],
+    [
+        VestedQuery,
+        {
+            kind: 'struct',
+            fields: [
+                ['claimable', 'u64'],
+                ['vested', 'u64'],
+                ['unvested', 'u64'],
+                ['locked', 'u64']
             ]
         }
     ]
@@ -34,6 +34,8 @@ BinaryReader.prototype.readSimpleTreasuryMode = function () {
             return SimpleTreasuryMode.Locked;
         case SimpleTreasuryMode.Unlocked:
             return SimpleTreasuryMode.Unlocked;
+        case SimpleTreasuryMode.VoteEscrow:
+            return SimpleTreasuryMode.VoteEscrow;
         default:
             throw new Error('invalid simple treasury mode');
     }
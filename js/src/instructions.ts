@@ -1,8 +1,10 @@
 import {
     AccountMeta,
+    Ed25519Program,
     PublicKey,
     SystemProgram,
     SYSVAR_CLOCK_PUBKEY,
+    SYSVAR_INSTRUCTIONS_PUBKEY,
     SYSVAR_RENT_PUBKEY,
     TransactionInstruction
 } from '@solana/web3.js';
@@ -13,13 +15,59 @@ import {
     Token,
     TOKEN_PROGRAM_ID
 } from '@solana/spl-token';
-import { SimpleTreasuryMode } from './accounts';
+import { MilestoneTreasury, SimpleTreasuryMode } from './accounts';
+
+const BPF_LOADER_UPGRADEABLE_PROGRAM_ID = new PublicKey(
+    'BPFLoaderUpgradeab1e11111111111111111111111'
+);
 
 export enum TreasuryInstructions {
     CreateSimpleTreasury,
     WithdrawSimple,
     CreatedVestedTreasury,
-    WithdrawVested
+    WithdrawVested,
+    QueryVested,
+    PauseVesting,
+    ResumeVesting,
+    AccelerateVesting,
+    SplitVested,
+    MergeVested,
+    SetVestedDelegate,
+    UpdateVoterWeightRecord,
+    UpdateMaxVoterWeightRecord,
+    ExtendLock,
+    UpdateVoteEscrowWeight,
+    CreateSplitterTreasury,
+    ClaimSplit,
+    CreateEscrowTreasury,
+    ReleaseEscrow,
+    RefundEscrow,
+    RefundExpiredEscrow,
+    CreateMilestoneTreasury,
+    ConfirmMilestone,
+    ConfirmMilestoneWithSignature,
+    WithdrawMilestones,
+    CreateHurdleTreasury,
+    UpdateHurdles,
+    WithdrawHurdles,
+    InitializeConfig,
+    UpdateConfig,
+    CollectFees,
+    PauseProgram,
+    UnpauseProgram,
+    SetRecovery,
+    Heartbeat,
+    ClaimRecovery,
+    SetGuardians,
+    ApproveRotation,
+    CancelRotation,
+    ExecuteRotation,
+    MigrateTreasury
+}
+
+export enum AccelerationKind {
+    Amount,
+    Percentage
 }
 
 export class BasicSchema {
@@ -50,6 +98,16 @@ export class SimpleWithdrawSchema {
     }
 }
 
+export class VestedWithdrawSchema {
+    instructionId: number;
+    amount: bigint | null;
+
+    constructor(params: { instructionId: number; amount: bigint | null }) {
+        this.instructionId = params.instructionId;
+        this.amount = params.amount;
+    }
+}
+
 export class VestedSchema {
     instructionId: number;
     amount: bigint;
@@ -69,6 +127,244 @@ export class VestedSchema {
     }
 }
 
+export class AccelerateAmountSchema {
+    instructionId: number;
+    kind: AccelerationKind;
+    amount: bigint;
+
+    constructor(params: { instructionId: number; amount: bigint }) {
+        this.instructionId = params.instructionId;
+        this.kind = AccelerationKind.Amount;
+        this.amount = params.amount;
+    }
+}
+
+export class AcceleratePercentageSchema {
+    instructionId: number;
+    kind: AccelerationKind;
+    percentage: number;
+
+    constructor(params: { instructionId: number; percentage: number }) {
+        this.instructionId = params.instructionId;
+        this.kind = AccelerationKind.Percentage;
+        this.percentage = params.percentage;
+    }
+}
+
+export class SplitVestedSchema {
+    instructionId: number;
+    amount: bigint;
+
+    constructor(params: { instructionId: number; amount: bigint }) {
+        this.instructionId = params.instructionId;
+        this.amount = params.amount;
+    }
+}
+
+export class ExtendLockSchema {
+    instructionId: number;
+    lockEnd: bigint;
+
+    constructor(params: { instructionId: number; lockEnd: bigint }) {
+        this.instructionId = params.instructionId;
+        this.lockEnd = params.lockEnd;
+    }
+}
+
+export class SetVestedDelegateSchema {
+    instructionId: number;
+    delegate: PublicKey | null;
+
+    constructor(params: {
+        instructionId: number;
+        delegate: PublicKey | null;
+    }) {
+        this.instructionId = params.instructionId;
+        this.delegate = params.delegate;
+    }
+}
+
+export class ShareSchema {
+    recipient: PublicKey;
+    share: number;
+
+    constructor(params: { recipient: PublicKey; share: number }) {
+        this.recipient = params.recipient;
+        this.share = params.share;
+    }
+}
+
+export class SplitterSchema {
+    instructionId: number;
+    shares: ShareSchema[];
+
+    constructor(params: { instructionId: number; shares: ShareSchema[] }) {
+        this.instructionId = params.instructionId;
+        this.shares = params.shares;
+    }
+}
+
+export class EscrowSchema {
+    instructionId: number;
+    expiry: bigint;
+
+    constructor(params: { instructionId: number; expiry: bigint }) {
+        this.instructionId = params.instructionId;
+        this.expiry = params.expiry;
+    }
+}
+
+export class MilestoneSchema {
+    amount: bigint;
+    attestor: PublicKey;
+
+    constructor(params: { amount: bigint; attestor: PublicKey }) {
+        this.amount = params.amount;
+        this.attestor = params.attestor;
+    }
+}
+
+export class MilestonesSchema {
+    instructionId: number;
+    milestones: MilestoneSchema[];
+
+    constructor(params: {
+        instructionId: number;
+        milestones: MilestoneSchema[];
+    }) {
+        this.instructionId = params.instructionId;
+        this.milestones = params.milestones;
+    }
+}
+
+export class MilestoneIndexSchema {
+    instructionId: number;
+    index: number;
+
+    constructor(params: { instructionId: number; index: number }) {
+        this.instructionId = params.instructionId;
+        this.index = params.index;
+    }
+}
+
+export class TrancheSchema {
+    amount: bigint;
+    price: bigint;
+
+    constructor(params: { amount: bigint; price: bigint }) {
+        this.amount = params.amount;
+        this.price = params.price;
+    }
+}
+
+export class HurdlesSchema {
+    instructionId: number;
+    duration: bigint;
+    tranches: TrancheSchema[];
+
+    constructor(params: {
+        instructionId: number;
+        duration: bigint;
+        tranches: TrancheSchema[];
+    }) {
+        this.instructionId = params.instructionId;
+        this.duration = params.duration;
+        this.tranches = params.tranches;
+    }
+}
+
+export class ConfigSchema {
+    instructionId: number;
+    feeReceiver: PublicKey;
+    creationFee: bigint;
+    withdrawalFee: number;
+    guardian: PublicKey;
+
+    constructor(params: {
+        instructionId: number;
+        feeReceiver: PublicKey;
+        creationFee: bigint;
+        withdrawalFee: number;
+        guardian: PublicKey;
+    }) {
+        this.instructionId = params.instructionId;
+        this.feeReceiver = params.feeReceiver;
+        this.creationFee = params.creationFee;
+        this.withdrawalFee = params.withdrawalFee;
+        this.guardian = params.guardian;
+    }
+}
+
+export class UpdateConfigSchema {
+    instructionId: number;
+    admin: PublicKey;
+    feeReceiver: PublicKey;
+    creationFee: bigint;
+    withdrawalFee: number;
+    guardian: PublicKey;
+
+    constructor(params: {
+        instructionId: number;
+        admin: PublicKey;
+        feeReceiver: PublicKey;
+        creationFee: bigint;
+        withdrawalFee: number;
+        guardian: PublicKey;
+    }) {
+        this.instructionId = params.instructionId;
+        this.admin = params.admin;
+        this.feeReceiver = params.feeReceiver;
+        this.creationFee = params.creationFee;
+        this.withdrawalFee = params.withdrawalFee;
+        this.guardian = params.guardian;
+    }
+}
+
+export class RecoverySchema {
+    instructionId: number;
+    backup: PublicKey;
+    inactivityPeriod: bigint;
+
+    constructor(params: {
+        instructionId: number;
+        backup: PublicKey;
+        inactivityPeriod: bigint;
+    }) {
+        this.instructionId = params.instructionId;
+        this.backup = params.backup;
+        this.inactivityPeriod = params.inactivityPeriod;
+    }
+}
+
+export class GuardiansSchema {
+    instructionId: number;
+    guardians: PublicKey[];
+    threshold: number;
+    challengeDelay: bigint;
+
+    constructor(params: {
+        instructionId: number;
+        guardians: PublicKey[];
+        threshold: number;
+        challengeDelay: bigint;
+    }) {
+        this.instructionId = params.instructionId;
+        this.guardians = params.guardians;
+        this.threshold = params.threshold;
+        this.challengeDelay = params.challengeDelay;
+    }
+}
+
+export class RotationSchema {
+    instructionId: number;
+    newAuthority: PublicKey;
+
+    constructor(params: { instructionId: number; newAuthority: PublicKey }) {
+        this.instructionId = params.instructionId;
+        this.newAuthority = params.newAuthority;
+    }
+}
+
 export class TreasuryInstruction {
     private static async CreateSimpleTreasury(
         programId: PublicKey,
@@ -80,12 +376,13 @@ export class TreasuryInstruction {
     ): Promise<TransactionInstruction> {
         const keys: AccountMeta[] = [
             am(funder, true, true),
-            am(authority, false, false),
+            am(authority, true, false),
             am(treasury, true, true),
             am(mint, false, false),
             am(SYSVAR_RENT_PUBKEY, false, false),
             am(TOKEN_PROGRAM_ID, false, false),
-            am(SystemProgram.programId, false, false)
+            am(SystemProgram.programId, false, false),
+            am(await Treasury.configAddress(programId), false, true)
         ];
 
         const instruction = new SimpleSchema({
@@ -155,17 +452,20 @@ export class TreasuryInstruction {
 
         const keys: AccountMeta[] = [
             am(funder, true, true),
-            am(authority, false, false),
+            am(authority, true, false),
             am(associated, false, true),
             am(treasury, false, false),
             am(fund.authority, false, false),
             am(fund.fund, false, true),
-            am(mint, false, false),
-            am(TOKEN_PROGRAM_ID, false, false)
+            am(TOKEN_PROGRAM_ID, false, false),
+            am(SYSVAR_CLOCK_PUBKEY, false, false),
+            am(await Treasury.configAddress(programId), false, false),
+            am(await Treasury.feeVaultAddress(mint, programId), false, true),
+            am(await Treasury.recoveryAddress(treasury, programId), false, true)
         ];
 
         const instruction = new SimpleWithdrawSchema({
-            instructionId: TreasuryInstructions.CreateSimpleTreasury,
+            instructionId: TreasuryInstructions.WithdrawSimple,
             amount
         });
         const instructionData = borsh.serialize(
@@ -180,16 +480,17 @@ export class TreasuryInstruction {
         });
     }
 
-    private static CreateVestedTreasury(
+    private static async CreateVestedTreasury(
         programId: PublicKey,
         mint: PublicKey,
         funder: PublicKey,
         treasury: PublicKey,
         authority: PublicKey,
+        grantor: PublicKey,
         amount: bigint,
         period: bigint,
         percentage: number
-    ): TransactionInstruction {
+    ): Promise<TransactionInstruction> {
         const keys: AccountMeta[] = [
             am(funder, true, true),
             am(authority, false, false),
@@ -197,7 +498,9 @@ export class TreasuryInstruction {
             am(mint, false, false),
             am(SYSVAR_RENT_PUBKEY, false, false),
             am(SYSVAR_CLOCK_PUBKEY, false, false),
-            am(SystemProgram.programId, false, false)
+            am(SystemProgram.programId, false, false),
+            am(grantor, false, false),
+            am(await Treasury.configAddress(programId), false, true)
         ];
 
         const instruction = new VestedSchema({
@@ -224,6 +527,7 @@ export class TreasuryInstruction {
         funder: PublicKey,
         treasury: PublicKey,
         authority: PublicKey,
+        grantor: PublicKey,
         amount: bigint,
         period: bigint,
         percentage: number
@@ -243,12 +547,13 @@ export class TreasuryInstruction {
                 fundAssoc.authority,
                 funder
             ),
-            TreasuryInstruction.CreateVestedTreasury(
+            await TreasuryInstruction.CreateVestedTreasury(
                 programId,
                 mint,
                 funder,
                 treasury,
                 authority,
+                grantor,
                 amount,
                 period,
                 percentage
@@ -256,12 +561,19 @@ export class TreasuryInstruction {
         ];
     }
 
+    /**
+     * The claimer is the authority or its delegate, funds always go to the
+     * authority's associated token account. Withdraws everything available
+     * unless an amount is given.
+     */
     public static async WithdrawVested(
         programId: PublicKey,
         funder: PublicKey,
         treasury: PublicKey,
         authority: PublicKey,
-        mint: PublicKey
+        mint: PublicKey,
+        claimer: PublicKey = authority,
+        amount: bigint | null = null
     ): Promise<TransactionInstruction> {
         const fundAssoc = await Treasury.vestedTreasuryAssociatedAccount(
             treasury,
@@ -277,18 +589,59 @@ export class TreasuryInstruction {
 
         const keys: AccountMeta[] = [
             am(funder, true, true),
-            am(authority, true, false),
+            am(claimer, true, false),
             am(recipient, false, true),
             am(treasury, false, true),
             am(fundAssoc.authority, false, false),
             am(fundAssoc.fund, false, true),
             am(SYSVAR_CLOCK_PUBKEY, false, false),
             am(TOKEN_PROGRAM_ID, false, false),
-            am(SystemProgram.programId, false, false)
+            am(SystemProgram.programId, false, false),
+            am(await Treasury.configAddress(programId), false, false),
+            am(await Treasury.feeVaultAddress(mint, programId), false, true),
+            am(await Treasury.recoveryAddress(treasury, programId), false, true)
+        ];
+
+        const instruction = new VestedWithdrawSchema({
+            instructionId: TreasuryInstructions.WithdrawVested,
+            amount
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Read-only instruction to be used with `simulateTransaction`. The
+     * program returns a borsh serialized `VestedQuery` as return data.
+     */
+    public static async QueryVested(
+        programId: PublicKey,
+        treasury: PublicKey,
+        mint: PublicKey
+    ): Promise<TransactionInstruction> {
+        const fundAssoc = await Treasury.vestedTreasuryAssociatedAccount(
+            treasury,
+            mint,
+            programId
+        );
+
+        const keys: AccountMeta[] = [
+            am(treasury, false, false),
+            am(fundAssoc.authority, false, false),
+            am(fundAssoc.fund, false, false),
+            am(SYSVAR_CLOCK_PUBKEY, false, false)
         ];
 
         const instruction = new BasicSchema({
-            instructionId: TreasuryInstructions.WithdrawVested
+            instructionId: TreasuryInstructions.QueryVested
         });
         const instructionData = borsh.serialize(
             INSTRUCTION_SCHEMA,
@@ -301,53 +654,1589 @@ export class TreasuryInstruction {
             data: Buffer.from(instructionData)
         });
     }
-}
 
-function am(
-    pubkey: PublicKey,
-    isSigner: boolean,
-    isWritable: boolean
-): AccountMeta {
-    return { pubkey, isSigner, isWritable };
-}
+    public static PauseVesting(
+        programId: PublicKey,
+        grantor: PublicKey,
+        treasury: PublicKey
+    ): TransactionInstruction {
+        return TreasuryInstruction.GrantorInstruction(
+            programId,
+            grantor,
+            treasury,
+            TreasuryInstructions.PauseVesting
+        );
+    }
 
-export const INSTRUCTION_SCHEMA: borsh.Schema = new Map<any, any>([
-    [
-        BasicSchema,
-        {
-            kind: 'struct',
-            fields: [['instructionId', 'u8']]
-        }
-    ],
-    [
-        SimpleSchema,
-        {
-            kind: 'struct',
-            fields: [
-                ['instructionId', 'u8'],
-                ['mode', 'SimpleTreasuryMode']
-            ]
-        }
-    ],
-    [
-        SimpleWithdrawSchema,
-        {
-            kind: 'struct',
-            fields: [
-                ['instructionId', 'u8'],
-                ['amount', 'BigInt']
-            ]
-        }
-    ],
-    [
-        VestedSchema,
-        {
-            kind: 'struct',
-            fields: [
-                ['instructionId', 'u8'],
-                ['amount', 'BigInt'],
-                ['period', 'BigInt'],
-                ['percentage', 'u16']
+    public static ResumeVesting(
+        programId: PublicKey,
+        grantor: PublicKey,
+        treasury: PublicKey
+    ): TransactionInstruction {
+        return TreasuryInstruction.GrantorInstruction(
+            programId,
+            grantor,
+            treasury,
+            TreasuryInstructions.ResumeVesting
+        );
+    }
+
+    /**
+     * Vest a fixed amount, or a share of the unvested amount in basis
+     * points, immediately.
+     */
+    public static AccelerateVesting(
+        programId: PublicKey,
+        grantor: PublicKey,
+        treasury: PublicKey,
+        acceleration: { amount: bigint } | { percentage: number }
+    ): TransactionInstruction {
+        const instructionId = TreasuryInstructions.AccelerateVesting;
+        const instruction =
+            'amount' in acceleration
+                ? new AccelerateAmountSchema({
+                      instructionId,
+                      amount: acceleration.amount
+                  })
+                : new AcceleratePercentageSchema({
+                      instructionId,
+                      percentage: acceleration.percentage
+                  });
+
+        return TreasuryInstruction.GrantorInstruction(
+            programId,
+            grantor,
+            treasury,
+            instruction
+        );
+    }
+
+    /**
+     * Move `amount` of a vested treasury's initial amount, along with the
+     * same share of its withdrawn amount and fund, into a new treasury. The
+     * new treasury's fund has to exist.
+     */
+    public static async SplitVested(
+        programId: PublicKey,
+        funder: PublicKey,
+        authority: PublicKey,
+        treasury: PublicKey,
+        newTreasury: PublicKey,
+        newAuthority: PublicKey,
+        mint: PublicKey,
+        amount: bigint
+    ): Promise<TransactionInstruction> {
+        const fundAssoc = await Treasury.vestedTreasuryAssociatedAccount(
+            treasury,
+            mint,
+            programId
+        );
+        const newFundAssoc = await Treasury.vestedTreasuryAssociatedAccount(
+            newTreasury,
+            mint,
+            programId
+        );
+
+        const keys: AccountMeta[] = [
+            am(funder, true, true),
+            am(authority, true, false),
+            am(treasury, false, true),
+            am(fundAssoc.authority, false, false),
+            am(fundAssoc.fund, false, true),
+            am(newTreasury, true, true),
+            am(newAuthority, false, false),
+            am(newFundAssoc.authority, false, false),
+            am(newFundAssoc.fund, false, true),
+            am(SYSVAR_RENT_PUBKEY, false, false),
+            am(TOKEN_PROGRAM_ID, false, false),
+            am(SystemProgram.programId, false, false),
+            am(await Treasury.configAddress(programId), false, false),
+            am(SYSVAR_CLOCK_PUBKEY, false, false)
+        ];
+
+        const instruction = new SplitVestedSchema({
+            instructionId: TreasuryInstructions.SplitVested,
+            amount
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Merge `source` into `treasury`. Both need the same authority, mint,
+     * grantor, schedule and pause state. The source treasury and its fund are
+     * closed and their SOL goes to `recipient`.
+     */
+    public static async MergeVested(
+        programId: PublicKey,
+        authority: PublicKey,
+        treasury: PublicKey,
+        source: PublicKey,
+        recipient: PublicKey,
+        mint: PublicKey
+    ): Promise<TransactionInstruction> {
+        const fundAssoc = await Treasury.vestedTreasuryAssociatedAccount(
+            treasury,
+            mint,
+            programId
+        );
+        const sourceFundAssoc = await Treasury.vestedTreasuryAssociatedAccount(
+            source,
+            mint,
+            programId
+        );
+
+        const keys: AccountMeta[] = [
+            am(authority, true, false),
+            am(treasury, false, true),
+            am(fundAssoc.authority, false, false),
+            am(fundAssoc.fund, false, true),
+            am(source, false, true),
+            am(sourceFundAssoc.authority, false, false),
+            am(sourceFundAssoc.fund, false, true),
+            am(recipient, false, true),
+            am(TOKEN_PROGRAM_ID, false, false),
+            am(await Treasury.configAddress(programId), false, false),
+            am(await Treasury.recoveryAddress(source, programId), false, true),
+            am(
+                await Treasury.socialRecoveryAddress(source, programId),
+                false,
+                true
+            )
+        ];
+
+        const instruction = new BasicSchema({
+            instructionId: TreasuryInstructions.MergeVested
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Let a delegate withdraw on behalf of the authority, `null` revokes the
+     * delegate.
+     */
+    public static SetVestedDelegate(
+        programId: PublicKey,
+        authority: PublicKey,
+        treasury: PublicKey,
+        delegate: PublicKey | null
+    ): TransactionInstruction {
+        const keys: AccountMeta[] = [
+            am(authority, true, false),
+            am(treasury, false, true)
+        ];
+
+        const instruction = new SetVestedDelegateSchema({
+            instructionId: TreasuryInstructions.SetVestedDelegate,
+            delegate
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Update the voter weight record of `owner` from the treasuries passed.
+     * The weight expires at the end of the slot, so send it in the same
+     * transaction as the governance instruction that reads it.
+     */
+    public static async UpdateVoterWeightRecord(
+        programId: PublicKey,
+        payer: PublicKey,
+        realm: PublicKey,
+        mint: PublicKey,
+        owner: PublicKey,
+        treasuries: { treasury: PublicKey; fund: PublicKey }[]
+    ): Promise<TransactionInstruction> {
+        const [record] = await PublicKey.findProgramAddress(
+            [
+                Buffer.from('voter-weight-record'),
+                realm.toBuffer(),
+                mint.toBuffer(),
+                owner.toBuffer()
+            ],
+            programId
+        );
+
+        const keys: AccountMeta[] = [
+            am(payer, true, true),
+            am(realm, false, false),
+            am(mint, false, false),
+            am(owner, false, false),
+            am(record, false, true),
+            am(SYSVAR_CLOCK_PUBKEY, false, false),
+            am(SYSVAR_RENT_PUBKEY, false, false),
+            am(SystemProgram.programId, false, false)
+        ];
+        for (const { treasury, fund } of treasuries) {
+            keys.push(am(treasury, false, false), am(fund, false, false));
+        }
+
+        const instruction = new BasicSchema({
+            instructionId: TreasuryInstructions.UpdateVoterWeightRecord
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** Update the max voter weight record of a realm to the mint supply */
+    public static async UpdateMaxVoterWeightRecord(
+        programId: PublicKey,
+        payer: PublicKey,
+        realm: PublicKey,
+        mint: PublicKey
+    ): Promise<TransactionInstruction> {
+        const [record] = await PublicKey.findProgramAddress(
+            [
+                Buffer.from('max-voter-weight-record'),
+                realm.toBuffer(),
+                mint.toBuffer()
+            ],
+            programId
+        );
+
+        const keys: AccountMeta[] = [
+            am(payer, true, true),
+            am(realm, false, false),
+            am(mint, false, false),
+            am(record, false, true),
+            am(SYSVAR_CLOCK_PUBKEY, false, false),
+            am(SYSVAR_RENT_PUBKEY, false, false),
+            am(SystemProgram.programId, false, false)
+        ];
+
+        const instruction = new BasicSchema({
+            instructionId: TreasuryInstructions.UpdateMaxVoterWeightRecord
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Lock a vote escrow treasury until `lockEnd`, at most four years from
+     * now. The lock can be extended but never shortened.
+     */
+    public static async ExtendLock(
+        programId: PublicKey,
+        mint: PublicKey,
+        authority: PublicKey,
+        treasury: PublicKey,
+        lockEnd: Date
+    ): Promise<TransactionInstruction> {
+        const fund = await Treasury.simpleTreasuryAssociatedAccount(
+            treasury,
+            mint,
+            programId
+        );
+
+        const keys: AccountMeta[] = [
+            am(authority, true, false),
+            am(treasury, false, true),
+            am(fund.authority, false, false),
+            am(fund.fund, false, false),
+            am(SYSVAR_CLOCK_PUBKEY, false, false)
+        ];
+
+        const instruction = new ExtendLockSchema({
+            instructionId: TreasuryInstructions.ExtendLock,
+            lockEnd: BigInt(Math.floor(lockEnd.getTime() / 1000))
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** Store the current weight of a vote escrow treasury, anyone can call */
+    public static async UpdateVoteEscrowWeight(
+        programId: PublicKey,
+        mint: PublicKey,
+        treasury: PublicKey
+    ): Promise<TransactionInstruction> {
+        const fund = await Treasury.simpleTreasuryAssociatedAccount(
+            treasury,
+            mint,
+            programId
+        );
+
+        const keys: AccountMeta[] = [
+            am(treasury, false, true),
+            am(fund.authority, false, false),
+            am(fund.fund, false, false),
+            am(SYSVAR_CLOCK_PUBKEY, false, false)
+        ];
+
+        const instruction = new BasicSchema({
+            instructionId: TreasuryInstructions.UpdateVoteEscrowWeight
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Create a treasury that splits its fund between up to 10 recipients.
+     * Shares are in basis points and have to add up to 10,000.
+     */
+    public static async CreateSplitterTreasury(
+        programId: PublicKey,
+        mint: PublicKey,
+        funder: PublicKey,
+        treasury: PublicKey,
+        shares: { recipient: PublicKey; share: number }[]
+    ): Promise<TransactionInstruction> {
+        const keys: AccountMeta[] = [
+            am(funder, true, true),
+            am(treasury, true, true),
+            am(mint, false, false),
+            am(SYSVAR_RENT_PUBKEY, false, false),
+            am(SystemProgram.programId, false, false),
+            am(await Treasury.configAddress(programId), false, true)
+        ];
+
+        const instruction = new SplitterSchema({
+            instructionId: TreasuryInstructions.CreateSplitterTreasury,
+            shares: shares.map((share) => new ShareSchema(share))
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** Claim a shareholder's part of a splitter treasury fund */
+    public static async ClaimSplit(
+        programId: PublicKey,
+        mint: PublicKey,
+        shareholder: PublicKey,
+        treasury: PublicKey
+    ): Promise<TransactionInstruction> {
+        const fund = await Treasury.splitterTreasuryAssociatedAccount(
+            treasury,
+            mint,
+            programId
+        );
+        const recipient = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            mint,
+            shareholder
+        );
+
+        const keys: AccountMeta[] = [
+            am(shareholder, true, false),
+            am(recipient, false, true),
+            am(treasury, false, true),
+            am(fund.authority, false, false),
+            am(fund.fund, false, true),
+            am(TOKEN_PROGRAM_ID, false, false),
+            am(await Treasury.configAddress(programId), false, false),
+            am(await Treasury.feeVaultAddress(mint, programId), false, true)
+        ];
+
+        const instruction = new BasicSchema({
+            instructionId: TreasuryInstructions.ClaimSplit
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Create a treasury that holds a deposit until the arbiter releases it to
+     * the payee or refunds the depositor. After `expiry` it can only be
+     * refunded.
+     */
+    public static async CreateEscrowTreasury(
+        programId: PublicKey,
+        mint: PublicKey,
+        funder: PublicKey,
+        treasury: PublicKey,
+        depositor: PublicKey,
+        payee: PublicKey,
+        arbiter: PublicKey,
+        expiry: Date
+    ): Promise<TransactionInstruction> {
+        const keys: AccountMeta[] = [
+            am(funder, true, true),
+            am(treasury, true, true),
+            am(mint, false, false),
+            am(depositor, false, false),
+            am(payee, false, false),
+            am(arbiter, false, false),
+            am(SYSVAR_RENT_PUBKEY, false, false),
+            am(SYSVAR_CLOCK_PUBKEY, false, false),
+            am(SystemProgram.programId, false, false),
+            am(await Treasury.configAddress(programId), false, true)
+        ];
+
+        const instruction = new EscrowSchema({
+            instructionId: TreasuryInstructions.CreateEscrowTreasury,
+            expiry: BigInt(Math.floor(expiry.getTime() / 1000))
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** The arbiter pays the whole escrow fund to the payee */
+    public static async ReleaseEscrow(
+        programId: PublicKey,
+        mint: PublicKey,
+        arbiter: PublicKey,
+        treasury: PublicKey,
+        payee: PublicKey
+    ): Promise<TransactionInstruction> {
+        return TreasuryInstruction.SettleEscrow(
+            programId,
+            TreasuryInstructions.ReleaseEscrow,
+            mint,
+            arbiter,
+            treasury,
+            payee
+        );
+    }
+
+    /** The arbiter returns the whole escrow fund to the depositor */
+    public static async RefundEscrow(
+        programId: PublicKey,
+        mint: PublicKey,
+        arbiter: PublicKey,
+        treasury: PublicKey,
+        depositor: PublicKey
+    ): Promise<TransactionInstruction> {
+        return TreasuryInstruction.SettleEscrow(
+            programId,
+            TreasuryInstructions.RefundEscrow,
+            mint,
+            arbiter,
+            treasury,
+            depositor
+        );
+    }
+
+    /** Return an expired escrow fund to the depositor, anyone can call */
+    public static async RefundExpiredEscrow(
+        programId: PublicKey,
+        mint: PublicKey,
+        treasury: PublicKey,
+        depositor: PublicKey
+    ): Promise<TransactionInstruction> {
+        return TreasuryInstruction.SettleEscrow(
+            programId,
+            TreasuryInstructions.RefundExpiredEscrow,
+            mint,
+            null,
+            treasury,
+            depositor
+        );
+    }
+
+    private static async SettleEscrow(
+        programId: PublicKey,
+        instructionId: TreasuryInstructions,
+        mint: PublicKey,
+        arbiter: PublicKey | null,
+        treasury: PublicKey,
+        owner: PublicKey
+    ): Promise<TransactionInstruction> {
+        const fund = await Treasury.escrowTreasuryAssociatedAccount(
+            treasury,
+            mint,
+            programId
+        );
+        const recipient = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            mint,
+            owner
+        );
+
+        const keys: AccountMeta[] = [
+            am(recipient, false, true),
+            am(treasury, false, false),
+            am(fund.authority, false, false),
+            am(fund.fund, false, true),
+            am(SYSVAR_CLOCK_PUBKEY, false, false),
+            am(TOKEN_PROGRAM_ID, false, false),
+            am(await Treasury.configAddress(programId), false, false),
+            am(await Treasury.feeVaultAddress(mint, programId), false, true)
+        ];
+        if (arbiter !== null) {
+            keys.unshift(am(arbiter, true, false));
+        }
+
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            new BasicSchema({ instructionId })
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Create a treasury that unlocks funds as attestors confirm milestones,
+     * up to 8 of them
+     */
+    public static async CreateMilestoneTreasury(
+        programId: PublicKey,
+        mint: PublicKey,
+        funder: PublicKey,
+        treasury: PublicKey,
+        authority: PublicKey,
+        milestones: { amount: bigint; attestor: PublicKey }[]
+    ): Promise<TransactionInstruction> {
+        const keys: AccountMeta[] = [
+            am(funder, true, true),
+            am(authority, false, false),
+            am(treasury, true, true),
+            am(mint, false, false),
+            am(SYSVAR_RENT_PUBKEY, false, false),
+            am(SystemProgram.programId, false, false),
+            am(await Treasury.configAddress(programId), false, true)
+        ];
+
+        const instruction = new MilestonesSchema({
+            instructionId: TreasuryInstructions.CreateMilestoneTreasury,
+            milestones: milestones.map((m) => new MilestoneSchema(m))
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** The attestor of a milestone confirms it by signing the transaction */
+    public static async ConfirmMilestone(
+        programId: PublicKey,
+        attestor: PublicKey,
+        treasury: PublicKey,
+        index: number
+    ): Promise<TransactionInstruction> {
+        const keys: AccountMeta[] = [
+            am(attestor, true, false),
+            am(treasury, false, true)
+        ];
+
+        const instruction = new MilestoneIndexSchema({
+            instructionId: TreasuryInstructions.ConfirmMilestone,
+            index
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Confirm a milestone with the attestor's ed25519 signature of
+     * `MilestoneTreasury.attestation`, made off-chain. Returns the ed25519
+     * program instruction that verifies the signature followed by the
+     * confirmation, they have to stay in this order in one transaction.
+     */
+    public static async ConfirmMilestoneWithSignature(
+        programId: PublicKey,
+        attestor: PublicKey,
+        treasury: PublicKey,
+        index: number,
+        signature: Uint8Array
+    ): Promise<TransactionInstruction[]> {
+        const verify = Ed25519Program.createInstructionWithPublicKey({
+            publicKey: attestor.toBytes(),
+            message: MilestoneTreasury.attestation(treasury, index),
+            signature
+        });
+
+        const keys: AccountMeta[] = [
+            am(treasury, false, true),
+            am(SYSVAR_INSTRUCTIONS_PUBKEY, false, false)
+        ];
+
+        const instruction = new MilestoneIndexSchema({
+            instructionId: TreasuryInstructions.ConfirmMilestoneWithSignature,
+            index
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return [
+            verify,
+            new TransactionInstruction({
+                keys: keys,
+                programId,
+                data: Buffer.from(instructionData)
+            })
+        ];
+    }
+
+    /** Withdraw the confirmed milestones to the authority's token account */
+    public static async WithdrawMilestones(
+        programId: PublicKey,
+        mint: PublicKey,
+        authority: PublicKey,
+        treasury: PublicKey
+    ): Promise<TransactionInstruction> {
+        const fund = await Treasury.milestoneTreasuryAssociatedAccount(
+            treasury,
+            mint,
+            programId
+        );
+        const recipient = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            mint,
+            authority
+        );
+
+        const keys: AccountMeta[] = [
+            am(authority, true, false),
+            am(recipient, false, true),
+            am(treasury, false, true),
+            am(fund.authority, false, false),
+            am(fund.fund, false, true),
+            am(TOKEN_PROGRAM_ID, false, false),
+            am(await Treasury.configAddress(programId), false, false),
+            am(await Treasury.feeVaultAddress(mint, programId), false, true)
+        ];
+
+        const instruction = new BasicSchema({
+            instructionId: TreasuryInstructions.WithdrawMilestones
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Create a treasury that unlocks tranches once the price reported by the
+     * oracle stays at or above their target for `duration` seconds, up to 8
+     * of them
+     */
+    public static async CreateHurdleTreasury(
+        programId: PublicKey,
+        mint: PublicKey,
+        funder: PublicKey,
+        treasury: PublicKey,
+        authority: PublicKey,
+        oracle: PublicKey,
+        duration: bigint,
+        tranches: { amount: bigint; price: bigint }[]
+    ): Promise<TransactionInstruction> {
+        const keys: AccountMeta[] = [
+            am(funder, true, true),
+            am(authority, false, false),
+            am(treasury, true, true),
+            am(mint, false, false),
+            am(oracle, false, false),
+            am(SYSVAR_RENT_PUBKEY, false, false),
+            am(SystemProgram.programId, false, false),
+            am(await Treasury.configAddress(programId), false, true)
+        ];
+
+        const instruction = new HurdlesSchema({
+            instructionId: TreasuryInstructions.CreateHurdleTreasury,
+            duration,
+            tranches: tranches.map((t) => new TrancheSchema(t))
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** Record the oracle's current price, anyone can send it */
+    public static async UpdateHurdles(
+        programId: PublicKey,
+        treasury: PublicKey,
+        oracle: PublicKey
+    ): Promise<TransactionInstruction> {
+        const keys: AccountMeta[] = [
+            am(treasury, false, true),
+            am(oracle, false, false),
+            am(SYSVAR_CLOCK_PUBKEY, false, false)
+        ];
+
+        const instruction = new BasicSchema({
+            instructionId: TreasuryInstructions.UpdateHurdles
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** Withdraw the unlocked tranches to the authority's token account */
+    public static async WithdrawHurdles(
+        programId: PublicKey,
+        mint: PublicKey,
+        authority: PublicKey,
+        treasury: PublicKey
+    ): Promise<TransactionInstruction> {
+        const fund = await Treasury.hurdleTreasuryAssociatedAccount(
+            treasury,
+            mint,
+            programId
+        );
+        const recipient = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            mint,
+            authority
+        );
+
+        const keys: AccountMeta[] = [
+            am(authority, true, false),
+            am(recipient, false, true),
+            am(treasury, false, true),
+            am(fund.authority, false, false),
+            am(fund.fund, false, true),
+            am(TOKEN_PROGRAM_ID, false, false),
+            am(await Treasury.configAddress(programId), false, false),
+            am(await Treasury.feeVaultAddress(mint, programId), false, true)
+        ];
+
+        const instruction = new BasicSchema({
+            instructionId: TreasuryInstructions.WithdrawHurdles
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Only the program's upgrade authority can initialize the config, it
+     * becomes the admin. The withdrawal fee is in basis points.
+     */
+    public static async InitializeConfig(
+        programId: PublicKey,
+        funder: PublicKey,
+        upgradeAuthority: PublicKey,
+        feeReceiver: PublicKey,
+        creationFee: bigint,
+        withdrawalFee: number,
+        guardian: PublicKey
+    ): Promise<TransactionInstruction> {
+        const programData = (
+            await PublicKey.findProgramAddress(
+                [programId.toBuffer()],
+                BPF_LOADER_UPGRADEABLE_PROGRAM_ID
+            )
+        )[0];
+
+        const keys: AccountMeta[] = [
+            am(funder, true, true),
+            am(upgradeAuthority, true, false),
+            am(await Treasury.configAddress(programId), false, true),
+            am(programData, false, false),
+            am(SYSVAR_RENT_PUBKEY, false, false),
+            am(SystemProgram.programId, false, false)
+        ];
+
+        const instruction = new ConfigSchema({
+            instructionId: TreasuryInstructions.InitializeConfig,
+            feeReceiver,
+            creationFee,
+            withdrawalFee,
+            guardian
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    public static async UpdateConfig(
+        programId: PublicKey,
+        admin: PublicKey,
+        newAdmin: PublicKey,
+        feeReceiver: PublicKey,
+        creationFee: bigint,
+        withdrawalFee: number,
+        guardian: PublicKey
+    ): Promise<TransactionInstruction> {
+        const keys: AccountMeta[] = [
+            am(admin, true, false),
+            am(await Treasury.configAddress(programId), false, true)
+        ];
+
+        const instruction = new UpdateConfigSchema({
+            instructionId: TreasuryInstructions.UpdateConfig,
+            admin: newAdmin,
+            feeReceiver,
+            creationFee,
+            withdrawalFee,
+            guardian
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Send the creation fees and the fee vault of the mint to the fee
+     * receiver's associated token account, anyone can collect
+     */
+    public static async CollectFees(
+        programId: PublicKey,
+        mint: PublicKey,
+        feeReceiver: PublicKey
+    ): Promise<TransactionInstruction> {
+        const recipient = await Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            mint,
+            feeReceiver
+        );
+
+        const keys: AccountMeta[] = [
+            am(await Treasury.configAddress(programId), false, true),
+            am(feeReceiver, false, true),
+            am(await Treasury.feeVaultAddress(mint, programId), false, true),
+            am(recipient, false, true),
+            am(SYSVAR_RENT_PUBKEY, false, false),
+            am(TOKEN_PROGRAM_ID, false, false)
+        ];
+
+        const instruction = new BasicSchema({
+            instructionId: TreasuryInstructions.CollectFees
+        });
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Stop every instruction that creates a treasury or moves tokens out of a
+     * fund, only the guardian can pause
+     */
+    public static async PauseProgram(
+        programId: PublicKey,
+        guardian: PublicKey
+    ): Promise<TransactionInstruction> {
+        return TreasuryInstruction.SetPaused(
+            programId,
+            TreasuryInstructions.PauseProgram,
+            guardian
+        );
+    }
+
+    /** Only the admin can unpause */
+    public static async UnpauseProgram(
+        programId: PublicKey,
+        admin: PublicKey
+    ): Promise<TransactionInstruction> {
+        return TreasuryInstruction.SetPaused(
+            programId,
+            TreasuryInstructions.UnpauseProgram,
+            admin
+        );
+    }
+
+    private static async SetPaused(
+        programId: PublicKey,
+        instructionId: TreasuryInstructions,
+        signer: PublicKey
+    ): Promise<TransactionInstruction> {
+        const keys: AccountMeta[] = [
+            am(signer, true, false),
+            am(await Treasury.configAddress(programId), false, true)
+        ];
+
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            new BasicSchema({ instructionId })
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Name the backup that can claim a simple or vested treasury once the
+     * authority has been inactive for `inactivityPeriod` seconds
+     */
+    public static async SetRecovery(
+        programId: PublicKey,
+        funder: PublicKey,
+        authority: PublicKey,
+        treasury: PublicKey,
+        backup: PublicKey,
+        inactivityPeriod: bigint
+    ): Promise<TransactionInstruction> {
+        const recovery = await Treasury.recoveryAddress(treasury, programId);
+
+        const keys: AccountMeta[] = [
+            am(funder, true, true),
+            am(authority, true, false),
+            am(treasury, false, false),
+            am(recovery, false, true),
+            am(SYSVAR_CLOCK_PUBKEY, false, false),
+            am(SYSVAR_RENT_PUBKEY, false, false),
+            am(SystemProgram.programId, false, false)
+        ];
+
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            new RecoverySchema({
+                instructionId: TreasuryInstructions.SetRecovery,
+                backup,
+                inactivityPeriod
+            })
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** Show that the authority is still active */
+    public static async Heartbeat(
+        programId: PublicKey,
+        authority: PublicKey,
+        treasury: PublicKey
+    ): Promise<TransactionInstruction> {
+        const recovery = await Treasury.recoveryAddress(treasury, programId);
+
+        const keys: AccountMeta[] = [
+            am(authority, true, false),
+            am(treasury, false, false),
+            am(recovery, false, true),
+            am(SYSVAR_CLOCK_PUBKEY, false, false)
+        ];
+
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            new BasicSchema({ instructionId: TreasuryInstructions.Heartbeat })
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** The backup becomes the authority once the authority is inactive */
+    public static async ClaimRecovery(
+        programId: PublicKey,
+        backup: PublicKey,
+        treasury: PublicKey
+    ): Promise<TransactionInstruction> {
+        const recovery = await Treasury.recoveryAddress(treasury, programId);
+        const social = await Treasury.socialRecoveryAddress(treasury, programId);
+
+        const keys: AccountMeta[] = [
+            am(backup, true, true),
+            am(treasury, false, true),
+            am(recovery, false, true),
+            am(SYSVAR_CLOCK_PUBKEY, false, false),
+            am(social, false, true)
+        ];
+
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            new BasicSchema({
+                instructionId: TreasuryInstructions.ClaimRecovery
+            })
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /**
+     * Name up to 8 guardians, `threshold` of which can rotate the authority of
+     * a simple or vested treasury after the challenge delay
+     */
+    public static async SetGuardians(
+        programId: PublicKey,
+        funder: PublicKey,
+        authority: PublicKey,
+        treasury: PublicKey,
+        guardians: PublicKey[],
+        threshold: number,
+        challengeDelay: bigint
+    ): Promise<TransactionInstruction> {
+        const social = await Treasury.socialRecoveryAddress(
+            treasury,
+            programId
+        );
+
+        const keys: AccountMeta[] = [
+            am(funder, true, true),
+            am(authority, true, false),
+            am(treasury, false, false),
+            am(social, false, true),
+            am(SYSVAR_RENT_PUBKEY, false, false),
+            am(SystemProgram.programId, false, false)
+        ];
+
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            new GuardiansSchema({
+                instructionId: TreasuryInstructions.SetGuardians,
+                guardians,
+                threshold,
+                challengeDelay
+            })
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** A guardian approves making `newAuthority` the authority */
+    public static async ApproveRotation(
+        programId: PublicKey,
+        guardian: PublicKey,
+        treasury: PublicKey,
+        newAuthority: PublicKey
+    ): Promise<TransactionInstruction> {
+        const social = await Treasury.socialRecoveryAddress(
+            treasury,
+            programId
+        );
+
+        const keys: AccountMeta[] = [
+            am(guardian, true, false),
+            am(treasury, false, false),
+            am(social, false, true),
+            am(SYSVAR_CLOCK_PUBKEY, false, false)
+        ];
+
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            new RotationSchema({
+                instructionId: TreasuryInstructions.ApproveRotation,
+                newAuthority
+            })
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** The authority drops the rotation and its approvals */
+    public static async CancelRotation(
+        programId: PublicKey,
+        authority: PublicKey,
+        treasury: PublicKey
+    ): Promise<TransactionInstruction> {
+        const social = await Treasury.socialRecoveryAddress(
+            treasury,
+            programId
+        );
+
+        const keys: AccountMeta[] = [
+            am(authority, true, false),
+            am(treasury, false, false),
+            am(social, false, true)
+        ];
+
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            new BasicSchema({
+                instructionId: TreasuryInstructions.CancelRotation
+            })
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** The new authority takes over once the challenge delay has passed */
+    public static async ExecuteRotation(
+        programId: PublicKey,
+        newAuthority: PublicKey,
+        treasury: PublicKey
+    ): Promise<TransactionInstruction> {
+        const social = await Treasury.socialRecoveryAddress(
+            treasury,
+            programId
+        );
+        const recovery = await Treasury.recoveryAddress(treasury, programId);
+
+        const keys: AccountMeta[] = [
+            am(newAuthority, true, true),
+            am(treasury, false, true),
+            am(social, false, true),
+            am(recovery, false, true),
+            am(SYSVAR_CLOCK_PUBKEY, false, false)
+        ];
+
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            new BasicSchema({
+                instructionId: TreasuryInstructions.ExecuteRotation
+            })
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    /** Grow a treasury stored with an earlier layout, anyone can pay for it */
+    public static async MigrateTreasury(
+        programId: PublicKey,
+        funder: PublicKey,
+        treasury: PublicKey
+    ): Promise<TransactionInstruction> {
+        const keys: AccountMeta[] = [
+            am(funder, true, true),
+            am(treasury, false, true),
+            am(SYSVAR_RENT_PUBKEY, false, false),
+            am(SystemProgram.programId, false, false)
+        ];
+
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            new BasicSchema({
+                instructionId: TreasuryInstructions.MigrateTreasury
+            })
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+
+    private static GrantorInstruction(
+        programId: PublicKey,
+        grantor: PublicKey,
+        treasury: PublicKey,
+        instruction:
+            | TreasuryInstructions
+            | AccelerateAmountSchema
+            | AcceleratePercentageSchema
+    ): TransactionInstruction {
+        const keys: AccountMeta[] = [
+            am(grantor, true, false),
+            am(treasury, false, true),
+            am(SYSVAR_CLOCK_PUBKEY, false, false)
+        ];
+
+        const instructionData = borsh.serialize(
+            INSTRUCTION_SCHEMA,
+            typeof instruction === 'number'
+                ? new BasicSchema({ instructionId: instruction })
+                : instruction
+        );
+
+        return new TransactionInstruction({
+            keys: keys,
+            programId,
+            data: Buffer.from(instructionData)
+        });
+    }
+}
+
+function am(
+    pubkey: PublicKey,
+    isSigner: boolean,
+    isWritable: boolean
+): AccountMeta {
+    return { pubkey, isSigner, isWritable };
+}
+
+export const INSTRUCTION_SCHEMA: borsh.Schema = new Map<any, any>([
+    [
+        BasicSchema,
+        {
+            kind: 'struct',
+            fields: [['instructionId', 'u8']]
+        }
+    ],
+    [
+        SimpleSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['mode', 'SimpleTreasuryMode']
+            ]
+        }
+    ],
+    [
+        SimpleWithdrawSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['amount', 'BigInt']
+            ]
+        }
+    ],
+    [
+        VestedWithdrawSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['amount', { kind: 'option', type: 'BigInt' }]
+            ]
+        }
+    ],
+    [
+        VestedSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['amount', 'BigInt'],
+                ['period', 'BigInt'],
+                ['percentage', 'u16']
+            ]
+        }
+    ],
+    [
+        AccelerateAmountSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['kind', 'u8'],
+                ['amount', 'BigInt']
+            ]
+        }
+    ],
+    [
+        AcceleratePercentageSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['kind', 'u8'],
+                ['percentage', 'u16']
+            ]
+        }
+    ],
+    [
+        ExtendLockSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['lockEnd', 'BigInt']
+            ]
+        }
+    ],
+    [
+        SetVestedDelegateSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['delegate', { kind: 'option', type: 'PublicKey' }]
+            ]
+        }
+    ],
+    [
+        ShareSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['recipient', 'PublicKey'],
+                ['share', 'u16']
+            ]
+        }
+    ],
+    [
+        SplitterSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['shares', [ShareSchema]]
+            ]
+        }
+    ],
+    [
+        EscrowSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['expiry', 'BigInt']
+            ]
+        }
+    ],
+    [
+        MilestoneSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['amount', 'BigInt'],
+                ['attestor', 'PublicKey']
+            ]
+        }
+    ],
+    [
+        MilestonesSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['milestones', [MilestoneSchema]]
+            ]
+        }
+    ],
+    [
+        MilestoneIndexSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['index', 'u8']
+            ]
+        }
+    ],
+    [
+        TrancheSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['amount', 'BigInt'],
+                ['price', 'BigInt']
+            ]
+        }
+    ],
+    [
+        HurdlesSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['duration', 'BigInt'],
+                ['tranches', [TrancheSchema]]
+            ]
+        }
+    ],
+    [
+        ConfigSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['feeReceiver', 'PublicKey'],
+                ['creationFee', 'BigInt'],
+                ['withdrawalFee', 'u16'],
+                ['guardian', 'PublicKey']
+            ]
+        }
+    ],
+    [
+        UpdateConfigSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['admin', 'PublicKey'],
+                ['feeReceiver', 'PublicKey'],
+                ['creationFee', 'BigInt'],
+                ['withdrawalFee', 'u16'],
+                ['guardian', 'PublicKey']
+            ]
+        }
+    ],
+    [
+        SplitVestedSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['amount', 'BigInt']
+            ]
+        }
+    ],
+    [
+        RecoverySchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['backup', 'PublicKey'],
+                ['inactivityPeriod', 'BigInt']
+            ]
+        }
+    ],
+    [
+        GuardiansSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['guardians', ['PublicKey']],
+                ['threshold', 'u8'],
+                ['challengeDelay', 'BigInt']
+            ]
+        }
+    ],
+    [
+        RotationSchema,
+        {
+            kind: 'struct',
+            fields: [
+                ['instructionId', 'u8'],
+                ['newAuthority', 'PublicKey']
             ]
         }
     ]
@@ -1,7 +1,17 @@
 import { Connection, PublicKey } from '@solana/web3.js';
 import { ACCOUNT_SCHEMA } from './';
 import * as borsh from 'borsh';
-import { SimpleTreasury, VestedTreasury } from './accounts';
+import {
+    Config,
+    EscrowTreasury,
+    HurdleTreasury,
+    MilestoneTreasury,
+    Recovery,
+    SimpleTreasury,
+    SocialRecovery,
+    SplitterTreasury,
+    VestedTreasury
+} from './accounts';
 import {
     ASSOCIATED_TOKEN_PROGRAM_ID,
     Token,
@@ -37,6 +47,136 @@ export class Treasury {
         return borsh.deserialize(ACCOUNT_SCHEMA, VestedTreasury, account.data);
     }
 
+    public async getSplitterTreasury(
+        treasuryId: PublicKey
+    ): Promise<SplitterTreasury> {
+        const account = await this.connection.getAccountInfo(treasuryId);
+        if (account === null)
+            throw new Error('Unable to find splitter treasury account');
+
+        return borsh.deserialize(
+            ACCOUNT_SCHEMA,
+            SplitterTreasury,
+            account.data
+        );
+    }
+
+    public async getEscrowTreasury(
+        treasuryId: PublicKey
+    ): Promise<EscrowTreasury> {
+        const account = await this.connection.getAccountInfo(treasuryId);
+        if (account === null)
+            throw new Error('Unable to find escrow treasury account');
+
+        return borsh.deserialize(ACCOUNT_SCHEMA, EscrowTreasury, account.data);
+    }
+
+    public async getMilestoneTreasury(
+        treasuryId: PublicKey
+    ): Promise<MilestoneTreasury> {
+        const account = await this.connection.getAccountInfo(treasuryId);
+        if (account === null)
+            throw new Error('Unable to find milestone treasury account');
+
+        return borsh.deserialize(
+            ACCOUNT_SCHEMA,
+            MilestoneTreasury,
+            account.data
+        );
+    }
+
+    public async getHurdleTreasury(
+        treasuryId: PublicKey
+    ): Promise<HurdleTreasury> {
+        const account = await this.connection.getAccountInfo(treasuryId);
+        if (account === null)
+            throw new Error('Unable to find hurdle treasury account');
+
+        return borsh.deserialize(ACCOUNT_SCHEMA, HurdleTreasury, account.data);
+    }
+
+    /** The program config, null until it's initialized */
+    public async getConfig(): Promise<Config | null> {
+        const config = await Treasury.configAddress(this.programId);
+        const account = await this.connection.getAccountInfo(config);
+        if (account === null || account.data.length === 0) return null;
+
+        return borsh.deserialize(ACCOUNT_SCHEMA, Config, account.data);
+    }
+
+    /** The recovery of a simple or vested treasury, null if none was set up */
+    public async getRecovery(treasuryId: PublicKey): Promise<Recovery | null> {
+        const recovery = await Treasury.recoveryAddress(
+            treasuryId,
+            this.programId
+        );
+        const account = await this.connection.getAccountInfo(recovery);
+        if (account === null || account.data.length === 0) return null;
+
+        return borsh.deserialize(ACCOUNT_SCHEMA, Recovery, account.data);
+    }
+
+    /** The guardians of a simple or vested treasury, null if none were named */
+    public async getSocialRecovery(
+        treasuryId: PublicKey
+    ): Promise<SocialRecovery | null> {
+        const social = await Treasury.socialRecoveryAddress(
+            treasuryId,
+            this.programId
+        );
+        const account = await this.connection.getAccountInfo(social);
+        if (account === null || account.data.length === 0) return null;
+
+        return borsh.deserialize(ACCOUNT_SCHEMA, SocialRecovery, account.data);
+    }
+
+    static async socialRecoveryAddress(
+        treasury: PublicKey,
+        programId: PublicKey
+    ): Promise<PublicKey> {
+        return (
+            await PublicKey.findProgramAddress(
+                [Buffer.from('social recovery'), treasury.toBuffer()],
+                programId
+            )
+        )[0];
+    }
+
+    static async recoveryAddress(
+        treasury: PublicKey,
+        programId: PublicKey
+    ): Promise<PublicKey> {
+        return (
+            await PublicKey.findProgramAddress(
+                [Buffer.from('recovery'), treasury.toBuffer()],
+                programId
+            )
+        )[0];
+    }
+
+    static async configAddress(programId: PublicKey): Promise<PublicKey> {
+        return (
+            await PublicKey.findProgramAddress(
+                [Buffer.from('config')],
+                programId
+            )
+        )[0];
+    }
+
+    /** The token account withdrawal fees of the mint are paid into */
+    static async feeVaultAddress(
+        mint: PublicKey,
+        programId: PublicKey
+    ): Promise<PublicKey> {
+        return Token.getAssociatedTokenAddress(
+            ASSOCIATED_TOKEN_PROGRAM_ID,
+            TOKEN_PROGRAM_ID,
+            mint,
+            await Treasury.configAddress(programId),
+            true
+        );
+    }
+
     private static async treasuryAssociatedAccount(
         phrase: string,
         treasury: PublicKey,
@@ -87,4 +227,56 @@ export class Treasury {
             programId
         );
     }
+
+    static async splitterTreasuryAssociatedAccount(
+        treasury: PublicKey,
+        mint: PublicKey,
+        programId: PublicKey
+    ): Promise<{ authority: PublicKey; fund: PublicKey }> {
+        return Treasury.treasuryAssociatedAccount(
+            'splitter authority',
+            treasury,
+            mint,
+            programId
+        );
+    }
+
+    static async escrowTreasuryAssociatedAccount(
+        treasury: PublicKey,
+        mint: PublicKey,
+        programId: PublicKey
+    ): Promise<{ authority: PublicKey; fund: PublicKey }> {
+        return Treasury.treasuryAssociatedAccount(
+            'escrow authority',
+            treasury,
+            mint,
+            programId
+        );
+    }
+
+    static async milestoneTreasuryAssociatedAccount(
+        treasury: PublicKey,
+        mint: PublicKey,
+        programId: PublicKey
+    ): Promise<{ authority: PublicKey; fund: PublicKey }> {
+        return Treasury.treasuryAssociatedAccount(
+            'milestone authority',
+            treasury,
+            mint,
+            programId
+        );
+    }
+
+    static async hurdleTreasuryAssociatedAccount(
+        treasury: PublicKey,
+        mint: PublicKey,
+        programId: PublicKey
+    ): Promise<{ authority: PublicKey; fund: PublicKey }> {
+        return Treasury.treasuryAssociatedAccount(
+            'hurdle authority',
+            treasury,
+            mint,
+            programId
+        );
+    }
 }
@@ -0,0 +1,414 @@
+//! Fuzzing harness for the treasury program
+//!
+//! `run` sets up synthetic accounts (treasuries, their fund authorities and funds, token
+//! accounts of the authorities and of an attacker, mints, sysvars and programs) and then
+//! executes a sequence of steps decoded from the input against `Processor::process`.
+//! A step either advances the clock, builds an instruction from raw bytes and arbitrary
+//! accounts, or builds a well-formed instruction that is then tampered with by
+//! replacing, swapping and duplicating accounts or changing their flags.
+//!
+//! Cross-program invocations go to the syscall stubs in `stubs`, which emulate the
+//! token program's `Transfer` and `CloseAccount` and the system program's
+//! `CreateAccount` and `Transfer`. After every instruction the runtime would accept, the harness
+//! asserts that:
+//! * tokens leaving a treasury's fund only go to accounts owned by its authority, one of
+//!   its shareholders or one of the parties of an escrow, or to the fund of another
+//!   vested treasury when splitting or merging
+//! * nothing leaves the fund of a Locked simple treasury, or of a vote escrow treasury
+//!   before its lock ends
+//! * a vested treasury's `withdrawn` never exceeds its `initial_amount` and matches the
+//!   amount that left the fund, with splits and merges moving part of both between
+//!   treasuries
+//! * a splitter treasury's `total_released` matches the amount that left the fund and
+//!   the amounts its shareholders released
+//! * an escrow treasury only pays its payee before the expiry, and otherwise only its
+//!   depositor
+//! * a milestone treasury's `withdrawn` never exceeds its confirmed milestones and
+//!   matches the amount that left the fund
+//! * a hurdle treasury's `withdrawn` never exceeds its unlocked tranches and matches the
+//!   amount that left the fund
+//! * withdrawal fees never exceed the configured share of a withdrawal and collected
+//!   fees only go to the fee receiver
+//! * nothing leaves a fund and no treasury is created while the program is paused
+//! * a treasury only changes hands when the backup of its recovery claims it after the
+//!   authority was inactive for the inactivity period, or when enough of its guardians
+//!   rotated the authority and the challenge delay passed
+
+mod input;
+mod stubs;
+mod universe;
+
+pub use input::Input;
+pub use universe::{Summary, Universe};
+
+/// The maximum number of steps in one run
+const MAX_STEPS: usize = 32;
+
+pub fn run(data: &[u8]) -> Summary {
+    stubs::install();
+
+    let mut input = Input::new(data);
+    let mut universe = Universe::new(&mut input);
+    for _ in 0..MAX_STEPS {
+        if universe.step(&mut input).is_none() {
+            break;
+        }
+    }
+    universe.summary()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Universe parameters with funded treasuries and nothing vested yet
+    fn universe() -> Vec<u8> {
+        let mut data = vec![];
+        // clock
+        data.extend_from_slice(&0u32.to_le_bytes());
+        for _ in 0..2 {
+            data.extend_from_slice(&1_000u64.to_le_bytes()); // amount
+            data.extend_from_slice(&60u32.to_le_bytes()); // period
+            data.extend_from_slice(&499u16.to_le_bytes()); // 5%
+            data.extend_from_slice(&0u32.to_le_bytes()); // age
+            data.extend_from_slice(&1_000u64.to_le_bytes()); // fund balance
+        }
+        for _ in 0..4 {
+            data.extend_from_slice(&1_000u64.to_le_bytes()); // fund balance
+        }
+        data.extend_from_slice(&1_000u64.to_le_bytes()); // escrow fund balance
+        data.extend_from_slice(&100u32.to_le_bytes()); // escrow expiry
+        data.extend_from_slice(&1_000u64.to_le_bytes()); // milestone fund balance
+        data.extend_from_slice(&1_000u64.to_le_bytes()); // hurdle fund balance
+        data.extend_from_slice(&1_000u64.to_le_bytes()); // price
+        data.push(30); // hurdle duration
+        data
+    }
+
+    #[test]
+    pub fn test_vested_withdrawal() {
+        let mut data = universe();
+        // ten periods pass
+        data.push(0);
+        data.extend_from_slice(&600u32.to_le_bytes());
+        // withdraw from the first vested treasury without tampering
+        data.extend_from_slice(&[2, 0, 1, 0, 0]);
+        // and again with the authority's signature removed
+        data.extend_from_slice(&[2, 0, 1, 0, 1, 1, 3, 0]);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 2);
+        assert_eq!(summary.succeeded, 1);
+        assert_eq!(summary.released, 500);
+    }
+
+    #[test]
+    pub fn test_partial_vested_withdrawal() {
+        let mut data = universe();
+        // ten periods pass, 500 have vested
+        data.push(0);
+        data.extend_from_slice(&600u32.to_le_bytes());
+        // withdraw 200 of them
+        data.extend_from_slice(&[2, 0, 1, 1]);
+        data.extend_from_slice(&200u64.to_le_bytes());
+        data.push(0);
+        // 400 more is more than is left
+        data.extend_from_slice(&[2, 0, 1, 1]);
+        data.extend_from_slice(&400u64.to_le_bytes());
+        data.push(0);
+        // the rest
+        data.extend_from_slice(&[2, 0, 1, 0, 0]);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 3);
+        assert_eq!(summary.succeeded, 2);
+        assert_eq!(summary.released, 500);
+    }
+
+    #[test]
+    pub fn test_simple_withdrawal() {
+        let mut data = universe();
+        // withdraw from the unlocked simple treasury without tampering
+        data.extend_from_slice(&[2, 3, 0]);
+        data.extend_from_slice(&400u64.to_le_bytes());
+        data.push(0);
+        // and from the locked one
+        data.extend_from_slice(&[2, 2, 0]);
+        data.extend_from_slice(&400u64.to_le_bytes());
+        data.push(0);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 2);
+        assert_eq!(summary.succeeded, 1);
+        assert_eq!(summary.released, 400);
+    }
+
+    #[test]
+    pub fn test_split_vested() {
+        let mut data = universe();
+        // ten periods pass and the first vested treasury withdraws half
+        data.push(0);
+        data.extend_from_slice(&600u32.to_le_bytes());
+        data.extend_from_slice(&[2, 0, 1, 0, 0]);
+        // split 400 off into a new vested treasury
+        data.extend_from_slice(&[2, 0, 8, 0, 0]);
+        data.extend_from_slice(&400u64.to_le_bytes());
+        data.push(0);
+        // one more period and both withdraw
+        data.push(0);
+        data.extend_from_slice(&60u32.to_le_bytes());
+        data.extend_from_slice(&[2, 0, 1, 0, 0]);
+        data.extend_from_slice(&[2, 5, 1, 0, 0]);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 4);
+        assert_eq!(summary.succeeded, 4);
+        assert_eq!(summary.released, 550);
+    }
+
+    #[test]
+    pub fn test_merge_vested() {
+        let mut data = universe();
+        // ten periods pass and the first vested treasury withdraws half
+        data.push(0);
+        data.extend_from_slice(&600u32.to_le_bytes());
+        data.extend_from_slice(&[2, 0, 1, 0, 0]);
+        // split 400 off for the same authority and merge it back
+        data.extend_from_slice(&[2, 0, 8, 0, 1]);
+        data.extend_from_slice(&400u64.to_le_bytes());
+        data.push(0);
+        data.extend_from_slice(&[2, 0, 9, 5, 0]);
+        // one more period
+        data.push(0);
+        data.extend_from_slice(&60u32.to_le_bytes());
+        data.extend_from_slice(&[2, 0, 1, 0, 0]);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 4);
+        assert_eq!(summary.succeeded, 4);
+        assert_eq!(summary.released, 550);
+    }
+
+    #[test]
+    pub fn test_vote_escrow() {
+        let mut data = universe();
+        // lock the vote escrow treasury for ten minutes
+        data.extend_from_slice(&[2, 8, 11]);
+        data.extend_from_slice(&600u32.to_le_bytes());
+        data.push(0);
+        // withdrawing fails until the lock ends
+        data.extend_from_slice(&[2, 8, 0]);
+        data.extend_from_slice(&400u64.to_le_bytes());
+        data.push(0);
+        data.push(0);
+        data.extend_from_slice(&600u32.to_le_bytes());
+        data.extend_from_slice(&[2, 8, 12, 0]);
+        data.extend_from_slice(&[2, 8, 0]);
+        data.extend_from_slice(&400u64.to_le_bytes());
+        data.push(0);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 4);
+        assert_eq!(summary.succeeded, 3);
+        assert_eq!(summary.released, 400);
+    }
+
+    #[test]
+    pub fn test_claim_split() {
+        let mut data = universe();
+        // alice and bob claim their shares of the splitter treasury, the attacker can't
+        for signer in 0..3 {
+            data.extend_from_slice(&[2, 9, 14, signer, 0]);
+        }
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 3);
+        assert_eq!(summary.succeeded, 2);
+        assert_eq!(summary.released, 1_000);
+    }
+
+    #[test]
+    pub fn test_escrow() {
+        let mut data = universe();
+        // the arbiter can't release to themselves or refund before the expiry
+        data.extend_from_slice(&[2, 10, 15, 2, 0]);
+        data.extend_from_slice(&[2, 10, 17, 0, 0]);
+        // but can release to the payee
+        data.extend_from_slice(&[2, 10, 15, 1, 0]);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 3);
+        assert_eq!(summary.succeeded, 1);
+        assert_eq!(summary.released, 1_000);
+    }
+
+    #[test]
+    pub fn test_milestones() {
+        let mut data = universe();
+        // alice can't withdraw before a milestone is confirmed, bob can't confirm the
+        // attacker's milestone
+        data.extend_from_slice(&[2, 11, 19, 0]);
+        data.extend_from_slice(&[2, 11, 18, 1, 1, 0]);
+        // bob confirms his
+        data.extend_from_slice(&[2, 11, 18, 1, 0, 0]);
+        data.extend_from_slice(&[2, 11, 19, 0]);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 4);
+        assert_eq!(summary.succeeded, 3);
+        assert_eq!(summary.released, 500);
+    }
+
+    #[test]
+    pub fn test_hurdles() {
+        let mut data = universe();
+        // the price meets the first target, which unlocks after the duration
+        data.extend_from_slice(&[2, 12, 20, 0]);
+        data.extend_from_slice(&[2, 12, 21, 0]);
+        data.push(0);
+        data.extend_from_slice(&30u32.to_le_bytes());
+        data.extend_from_slice(&[2, 12, 20, 0]);
+        data.extend_from_slice(&[2, 12, 21, 0]);
+        // until the price goes stale
+        data.push(0);
+        data.extend_from_slice(&61u32.to_le_bytes());
+        data.extend_from_slice(&[2, 12, 20, 0]);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 5);
+        assert_eq!(summary.succeeded, 4);
+        assert_eq!(summary.released, 500);
+    }
+
+    #[test]
+    pub fn test_fees() {
+        let mut data = universe();
+        // alice initializes the config with bob receiving a 4% withdrawal fee
+        data.extend_from_slice(&[2, 0, 22, 0, 1]);
+        data.extend_from_slice(&5_000u32.to_le_bytes());
+        data.extend_from_slice(&400u16.to_le_bytes());
+        data.extend_from_slice(&[2, 0]);
+        // withdraw from the unlocked simple treasury
+        data.extend_from_slice(&[2, 3, 0]);
+        data.extend_from_slice(&400u64.to_le_bytes());
+        data.push(0);
+        // the attacker can't collect the fees, bob can
+        data.extend_from_slice(&[2, 0, 24, 2, 0]);
+        data.extend_from_slice(&[2, 0, 24, 1, 0]);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 4);
+        assert_eq!(summary.succeeded, 3);
+        assert_eq!(summary.released, 400);
+    }
+
+    #[test]
+    pub fn test_pause() {
+        let mut data = universe();
+        // alice initializes the config without fees and bob as the guardian, who pauses
+        data.extend_from_slice(&[2, 0, 22, 0, 0]);
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&0u16.to_le_bytes());
+        data.extend_from_slice(&[1, 0]);
+        data.extend_from_slice(&[2, 0, 25, 1, 0]);
+        // withdrawing from the unlocked simple treasury fails
+        data.extend_from_slice(&[2, 3, 0]);
+        data.extend_from_slice(&400u64.to_le_bytes());
+        data.push(0);
+        // bob can't unpause, alice can
+        data.extend_from_slice(&[2, 0, 26, 1, 0]);
+        data.extend_from_slice(&[2, 0, 26, 0, 0]);
+        data.extend_from_slice(&[2, 3, 0]);
+        data.extend_from_slice(&400u64.to_le_bytes());
+        data.push(0);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 6);
+        assert_eq!(summary.succeeded, 4);
+        assert_eq!(summary.released, 400);
+    }
+
+    #[test]
+    pub fn test_recovery() {
+        let mut data = universe();
+        // alice names bob the backup of the unlocked simple treasury, one minute
+        data.extend_from_slice(&[2, 3, 27, 1]);
+        data.extend_from_slice(&60u32.to_le_bytes());
+        data.push(0);
+        // bob can't claim it yet
+        data.extend_from_slice(&[2, 3, 29, 1, 0]);
+        data.push(0);
+        data.extend_from_slice(&60u32.to_le_bytes());
+        // the attacker can't claim it, bob can and then withdraws
+        data.extend_from_slice(&[2, 3, 29, 2, 0]);
+        data.extend_from_slice(&[2, 3, 29, 1, 0]);
+        data.extend_from_slice(&[2, 3, 0]);
+        data.extend_from_slice(&400u64.to_le_bytes());
+        data.push(0);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 5);
+        assert_eq!(summary.succeeded, 3);
+        assert_eq!(summary.released, 400);
+    }
+
+    #[test]
+    pub fn test_rotation() {
+        let mut data = universe();
+        // alice makes herself and bob guardians of the unlocked simple treasury, both
+        // have to approve and she has a minute to cancel
+        data.extend_from_slice(&[2, 3, 30, 1, 2]);
+        data.extend_from_slice(&60u32.to_le_bytes());
+        data.push(0);
+        // both approve the attacker, who can't take over before the delay passed
+        data.extend_from_slice(&[2, 3, 31, 0, 2, 0]);
+        data.extend_from_slice(&[2, 3, 31, 1, 2, 0]);
+        data.extend_from_slice(&[2, 3, 33, 2, 0]);
+        data.push(0);
+        data.extend_from_slice(&30u32.to_le_bytes());
+        // alice cancels, so the attacker can't take over after the delay either
+        data.extend_from_slice(&[2, 3, 32, 0]);
+        data.push(0);
+        data.extend_from_slice(&30u32.to_le_bytes());
+        data.extend_from_slice(&[2, 3, 33, 2, 0]);
+        // approving bob and waiting a minute lets bob take over
+        data.extend_from_slice(&[2, 3, 31, 0, 1, 0]);
+        data.extend_from_slice(&[2, 3, 31, 1, 1, 0]);
+        data.push(0);
+        data.extend_from_slice(&60u32.to_le_bytes());
+        data.extend_from_slice(&[2, 3, 33, 1, 0]);
+        // and withdraw
+        data.extend_from_slice(&[2, 3, 0]);
+        data.extend_from_slice(&400u64.to_le_bytes());
+        data.push(0);
+
+        let summary = run(&data);
+        assert_eq!(summary.executed, 10);
+        assert_eq!(summary.succeeded, 8);
+        assert_eq!(summary.released, 400);
+    }
+
+    #[test]
+    pub fn test_random_inputs() {
+        // xorshift, to get the same inputs every time
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut total = Summary::default();
+        for _ in 0..1_000 {
+            let data: Vec<u8> = (0..512)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect();
+            let summary = run(&data);
+            total.executed += summary.executed;
+            total.succeeded += summary.succeeded;
+            total.released += summary.released;
+        }
+        assert!(total.executed > 0);
+        assert!(total.succeeded > 0);
+        assert!(total.released > 0);
+    }
+}
@@ -0,0 +1,318 @@
+//! Syscall stubs that emulate the cross-program invocations made by the treasury program
+
+use std::{cell::RefCell, sync::Once};
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_pack::Pack,
+    program_stubs::{self, SyscallStubs},
+    pubkey::Pubkey,
+    system_instruction::SystemInstruction,
+    system_program,
+};
+use spl_token::{error::TokenError, instruction::TokenInstruction, state::Account};
+
+/// The largest account the emulated system program will create
+const MAX_ACCOUNT_SIZE: u64 = 10_240;
+
+/// A token transfer between two different accounts
+#[derive(Clone, Debug)]
+pub struct Transfer {
+    pub source_owner: Pubkey,
+    pub destination_owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(Default)]
+struct Effects {
+    transfers: Vec<Transfer>,
+    /// Accounts created by the system program and their new owner
+    created: Vec<(Pubkey, Pubkey)>,
+    /// Data handed out to created accounts
+    allocations: Vec<*mut [u8]>,
+}
+
+thread_local! {
+    static CALLER: RefCell<Pubkey> = RefCell::new(Pubkey::default());
+    static EFFECTS: RefCell<Effects> = RefCell::new(Effects::default());
+}
+
+struct Stubs;
+
+impl SyscallStubs for Stubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, _fields: &[&[u8]]) {}
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        let caller = CALLER.with(|caller| *caller.borrow());
+        let signers = signers_seeds
+            .iter()
+            .map(|seeds| Pubkey::create_program_address(seeds, &caller))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ProgramError::InvalidSeeds)?;
+
+        // like the runtime, the program and all accounts have to be passed along
+        // without escalating their privileges
+        match find(account_infos, &instruction.program_id) {
+            Some(program) if program.executable => {}
+            _ => return Err(ProgramError::IncorrectProgramId),
+        }
+        for meta in &instruction.accounts {
+            let info =
+                find(account_infos, &meta.pubkey).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            if meta.is_writable && !info.is_writable {
+                return Err(ProgramError::InvalidArgument);
+            }
+            if meta.is_signer && !info.is_signer && !signers.contains(&meta.pubkey) {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        }
+
+        if instruction.program_id == spl_token::id() {
+            match TokenInstruction::unpack(&instruction.data)? {
+                TokenInstruction::Transfer { amount } => {
+                    token_transfer(instruction, account_infos, amount)
+                }
+                TokenInstruction::CloseAccount => token_close_account(instruction, account_infos),
+                _ => Err(ProgramError::InvalidInstructionData),
+            }
+        } else if instruction.program_id == system_program::id() {
+            match bincode::deserialize(&instruction.data)
+                .map_err(|_| ProgramError::InvalidInstructionData)?
+            {
+                SystemInstruction::CreateAccount {
+                    lamports,
+                    space,
+                    owner,
+                } => system_create_account(instruction, account_infos, lamports, space, owner),
+                SystemInstruction::Transfer { lamports } => {
+                    system_transfer(instruction, account_infos, lamports)
+                }
+                _ => Err(ProgramError::InvalidInstructionData),
+            }
+        } else {
+            Err(ProgramError::IncorrectProgramId)
+        }
+    }
+}
+
+fn find<'a, 'b>(account_infos: &'b [AccountInfo<'a>], key: &Pubkey) -> Option<&'b AccountInfo<'a>> {
+    account_infos.iter().find(|info| info.key == key)
+}
+
+fn token_transfer(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    amount: u64,
+) -> ProgramResult {
+    let (source_info, destination_info, authority) = match instruction.accounts.as_slice() {
+        [source, destination, authority, ..] => (
+            find(account_infos, &source.pubkey).unwrap(),
+            find(account_infos, &destination.pubkey).unwrap(),
+            authority.pubkey,
+        ),
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
+    };
+
+    if *source_info.owner != spl_token::id() || *destination_info.owner != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let mut source = Account::unpack(&source_info.data.borrow())?;
+    let mut destination = Account::unpack(&destination_info.data.borrow())?;
+    if source.mint != destination.mint {
+        return Err(TokenError::MintMismatch.into());
+    }
+    if source.owner != authority {
+        return Err(TokenError::OwnerMismatch.into());
+    }
+    if source.amount < amount {
+        return Err(TokenError::InsufficientFunds.into());
+    }
+    if source_info.key == destination_info.key {
+        return Ok(());
+    }
+
+    source.amount -= amount;
+    destination.amount = destination
+        .amount
+        .checked_add(amount)
+        .ok_or(TokenError::Overflow)?;
+    Account::pack(source, &mut source_info.data.borrow_mut())?;
+    Account::pack(destination, &mut destination_info.data.borrow_mut())?;
+
+    EFFECTS.with(|effects| {
+        effects.borrow_mut().transfers.push(Transfer {
+            source_owner: source.owner,
+            destination_owner: destination.owner,
+            mint: source.mint,
+            amount,
+        })
+    });
+    Ok(())
+}
+
+fn token_close_account(instruction: &Instruction, account_infos: &[AccountInfo]) -> ProgramResult {
+    let (account_info, destination_info, authority) = match instruction.accounts.as_slice() {
+        [account, destination, authority, ..] => (
+            find(account_infos, &account.pubkey).unwrap(),
+            find(account_infos, &destination.pubkey).unwrap(),
+            authority.pubkey,
+        ),
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
+    };
+
+    if *account_info.owner != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let account = Account::unpack(&account_info.data.borrow())?;
+    if account.close_authority.unwrap_or(account.owner) != authority {
+        return Err(TokenError::OwnerMismatch.into());
+    }
+    if account.amount != 0 {
+        return Err(TokenError::NonNativeHasBalance.into());
+    }
+    if account_info.key == destination_info.key {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let lamports = destination_info
+        .lamports()
+        .checked_add(account_info.lamports())
+        .ok_or(TokenError::Overflow)?;
+    **destination_info.lamports.borrow_mut() = lamports;
+    **account_info.lamports.borrow_mut() = 0;
+    account_info.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+fn system_create_account(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    lamports: u64,
+    space: u64,
+    owner: Pubkey,
+) -> ProgramResult {
+    let (funder_info, account_info) = match instruction.accounts.as_slice() {
+        [funder, account, ..] => (
+            find(account_infos, &funder.pubkey).unwrap(),
+            find(account_infos, &account.pubkey).unwrap(),
+        ),
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
+    };
+
+    if *funder_info.owner != system_program::id() || !funder_info.data_is_empty() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if account_info.lamports() > 0
+        || !account_info.data_is_empty()
+        || *account_info.owner != system_program::id()
+    {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    if funder_info.lamports() < lamports {
+        return Err(ProgramError::InsufficientFunds);
+    }
+    if space > MAX_ACCOUNT_SIZE {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    **funder_info.lamports.borrow_mut() -= lamports;
+    **account_info.lamports.borrow_mut() += lamports;
+
+    let data = Box::into_raw(vec![0u8; space as usize].into_boxed_slice());
+    EFFECTS.with(|effects| {
+        let mut effects = effects.borrow_mut();
+        effects.allocations.push(data);
+        effects.created.push((*account_info.key, owner));
+    });
+    // SAFETY: the allocation is only freed when the `Invocation` is dropped, which
+    // outlives the account infos
+    *account_info.data.borrow_mut() = unsafe { &mut *data };
+    Ok(())
+}
+
+fn system_transfer(
+    instruction: &Instruction,
+    account_infos: &[AccountInfo],
+    lamports: u64,
+) -> ProgramResult {
+    let (from_info, to_info) = match instruction.accounts.as_slice() {
+        [from, to, ..] => (
+            find(account_infos, &from.pubkey).unwrap(),
+            find(account_infos, &to.pubkey).unwrap(),
+        ),
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
+    };
+
+    if *from_info.owner != system_program::id() || !from_info.data_is_empty() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if from_info.lamports() < lamports {
+        return Err(ProgramError::InsufficientFunds);
+    }
+    if from_info.key == to_info.key {
+        return Ok(());
+    }
+
+    let balance = to_info
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::InvalidArgument)?;
+    **from_info.lamports.borrow_mut() -= lamports;
+    **to_info.lamports.borrow_mut() = balance;
+    Ok(())
+}
+
+/// Install the stubs, once per process
+pub fn install() {
+    static INSTALL: Once = Once::new();
+    INSTALL.call_once(|| {
+        program_stubs::set_syscall_stubs(Box::new(Stubs));
+    });
+}
+
+/// Collects the effects of the cross-program invocations made while processing one
+/// instruction.
+///
+/// Dropping it frees the data of accounts created during the instruction, so it has
+/// to outlive the `AccountInfo`s passed to the program.
+pub struct Invocation {
+    _private: (),
+}
+
+impl Invocation {
+    pub fn begin(program_id: &Pubkey) -> Invocation {
+        CALLER.with(|caller| *caller.borrow_mut() = *program_id);
+        EFFECTS.with(|effects| *effects.borrow_mut() = Effects::default());
+        Invocation { _private: () }
+    }
+
+    pub fn transfers(&self) -> Vec<Transfer> {
+        EFFECTS.with(|effects| effects.borrow().transfers.clone())
+    }
+
+    pub fn created(&self) -> Vec<(Pubkey, Pubkey)> {
+        EFFECTS.with(|effects| effects.borrow().created.clone())
+    }
+}
+
+impl Drop for Invocation {
+    fn drop(&mut self) {
+        EFFECTS.with(|effects| {
+            for data in effects.borrow_mut().allocations.drain(..) {
+                // SAFETY: created by `Box::into_raw` and no longer referenced
+                drop(unsafe { Box::from_raw(data) });
+            }
+        });
+    }
+}
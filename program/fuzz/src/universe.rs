@@ -0,0 +1,1280 @@
+//! The synthetic accounts the program is run against
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    clock::Clock,
+    instruction::Instruction,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+    sysvar::{self, Sysvar},
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account, AccountState, Mint};
+use treasury::{
+    account::{
+        Acceleration, EscrowTreasury, HurdleTreasury, MilestoneTreasury, SimpleTreasury,
+        SimpleTreasuryMode, SplitterTreasury, VestedTreasury,
+    },
+    config::Config,
+    instruction,
+    oracle::PriceFeed,
+    processor::Processor,
+    recovery::{Recovery, SocialRecovery},
+};
+
+use crate::{input::Input, stubs::Invocation};
+
+/// Enough lamports for any account the program creates
+const LAMPORTS: u64 = 1_000_000_000_000;
+/// Upper bound of accounts passed to an instruction made from raw bytes
+const MAX_RAW_ACCOUNTS: usize = 12;
+/// Upper bound of tampering done to an instruction built from a template
+const MAX_MUTATIONS: u8 = 4;
+
+/// A synthetic account
+#[derive(Clone, Debug)]
+pub struct FuzzAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub executable: bool,
+    /// Whether a transaction can carry this account's signature
+    pub can_sign: bool,
+}
+
+impl FuzzAccount {
+    fn new(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> FuzzAccount {
+        FuzzAccount {
+            key,
+            owner,
+            lamports: LAMPORTS,
+            data,
+            executable: false,
+            can_sign: false,
+        }
+    }
+
+    fn program(key: Pubkey) -> FuzzAccount {
+        FuzzAccount {
+            executable: true,
+            ..FuzzAccount::new(key, bpf_loader_upgradeable::id(), vec![])
+        }
+    }
+
+    fn keypair(key: Pubkey) -> FuzzAccount {
+        FuzzAccount {
+            can_sign: true,
+            ..FuzzAccount::new(key, system_program::id(), vec![])
+        }
+    }
+
+    fn sysvar<S: Sysvar>(sysvar: &S) -> FuzzAccount {
+        FuzzAccount::new(S::id(), sysvar::id(), bincode::serialize(sysvar).unwrap())
+    }
+
+    fn token_account(owner: &Pubkey, mint: &Pubkey, amount: u64) -> FuzzAccount {
+        let mut data = vec![0; Account::LEN];
+        Account::pack(
+            Account {
+                mint: *mint,
+                owner: *owner,
+                amount,
+                delegate: COption::None,
+                state: AccountState::Initialized,
+                is_native: COption::None,
+                delegated_amount: 0,
+                close_authority: COption::None,
+            },
+            &mut data,
+        )
+        .unwrap();
+        FuzzAccount::new(
+            get_associated_token_address(owner, mint),
+            spl_token::id(),
+            data,
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Simple,
+    Vested,
+    Splitter,
+    Escrow,
+    Milestone,
+    Hurdle,
+}
+
+/// A treasury the harness keeps track of, whether it exists yet or not
+#[derive(Debug)]
+struct Record {
+    key: Pubkey,
+    kind: Kind,
+    fund_authority: Pubkey,
+    /// The total amount of tokens that left accounts owned by the fund authority
+    released: u128,
+}
+
+enum Treasury {
+    Simple(SimpleTreasury),
+    Vested(VestedTreasury),
+    Splitter(Box<SplitterTreasury>),
+    Escrow(EscrowTreasury),
+    Milestone(Box<MilestoneTreasury>),
+    Hurdle(Box<HurdleTreasury>),
+}
+
+impl Treasury {
+    /// The key that can take tokens out of the fund, the first shareholder of a splitter
+    /// or the arbiter of an escrow
+    fn authority(&self) -> Pubkey {
+        match self {
+            Treasury::Simple(simple) => simple.authority,
+            Treasury::Vested(vested) => vested.authority,
+            Treasury::Splitter(splitter) => splitter.shareholders[0].recipient,
+            Treasury::Escrow(escrow) => escrow.arbiter,
+            Treasury::Milestone(milestone) => milestone.authority,
+            Treasury::Hurdle(hurdle) => hurdle.authority,
+        }
+    }
+
+    /// Whether tokens from the fund may go to accounts of this owner
+    fn pays(&self, owner: &Pubkey) -> bool {
+        match self {
+            Treasury::Splitter(splitter) => splitter
+                .shareholders()
+                .any(|holder| holder.recipient == *owner),
+            Treasury::Escrow(escrow) => escrow.payee == *owner || escrow.depositor == *owner,
+            _ => self.authority() == *owner,
+        }
+    }
+
+    fn mint(&self) -> Pubkey {
+        match self {
+            Treasury::Simple(simple) => simple.mint,
+            Treasury::Vested(vested) => vested.mint,
+            Treasury::Splitter(splitter) => splitter.mint,
+            Treasury::Escrow(escrow) => escrow.mint,
+            Treasury::Milestone(milestone) => milestone.mint,
+            Treasury::Hurdle(hurdle) => hurdle.mint,
+        }
+    }
+}
+
+/// An account passed to an instruction
+#[derive(Clone, Copy, Debug)]
+struct Meta {
+    index: usize,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Summary {
+    /// Instructions passed to the program
+    pub executed: usize,
+    /// Instructions that the runtime would have accepted
+    pub succeeded: usize,
+    /// Tokens released from treasury funds
+    pub released: u128,
+}
+
+pub struct Universe {
+    program_id: Pubkey,
+    accounts: Vec<FuzzAccount>,
+    records: Vec<Record>,
+    clock: Clock,
+    payer: Pubkey,
+    config: Pubkey,
+    mints: [Pubkey; 2],
+    /// Keys that can be treasury authorities
+    signers: Vec<Pubkey>,
+    summary: Summary,
+}
+
+/// The authority of a treasury and the records that let others take it over
+#[derive(Debug)]
+struct Custody {
+    authority: Pubkey,
+    recovery: Option<Recovery>,
+    social: Option<SocialRecovery>,
+}
+
+/// Fixed keys keep runs deterministic
+fn key(n: u8) -> Pubkey {
+    Pubkey::new_from_array([n; 32])
+}
+
+impl Universe {
+    /// Set up the accounts. Values that vary between runs are read from the input in
+    /// this order:
+    /// * clock: `u32` seconds added to the initial timestamp
+    /// * for each of the two vested treasuries: `u64` amount, `u32` period, `u16`
+    ///   percentage (modulo 10,000, plus one), `u32` age in seconds, `u64` fund balance
+    /// * for each of the two simple treasuries: `u64` fund balance
+    /// * for the vote escrow treasury: `u64` fund balance
+    /// * for the splitter treasury: `u64` fund balance
+    /// * for the escrow treasury: `u64` fund balance, `u32` seconds until it expires
+    /// * for the milestone treasury: `u64` fund balance
+    /// * for the hurdle treasury: `u64` fund balance, `u64` price published now, `u8`
+    ///   duration in seconds
+    pub fn new(input: &mut Input) -> Universe {
+        let program_id = key(1);
+        let payer = key(2);
+        let mints = [key(3), key(4)];
+        let alice = key(5);
+        let bob = key(6);
+        let attacker = key(7);
+        let signers = vec![alice, bob, attacker];
+        let (config, _) = Config::address(&program_id);
+
+        let clock = Clock {
+            slot: 1,
+            unix_timestamp: 1_600_000_000 + input.u32().unwrap_or(0) as i64,
+            ..Clock::default()
+        };
+
+        let mut universe = Universe {
+            program_id,
+            accounts: vec![
+                FuzzAccount::program(program_id),
+                FuzzAccount::program(spl_token::id()),
+                FuzzAccount::program(system_program::id()),
+                FuzzAccount::sysvar(&Rent::default()),
+                FuzzAccount::sysvar(&clock),
+                FuzzAccount::keypair(payer),
+            ],
+            records: vec![],
+            clock: clock.clone(),
+            payer,
+            config,
+            mints,
+            signers: signers.clone(),
+            summary: Summary::default(),
+        };
+
+        for mint in &mints {
+            let mut data = vec![0; Mint::LEN];
+            Mint::pack(
+                Mint {
+                    mint_authority: COption::None,
+                    supply: 0,
+                    decimals: 0,
+                    is_initialized: true,
+                    freeze_authority: COption::None,
+                },
+                &mut data,
+            )
+            .unwrap();
+            universe
+                .accounts
+                .push(FuzzAccount::new(*mint, spl_token::id(), data));
+        }
+
+        for signer in &signers {
+            universe.accounts.push(FuzzAccount::keypair(*signer));
+            for mint in &mints {
+                universe
+                    .accounts
+                    .push(FuzzAccount::token_account(signer, mint, 0));
+            }
+        }
+
+        // alice can upgrade the program and so initialize the config, which doesn't
+        // exist yet, its fee vaults do
+        let (program_data, _) =
+            Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+        let state = UpgradeableLoaderState::ProgramData {
+            slot: 0,
+            upgrade_authority_address: Some(alice),
+        };
+        universe.accounts.push(FuzzAccount::new(
+            program_data,
+            bpf_loader_upgradeable::id(),
+            bincode::serialize(&state).unwrap(),
+        ));
+        universe.accounts.push(FuzzAccount {
+            lamports: 0,
+            ..FuzzAccount::new(config, system_program::id(), vec![])
+        });
+        for mint in &mints {
+            universe
+                .accounts
+                .push(FuzzAccount::token_account(&config, mint, 0));
+        }
+
+        for (n, authority) in [(8, bob), (9, alice)].iter() {
+            let amount = input.u64().unwrap_or(1_000).max(1);
+            let period = input.u32().unwrap_or(60).max(1) as u64;
+            let percentage = input.u16().unwrap_or(499) % VestedTreasury::MAX_PERCENTAGE + 1;
+            let age = input.u32().unwrap_or(0) as i64;
+            let balance = input.u64().unwrap_or(amount);
+
+            let vested = VestedTreasury {
+                mint: mints[0],
+                authority: *authority,
+                initial_amount: amount,
+                start: clock.unix_timestamp - age,
+                vestment_period: period,
+                vestment_percentage: percentage,
+                withdrawn: 0,
+                grantor: payer,
+                paused_at: 0,
+                accelerated: 0,
+                delegate: Pubkey::default(),
+            };
+            universe.add_treasury(key(*n), Kind::Vested, vested.try_to_vec().ok(), balance);
+        }
+
+        for (n, mode) in [
+            (10, SimpleTreasuryMode::Locked),
+            (11, SimpleTreasuryMode::Unlocked),
+        ]
+        .iter()
+        {
+            let balance = input.u64().unwrap_or(1_000);
+            let simple = SimpleTreasury {
+                mint: mints[0],
+                mode: *mode,
+                authority: alice,
+                lock_end: 0,
+                weight: 0,
+                weight_updated_at: 0,
+            };
+            universe.add_treasury(key(*n), Kind::Simple, simple.try_to_vec().ok(), balance);
+        }
+
+        // keys that can become new treasuries, with funds ready for either kind
+        for n in &[12, 13] {
+            universe.accounts.push(FuzzAccount {
+                lamports: 0,
+                ..FuzzAccount::keypair(key(*n))
+            });
+            universe.add_treasury(key(*n), Kind::Simple, None, 0);
+            universe.add_treasury(key(*n), Kind::Vested, None, 0);
+        }
+
+        // a vote escrow treasury that isn't locked yet
+        let balance = input.u64().unwrap_or(1_000);
+        let escrow = SimpleTreasury {
+            mint: mints[0],
+            mode: SimpleTreasuryMode::VoteEscrow,
+            authority: bob,
+            lock_end: 0,
+            weight: 0,
+            weight_updated_at: 0,
+        };
+        universe.add_treasury(key(14), Kind::Simple, escrow.try_to_vec().ok(), balance);
+
+        let balance = input.u64().unwrap_or(1_000);
+        let splitter = SplitterTreasury::new(mints[0], &[(alice, 6_000), (bob, 4_000)]).unwrap();
+        universe.add_treasury(key(15), Kind::Splitter, splitter.try_to_vec().ok(), balance);
+
+        // the attacker arbitrates, which still doesn't let them take the deposit
+        let balance = input.u64().unwrap_or(1_000);
+        let expiry = clock.unix_timestamp + input.u32().unwrap_or(0) as i64;
+        let deal = EscrowTreasury {
+            mint: mints[0],
+            depositor: alice,
+            payee: bob,
+            arbiter: attacker,
+            expiry,
+        };
+        universe.add_treasury(key(16), Kind::Escrow, deal.try_to_vec().ok(), balance);
+
+        let balance = input.u64().unwrap_or(1_000);
+        let milestones =
+            MilestoneTreasury::new(mints[0], alice, &[(500, bob), (500, attacker)]).unwrap();
+        universe.add_treasury(
+            key(17),
+            Kind::Milestone,
+            milestones.try_to_vec().ok(),
+            balance,
+        );
+
+        // the price never changes, but goes stale after a minute
+        let balance = input.u64().unwrap_or(1_000);
+        let feed = PriceFeed {
+            price: input.u64().unwrap_or(1_000),
+            publish_time: clock.unix_timestamp,
+        };
+        universe.accounts.push(FuzzAccount::new(
+            key(18),
+            key(19),
+            feed.try_to_vec().unwrap(),
+        ));
+        let duration = input.u8().unwrap_or(30) as u64;
+        let hurdles = HurdleTreasury::new(
+            mints[0],
+            alice,
+            key(18),
+            duration,
+            &[(500, 100), (500, 10_000)],
+        )
+        .unwrap();
+        universe.add_treasury(key(20), Kind::Hurdle, hurdles.try_to_vec().ok(), balance);
+
+        universe
+    }
+
+    /// Track a treasury and create its fund authority and funds, one for the
+    /// treasury's mint and one for the other mint
+    fn add_treasury(&mut self, key: Pubkey, kind: Kind, data: Option<Vec<u8>>, balance: u64) {
+        if let Some(data) = data {
+            self.accounts
+                .push(FuzzAccount::new(key, self.program_id, data));
+        }
+
+        let (fund_authority, _) = match kind {
+            Kind::Simple => SimpleTreasury::fund_authority_address(&key, &self.program_id),
+            Kind::Vested => VestedTreasury::fund_authority_address(&key, &self.program_id),
+            Kind::Splitter => SplitterTreasury::fund_authority_address(&key, &self.program_id),
+            Kind::Escrow => EscrowTreasury::fund_authority_address(&key, &self.program_id),
+            Kind::Milestone => MilestoneTreasury::fund_authority_address(&key, &self.program_id),
+            Kind::Hurdle => HurdleTreasury::fund_authority_address(&key, &self.program_id),
+        };
+        self.accounts.push(FuzzAccount::new(
+            fund_authority,
+            system_program::id(),
+            vec![],
+        ));
+        // simple and vested treasuries can get a recovery and guardians, which don't
+        // exist yet
+        if matches!(kind, Kind::Simple | Kind::Vested) {
+            for (address, _) in [
+                Recovery::address(&key, &self.program_id),
+                SocialRecovery::address(&key, &self.program_id),
+            ] {
+                if self.index_of(&address).is_none() {
+                    self.accounts.push(FuzzAccount {
+                        lamports: 0,
+                        ..FuzzAccount::new(address, system_program::id(), vec![])
+                    });
+                }
+            }
+        }
+        self.accounts.push(FuzzAccount::token_account(
+            &fund_authority,
+            &self.mints[0],
+            balance,
+        ));
+        self.accounts.push(FuzzAccount::token_account(
+            &fund_authority,
+            &self.mints[1],
+            balance,
+        ));
+
+        self.records.push(Record {
+            key,
+            kind,
+            fund_authority,
+            released: 0,
+        });
+    }
+
+    pub fn summary(&self) -> Summary {
+        self.summary
+    }
+
+    fn index_of(&self, key: &Pubkey) -> Option<usize> {
+        self.accounts.iter().position(|account| account.key == *key)
+    }
+
+    /// Run the next step from the input, `None` once the input is exhausted
+    pub fn step(&mut self, input: &mut Input) -> Option<()> {
+        match input.u8()? % 4 {
+            0 => {
+                let seconds = input.u32()?;
+                self.warp(seconds as i64);
+            }
+            1 => {
+                let (data, metas) = self.raw(input)?;
+                self.execute(&data, &metas);
+            }
+            _ => {
+                let (data, metas) = self.template(input)?;
+                self.execute(&data, &metas);
+            }
+        }
+        Some(())
+    }
+
+    fn warp(&mut self, seconds: i64) {
+        self.clock.slot += 1;
+        self.clock.unix_timestamp += seconds;
+        let index = self.index_of(&sysvar::clock::id()).unwrap();
+        self.accounts[index] = FuzzAccount::sysvar(&self.clock);
+    }
+
+    /// An instruction made of arbitrary data and accounts
+    fn raw(&self, input: &mut Input) -> Option<(Vec<u8>, Vec<Meta>)> {
+        let len = input.u8()? as usize % 64;
+        let data = input.bytes(len)?.to_vec();
+        let count = input.u8()? as usize % MAX_RAW_ACCOUNTS;
+        let metas = (0..count)
+            .map(|_| {
+                let index = input.u8()? as usize % self.accounts.len();
+                let flags = input.u8()?;
+                Some(Meta {
+                    index,
+                    is_signer: flags & 1 != 0,
+                    is_writable: flags & 2 != 0,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some((data, metas))
+    }
+
+    /// A well-formed instruction for one of the treasuries that is then tampered with
+    fn template(&self, input: &mut Input) -> Option<(Vec<u8>, Vec<Meta>)> {
+        let record = &self.records[input.u8()? as usize % self.records.len()];
+        let authority = match self.treasury(record) {
+            Some(treasury) => treasury.authority(),
+            None => self.signers[0],
+        };
+        let mint = self.mints[0];
+        let recipient = get_associated_token_address(&authority, &mint);
+
+        let instruction: Instruction = match input.u8()? % 34 {
+            0 => instruction::withdraw_simple(
+                &self.program_id,
+                &self.payer,
+                &authority,
+                &recipient,
+                &record.key,
+                &mint,
+                input.u64()?,
+            ),
+            1 => instruction::withdraw_vested(
+                &self.program_id,
+                &self.payer,
+                &authority,
+                &recipient,
+                &record.key,
+                &mint,
+                match input.u8()? % 2 {
+                    0 => None,
+                    _ => Some(input.u64()?),
+                },
+            ),
+            2 => instruction::query_vested(&self.program_id, &record.key, &mint),
+            3 => instruction::create_simple_treasury(
+                &self.program_id,
+                &self.payer,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                &record.key,
+                &mint,
+                match input.u8()? % 3 {
+                    0 => SimpleTreasuryMode::Locked,
+                    1 => SimpleTreasuryMode::Unlocked,
+                    _ => SimpleTreasuryMode::VoteEscrow,
+                },
+            ),
+            4 => instruction::create_vested_treasury(
+                &self.program_id,
+                &self.payer,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                &self.payer,
+                &record.key,
+                &mint,
+                input.u64()?,
+                input.u64()?,
+                input.u16()?,
+            ),
+            5 => instruction::pause_vesting(&self.program_id, &self.payer, &record.key),
+            6 => instruction::resume_vesting(&self.program_id, &self.payer, &record.key),
+            7 => instruction::accelerate_vesting(
+                &self.program_id,
+                &self.payer,
+                &record.key,
+                if input.u8()? % 2 == 0 {
+                    Acceleration::Amount(input.u64()?)
+                } else {
+                    Acceleration::Percentage(input.u16()?)
+                },
+            ),
+            8 => instruction::split_vested(
+                &self.program_id,
+                &self.payer,
+                &authority,
+                &record.key,
+                &key(12 + input.u8()? % 2),
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                &mint,
+                input.u64()?,
+            ),
+            9 => instruction::merge_vested(
+                &self.program_id,
+                &authority,
+                &record.key,
+                &self.records[input.u8()? as usize % self.records.len()].key,
+                &self.payer,
+                &mint,
+            ),
+            10 => instruction::set_vested_delegate(
+                &self.program_id,
+                &authority,
+                &record.key,
+                match input.u8()? as usize % (self.signers.len() + 1) {
+                    0 => None,
+                    n => Some(self.signers[n - 1]),
+                },
+            ),
+            11 => instruction::extend_lock(
+                &self.program_id,
+                &authority,
+                &record.key,
+                &mint,
+                self.clock.unix_timestamp + input.u32()? as i64,
+            ),
+            12 => instruction::update_vote_escrow_weight(&self.program_id, &record.key, &mint),
+            13 => {
+                let share = input.u16()?;
+                instruction::create_splitter_treasury(
+                    &self.program_id,
+                    &self.payer,
+                    &record.key,
+                    &mint,
+                    vec![
+                        (
+                            self.signers[input.u8()? as usize % self.signers.len()],
+                            share,
+                        ),
+                        (
+                            self.signers[input.u8()? as usize % self.signers.len()],
+                            10_000u16.wrapping_sub(share),
+                        ),
+                    ],
+                )
+            }
+            14 => {
+                let shareholder = self.signers[input.u8()? as usize % self.signers.len()];
+                instruction::claim_split(
+                    &self.program_id,
+                    &shareholder,
+                    &get_associated_token_address(&shareholder, &mint),
+                    &record.key,
+                    &mint,
+                )
+            }
+            15 => instruction::release_escrow(
+                &self.program_id,
+                &authority,
+                &get_associated_token_address(
+                    &self.signers[input.u8()? as usize % self.signers.len()],
+                    &mint,
+                ),
+                &record.key,
+                &mint,
+            ),
+            16 => instruction::refund_escrow(
+                &self.program_id,
+                &authority,
+                &get_associated_token_address(
+                    &self.signers[input.u8()? as usize % self.signers.len()],
+                    &mint,
+                ),
+                &record.key,
+                &mint,
+            ),
+            17 => instruction::refund_expired_escrow(
+                &self.program_id,
+                &get_associated_token_address(
+                    &self.signers[input.u8()? as usize % self.signers.len()],
+                    &mint,
+                ),
+                &record.key,
+                &mint,
+            ),
+            18 => instruction::confirm_milestone(
+                &self.program_id,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                &record.key,
+                input.u8()?,
+            ),
+            19 => instruction::withdraw_milestones(
+                &self.program_id,
+                &authority,
+                &recipient,
+                &record.key,
+                &mint,
+            ),
+            20 => instruction::update_hurdles(&self.program_id, &record.key, &key(18)),
+            21 => instruction::withdraw_hurdles(
+                &self.program_id,
+                &authority,
+                &recipient,
+                &record.key,
+                &mint,
+            ),
+            22 => instruction::initialize_config(
+                &self.program_id,
+                &self.payer,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                input.u32()? as u64,
+                input.u16()?,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+            ),
+            23 => instruction::update_config(
+                &self.program_id,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                input.u32()? as u64,
+                input.u16()?,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+            ),
+            24 => {
+                let fee_receiver = self.signers[input.u8()? as usize % self.signers.len()];
+                instruction::collect_fees(
+                    &self.program_id,
+                    &fee_receiver,
+                    &get_associated_token_address(&fee_receiver, &mint),
+                    &mint,
+                )
+            }
+            25 => instruction::pause_program(
+                &self.program_id,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+            ),
+            26 => instruction::unpause_program(
+                &self.program_id,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+            ),
+            27 => instruction::set_recovery(
+                &self.program_id,
+                &self.payer,
+                &authority,
+                &record.key,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                input.u32()? as u64,
+            ),
+            28 => instruction::heartbeat(&self.program_id, &authority, &record.key),
+            29 => instruction::claim_recovery(
+                &self.program_id,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                &record.key,
+            ),
+            30 => instruction::set_guardians(
+                &self.program_id,
+                &self.payer,
+                &authority,
+                &record.key,
+                &self.signers[..input.u8()? as usize % self.signers.len() + 1],
+                input.u8()? % 4,
+                input.u32()? as u64,
+            ),
+            31 => instruction::approve_rotation(
+                &self.program_id,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                &record.key,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+            ),
+            32 => instruction::cancel_rotation(&self.program_id, &authority, &record.key),
+            _ => instruction::execute_rotation(
+                &self.program_id,
+                &self.signers[input.u8()? as usize % self.signers.len()],
+                &record.key,
+            ),
+        };
+
+        let mut metas = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                Some(Meta {
+                    index: self.index_of(&meta.pubkey)?,
+                    is_signer: meta.is_signer,
+                    is_writable: meta.is_writable,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        for _ in 0..input.u8()? % MAX_MUTATIONS {
+            let slot = input.u8()? as usize % metas.len();
+            match input.u8()? % 4 {
+                // replace with any account
+                0 => metas[slot].index = input.u8()? as usize % self.accounts.len(),
+                // swap with another slot
+                1 => {
+                    let other = input.u8()? as usize % metas.len();
+                    metas.swap(slot, other);
+                }
+                // duplicate another slot
+                2 => {
+                    let other = input.u8()? as usize % metas.len();
+                    metas[slot].index = metas[other].index;
+                }
+                // change the flags
+                _ => {
+                    let flags = input.u8()?;
+                    metas[slot].is_signer = flags & 1 != 0;
+                    metas[slot].is_writable = flags & 2 != 0;
+                }
+            }
+        }
+
+        Some((instruction.data, metas))
+    }
+
+    fn treasury(&self, record: &Record) -> Option<Treasury> {
+        decode(&self.accounts, &self.program_id, record)
+    }
+
+    /// Process an instruction and keep its changes if the runtime would have
+    /// accepted them
+    fn execute(&mut self, data: &[u8], metas: &[Meta]) {
+        self.summary.executed += 1;
+        let custody = self.custody();
+
+        // signer and writable flags apply to every occurrence of an account and only
+        // keypairs can sign a transaction
+        let mut unique: Vec<Meta> = vec![];
+        for meta in metas {
+            match unique.iter_mut().find(|u| u.index == meta.index) {
+                Some(u) => {
+                    u.is_signer |= meta.is_signer;
+                    u.is_writable |= meta.is_writable;
+                }
+                None => unique.push(*meta),
+            }
+        }
+        for u in &mut unique {
+            u.is_signer &= self.accounts[u.index].can_sign;
+        }
+
+        let mut working: Vec<FuzzAccount> = unique
+            .iter()
+            .map(|u| self.accounts[u.index].clone())
+            .collect();
+
+        // declared before the account infos so it is dropped after them
+        let invocation = Invocation::begin(&self.program_id);
+        let (result, after) = {
+            let infos: Vec<AccountInfo> = working
+                .iter_mut()
+                .zip(&unique)
+                .map(|(account, u)| {
+                    let FuzzAccount {
+                        key,
+                        owner,
+                        lamports,
+                        data,
+                        executable,
+                        ..
+                    } = account;
+                    AccountInfo::new(
+                        key,
+                        u.is_signer,
+                        u.is_writable,
+                        lamports,
+                        data.as_mut_slice(),
+                        owner,
+                        *executable,
+                        0,
+                    )
+                })
+                .collect();
+            let account_infos: Vec<AccountInfo> = metas
+                .iter()
+                .map(|meta| {
+                    let position = unique.iter().position(|u| u.index == meta.index);
+                    infos[position.unwrap()].clone()
+                })
+                .collect();
+
+            let result = Processor::process(&self.program_id, &account_infos, data);
+            let after: Vec<(u64, Vec<u8>)> = infos
+                .iter()
+                .map(|info| (info.lamports(), info.data.borrow().to_vec()))
+                .collect();
+            (result, after)
+        };
+        let transfers = invocation.transfers();
+        let created = invocation.created();
+        drop(invocation);
+
+        if result.is_err() {
+            return;
+        }
+
+        let lamports_before: u128 = unique
+            .iter()
+            .map(|u| self.accounts[u.index].lamports as u128)
+            .sum();
+        let lamports_after: u128 = after.iter().map(|(lamports, _)| *lamports as u128).sum();
+        assert_eq!(lamports_before, lamports_after, "lamports were created");
+
+        // the runtime rejects changes to read-only accounts and data changes to
+        // accounts owned by other programs
+        for (u, (lamports, data)) in unique.iter().zip(&after) {
+            let account = &self.accounts[u.index];
+            let data_changed = *data != account.data;
+            if (data_changed || *lamports != account.lamports) && !u.is_writable {
+                return;
+            }
+            let created = created.iter().any(|(key, _)| *key == account.key);
+            if data_changed
+                && !created
+                && account.owner != self.program_id
+                && account.owner != spl_token::id()
+            {
+                return;
+            }
+        }
+
+        self.summary.succeeded += 1;
+
+        for (u, (lamports, data)) in unique.iter().zip(after) {
+            let account = &mut self.accounts[u.index];
+            account.lamports = lamports;
+            account.data = data;
+            if let Some((_, owner)) = created.iter().find(|(key, _)| *key == account.key) {
+                account.owner = *owner;
+            }
+        }
+
+        if self.config().is_some_and(|config| config.paused) {
+            for (key, _) in &created {
+                assert!(
+                    self.records.iter().all(|record| record.key != *key),
+                    "{} created while paused",
+                    key
+                );
+            }
+        }
+        self.check_transfers(&transfers);
+        self.check_treasuries();
+        self.check_authorities(&custody);
+    }
+
+    /// Who controls every existing treasury and who could take it over
+    fn custody(&self) -> Vec<Option<Custody>> {
+        self.records
+            .iter()
+            .map(|record| {
+                Some(Custody {
+                    authority: self.treasury(record)?.authority(),
+                    recovery: self.recovery(record),
+                    social: self.social_recovery(record),
+                })
+            })
+            .collect()
+    }
+
+    /// A treasury only changes hands when the backup of its recovery claims it after the
+    /// authority was inactive for the whole inactivity period, or when enough of its
+    /// guardians rotated the authority and the challenge delay passed
+    fn check_authorities(&self, before: &[Option<Custody>]) {
+        let now = self.clock.unix_timestamp;
+        for (record, before) in self.records.iter().zip(before) {
+            let (before, authority) = match (before, self.treasury(record)) {
+                (Some(before), Some(after)) if before.authority != after.authority() => {
+                    (before, after.authority())
+                }
+                _ => continue,
+            };
+            let claimed = before.recovery.is_some_and(|recovery| {
+                recovery.backup == authority && now >= recovery.claimable_at()
+            });
+            let rotated = before.social.is_some_and(|social| {
+                social.new_authority == authority
+                    && social.effective_at != 0
+                    && now >= social.effective_at
+                    && social.approvals_of(&authority) >= social.threshold as usize
+            });
+            assert!(
+                claimed || rotated,
+                "{:?} changed hands from {} to {} without a recovery: {:?}",
+                record,
+                before.authority,
+                authority,
+                before
+            );
+        }
+    }
+
+    /// The guardians of a treasury, if they were set up
+    fn social_recovery(&self, record: &Record) -> Option<SocialRecovery> {
+        let (address, _) = SocialRecovery::address(&record.key, &self.program_id);
+        let account = self
+            .accounts
+            .iter()
+            .find(|account| account.key == address)?;
+        if account.owner != self.program_id || account.lamports == 0 {
+            return None;
+        }
+        SocialRecovery::try_from_slice(&account.data).ok()
+    }
+
+    /// The recovery of a treasury, if one was set up
+    fn recovery(&self, record: &Record) -> Option<Recovery> {
+        let (address, _) = Recovery::address(&record.key, &self.program_id);
+        let account = self
+            .accounts
+            .iter()
+            .find(|account| account.key == address)?;
+        if account.owner != self.program_id || account.lamports == 0 {
+            return None;
+        }
+        Recovery::try_from_slice(&account.data).ok()
+    }
+
+    /// Tokens may only leave a treasury's fund towards its authority, or towards the
+    /// fund of another vested treasury when one is split off or merged into another.
+    /// Locked simple treasuries and vote escrow treasuries before their lock ends never
+    /// release anything, escrows only pay the payee before they expire. Withdrawal fees
+    /// never exceed the configured share of what leaves a fund and collected fees only
+    /// go to the fee receiver. Nothing leaves a fund while the program is paused.
+    fn check_transfers(&mut self, transfers: &[crate::stubs::Transfer]) {
+        let config = self.config();
+        let paused = config.is_some_and(|config| config.paused);
+        // per record, the fees and the total that left its fund
+        let mut fees = vec![(0u128, 0u128); self.records.len()];
+        for transfer in transfers {
+            if transfer.source_owner == self.config {
+                let config = config.expect("fees collected without a config");
+                assert_eq!(
+                    transfer.destination_owner, config.fee_receiver,
+                    "{:?} collected by someone other than the fee receiver",
+                    transfer
+                );
+                continue;
+            }
+
+            let source = match self
+                .records
+                .iter()
+                .position(|record| record.fund_authority == transfer.source_owner)
+            {
+                Some(source) => source,
+                None => continue,
+            };
+            assert!(!paused, "{:?} left a fund while paused", transfer);
+            if let Some(destination) = self
+                .records
+                .iter()
+                .position(|record| record.fund_authority == transfer.destination_owner)
+            {
+                self.check_move(transfer, source, destination);
+                continue;
+            }
+
+            let record = &mut self.records[source];
+            let treasury = decode(&self.accounts, &self.program_id, record).unwrap_or_else(|| {
+                panic!(
+                    "{:?} released from the fund of a treasury that doesn't exist: {:?}",
+                    transfer, record
+                )
+            });
+            let fee = transfer.destination_owner == self.config;
+            assert!(
+                fee || treasury.pays(&transfer.destination_owner),
+                "{:?} released to someone other than the authority of {:?}",
+                transfer,
+                record
+            );
+            assert_eq!(
+                transfer.mint,
+                treasury.mint(),
+                "{:?} of the wrong mint",
+                transfer
+            );
+            if let Treasury::Simple(simple) = treasury {
+                let locked = match simple.mode {
+                    SimpleTreasuryMode::Locked => true,
+                    SimpleTreasuryMode::Unlocked => false,
+                    SimpleTreasuryMode::VoteEscrow => self.clock.unix_timestamp < simple.lock_end,
+                };
+                assert!(!locked, "{:?} released from locked {:?}", transfer, simple);
+            }
+            if let Treasury::Escrow(escrow) = treasury {
+                assert!(
+                    transfer.destination_owner != escrow.payee
+                        || self.clock.unix_timestamp < escrow.expiry,
+                    "{:?} released expired {:?}",
+                    transfer,
+                    escrow
+                );
+            }
+
+            record.released += transfer.amount as u128;
+            self.summary.released += transfer.amount as u128;
+            if fee {
+                fees[source].0 += transfer.amount as u128;
+            }
+            fees[source].1 += transfer.amount as u128;
+        }
+
+        for ((fee, total), record) in fees.into_iter().zip(&self.records) {
+            if fee == 0 {
+                continue;
+            }
+            let withdrawal_fee = config.map_or(0, |config| config.withdrawal_fee);
+            assert!(
+                fee * 10_000 <= total * withdrawal_fee as u128,
+                "fee of {} out of {} exceeds {} basis points: {:?}",
+                fee,
+                total,
+                withdrawal_fee,
+                record
+            );
+        }
+    }
+
+    /// The program config, if it was initialized
+    fn config(&self) -> Option<Config> {
+        let account = self
+            .accounts
+            .iter()
+            .find(|account| account.key == self.config)?;
+        if account.owner != self.program_id {
+            return None;
+        }
+        Config::try_from_slice(&account.data).ok()
+    }
+
+    /// Tokens moving between the funds of two vested treasuries take the matching part
+    /// of `withdrawn` along, so together they still account for everything that left
+    /// either fund. The source no longer exists after a merge.
+    fn check_move(&mut self, transfer: &crate::stubs::Transfer, source: usize, destination: usize) {
+        let vested =
+            |index: usize| match decode(&self.accounts, &self.program_id, &self.records[index]) {
+                Some(Treasury::Vested(vested)) => Some(vested),
+                Some(Treasury::Simple(_))
+                | Some(Treasury::Splitter(_))
+                | Some(Treasury::Escrow(_))
+                | Some(Treasury::Milestone(_))
+                | Some(Treasury::Hurdle(_)) => panic!(
+                    "{:?} moved between funds of treasuries that aren't vested: {:?} {:?}",
+                    transfer, self.records[source], self.records[destination]
+                ),
+                None => None,
+            };
+        let from = vested(source);
+        let to = vested(destination).unwrap_or_else(|| {
+            panic!(
+                "{:?} moved to the fund of a treasury that doesn't exist: {:?}",
+                transfer, self.records[destination]
+            )
+        });
+        assert_eq!(transfer.mint, to.mint, "{:?} of the wrong mint", transfer);
+        if let Some(from) = from {
+            assert_eq!(
+                from.mint, to.mint,
+                "{:?} moved to a different mint",
+                transfer
+            );
+        }
+
+        let released = self.records[source].released + self.records[destination].released;
+        let from_withdrawn = from.map_or(0, |from| from.withdrawn as u128);
+        assert_eq!(
+            released,
+            from_withdrawn + to.withdrawn as u128,
+            "{:?} changed the total withdrawn amount",
+            transfer
+        );
+        self.records[source].released = from_withdrawn;
+        self.records[destination].released = to.withdrawn as u128;
+    }
+
+    /// Vested, milestone and hurdle treasuries never release more than they unlocked,
+    /// and they and splitter treasuries keep track of everything that left the fund
+    fn check_treasuries(&self) {
+        for record in &self.records {
+            match decode(&self.accounts, &self.program_id, record) {
+                Some(Treasury::Vested(vested)) => {
+                    assert!(
+                        vested.withdrawn <= vested.initial_amount,
+                        "withdrew more than the initial amount: {:?}",
+                        vested
+                    );
+                    assert_eq!(
+                        record.released, vested.withdrawn as u128,
+                        "fund released a different amount than withdrawn: {:?}",
+                        vested
+                    );
+                }
+                Some(Treasury::Splitter(splitter)) => {
+                    let released: u128 = splitter
+                        .shareholders()
+                        .map(|holder| holder.released as u128)
+                        .sum();
+                    assert_eq!(
+                        released, splitter.total_released as u128,
+                        "shareholders released a different amount than the total: {:?}",
+                        splitter
+                    );
+                    assert_eq!(
+                        record.released, released,
+                        "fund released a different amount than claimed: {:?}",
+                        splitter
+                    );
+                }
+                Some(Treasury::Milestone(milestone)) => {
+                    assert!(
+                        milestone.withdrawn <= milestone.unlocked(),
+                        "withdrew more than the confirmed milestones: {:?}",
+                        milestone
+                    );
+                    assert_eq!(
+                        record.released, milestone.withdrawn as u128,
+                        "fund released a different amount than withdrawn: {:?}",
+                        milestone
+                    );
+                }
+                Some(Treasury::Hurdle(hurdle)) => {
+                    assert!(
+                        hurdle.withdrawn <= hurdle.unlocked(),
+                        "withdrew more than the unlocked tranches: {:?}",
+                        hurdle
+                    );
+                    assert_eq!(
+                        record.released, hurdle.withdrawn as u128,
+                        "fund released a different amount than withdrawn: {:?}",
+                        hurdle
+                    );
+                }
+                Some(Treasury::Simple(_)) | Some(Treasury::Escrow(_)) => {}
+                None => assert_eq!(record.released, 0),
+            }
+        }
+    }
+}
+
+fn decode(accounts: &[FuzzAccount], program_id: &Pubkey, record: &Record) -> Option<Treasury> {
+    let account = accounts.iter().find(|account| account.key == record.key)?;
+    // closed accounts are removed at the end of the transaction
+    if account.owner != *program_id || account.lamports == 0 {
+        return None;
+    }
+    match record.kind {
+        Kind::Simple => SimpleTreasury::try_from_slice(&account.data)
+            .ok()
+            .map(Treasury::Simple),
+        Kind::Vested => VestedTreasury::try_from_slice(&account.data)
+            .ok()
+            .map(Treasury::Vested),
+        Kind::Splitter => SplitterTreasury::try_from_slice(&account.data)
+            .ok()
+            .map(|splitter| Treasury::Splitter(Box::new(splitter))),
+        Kind::Escrow => EscrowTreasury::try_from_slice(&account.data)
+            .ok()
+            .map(Treasury::Escrow),
+        Kind::Milestone => MilestoneTreasury::try_from_slice(&account.data)
+            .ok()
+            .map(|milestone| Treasury::Milestone(Box::new(milestone))),
+        Kind::Hurdle => HurdleTreasury::try_from_slice(&account.data)
+            .ok()
+            .map(|hurdle| Treasury::Hurdle(Box::new(hurdle))),
+    }
+}
@@ -1,38 +1,83 @@
-use crate::error::TreasuryError;
+use crate::{
+    error::TreasuryError,
+    zero_copy::{impl_borsh_as_bytes, PodBool, ZeroCopy},
+};
 use borsh::{BorshDeserialize, BorshSerialize};
+use bytemuck::{CheckedBitPattern, NoUninit, Pod, Zeroable};
 use solana_program::{
     account_info::AccountInfo, clock::UnixTimestamp, msg, program_error::ProgramError,
     pubkey::Pubkey,
 };
+use spl_associated_token_account::get_associated_token_address;
+use std::cell::{Ref, RefMut};
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+#[repr(u8)]
+#[derive(
+    Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize, CheckedBitPattern, NoUninit,
+)]
 pub enum SimpleTreasuryMode {
     Locked,
     Unlocked,
+    /// Locked until `lock_end`, with a weight that decays as the end approaches
+    VoteEscrow,
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, CheckedBitPattern, NoUninit)]
 pub struct SimpleTreasury {
     pub mint: Pubkey,
     pub mode: SimpleTreasuryMode,
     pub authority: Pubkey,
+    /// When a vote escrow treasury unlocks, 0 for other modes
+    pub lock_end: UnixTimestamp,
+    /// The vote escrow weight as of `weight_updated_at`
+    pub weight: u64,
+    pub weight_updated_at: UnixTimestamp,
 }
 
+impl ZeroCopy for SimpleTreasury {}
+impl_borsh_as_bytes!(SimpleTreasury);
+
 impl SimpleTreasury {
-    pub fn from_account_info(
-        treasury_info: &AccountInfo,
+    /// The longest a vote escrow treasury can be locked for, four years
+    pub const MAX_LOCK_DURATION: i64 = 4 * 365 * 24 * 60 * 60;
+
+    /// The size of a serialized simple treasury
+    pub const LEN: usize = 89;
+    /// The sizes of earlier layouts, which `MigrateTreasury` grows to `LEN`
+    pub const LEGACY_LENS: [usize; 1] = [65];
+    /// The offset of `mint` in a serialized simple treasury
+    pub const MINT_OFFSET: usize = 0;
+    /// The offset of `authority` in a serialized simple treasury
+    pub const AUTHORITY_OFFSET: usize = 33;
+    /// The offset of `weight` in a serialized simple treasury
+    pub const WEIGHT_OFFSET: usize = 73;
+
+    pub fn load<'a>(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Ref<'a, Self>, ProgramError> {
+        check_owner(treasury_info, program_id)?;
+        Self::borrow(treasury_info).ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+    }
+
+    /// Borrow the treasury to modify it in place
+    pub fn load_mut<'a>(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<RefMut<'a, Self>, ProgramError> {
+        check_owner(treasury_info, program_id)?;
+        Self::borrow_mut(treasury_info)
+            .ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+    }
+
+    /// Borrow the treasury to modify it, it has to be signed for by the authority
+    pub fn from_account_info<'a>(
+        treasury_info: &'a AccountInfo,
         authority_info: &AccountInfo,
         program_id: &Pubkey,
-    ) -> Result<SimpleTreasury, ProgramError> {
-        // treasury account checks
-        if *treasury_info.owner != *program_id {
-            msg!("treasury account not owned by program");
-            return Err(TreasuryError::InvalidTreasuryFundAccount.into());
-        }
-        let treasury = Self::try_from_slice(&treasury_info.data.borrow())
-            .map_err(|_| TreasuryError::InvalidTreasuryFundAccount)?;
+    ) -> Result<RefMut<'a, Self>, ProgramError> {
+        let treasury = Self::load_mut(treasury_info, program_id)?;
 
         // authority owner checks
         if !authority_info.is_signer {
@@ -50,6 +95,12 @@ impl SimpleTreasury {
         Pubkey::find_program_address(&[b"simple authority", &treasury_id.to_bytes()], program_id)
     }
 
+    /// The address of the associated token account that holds the treasury's funds
+    pub fn fund_address(treasury_id: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+        let (fund_authority, _) = Self::fund_authority_address(treasury_id, program_id);
+        get_associated_token_address(&fund_authority, mint)
+    }
+
     pub fn verify_fund_authority_address(
         key: &Pubkey,
         authority: &Pubkey,
@@ -61,10 +112,50 @@ impl SimpleTreasury {
         }
         Ok(seed)
     }
+
+    /// Lock a vote escrow treasury until `lock_end`. The lock can be extended up to
+    /// `MAX_LOCK_DURATION` from now but never shortened.
+    pub fn extend_lock(
+        &mut self,
+        now: UnixTimestamp,
+        lock_end: UnixTimestamp,
+    ) -> Result<(), TreasuryError> {
+        if self.mode != SimpleTreasuryMode::VoteEscrow {
+            return Err(TreasuryError::NotVoteEscrow);
+        }
+        if lock_end <= now || lock_end <= self.lock_end {
+            return Err(TreasuryError::InvalidLockEnd);
+        }
+        if lock_end - now > Self::MAX_LOCK_DURATION {
+            return Err(TreasuryError::InvalidLockEnd);
+        }
+        self.lock_end = lock_end;
+        Ok(())
+    }
+
+    /// The vote escrow weight of the fund at a specific time. A fund locked for the
+    /// maximum duration counts fully, the weight then decays linearly to 0 at `lock_end`.
+    pub fn vote_escrow_weight(&self, now: UnixTimestamp, fund_balance: u64) -> u64 {
+        if self.mode != SimpleTreasuryMode::VoteEscrow {
+            return 0;
+        }
+        let remaining = self
+            .lock_end
+            .saturating_sub(now)
+            .clamp(0, Self::MAX_LOCK_DURATION);
+        // remaining <= MAX_LOCK_DURATION so the result fits in a u64
+        (fund_balance as u128 * remaining as u128 / Self::MAX_LOCK_DURATION as u128) as u64
+    }
+
+    /// Store the weight at a specific time so other programs can read it
+    pub fn update_weight(&mut self, now: UnixTimestamp, fund_balance: u64) {
+        self.weight = self.vote_escrow_weight(now, fund_balance);
+        self.weight_updated_at = now;
+    }
 }
 
-#[repr(C)]
-#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, CheckedBitPattern, NoUninit)]
 pub struct VestedTreasury {
     pub mint: Pubkey,
     pub authority: Pubkey,
@@ -73,23 +164,57 @@ pub struct VestedTreasury {
     pub vestment_period: u64,
     pub vestment_percentage: u16,
     pub withdrawn: u64,
+    /// The key that can pause and resume vesting
+    pub grantor: Pubkey,
+    /// When vesting was paused, 0 while vesting runs
+    pub paused_at: UnixTimestamp,
+    /// The amount the grantor vested ahead of the schedule
+    pub accelerated: u64,
+    /// The key that can withdraw on behalf of the authority, the default key if none
+    pub delegate: Pubkey,
 }
+
+impl ZeroCopy for VestedTreasury {}
+impl_borsh_as_bytes!(VestedTreasury);
+
 impl VestedTreasury {
     pub const MIN_PERCENTAGE: u16 = 1;
     pub const MAX_PERCENTAGE: u16 = 10_000;
 
-    pub fn from_account_info(
-        treasury_info: &AccountInfo,
+    /// The size of a serialized vested treasury
+    pub const LEN: usize = 178;
+    /// The sizes of earlier layouts, which `MigrateTreasury` grows to `LEN`
+    pub const LEGACY_LENS: [usize; 1] = [98];
+    /// The offset of `mint` in a serialized vested treasury
+    pub const MINT_OFFSET: usize = 0;
+    /// The offset of `authority` in a serialized vested treasury
+    pub const AUTHORITY_OFFSET: usize = 32;
+
+    /// Verify the parameters used to create a vested treasury
+    pub fn verify_parameters(
+        amount: u64,
+        period: u64,
+        percentage: u16,
+    ) -> Result<(), TreasuryError> {
+        if amount == 0 {
+            return Err(TreasuryError::InvalidVestmentAmount);
+        }
+        if period == 0 {
+            return Err(TreasuryError::InvalidVestmentPeriod);
+        }
+        if !(Self::MIN_PERCENTAGE..=Self::MAX_PERCENTAGE).contains(&percentage) {
+            return Err(TreasuryError::InvalidVestmentPercentage);
+        }
+        Ok(())
+    }
+
+    /// Borrow the treasury to modify it, it has to be signed for by the authority
+    pub fn from_account_info<'a>(
+        treasury_info: &'a AccountInfo,
         authority_info: &AccountInfo,
         program_id: &Pubkey,
-    ) -> Result<VestedTreasury, ProgramError> {
-        // treasury account checks
-        if *treasury_info.owner != *program_id {
-            msg!("treasury account not owned by program");
-            return Err(TreasuryError::InvalidTreasuryFundAccount.into());
-        }
-        let treasury = Self::try_from_slice(&treasury_info.data.borrow())
-            .map_err(|_| TreasuryError::InvalidTreasuryFundAccount)?;
+    ) -> Result<RefMut<'a, Self>, ProgramError> {
+        let treasury = Self::load_mut(treasury_info, program_id)?;
 
         // authority owner checks
         if !authority_info.is_signer {
@@ -103,10 +228,59 @@ impl VestedTreasury {
         Ok(treasury)
     }
 
+    /// Borrow a vested treasury that the authority or its delegate signed for
+    pub fn from_claimer_info<'a>(
+        treasury_info: &'a AccountInfo,
+        claimer_info: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<RefMut<'a, Self>, ProgramError> {
+        let treasury = Self::load_mut(treasury_info, program_id)?;
+
+        if !claimer_info.is_signer {
+            return Err(TreasuryError::MissingAuthoritySignature.into());
+        }
+
+        if !treasury.is_claimer(claimer_info.key) {
+            return Err(TreasuryError::InvalidTreasuryOwner.into());
+        }
+
+        Ok(treasury)
+    }
+
+    /// The key is the authority or its delegate
+    pub fn is_claimer(&self, key: &Pubkey) -> bool {
+        *key == self.authority || (self.delegate != Pubkey::default() && *key == self.delegate)
+    }
+
+    /// Borrow a vested treasury without checking the authority
+    pub fn load<'a>(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Ref<'a, Self>, ProgramError> {
+        check_owner(treasury_info, program_id)?;
+        Self::borrow(treasury_info).ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+    }
+
+    /// Borrow the treasury to modify it in place
+    pub fn load_mut<'a>(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<RefMut<'a, Self>, ProgramError> {
+        check_owner(treasury_info, program_id)?;
+        Self::borrow_mut(treasury_info)
+            .ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+    }
+
     pub fn fund_authority_address(treasury_id: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
         Pubkey::find_program_address(&[b"vested authority", &treasury_id.to_bytes()], program_id)
     }
 
+    /// The address of the associated token account that holds the treasury's funds
+    pub fn fund_address(treasury_id: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+        let (fund_authority, _) = Self::fund_authority_address(treasury_id, program_id);
+        get_associated_token_address(&fund_authority, mint)
+    }
+
     pub fn verify_fund_authority_address(
         key: &Pubkey,
         treasury_id: &Pubkey,
@@ -119,63 +293,1528 @@ impl VestedTreasury {
         Ok(seed)
     }
 
+    pub fn is_paused(&self) -> bool {
+        self.paused_at != 0
+    }
+
+    /// Stop the vesting clock
+    pub fn pause(&mut self, now: UnixTimestamp) -> Result<(), TreasuryError> {
+        if self.is_paused() {
+            return Err(TreasuryError::VestingPaused);
+        }
+        self.paused_at = now;
+        Ok(())
+    }
+
+    /// Restart the vesting clock, moving the schedule back by the time spent paused
+    pub fn resume(&mut self, now: UnixTimestamp) -> Result<(), TreasuryError> {
+        if !self.is_paused() {
+            return Err(TreasuryError::VestingNotPaused);
+        }
+        let paused = now.saturating_sub(self.paused_at).max(0);
+        self.start = self.start.saturating_add(paused);
+        self.paused_at = 0;
+        Ok(())
+    }
+
+    /// Vest part of the unvested amount immediately
+    pub fn accelerate(
+        &mut self,
+        now: UnixTimestamp,
+        acceleration: Acceleration,
+    ) -> Result<(), TreasuryError> {
+        let unvested = self.initial_amount - self.maximum_available(now);
+        let amount = match acceleration {
+            Acceleration::Amount(amount) if amount > 0 => amount.min(unvested),
+            Acceleration::Percentage(percentage)
+                if (Self::MIN_PERCENTAGE..=Self::MAX_PERCENTAGE).contains(&percentage) =>
+            {
+                (unvested as u128 * percentage as u128 / Self::MAX_PERCENTAGE as u128) as u64
+            }
+            _ => return Err(TreasuryError::InvalidAcceleration),
+        };
+        self.accelerated += amount;
+        Ok(())
+    }
+
+    /// Split off `amount` of the initial amount into a new treasury with the same
+    /// schedule. The withdrawn and accelerated amounts are divided in the same
+    /// proportion, as long as neither treasury ends up having withdrawn more than it has
+    /// vested at `now`. Returns the new treasury and the share of `fund_balance` it is
+    /// owed.
+    pub fn split(
+        &mut self,
+        amount: u64,
+        authority: Pubkey,
+        fund_balance: u64,
+        now: UnixTimestamp,
+    ) -> Result<(VestedTreasury, u64), TreasuryError> {
+        if amount == 0 || amount >= self.initial_amount {
+            return Err(TreasuryError::InvalidSplitAmount);
+        }
+        let share =
+            |value: u64| (value as u128 * amount as u128 / self.initial_amount as u128) as u64;
+
+        let mut new = VestedTreasury {
+            authority,
+            initial_amount: amount,
+            withdrawn: 0,
+            accelerated: share(self.accelerated),
+            delegate: Pubkey::default(),
+            ..*self
+        };
+        let mut rest = VestedTreasury {
+            initial_amount: self.initial_amount - new.initial_amount,
+            withdrawn: 0,
+            accelerated: self.accelerated - new.accelerated,
+            ..*self
+        };
+        // each half rounds its amount per period down, so together they can have vested
+        // less than the whole
+        new.withdrawn = share(self.withdrawn).min(new.maximum_available(now));
+        rest.withdrawn = self.withdrawn - new.withdrawn;
+        if rest.withdrawn > rest.maximum_available(now) {
+            return Err(TreasuryError::SplitExceedsVested);
+        }
+
+        // the new treasury's fund covers what it can still withdraw, as far as the fund
+        // goes, the rest stays with the original
+        let fund_share = fund_balance.min(new.initial_amount - new.withdrawn);
+
+        *self = rest;
+        Ok((new, fund_share))
+    }
+
+    /// Add another treasury's amounts to this one. Both have to belong to the same
+    /// authority and have the same mint, grantor, schedule and pause state.
+    pub fn merge(&mut self, other: &VestedTreasury) -> Result<(), TreasuryError> {
+        if self.mint != other.mint
+            || self.authority != other.authority
+            || self.grantor != other.grantor
+            || self.start != other.start
+            || self.vestment_period != other.vestment_period
+            || self.vestment_percentage != other.vestment_percentage
+            || self.paused_at != other.paused_at
+        {
+            return Err(TreasuryError::IncompatibleTreasuries);
+        }
+
+        self.initial_amount = self
+            .initial_amount
+            .checked_add(other.initial_amount)
+            .ok_or(TreasuryError::IncompatibleTreasuries)?;
+        self.withdrawn += other.withdrawn;
+        self.accelerated += other.accelerated;
+        Ok(())
+    }
+
     pub fn maximum_available(&self, now: UnixTimestamp) -> u64 {
+        // nothing vests while paused
+        let now = if self.is_paused() {
+            now.min(self.paused_at)
+        } else {
+            now
+        };
+
         let period = now - self.start;
-        if period <= 0 {
-            return 0;
-        }
+        let ticks = if period > 0 {
+            period as u64 / self.vestment_period
+        } else {
+            0
+        };
 
-        let ticks = period as u64 / self.vestment_period;
         let percentage = self.vestment_percentage as f64 / 10_000f64;
-        let amount = (self.initial_amount as f64 * percentage) as u64 * ticks;
+        let amount = ((self.initial_amount as f64 * percentage) as u64)
+            .saturating_mul(ticks)
+            .saturating_add(self.accelerated);
         if amount > self.initial_amount {
             self.initial_amount
         } else {
             amount
         }
     }
+
+    /// Break down the state of the treasury at a specific time, given the current balance
+    /// of the fund account.
+    pub fn query(&self, now: UnixTimestamp, fund_balance: u64) -> VestedQuery {
+        let vested = self.maximum_available(now);
+        let available = vested.saturating_sub(self.withdrawn);
+        let claimable = if available > fund_balance {
+            fund_balance
+        } else {
+            available
+        };
+
+        VestedQuery {
+            claimable,
+            vested,
+            unvested: self.initial_amount - vested,
+            locked: fund_balance - claimable,
+        }
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// A recipient of a splitter treasury and their share
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Shareholder {
+    pub recipient: Pubkey,
+    /// The share of everything deposited, in basis points. 0 for unused slots.
+    pub share: u16,
+    /// The amount the recipient has claimed so far
+    pub released: u64,
+}
 
-    #[test]
-    pub fn test_serialize_accounts() {
-        let user_treasury = SimpleTreasury {
-            mint: Pubkey::new_unique(),
-            mode: SimpleTreasuryMode::Locked,
-            authority: Pubkey::new_unique(),
-        };
-        let user_treasury_data = user_treasury.try_to_vec().unwrap();
-        assert_eq!(
-            user_treasury,
-            SimpleTreasury::try_from_slice(&user_treasury_data).unwrap()
-        );
+/// Splits everything deposited into its fund between a fixed list of shareholders
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, CheckedBitPattern, NoUninit)]
+pub struct SplitterTreasury {
+    pub mint: Pubkey,
+    /// The sum of `released` of all shareholders
+    pub total_released: u64,
+    pub shareholders: [Shareholder; 10],
+}
+
+impl ZeroCopy for SplitterTreasury {}
+impl_borsh_as_bytes!(SplitterTreasury);
+
+impl SplitterTreasury {
+    pub const MAX_SHAREHOLDERS: usize = 10;
+    /// The shares of all shareholders add up to 100%
+    pub const TOTAL_SHARES: u16 = 10_000;
+
+    /// The size of a serialized splitter treasury
+    pub const LEN: usize = 460;
+    /// The offset of `mint` in a serialized splitter treasury
+    pub const MINT_OFFSET: usize = 0;
+
+    /// Create a splitter treasury from pairs of recipient and share. Every share has
+    /// to be above 0, the recipients distinct and the shares add up to `TOTAL_SHARES`.
+    pub fn new(mint: Pubkey, shares: &[(Pubkey, u16)]) -> Result<Self, TreasuryError> {
+        if shares.is_empty() || shares.len() > Self::MAX_SHAREHOLDERS {
+            return Err(TreasuryError::InvalidShares);
+        }
+        let mut shareholders = [Shareholder::default(); Self::MAX_SHAREHOLDERS];
+        let mut total = 0u32;
+        for (i, (recipient, share)) in shares.iter().enumerate() {
+            if *share == 0 || shares[..i].iter().any(|(other, _)| other == recipient) {
+                return Err(TreasuryError::InvalidShares);
+            }
+            total += *share as u32;
+            shareholders[i] = Shareholder {
+                recipient: *recipient,
+                share: *share,
+                released: 0,
+            };
+        }
+        if total != Self::TOTAL_SHARES as u32 {
+            return Err(TreasuryError::InvalidShares);
+        }
+
+        Ok(SplitterTreasury {
+            mint,
+            total_released: 0,
+            shareholders,
+        })
     }
 
-    #[test]
-    pub fn test_vested_max() {
-        let vest = VestedTreasury {
-            mint: Pubkey::new_unique(),
-            authority: Pubkey::new_unique(),
-            initial_amount: 100_000,
-            start: 0,
-            vestment_period: 60,
-            vestment_percentage: 500, // 5%
+    /// Borrow a splitter treasury, anyone can deposit into it
+    pub fn load<'a>(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Ref<'a, Self>, ProgramError> {
+        check_owner(treasury_info, program_id)?;
+        Self::borrow(treasury_info).ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+    }
+
+    /// Borrow the treasury to modify it in place
+    pub fn load_mut<'a>(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<RefMut<'a, Self>, ProgramError> {
+        check_owner(treasury_info, program_id)?;
+        Self::borrow_mut(treasury_info)
+            .ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+    }
+
+    pub fn fund_authority_address(treasury_id: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"splitter authority", &treasury_id.to_bytes()],
+            program_id,
+        )
+    }
+
+    /// The address of the associated token account that holds the treasury's funds
+    pub fn fund_address(treasury_id: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+        let (fund_authority, _) = Self::fund_authority_address(treasury_id, program_id);
+        get_associated_token_address(&fund_authority, mint)
+    }
+
+    pub fn verify_fund_authority_address(
+        key: &Pubkey,
+        treasury_id: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Result<u8, ProgramError> {
+        let (derived_key, seed) = Self::fund_authority_address(treasury_id, program_id);
+        if *key != derived_key {
+            return Err(TreasuryError::InvalidTreasuryFundAuthorityAddress.into());
+        }
+        Ok(seed)
+    }
+
+    /// The shareholders in use
+    pub fn shareholders(&self) -> impl Iterator<Item = &Shareholder> {
+        self.shareholders.iter().filter(|holder| holder.share > 0)
+    }
+
+    /// The amount a recipient can claim right now, given the current balance of the
+    /// fund. Their share of everything ever deposited, less what they have claimed.
+    pub fn claimable(&self, recipient: &Pubkey, fund_balance: u64) -> Option<u64> {
+        let holder = self
+            .shareholders()
+            .find(|holder| holder.recipient == *recipient)?;
+        let received = fund_balance as u128 + self.total_released as u128;
+        let entitled = received * holder.share as u128 / Self::TOTAL_SHARES as u128;
+        let entitled = entitled.min(u64::MAX as u128) as u64;
+        Some(entitled.saturating_sub(holder.released))
+    }
+
+    /// Record a claim of everything the recipient is owed and return the amount
+    pub fn claim(&mut self, recipient: &Pubkey, fund_balance: u64) -> Result<u64, TreasuryError> {
+        let amount = self
+            .claimable(recipient, fund_balance)
+            .ok_or(TreasuryError::NotAShareholder)?;
+        let holder = self
+            .shareholders
+            .iter_mut()
+            .find(|holder| holder.share > 0 && holder.recipient == *recipient)
+            .ok_or(TreasuryError::NotAShareholder)?;
+        holder.released += amount;
+        self.total_released += amount;
+        Ok(amount)
+    }
+}
+
+/// A deliverable of a milestone treasury
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Milestone {
+    /// The amount unlocked by the milestone, 0 for unused slots
+    pub amount: u64,
+    /// The key that confirms the milestone was reached
+    pub attestor: Pubkey,
+    pub confirmed: PodBool,
+}
+
+/// Unlocks funds as attestors confirm milestones, instead of over time
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, CheckedBitPattern, NoUninit)]
+pub struct MilestoneTreasury {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    pub withdrawn: u64,
+    pub milestones: [Milestone; 8],
+}
+
+impl ZeroCopy for MilestoneTreasury {}
+impl_borsh_as_bytes!(MilestoneTreasury);
+
+impl MilestoneTreasury {
+    pub const MAX_MILESTONES: usize = 8;
+    /// Signed by attestors off-chain, followed by the treasury address and the index of
+    /// the milestone
+    pub const ATTESTATION_PREFIX: &'static [u8] = b"confirm milestone";
+
+    /// The size of a serialized milestone treasury
+    pub const LEN: usize = 400;
+    /// The offset of `mint` in a serialized milestone treasury
+    pub const MINT_OFFSET: usize = 0;
+    /// The offset of `authority` in a serialized milestone treasury
+    pub const AUTHORITY_OFFSET: usize = 32;
+
+    /// Create a milestone treasury from pairs of amount and attestor. There has to be
+    /// at least one milestone, every amount above 0 and the total fit in a `u64`.
+    pub fn new(
+        mint: Pubkey,
+        authority: Pubkey,
+        milestones: &[(u64, Pubkey)],
+    ) -> Result<Self, TreasuryError> {
+        if milestones.is_empty() || milestones.len() > Self::MAX_MILESTONES {
+            return Err(TreasuryError::InvalidMilestones);
+        }
+        let mut slots = [Milestone::default(); Self::MAX_MILESTONES];
+        let mut total = 0u64;
+        for (slot, (amount, attestor)) in slots.iter_mut().zip(milestones) {
+            if *amount == 0 {
+                return Err(TreasuryError::InvalidMilestones);
+            }
+            total = total
+                .checked_add(*amount)
+                .ok_or(TreasuryError::InvalidMilestones)?;
+            *slot = Milestone {
+                amount: *amount,
+                attestor: *attestor,
+                confirmed: false.into(),
+            };
+        }
+
+        Ok(MilestoneTreasury {
+            mint,
+            authority,
             withdrawn: 0,
-        };
+            milestones: slots,
+        })
+    }
 
-        assert_eq!(vest.maximum_available(-5000), 0);
-        assert_eq!(vest.maximum_available(0), 0);
-        assert_eq!(vest.maximum_available(1), 0);
-        assert_eq!(vest.maximum_available(59), 0);
-        assert_eq!(vest.maximum_available(60), 5_000);
-        assert_eq!(vest.maximum_available(61), 5_000);
-        assert_eq!(vest.maximum_available(119), 5_000);
-        assert_eq!(vest.maximum_available(120), 10_000);
-        assert_eq!(vest.maximum_available(1_199), 95_000);
-        assert_eq!(vest.maximum_available(1_200), 100_000);
-        assert_eq!(vest.maximum_available(5_000), 100_000);
+    pub fn load<'a>(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Ref<'a, Self>, ProgramError> {
+        check_owner(treasury_info, program_id)?;
+        Self::borrow(treasury_info).ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+    }
+
+    /// Borrow the treasury to modify it in place
+    pub fn load_mut<'a>(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<RefMut<'a, Self>, ProgramError> {
+        check_owner(treasury_info, program_id)?;
+        Self::borrow_mut(treasury_info)
+            .ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+    }
+
+    /// Borrow the treasury to modify it, it has to be signed for by the authority
+    pub fn from_account_info<'a>(
+        treasury_info: &'a AccountInfo,
+        authority_info: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<RefMut<'a, Self>, ProgramError> {
+        let treasury = Self::load_mut(treasury_info, program_id)?;
+
+        // authority owner checks
+        if !authority_info.is_signer {
+            return Err(TreasuryError::MissingAuthoritySignature.into());
+        }
+
+        if treasury.authority != *authority_info.key {
+            return Err(TreasuryError::InvalidTreasuryOwner.into());
+        }
+
+        Ok(treasury)
+    }
+
+    pub fn fund_authority_address(treasury_id: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[b"milestone authority", &treasury_id.to_bytes()],
+            program_id,
+        )
+    }
+
+    /// The address of the associated token account that holds the treasury's funds
+    pub fn fund_address(treasury_id: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+        let (fund_authority, _) = Self::fund_authority_address(treasury_id, program_id);
+        get_associated_token_address(&fund_authority, mint)
+    }
+
+    pub fn verify_fund_authority_address(
+        key: &Pubkey,
+        treasury_id: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Result<u8, ProgramError> {
+        let (derived_key, seed) = Self::fund_authority_address(treasury_id, program_id);
+        if *key != derived_key {
+            return Err(TreasuryError::InvalidTreasuryFundAuthorityAddress.into());
+        }
+        Ok(seed)
+    }
+
+    /// The message an attestor signs off-chain to confirm a milestone of a treasury
+    pub fn attestation(treasury_id: &Pubkey, index: u8) -> Vec<u8> {
+        let mut message = Self::ATTESTATION_PREFIX.to_vec();
+        message.extend_from_slice(&treasury_id.to_bytes());
+        message.push(index);
+        message
+    }
+
+    /// The milestones in use
+    pub fn milestones(&self) -> impl Iterator<Item = &Milestone> {
+        self.milestones
+            .iter()
+            .filter(|milestone| milestone.amount > 0)
+    }
+
+    /// The attestor of a milestone that hasn't been confirmed yet
+    pub fn attestor(&self, index: u8) -> Result<Pubkey, TreasuryError> {
+        let milestone = self
+            .milestones()
+            .nth(index as usize)
+            .ok_or(TreasuryError::InvalidMilestone)?;
+        if milestone.confirmed.get() {
+            return Err(TreasuryError::MilestoneConfirmed);
+        }
+        Ok(milestone.attestor)
+    }
+
+    /// Mark a milestone as reached, only its attestor can confirm it
+    pub fn confirm(&mut self, index: u8, attestor: &Pubkey) -> Result<(), TreasuryError> {
+        if self.attestor(index)? != *attestor {
+            return Err(TreasuryError::InvalidAttestor);
+        }
+        self.milestones[index as usize].confirmed = true.into();
+        Ok(())
+    }
+
+    /// The total amount of the confirmed milestones
+    pub fn unlocked(&self) -> u64 {
+        self.milestones()
+            .filter(|milestone| milestone.confirmed.get())
+            .map(|milestone| milestone.amount)
+            .sum()
+    }
+
+    /// The amount that can be withdrawn given the current balance of the fund
+    pub fn available(&self, fund_balance: u64) -> u64 {
+        (self.unlocked() - self.withdrawn).min(fund_balance)
+    }
+}
+
+/// A part of a hurdle treasury that unlocks once the price stays at or above a target
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Tranche {
+    /// The amount unlocked by the tranche, 0 for unused slots
+    pub amount: u64,
+    /// The target price, in the unit of the price feed
+    pub price: u64,
+    /// When the price was first seen at or above the target since it was last below,
+    /// 0 while it's below
+    pub met_since: UnixTimestamp,
+    pub unlocked: PodBool,
+}
+
+/// Unlocks tranches once the token trades at or above target prices for a sustained
+/// period, as reported by a price feed
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, CheckedBitPattern, NoUninit)]
+pub struct HurdleTreasury {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+    /// The price feed account, see `oracle`
+    pub oracle: Pubkey,
+    /// How long the price has to stay at or above a target, in seconds
+    pub duration: u64,
+    pub withdrawn: u64,
+    pub tranches: [Tranche; 8],
+}
+
+impl ZeroCopy for HurdleTreasury {}
+impl_borsh_as_bytes!(HurdleTreasury);
+
+impl HurdleTreasury {
+    pub const MAX_TRANCHES: usize = 8;
+
+    /// The size of a serialized hurdle treasury
+    pub const LEN: usize = 312;
+    /// The offset of `mint` in a serialized hurdle treasury
+    pub const MINT_OFFSET: usize = 0;
+    /// The offset of `authority` in a serialized hurdle treasury
+    pub const AUTHORITY_OFFSET: usize = 32;
+
+    /// Create a hurdle treasury from pairs of amount and target price. There has to be
+    /// at least one tranche, every amount and price above 0 and the total amount fit in
+    /// a `u64`.
+    pub fn new(
+        mint: Pubkey,
+        authority: Pubkey,
+        oracle: Pubkey,
+        duration: u64,
+        tranches: &[(u64, u64)],
+    ) -> Result<Self, TreasuryError> {
+        if tranches.is_empty() || tranches.len() > Self::MAX_TRANCHES {
+            return Err(TreasuryError::InvalidTranches);
+        }
+        let mut slots = [Tranche::default(); Self::MAX_TRANCHES];
+        let mut total = 0u64;
+        for (slot, (amount, price)) in slots.iter_mut().zip(tranches) {
+            if *amount == 0 || *price == 0 {
+                return Err(TreasuryError::InvalidTranches);
+            }
+            total = total
+                .checked_add(*amount)
+                .ok_or(TreasuryError::InvalidTranches)?;
+            *slot = Tranche {
+                amount: *amount,
+                price: *price,
+                ..Tranche::default()
+            };
+        }
+
+        Ok(HurdleTreasury {
+            mint,
+            authority,
+            oracle,
+            duration,
+            withdrawn: 0,
+            tranches: slots,
+        })
+    }
+
+    pub fn load<'a>(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Ref<'a, Self>, ProgramError> {
+        check_owner(treasury_info, program_id)?;
+        Self::borrow(treasury_info).ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+    }
+
+    /// Borrow the treasury to modify it in place
+    pub fn load_mut<'a>(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<RefMut<'a, Self>, ProgramError> {
+        check_owner(treasury_info, program_id)?;
+        Self::borrow_mut(treasury_info)
+            .ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+    }
+
+    /// Borrow the treasury to modify it, it has to be signed for by the authority
+    pub fn from_account_info<'a>(
+        treasury_info: &'a AccountInfo,
+        authority_info: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<RefMut<'a, Self>, ProgramError> {
+        let treasury = Self::load_mut(treasury_info, program_id)?;
+
+        // authority owner checks
+        if !authority_info.is_signer {
+            return Err(TreasuryError::MissingAuthoritySignature.into());
+        }
+
+        if treasury.authority != *authority_info.key {
+            return Err(TreasuryError::InvalidTreasuryOwner.into());
+        }
+
+        Ok(treasury)
+    }
+
+    pub fn fund_authority_address(treasury_id: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"hurdle authority", &treasury_id.to_bytes()], program_id)
+    }
+
+    /// The address of the associated token account that holds the treasury's funds
+    pub fn fund_address(treasury_id: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+        let (fund_authority, _) = Self::fund_authority_address(treasury_id, program_id);
+        get_associated_token_address(&fund_authority, mint)
+    }
+
+    pub fn verify_fund_authority_address(
+        key: &Pubkey,
+        treasury_id: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Result<u8, ProgramError> {
+        let (derived_key, seed) = Self::fund_authority_address(treasury_id, program_id);
+        if *key != derived_key {
+            return Err(TreasuryError::InvalidTreasuryFundAuthorityAddress.into());
+        }
+        Ok(seed)
+    }
+
+    /// The tranches in use
+    pub fn tranches(&self) -> impl Iterator<Item = &Tranche> {
+        self.tranches.iter().filter(|tranche| tranche.amount > 0)
+    }
+
+    /// Record the current price. A tranche unlocks for good once the price was at or
+    /// above its target at two updates `duration` apart, without being seen below it in
+    /// between. Dips between updates go unnoticed.
+    pub fn update(&mut self, now: UnixTimestamp, price: u64) {
+        let duration = self.duration.min(i64::MAX as u64) as i64;
+        for tranche in self.tranches.iter_mut() {
+            if tranche.amount == 0 || tranche.unlocked.get() {
+                continue;
+            }
+            if price < tranche.price {
+                tranche.met_since = 0;
+                continue;
+            }
+            if tranche.met_since == 0 {
+                tranche.met_since = now;
+            }
+            if now.saturating_sub(tranche.met_since) >= duration {
+                tranche.unlocked = true.into();
+            }
+        }
+    }
+
+    /// The total amount of the unlocked tranches
+    pub fn unlocked(&self) -> u64 {
+        self.tranches()
+            .filter(|tranche| tranche.unlocked.get())
+            .map(|tranche| tranche.amount)
+            .sum()
+    }
+
+    /// The amount that can be withdrawn given the current balance of the fund
+    pub fn available(&self, fund_balance: u64) -> u64 {
+        (self.unlocked() - self.withdrawn).min(fund_balance)
+    }
+}
+
+/// How an escrow treasury's fund is paid out
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Settlement {
+    /// The arbiter pays the fund to the payee, before the expiry
+    Release,
+    /// The arbiter returns the fund to the depositor
+    Refund,
+    /// Anyone returns the fund to the depositor once the escrow expired
+    Expire,
+}
+
+/// Holds a deposit until a third party arbiter releases it to the payee or refunds the
+/// depositor. Funds that are neither released nor refunded go back to the depositor
+/// at the expiry.
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, CheckedBitPattern, NoUninit)]
+pub struct EscrowTreasury {
+    pub mint: Pubkey,
+    pub depositor: Pubkey,
+    pub payee: Pubkey,
+    pub arbiter: Pubkey,
+    /// After this time the fund can only be refunded
+    pub expiry: UnixTimestamp,
+}
+
+impl ZeroCopy for EscrowTreasury {}
+impl_borsh_as_bytes!(EscrowTreasury);
+
+impl EscrowTreasury {
+    /// The size of a serialized escrow treasury
+    pub const LEN: usize = 136;
+    /// The offset of `mint` in a serialized escrow treasury
+    pub const MINT_OFFSET: usize = 0;
+
+    /// Create an escrow treasury. The expiry has to be in the future and the arbiter
+    /// can't be one of the parties.
+    pub fn new(
+        mint: Pubkey,
+        depositor: Pubkey,
+        payee: Pubkey,
+        arbiter: Pubkey,
+        expiry: UnixTimestamp,
+        now: UnixTimestamp,
+    ) -> Result<Self, TreasuryError> {
+        if expiry <= now || arbiter == depositor || arbiter == payee {
+            return Err(TreasuryError::InvalidEscrow);
+        }
+        Ok(EscrowTreasury {
+            mint,
+            depositor,
+            payee,
+            arbiter,
+            expiry,
+        })
+    }
+
+    /// Borrow an escrow treasury, anyone can deposit into it
+    pub fn load<'a>(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Ref<'a, Self>, ProgramError> {
+        check_owner(treasury_info, program_id)?;
+        Self::borrow(treasury_info).ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+    }
+
+    /// Borrow the treasury to modify it in place
+    pub fn load_mut<'a>(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<RefMut<'a, Self>, ProgramError> {
+        check_owner(treasury_info, program_id)?;
+        Self::borrow_mut(treasury_info)
+            .ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+    }
+
+    pub fn fund_authority_address(treasury_id: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"escrow authority", &treasury_id.to_bytes()], program_id)
+    }
+
+    /// The address of the associated token account that holds the treasury's funds
+    pub fn fund_address(treasury_id: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+        let (fund_authority, _) = Self::fund_authority_address(treasury_id, program_id);
+        get_associated_token_address(&fund_authority, mint)
+    }
+
+    pub fn verify_fund_authority_address(
+        key: &Pubkey,
+        treasury_id: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Result<u8, ProgramError> {
+        let (derived_key, seed) = Self::fund_authority_address(treasury_id, program_id);
+        if *key != derived_key {
+            return Err(TreasuryError::InvalidTreasuryFundAuthorityAddress.into());
+        }
+        Ok(seed)
+    }
+
+    pub fn is_expired(&self, now: UnixTimestamp) -> bool {
+        now >= self.expiry
+    }
+
+    /// Check that the escrow can be settled this way right now and return the owner
+    /// of the account that receives the fund
+    pub fn settle(
+        &self,
+        settlement: Settlement,
+        now: UnixTimestamp,
+    ) -> Result<Pubkey, TreasuryError> {
+        match settlement {
+            Settlement::Release if self.is_expired(now) => Err(TreasuryError::EscrowExpired),
+            Settlement::Release => Ok(self.payee),
+            Settlement::Refund => Ok(self.depositor),
+            Settlement::Expire if !self.is_expired(now) => Err(TreasuryError::EscrowNotExpired),
+            Settlement::Expire => Ok(self.depositor),
+        }
+    }
+}
+
+/// Treasury accounts have to be owned by the program
+fn check_owner(treasury_info: &AccountInfo, program_id: &Pubkey) -> Result<(), ProgramError> {
+    if *treasury_info.owner != *program_id {
+        msg!("treasury account not owned by program");
+        return Err(TreasuryError::InvalidTreasuryFundAccount.into());
+    }
+    Ok(())
+}
+
+/// How much of a vested treasury's unvested amount to vest immediately
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub enum Acceleration {
+    /// A fixed amount, capped at the unvested amount
+    Amount(u64),
+    /// A share of the unvested amount in basis points, 10,000 vests everything
+    Percentage(u16),
+}
+
+/// The result of a `QueryVested` instruction, returned via `set_return_data`
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct VestedQuery {
+    /// The amount that can be withdrawn right now
+    pub claimable: u64,
+    /// The total amount released by the vesting schedule so far, including withdrawn funds
+    pub vested: u64,
+    /// The amount of the initial amount that has not vested yet
+    pub unvested: u64,
+    /// The amount in the fund account that can't be withdrawn yet
+    pub locked: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_serialize_accounts() {
+        let user_treasury = SimpleTreasury {
+            mint: Pubkey::new_unique(),
+            mode: SimpleTreasuryMode::Locked,
+            authority: Pubkey::new_unique(),
+            lock_end: 0,
+            weight: 0,
+            weight_updated_at: 0,
+        };
+        let user_treasury_data = user_treasury.try_to_vec().unwrap();
+        assert_eq!(
+            user_treasury,
+            SimpleTreasury::try_from_slice(&user_treasury_data).unwrap()
+        );
+    }
+
+    #[test]
+    pub fn test_account_offsets() {
+        let simple = SimpleTreasury {
+            mint: Pubkey::new_unique(),
+            mode: SimpleTreasuryMode::VoteEscrow,
+            authority: Pubkey::new_unique(),
+            lock_end: 1,
+            weight: 2,
+            weight_updated_at: 3,
+        };
+        let data = simple.try_to_vec().unwrap();
+        assert_eq!(data.len(), SimpleTreasury::LEN);
+        assert_eq!(
+            data[SimpleTreasury::WEIGHT_OFFSET..SimpleTreasury::WEIGHT_OFFSET + 8],
+            simple.weight.to_le_bytes()
+        );
+        assert_eq!(
+            data[SimpleTreasury::MINT_OFFSET..SimpleTreasury::MINT_OFFSET + 32],
+            simple.mint.to_bytes()
+        );
+        assert_eq!(
+            data[SimpleTreasury::AUTHORITY_OFFSET..SimpleTreasury::AUTHORITY_OFFSET + 32],
+            simple.authority.to_bytes()
+        );
+
+        let vested = VestedTreasury {
+            mint: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            initial_amount: 1,
+            start: 2,
+            vestment_period: 3,
+            vestment_percentage: 4,
+            withdrawn: 5,
+            grantor: Pubkey::new_unique(),
+            paused_at: 6,
+            accelerated: 7,
+            delegate: Pubkey::new_unique(),
+        };
+        let data = vested.try_to_vec().unwrap();
+        assert_eq!(data.len(), VestedTreasury::LEN);
+        assert_eq!(
+            data[VestedTreasury::MINT_OFFSET..VestedTreasury::MINT_OFFSET + 32],
+            vested.mint.to_bytes()
+        );
+        assert_eq!(
+            data[VestedTreasury::AUTHORITY_OFFSET..VestedTreasury::AUTHORITY_OFFSET + 32],
+            vested.authority.to_bytes()
+        );
+    }
+
+    #[test]
+    pub fn test_vested_max() {
+        let vest = VestedTreasury {
+            mint: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            initial_amount: 100_000,
+            start: 0,
+            vestment_period: 60,
+            vestment_percentage: 500, // 5%
+            withdrawn: 0,
+            grantor: Pubkey::new_unique(),
+            paused_at: 0,
+            accelerated: 0,
+            delegate: Pubkey::default(),
+        };
+
+        assert_eq!(vest.maximum_available(-5000), 0);
+        assert_eq!(vest.maximum_available(0), 0);
+        assert_eq!(vest.maximum_available(1), 0);
+        assert_eq!(vest.maximum_available(59), 0);
+        assert_eq!(vest.maximum_available(60), 5_000);
+        assert_eq!(vest.maximum_available(61), 5_000);
+        assert_eq!(vest.maximum_available(119), 5_000);
+        assert_eq!(vest.maximum_available(120), 10_000);
+        assert_eq!(vest.maximum_available(1_199), 95_000);
+        assert_eq!(vest.maximum_available(1_200), 100_000);
+        assert_eq!(vest.maximum_available(5_000), 100_000);
+
+        // enough ticks to overflow the multiplication
+        let vest = VestedTreasury {
+            initial_amount: u64::MAX,
+            vestment_period: 1,
+            vestment_percentage: 1,
+            ..vest
+        };
+        assert_eq!(vest.maximum_available(i64::MAX), u64::MAX);
+    }
+
+    #[test]
+    pub fn test_vested_pause() {
+        let mut vest = VestedTreasury {
+            mint: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            initial_amount: 100_000,
+            start: 0,
+            vestment_period: 60,
+            vestment_percentage: 500, // 5%
+            withdrawn: 0,
+            grantor: Pubkey::new_unique(),
+            paused_at: 0,
+            accelerated: 0,
+            delegate: Pubkey::default(),
+        };
+
+        assert_eq!(vest.resume(100), Err(TreasuryError::VestingNotPaused));
+        assert_eq!(vest.pause(150), Ok(()));
+        assert_eq!(vest.pause(160), Err(TreasuryError::VestingPaused));
+
+        // frozen at two periods
+        assert_eq!(vest.maximum_available(100), 5_000);
+        assert_eq!(vest.maximum_available(150), 10_000);
+        assert_eq!(vest.maximum_available(5_000), 10_000);
+
+        // paused for 1,000 seconds
+        assert_eq!(vest.resume(1_150), Ok(()));
+        assert_eq!({ vest.start }, 1_000);
+        assert!(!vest.is_paused());
+        assert_eq!(vest.maximum_available(1_150), 10_000);
+        assert_eq!(vest.maximum_available(1_180), 15_000);
+        assert_eq!(vest.maximum_available(2_199), 95_000);
+        assert_eq!(vest.maximum_available(2_200), 100_000);
+    }
+
+    #[test]
+    pub fn test_vested_accelerate() {
+        let mut vest = VestedTreasury {
+            mint: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            initial_amount: 100_000,
+            start: 0,
+            vestment_period: 60,
+            vestment_percentage: 500, // 5%
+            withdrawn: 0,
+            grantor: Pubkey::new_unique(),
+            paused_at: 0,
+            accelerated: 0,
+            delegate: Pubkey::default(),
+        };
+
+        assert_eq!(
+            vest.accelerate(0, Acceleration::Amount(0)),
+            Err(TreasuryError::InvalidAcceleration)
+        );
+        assert_eq!(
+            vest.accelerate(0, Acceleration::Percentage(0)),
+            Err(TreasuryError::InvalidAcceleration)
+        );
+        assert_eq!(
+            vest.accelerate(0, Acceleration::Percentage(10_001)),
+            Err(TreasuryError::InvalidAcceleration)
+        );
+
+        // before the first period
+        assert_eq!(vest.accelerate(0, Acceleration::Amount(1_000)), Ok(()));
+        assert_eq!(vest.maximum_available(0), 1_000);
+        assert_eq!(vest.maximum_available(60), 6_000);
+
+        // half of the 89,000 unvested after two periods
+        assert_eq!(
+            vest.accelerate(120, Acceleration::Percentage(5_000)),
+            Ok(())
+        );
+        assert_eq!({ vest.accelerated }, 45_500);
+        assert_eq!(vest.maximum_available(120), 55_500);
+        assert_eq!(vest.maximum_available(659), 95_500);
+        assert_eq!(vest.maximum_available(660), 100_000);
+
+        // capped at the unvested amount
+        assert_eq!(vest.accelerate(120, Acceleration::Amount(u64::MAX)), Ok(()));
+        assert_eq!({ vest.accelerated }, 90_000);
+        assert_eq!(vest.maximum_available(120), 100_000);
+        assert_eq!(vest.maximum_available(u32::MAX as i64), 100_000);
+    }
+
+    #[test]
+    pub fn test_vested_split() {
+        let mut vest = VestedTreasury {
+            mint: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            initial_amount: 100_000,
+            start: 0,
+            vestment_period: 60,
+            vestment_percentage: 500, // 5%
+            withdrawn: 10_001,
+            grantor: Pubkey::new_unique(),
+            paused_at: 0,
+            accelerated: 3_000,
+            delegate: Pubkey::default(),
+        };
+        let authority = Pubkey::new_unique();
+
+        assert_eq!(
+            vest.split(0, authority, 90_000, 120),
+            Err(TreasuryError::InvalidSplitAmount)
+        );
+        assert_eq!(
+            vest.split(100_000, authority, 90_000, 120),
+            Err(TreasuryError::InvalidSplitAmount)
+        );
+
+        let (new, fund_share) = vest.split(25_000, authority, 89_999, 120).unwrap();
+        // 25,000 - 2,500, the original keeps 75,000 - 7,501
+        assert_eq!(fund_share, 22_500);
+        assert_eq!(
+            new,
+            VestedTreasury {
+                authority,
+                initial_amount: 25_000,
+                withdrawn: 2_500,
+                accelerated: 750,
+                ..vest
+            }
+        );
+        assert_eq!({ vest.initial_amount }, 75_000);
+        assert_eq!({ vest.withdrawn }, 7_501);
+        assert_eq!({ vest.accelerated }, 2_250);
+
+        // both halves vest in lockstep
+        assert_eq!(vest.maximum_available(600), 39_750);
+        assert_eq!(new.maximum_available(600), 13_250);
+    }
+
+    #[test]
+    pub fn test_vested_split_rounding() {
+        // 33 per period, 99 vested and withdrawn after 3 periods
+        let original = VestedTreasury {
+            mint: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            initial_amount: 100,
+            start: 0,
+            vestment_period: 1,
+            vestment_percentage: 3_333,
+            withdrawn: 99,
+            grantor: Pubkey::new_unique(),
+            paused_at: 0,
+            accelerated: 0,
+            delegate: Pubkey::default(),
+        };
+        assert_eq!(original.maximum_available(3), 99);
+
+        // the halves only vest 16 per period, 48 each, which is less than was withdrawn
+        let mut vest = original;
+        assert_eq!(
+            vest.split(50, Pubkey::new_unique(), 1, 3),
+            Err(TreasuryError::SplitExceedsVested)
+        );
+        assert_eq!(vest, original);
+
+        let mut vest = VestedTreasury {
+            withdrawn: 96,
+            ..original
+        };
+        let (new, fund_share) = vest.split(50, Pubkey::new_unique(), 4, 3).unwrap();
+        assert_eq!(fund_share, 2);
+        for half in [vest, new] {
+            assert_eq!(half.maximum_available(3), 48);
+            assert_eq!({ half.withdrawn }, 48);
+        }
+
+        // the new half never gets more than it has vested
+        let mut vest = VestedTreasury {
+            withdrawn: 97,
+            initial_amount: 200,
+            ..original
+        };
+        let (new, fund_share) = vest.split(50, Pubkey::new_unique(), 103, 3).unwrap();
+        assert_eq!(new.maximum_available(3), 48);
+        assert_eq!({ new.withdrawn }, 24);
+        assert_eq!(vest.maximum_available(3), 147);
+        assert_eq!({ vest.withdrawn }, 73);
+        // both funds cover what their treasury is still owed
+        assert_eq!(fund_share, 26);
+        assert_eq!(103 - fund_share, 150 - 73);
+    }
+
+    #[test]
+    pub fn test_vested_claimer() {
+        let mut vest = VestedTreasury {
+            mint: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            initial_amount: 100_000,
+            start: 0,
+            vestment_period: 60,
+            vestment_percentage: 500,
+            withdrawn: 0,
+            grantor: Pubkey::new_unique(),
+            paused_at: 0,
+            accelerated: 0,
+            delegate: Pubkey::default(),
+        };
+        let delegate = Pubkey::new_unique();
+
+        assert!(vest.is_claimer(&vest.authority));
+        assert!(!vest.is_claimer(&delegate));
+        assert!(!vest.is_claimer(&Pubkey::default()));
+
+        vest.delegate = delegate;
+        assert!(vest.is_claimer(&vest.authority));
+        assert!(vest.is_claimer(&delegate));
+        assert!(!vest.is_claimer(&vest.grantor));
+
+        // a split off treasury belongs to someone else
+        let (new, _) = vest.split(1_000, Pubkey::new_unique(), 0, 0).unwrap();
+        assert_eq!(new.delegate, Pubkey::default());
+        assert!(!new.is_claimer(&delegate));
+    }
+
+    #[test]
+    pub fn test_vested_merge() {
+        let original = VestedTreasury {
+            mint: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            initial_amount: 100_000,
+            start: 0,
+            vestment_period: 60,
+            vestment_percentage: 500, // 5%
+            withdrawn: 10_001,
+            grantor: Pubkey::new_unique(),
+            paused_at: 0,
+            accelerated: 3_000,
+            delegate: Pubkey::default(),
+        };
+        let mut vest = original;
+        let (new, _) = vest.split(25_000, original.authority, 0, 120).unwrap();
+
+        for other in [
+            VestedTreasury {
+                authority: Pubkey::new_unique(),
+                ..new
+            },
+            VestedTreasury {
+                vestment_period: 61,
+                ..new
+            },
+            VestedTreasury {
+                paused_at: 1,
+                ..new
+            },
+        ] {
+            assert_eq!(
+                vest.merge(&other),
+                Err(TreasuryError::IncompatibleTreasuries)
+            );
+        }
+
+        // merging the halves restores the original
+        assert_eq!(vest.merge(&new), Ok(()));
+        assert_eq!(vest, original);
+
+        let huge = VestedTreasury {
+            initial_amount: u64::MAX,
+            ..original
+        };
+        assert_eq!(
+            vest.merge(&huge),
+            Err(TreasuryError::IncompatibleTreasuries)
+        );
+    }
+
+    #[test]
+    pub fn test_verify_parameters() {
+        assert_eq!(VestedTreasury::verify_parameters(1, 1, 1), Ok(()));
+        assert_eq!(VestedTreasury::verify_parameters(1, 1, 10_000), Ok(()));
+        assert_eq!(
+            VestedTreasury::verify_parameters(0, 1, 1),
+            Err(TreasuryError::InvalidVestmentAmount)
+        );
+        assert_eq!(
+            VestedTreasury::verify_parameters(1, 0, 1),
+            Err(TreasuryError::InvalidVestmentPeriod)
+        );
+        assert_eq!(
+            VestedTreasury::verify_parameters(1, 1, 0),
+            Err(TreasuryError::InvalidVestmentPercentage)
+        );
+        assert_eq!(
+            VestedTreasury::verify_parameters(1, 1, 10_001),
+            Err(TreasuryError::InvalidVestmentPercentage)
+        );
+    }
+
+    #[test]
+    pub fn test_vested_query() {
+        let vest = VestedTreasury {
+            mint: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            initial_amount: 100_000,
+            start: 0,
+            vestment_period: 60,
+            vestment_percentage: 500, // 5%
+            withdrawn: 5_000,
+            grantor: Pubkey::new_unique(),
+            paused_at: 0,
+            accelerated: 0,
+            delegate: Pubkey::default(),
+        };
+
+        assert_eq!(
+            vest.query(120, 95_000),
+            VestedQuery {
+                claimable: 5_000,
+                vested: 10_000,
+                unvested: 90_000,
+                locked: 90_000,
+            }
+        );
+        // fund underfunded
+        assert_eq!(
+            vest.query(600, 20_000),
+            VestedQuery {
+                claimable: 20_000,
+                vested: 50_000,
+                unvested: 50_000,
+                locked: 0,
+            }
+        );
+        // more in the fund than the schedule
+        assert_eq!(
+            vest.query(5_000, 150_000),
+            VestedQuery {
+                claimable: 95_000,
+                vested: 100_000,
+                unvested: 0,
+                locked: 55_000,
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_vote_escrow() {
+        const MAX: i64 = SimpleTreasury::MAX_LOCK_DURATION;
+        let mut ve = SimpleTreasury {
+            mint: Pubkey::new_unique(),
+            mode: SimpleTreasuryMode::VoteEscrow,
+            authority: Pubkey::new_unique(),
+            lock_end: 0,
+            weight: 0,
+            weight_updated_at: 0,
+        };
+        assert_eq!(ve.vote_escrow_weight(0, 1_000), 0);
+
+        assert_eq!(ve.extend_lock(100, MAX + 100), Ok(()));
+        assert_eq!(ve.vote_escrow_weight(100, 1_000), 1_000);
+        assert_eq!(ve.vote_escrow_weight(100 + MAX / 4, 1_000), 750);
+        assert_eq!(ve.vote_escrow_weight(MAX + 100, 1_000), 0);
+        assert_eq!(ve.vote_escrow_weight(2 * MAX, 1_000), 0);
+
+        // can't shorten or go past the maximum
+        assert_eq!(
+            ve.extend_lock(200, MAX + 100),
+            Err(TreasuryError::InvalidLockEnd)
+        );
+        assert_eq!(
+            ve.extend_lock(200, MAX + 201),
+            Err(TreasuryError::InvalidLockEnd)
+        );
+        // an expired lock can't be renewed in the past
+        assert_eq!(
+            ve.extend_lock(MAX + 300, MAX + 250),
+            Err(TreasuryError::InvalidLockEnd)
+        );
+        assert_eq!(ve.extend_lock(MAX + 300, MAX + 400), Ok(()));
+
+        ve.update_weight(MAX + 300, u64::MAX);
+        assert_eq!({ ve.weight }, (u64::MAX as u128 * 100 / MAX as u128) as u64);
+        assert_eq!({ ve.weight_updated_at }, MAX + 300);
+
+        let mut locked = SimpleTreasury {
+            mode: SimpleTreasuryMode::Locked,
+            ..ve
+        };
+        assert_eq!(locked.vote_escrow_weight(0, 1_000), 0);
+        assert_eq!(locked.extend_lock(0, 1), Err(TreasuryError::NotVoteEscrow));
+    }
+
+    #[test]
+    pub fn test_splitter() {
+        let (alice, bob, carol) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut splitter =
+            SplitterTreasury::new(Pubkey::new_unique(), &[(alice, 6_000), (bob, 4_000)]).unwrap();
+        assert_eq!(splitter.try_to_vec().unwrap().len(), SplitterTreasury::LEN);
+        assert_eq!(splitter.shareholders().count(), 2);
+
+        assert_eq!(splitter.claimable(&alice, 1_000), Some(600));
+        assert_eq!(splitter.claimable(&carol, 1_000), None);
+        assert_eq!(splitter.claim(&alice, 1_000), Ok(600));
+        assert_eq!(splitter.claim(&alice, 400), Ok(0));
+        assert_eq!(
+            splitter.claim(&carol, 400),
+            Err(TreasuryError::NotAShareholder)
+        );
+
+        // a late deposit of 1,000 is split as well
+        assert_eq!(splitter.claimable(&alice, 1_400), Some(600));
+        assert_eq!(splitter.claim(&bob, 1_400), Ok(800));
+        assert_eq!(splitter.claim(&alice, 600), Ok(600));
+        assert_eq!({ splitter.total_released }, 2_000);
+        assert_eq!({ splitter.shareholders[0].released }, 1_200);
+        assert_eq!({ splitter.shareholders[1].released }, 800);
+
+        // rounding never pays out more than the fund holds
+        let mut thirds = SplitterTreasury::new(
+            Pubkey::new_unique(),
+            &[(alice, 3_333), (bob, 3_333), (carol, 3_334)],
+        )
+        .unwrap();
+        let mut balance = 10;
+        for recipient in [alice, bob, carol] {
+            balance -= thirds.claim(&recipient, balance).unwrap();
+        }
+        assert_eq!(balance, 1);
+    }
+
+    #[test]
+    pub fn test_splitter_shares() {
+        let mint = Pubkey::new_unique();
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert!(SplitterTreasury::new(mint, &[(alice, 10_000)]).is_ok());
+        for shares in [
+            vec![],
+            vec![(alice, 9_999)],
+            vec![(alice, 5_000), (bob, 5_001)],
+            vec![(alice, 10_000), (bob, 0)],
+            vec![(alice, 5_000), (alice, 5_000)],
+            vec![(alice, 1_000); 11],
+        ] {
+            assert_eq!(
+                SplitterTreasury::new(mint, &shares),
+                Err(TreasuryError::InvalidShares)
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_escrow() {
+        let (mint, depositor, payee, arbiter) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let escrow = EscrowTreasury::new(mint, depositor, payee, arbiter, 100, 0).unwrap();
+        assert_eq!(escrow.try_to_vec().unwrap().len(), EscrowTreasury::LEN);
+
+        assert_eq!(escrow.settle(Settlement::Release, 99), Ok(payee));
+        assert_eq!(escrow.settle(Settlement::Refund, 99), Ok(depositor));
+        assert_eq!(
+            escrow.settle(Settlement::Expire, 99),
+            Err(TreasuryError::EscrowNotExpired)
+        );
+
+        assert_eq!(
+            escrow.settle(Settlement::Release, 100),
+            Err(TreasuryError::EscrowExpired)
+        );
+        assert_eq!(escrow.settle(Settlement::Refund, 100), Ok(depositor));
+        assert_eq!(escrow.settle(Settlement::Expire, 100), Ok(depositor));
+
+        for (depositor, payee, arbiter, expiry) in [
+            (depositor, payee, arbiter, 0),
+            (depositor, payee, depositor, 100),
+            (depositor, payee, payee, 100),
+        ] {
+            assert_eq!(
+                EscrowTreasury::new(mint, depositor, payee, arbiter, expiry, 0),
+                Err(TreasuryError::InvalidEscrow)
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_milestones() {
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut treasury = MilestoneTreasury::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            &[(300, alice), (700, bob)],
+        )
+        .unwrap();
+        let data = treasury.try_to_vec().unwrap();
+        assert_eq!(data.len(), MilestoneTreasury::LEN);
+        assert_eq!(
+            data[MilestoneTreasury::AUTHORITY_OFFSET..MilestoneTreasury::AUTHORITY_OFFSET + 32],
+            treasury.authority.to_bytes()
+        );
+        assert_eq!(treasury.milestones().count(), 2);
+        assert_eq!(treasury.available(1_000), 0);
+
+        assert_eq!(
+            treasury.confirm(1, &alice),
+            Err(TreasuryError::InvalidAttestor)
+        );
+        assert_eq!(
+            treasury.confirm(2, &bob),
+            Err(TreasuryError::InvalidMilestone)
+        );
+        assert_eq!(treasury.confirm(1, &bob), Ok(()));
+        assert_eq!(
+            treasury.confirm(1, &bob),
+            Err(TreasuryError::MilestoneConfirmed)
+        );
+        assert_eq!(treasury.unlocked(), 700);
+        assert_eq!(treasury.available(1_000), 700);
+        // underfunded
+        assert_eq!(treasury.available(500), 500);
+
+        treasury.withdrawn = 500;
+        assert_eq!(treasury.confirm(0, &alice), Ok(()));
+        assert_eq!(treasury.available(500), 500);
+
+        let mint = Pubkey::new_unique();
+        for milestones in [
+            vec![],
+            vec![(0, alice)],
+            vec![(u64::MAX, alice), (1, bob)],
+            vec![(1, alice); 9],
+        ] {
+            assert_eq!(
+                MilestoneTreasury::new(mint, alice, &milestones),
+                Err(TreasuryError::InvalidMilestones)
+            );
+        }
+    }
+
+    #[test]
+    pub fn test_hurdles() {
+        let mut treasury = HurdleTreasury::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            100,
+            &[(300, 10), (700, 20)],
+        )
+        .unwrap();
+        let data = treasury.try_to_vec().unwrap();
+        assert_eq!(data.len(), HurdleTreasury::LEN);
+        assert_eq!(
+            data[HurdleTreasury::AUTHORITY_OFFSET..HurdleTreasury::AUTHORITY_OFFSET + 32],
+            treasury.authority.to_bytes()
+        );
+
+        // the first hurdle is met, but not for long enough
+        treasury.update(1_000, 15);
+        assert_eq!({ treasury.tranches[0].met_since }, 1_000);
+        assert_eq!({ treasury.tranches[1].met_since }, 0);
+        treasury.update(1_099, 25);
+        assert_eq!(treasury.unlocked(), 0);
+        assert_eq!({ treasury.tranches[1].met_since }, 1_099);
+
+        treasury.update(1_100, 25);
+        assert_eq!(treasury.unlocked(), 300);
+        assert_eq!(treasury.available(1_000), 300);
+
+        // a dip resets the second hurdle but the first stays unlocked
+        treasury.update(1_150, 5);
+        assert_eq!({ treasury.tranches[1].met_since }, 0);
+        treasury.update(1_200, 20);
+        treasury.update(1_299, 20);
+        assert_eq!(treasury.unlocked(), 300);
+        treasury.update(1_300, 20);
+        assert_eq!(treasury.unlocked(), 1_000);
+
+        treasury.withdrawn = 300;
+        assert_eq!(treasury.available(1_000), 700);
+        assert_eq!(treasury.available(500), 500);
+
+        let mint = Pubkey::new_unique();
+        for tranches in [
+            vec![],
+            vec![(0, 10)],
+            vec![(10, 0)],
+            vec![(u64::MAX, 10), (1, 20)],
+            vec![(1, 10); 9],
+        ] {
+            assert_eq!(
+                HurdleTreasury::new(mint, mint, mint, 100, &tranches),
+                Err(TreasuryError::InvalidTranches)
+            );
+        }
     }
 }
@@ -0,0 +1,151 @@
+//! Program-wide configuration and protocol fees
+//!
+//! A single config account at the program address `Config::address` holds the fees the
+//! program charges. Until the program's upgrade authority initializes it, nothing is
+//! charged. Creating a treasury costs the funder `creation_fee` lamports, which are
+//! kept in the config account. Withdrawals pay `withdrawal_fee` basis points of the
+//! withdrawn amount into the fee vault of the mint, the config's associated token
+//! account, and so do escrow settlements. Splitting and merging vested treasuries
+//! moves tokens between treasury funds and pays no fee. `CollectFees` sends both to the
+//! fee receiver.
+//!
+//! The config also holds the emergency pause. The guardian can pause the program, which
+//! stops every instruction that creates a treasury or moves tokens out of a fund, and
+//! only the admin can unpause it. Deposits are plain token transfers and still work.
+
+use std::cell::RefMut;
+
+use bytemuck::{CheckedBitPattern, NoUninit};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address;
+
+use crate::{
+    error::TreasuryError,
+    zero_copy::{impl_borsh_as_bytes, ZeroCopy},
+};
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, CheckedBitPattern, NoUninit)]
+pub struct Config {
+    /// The key that can change the config
+    pub admin: Pubkey,
+    /// The owner of the accounts collected fees are sent to
+    pub fee_receiver: Pubkey,
+    /// Lamports the funder pays for every new treasury
+    pub creation_fee: u64,
+    /// The share of every withdrawal paid into the fee vault, in basis points
+    pub withdrawal_fee: u16,
+    /// The key that can pause the program
+    pub guardian: Pubkey,
+    pub paused: bool,
+}
+
+impl ZeroCopy for Config {}
+impl_borsh_as_bytes!(Config);
+
+impl Config {
+    /// The size of a serialized config
+    pub const LEN: usize = 107;
+    /// The highest withdrawal fee, 5%
+    pub const MAX_WITHDRAWAL_FEE: u16 = 500;
+
+    pub fn new(
+        admin: Pubkey,
+        fee_receiver: Pubkey,
+        creation_fee: u64,
+        withdrawal_fee: u16,
+        guardian: Pubkey,
+    ) -> Result<Self, TreasuryError> {
+        if withdrawal_fee > Self::MAX_WITHDRAWAL_FEE {
+            return Err(TreasuryError::InvalidFee);
+        }
+        Ok(Config {
+            admin,
+            fee_receiver,
+            creation_fee,
+            withdrawal_fee,
+            guardian,
+            paused: false,
+        })
+    }
+
+    pub fn address(program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"config"], program_id)
+    }
+
+    /// The token account withdrawal fees of a mint are paid into
+    pub fn fee_vault_address(mint: &Pubkey, program_id: &Pubkey) -> Pubkey {
+        get_associated_token_address(&Self::address(program_id).0, mint)
+    }
+
+    /// Read the config, `None` if it hasn't been initialized yet
+    pub fn load(
+        config_info: &AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Option<Self>, ProgramError> {
+        Ok(Self::load_mut(config_info, program_id)?.map(|config| *config))
+    }
+
+    /// Borrow the config to modify it in place, `None` if it hasn't been initialized yet
+    pub fn load_mut<'a>(
+        config_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Option<RefMut<'a, Self>>, ProgramError> {
+        if *config_info.key != Self::address(program_id).0 {
+            return Err(TreasuryError::InvalidConfig.into());
+        }
+        if config_info.data_is_empty() {
+            return Ok(None);
+        }
+        if *config_info.owner != *program_id {
+            return Err(TreasuryError::InvalidConfig.into());
+        }
+        Self::borrow_mut(config_info)
+            .map(Some)
+            .ok_or_else(|| TreasuryError::InvalidConfig.into())
+    }
+
+    /// Fail while the program is paused
+    pub fn check_not_paused(&self) -> Result<(), TreasuryError> {
+        if self.paused {
+            return Err(TreasuryError::Paused);
+        }
+        Ok(())
+    }
+
+    /// The part of a withdrawal of `amount` that goes to the fee vault, rounded down
+    pub fn fee_on(&self, amount: u64) -> u64 {
+        (amount as u128 * self.withdrawal_fee as u128 / 10_000) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshSerialize;
+
+    #[test]
+    pub fn test_config() {
+        let mut config = Config::new(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            5_000,
+            250,
+            Pubkey::new_unique(),
+        )
+        .unwrap();
+        assert_eq!(config.try_to_vec().unwrap().len(), Config::LEN);
+        assert_eq!(config.check_not_paused(), Ok(()));
+        config.paused = true;
+        assert_eq!(config.check_not_paused(), Err(TreasuryError::Paused));
+
+        assert_eq!(config.fee_on(1_000), 25);
+        assert_eq!(config.fee_on(39), 0);
+        assert_eq!(config.fee_on(u64::MAX), u64::MAX / 40);
+
+        assert_eq!(
+            Config::new(config.admin, config.fee_receiver, 0, 501, config.guardian),
+            Err(TreasuryError::InvalidFee)
+        );
+    }
+}
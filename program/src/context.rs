@@ -0,0 +1,2245 @@
+//! Account validation
+//!
+//! Every instruction has a context that reads its accounts in the documented order and
+//! verifies them before the processor acts on them: account owners, program ids,
+//! sysvars, signer and writable flags, and that accounts which have to be different
+//! are not the same account passed twice.
+
+use std::cell::RefMut;
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable,
+    clock::Clock,
+    ed25519_program,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    system_program,
+    sysvar::{self, rent::Rent, Sysvar},
+};
+use spl_associated_token_account::get_associated_token_address;
+use spl_token::state::{Account, Mint};
+
+use crate::{
+    account::{
+        EscrowTreasury, HurdleTreasury, MilestoneTreasury, Settlement, SimpleTreasury,
+        SplitterTreasury, VestedTreasury,
+    },
+    config::Config,
+    error::TreasuryError,
+    governance::{MaxVoterWeightRecord, VoterWeightRecord, WeightedTreasury},
+    oracle::PriceFeed,
+    recovery::{RecoverableTreasury, Recovery, SocialRecovery},
+    zero_copy::ZeroCopy,
+};
+
+/// Accounts of `CreateSimpleTreasury`
+pub struct CreateSimpleTreasuryContext<'a, 'b> {
+    pub funder_info: &'a AccountInfo<'b>,
+    pub authority_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub mint_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub creation_fee: u64,
+    pub rent: Rent,
+}
+
+impl<'a, 'b> CreateSimpleTreasuryContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let funder_info = next_account_info(iter)?;
+        let authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let mint_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let token_program_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+
+        signer(funder_info)?;
+        writable(funder_info)?;
+        signer(authority_info)?;
+        signer(treasury_info)?;
+        writable(treasury_info)?;
+        distinct(&[funder_info, treasury_info])?;
+
+        mint(mint_info)?;
+        let rent = rent(rent_info)?;
+        token_program(token_program_info)?;
+        system_program(system_program_info)?;
+        let creation_fee = creation_fee(config_info, program_id)?;
+
+        if !treasury_info.data_is_empty() {
+            return Err(TreasuryError::TreasuryAlreadyExists.into());
+        }
+
+        Ok(CreateSimpleTreasuryContext {
+            funder_info,
+            authority_info,
+            treasury_info,
+            mint_info,
+            system_program_info,
+            config_info,
+            creation_fee,
+            rent,
+        })
+    }
+}
+
+/// Accounts of `WithdrawSimple`
+pub struct WithdrawSimpleContext<'a, 'b> {
+    pub authority_info: &'a AccountInfo<'b>,
+    pub recipient_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub fund_authority_info: &'a AccountInfo<'b>,
+    pub fund_info: &'a AccountInfo<'b>,
+    pub token_program_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, SimpleTreasury>,
+    pub fund_authority_seed: u8,
+    pub fund: Account,
+    pub fee: WithdrawalFee<'a, 'b>,
+    pub activity: Activity<'a>,
+    pub clock: Clock,
+}
+
+impl<'a, 'b> WithdrawSimpleContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let _funder_info = next_account_info(iter)?;
+        let authority_info = next_account_info(iter)?;
+        let recipient_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let fund_authority_info = next_account_info(iter)?;
+        let fund_info = next_account_info(iter)?;
+        let token_program_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+        let fee_vault_info = next_account_info(iter)?;
+        let recovery_info = next_account_info(iter)?;
+
+        let treasury =
+            SimpleTreasury::from_account_info(treasury_info, authority_info, program_id)?;
+        writable(recipient_info)?;
+        writable(fund_info)?;
+
+        let fund_authority_seed = SimpleTreasury::verify_fund_authority_address(
+            fund_authority_info.key,
+            treasury_info.key,
+            program_id,
+        )?;
+        fund_address(fund_info, fund_authority_info, &treasury.mint)?;
+        distinct(&[recipient_info, treasury_info, fund_info])?;
+
+        let fund = token_account(fund_info, fund_authority_info.key, &treasury.mint)?;
+        token_account(recipient_info, &treasury.authority, &treasury.mint)?;
+        token_program(token_program_info)?;
+        let fee = withdrawal_fee(config_info, fee_vault_info, program_id, &treasury.mint)?;
+        let activity = activity(recovery_info, treasury_info, program_id)?;
+        let clock = clock(clock_info)?;
+
+        Ok(WithdrawSimpleContext {
+            authority_info,
+            recipient_info,
+            treasury_info,
+            fund_authority_info,
+            fund_info,
+            token_program_info,
+            treasury,
+            fund_authority_seed,
+            fund,
+            fee,
+            activity,
+            clock,
+        })
+    }
+}
+
+/// Accounts of `CreatedVestedTreaury`
+pub struct CreateVestedTreasuryContext<'a, 'b> {
+    pub funder_info: &'a AccountInfo<'b>,
+    pub authority_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub mint_info: &'a AccountInfo<'b>,
+    pub grantor_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub creation_fee: u64,
+    pub rent: Rent,
+    pub clock: Clock,
+}
+
+impl<'a, 'b> CreateVestedTreasuryContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let funder_info = next_account_info(iter)?;
+        let authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let mint_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+        let grantor_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+
+        signer(funder_info)?;
+        writable(funder_info)?;
+        signer(treasury_info)?;
+        writable(treasury_info)?;
+        distinct(&[funder_info, treasury_info])?;
+
+        mint(mint_info)?;
+        let rent = rent(rent_info)?;
+        let clock = clock(clock_info)?;
+        system_program(system_program_info)?;
+        let creation_fee = creation_fee(config_info, program_id)?;
+
+        if !treasury_info.data_is_empty() {
+            return Err(TreasuryError::TreasuryAlreadyExists.into());
+        }
+
+        Ok(CreateVestedTreasuryContext {
+            funder_info,
+            authority_info,
+            treasury_info,
+            mint_info,
+            grantor_info,
+            system_program_info,
+            config_info,
+            creation_fee,
+            rent,
+            clock,
+        })
+    }
+}
+
+/// Accounts of `WithdrawVested`
+pub struct WithdrawVestedContext<'a, 'b> {
+    pub claimer_info: &'a AccountInfo<'b>,
+    pub recipient_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub fund_authority_info: &'a AccountInfo<'b>,
+    pub fund_info: &'a AccountInfo<'b>,
+    pub token_program_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, VestedTreasury>,
+    pub fund_authority_seed: u8,
+    pub fund: Account,
+    pub fee: WithdrawalFee<'a, 'b>,
+    pub activity: Activity<'a>,
+    pub clock: Clock,
+}
+
+impl<'a, 'b> WithdrawVestedContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let _funder_info = next_account_info(iter)?;
+        let claimer_info = next_account_info(iter)?;
+        let recipient_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let fund_authority_info = next_account_info(iter)?;
+        let fund_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+        let token_program_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+        let fee_vault_info = next_account_info(iter)?;
+        let recovery_info = next_account_info(iter)?;
+
+        let treasury = VestedTreasury::from_claimer_info(treasury_info, claimer_info, program_id)?;
+        writable(recipient_info)?;
+        writable(treasury_info)?;
+        writable(fund_info)?;
+
+        let fund_authority_seed = VestedTreasury::verify_fund_authority_address(
+            fund_authority_info.key,
+            treasury_info.key,
+            program_id,
+        )?;
+        fund_address(fund_info, fund_authority_info, &treasury.mint)?;
+        distinct(&[recipient_info, treasury_info, fund_info])?;
+
+        let fund = token_account(fund_info, fund_authority_info.key, &treasury.mint)?;
+        token_account(recipient_info, &treasury.authority, &treasury.mint)?;
+        let clock = clock(clock_info)?;
+        token_program(token_program_info)?;
+        let fee = withdrawal_fee(config_info, fee_vault_info, program_id, &treasury.mint)?;
+        system_program(system_program_info)?;
+        // a withdrawal by the delegate doesn't show that the authority is active
+        let mut activity = activity(recovery_info, treasury_info, program_id)?;
+        if *claimer_info.key != treasury.authority {
+            activity.recovery = None;
+        }
+
+        Ok(WithdrawVestedContext {
+            claimer_info,
+            recipient_info,
+            treasury_info,
+            fund_authority_info,
+            fund_info,
+            token_program_info,
+            treasury,
+            fund_authority_seed,
+            fund,
+            fee,
+            activity,
+            clock,
+        })
+    }
+}
+
+/// Accounts of `QueryVested`
+pub struct QueryVestedContext {
+    pub treasury: VestedTreasury,
+    pub fund: Account,
+    pub clock: Clock,
+}
+
+impl QueryVestedContext {
+    pub fn load(program_id: &Pubkey, accounts: &[AccountInfo]) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let treasury_info = next_account_info(iter)?;
+        let fund_authority_info = next_account_info(iter)?;
+        let fund_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+
+        let treasury = *VestedTreasury::load(treasury_info, program_id)?;
+        VestedTreasury::verify_fund_authority_address(
+            fund_authority_info.key,
+            treasury_info.key,
+            program_id,
+        )?;
+        fund_address(fund_info, fund_authority_info, &treasury.mint)?;
+
+        let fund = token_account(fund_info, fund_authority_info.key, &treasury.mint)?;
+        let clock = clock(clock_info)?;
+
+        Ok(QueryVestedContext {
+            treasury,
+            fund,
+            clock,
+        })
+    }
+}
+
+/// Accounts of instructions signed by the grantor of a vested treasury
+pub struct GrantorContext<'a, 'b> {
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, VestedTreasury>,
+    pub clock: Clock,
+}
+
+impl<'a, 'b> GrantorContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let grantor_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+
+        let treasury = VestedTreasury::load_mut(treasury_info, program_id)?;
+        signer(grantor_info)?;
+        if treasury.grantor != *grantor_info.key {
+            return Err(TreasuryError::InvalidTreasuryGrantor.into());
+        }
+        writable(treasury_info)?;
+        let clock = clock(clock_info)?;
+
+        Ok(GrantorContext {
+            treasury_info,
+            treasury,
+            clock,
+        })
+    }
+}
+
+/// Accounts of `SetVestedDelegate`
+pub struct SetVestedDelegateContext<'a, 'b> {
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, VestedTreasury>,
+}
+
+impl<'a, 'b> SetVestedDelegateContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+
+        let treasury =
+            VestedTreasury::from_account_info(treasury_info, authority_info, program_id)?;
+        writable(treasury_info)?;
+
+        Ok(SetVestedDelegateContext {
+            treasury_info,
+            treasury,
+        })
+    }
+}
+
+/// Accounts of `SplitVested`
+pub struct SplitVestedContext<'a, 'b> {
+    pub funder_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub fund_authority_info: &'a AccountInfo<'b>,
+    pub fund_info: &'a AccountInfo<'b>,
+    pub new_treasury_info: &'a AccountInfo<'b>,
+    pub new_authority_info: &'a AccountInfo<'b>,
+    pub new_fund_info: &'a AccountInfo<'b>,
+    pub token_program_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, VestedTreasury>,
+    pub fund_authority_seed: u8,
+    pub fund: Account,
+    pub rent: Rent,
+    pub clock: Clock,
+}
+
+impl<'a, 'b> SplitVestedContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let funder_info = next_account_info(iter)?;
+        let authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let fund_authority_info = next_account_info(iter)?;
+        let fund_info = next_account_info(iter)?;
+        let new_treasury_info = next_account_info(iter)?;
+        let new_authority_info = next_account_info(iter)?;
+        let new_fund_authority_info = next_account_info(iter)?;
+        let new_fund_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let token_program_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+
+        let treasury =
+            VestedTreasury::from_account_info(treasury_info, authority_info, program_id)?;
+        not_paused(config_info, program_id)?;
+        signer(funder_info)?;
+        writable(funder_info)?;
+        writable(treasury_info)?;
+        writable(fund_info)?;
+        signer(new_treasury_info)?;
+        writable(new_treasury_info)?;
+        writable(new_fund_info)?;
+
+        let fund_authority_seed = VestedTreasury::verify_fund_authority_address(
+            fund_authority_info.key,
+            treasury_info.key,
+            program_id,
+        )?;
+        fund_address(fund_info, fund_authority_info, &treasury.mint)?;
+        VestedTreasury::verify_fund_authority_address(
+            new_fund_authority_info.key,
+            new_treasury_info.key,
+            program_id,
+        )?;
+        fund_address(new_fund_info, new_fund_authority_info, &treasury.mint)?;
+        distinct(&[
+            funder_info,
+            treasury_info,
+            fund_info,
+            new_treasury_info,
+            new_fund_info,
+        ])?;
+
+        let fund = token_account(fund_info, fund_authority_info.key, &treasury.mint)?;
+        token_account(new_fund_info, new_fund_authority_info.key, &treasury.mint)?;
+        let rent = rent(rent_info)?;
+        token_program(token_program_info)?;
+        system_program(system_program_info)?;
+        let clock = clock(clock_info)?;
+
+        if !new_treasury_info.data_is_empty() {
+            return Err(TreasuryError::TreasuryAlreadyExists.into());
+        }
+
+        Ok(SplitVestedContext {
+            funder_info,
+            treasury_info,
+            fund_authority_info,
+            fund_info,
+            new_treasury_info,
+            new_authority_info,
+            new_fund_info,
+            token_program_info,
+            system_program_info,
+            treasury,
+            fund_authority_seed,
+            fund,
+            rent,
+            clock,
+        })
+    }
+}
+
+/// Accounts of `MergeVested`
+pub struct MergeVestedContext<'a, 'b> {
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub fund_info: &'a AccountInfo<'b>,
+    pub source_info: &'a AccountInfo<'b>,
+    pub source_fund_authority_info: &'a AccountInfo<'b>,
+    pub source_fund_info: &'a AccountInfo<'b>,
+    pub recipient_info: &'a AccountInfo<'b>,
+    pub token_program_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, VestedTreasury>,
+    /// A copy, the source is closed once it's merged
+    pub source: VestedTreasury,
+    pub source_fund_authority_seed: u8,
+    pub source_fund: Account,
+    pub source_recovery_info: &'a AccountInfo<'b>,
+    pub source_social_info: &'a AccountInfo<'b>,
+    /// Closed with the source
+    pub source_recovery: Option<RefMut<'a, Recovery>>,
+    pub source_social: Option<RefMut<'a, SocialRecovery>>,
+}
+
+impl<'a, 'b> MergeVestedContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let fund_authority_info = next_account_info(iter)?;
+        let fund_info = next_account_info(iter)?;
+        let source_info = next_account_info(iter)?;
+        let source_fund_authority_info = next_account_info(iter)?;
+        let source_fund_info = next_account_info(iter)?;
+        let recipient_info = next_account_info(iter)?;
+        let token_program_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+        let source_recovery_info = next_account_info(iter)?;
+        let source_social_info = next_account_info(iter)?;
+
+        let source = *VestedTreasury::from_account_info(source_info, authority_info, program_id)?;
+        let treasury =
+            VestedTreasury::from_account_info(treasury_info, authority_info, program_id)?;
+        not_paused(config_info, program_id)?;
+        writable(treasury_info)?;
+        writable(fund_info)?;
+        writable(source_info)?;
+        writable(source_fund_info)?;
+        writable(recipient_info)?;
+
+        VestedTreasury::verify_fund_authority_address(
+            fund_authority_info.key,
+            treasury_info.key,
+            program_id,
+        )?;
+        fund_address(fund_info, fund_authority_info, &treasury.mint)?;
+        let source_fund_authority_seed = VestedTreasury::verify_fund_authority_address(
+            source_fund_authority_info.key,
+            source_info.key,
+            program_id,
+        )?;
+        fund_address(source_fund_info, source_fund_authority_info, &source.mint)?;
+        distinct(&[
+            treasury_info,
+            fund_info,
+            source_info,
+            source_fund_info,
+            recipient_info,
+            source_recovery_info,
+            source_social_info,
+        ])?;
+
+        token_account(fund_info, fund_authority_info.key, &treasury.mint)?;
+        let source_fund = token_account(
+            source_fund_info,
+            source_fund_authority_info.key,
+            &source.mint,
+        )?;
+        token_program(token_program_info)?;
+        let source_recovery =
+            Recovery::load_mut(source_recovery_info, source_info.key, program_id)?;
+        if source_recovery.is_some() {
+            writable(source_recovery_info)?;
+        }
+        let source_social =
+            SocialRecovery::load_mut(source_social_info, source_info.key, program_id)?;
+        if source_social.is_some() {
+            writable(source_social_info)?;
+        }
+
+        Ok(MergeVestedContext {
+            treasury_info,
+            fund_info,
+            source_info,
+            source_fund_authority_info,
+            source_fund_info,
+            recipient_info,
+            token_program_info,
+            treasury,
+            source,
+            source_fund_authority_seed,
+            source_fund,
+            source_recovery_info,
+            source_social_info,
+            source_recovery,
+            source_social,
+        })
+    }
+}
+
+/// Accounts of `UpdateVoterWeightRecord`
+pub struct UpdateVoterWeightRecordContext<'a, 'b> {
+    pub payer_info: &'a AccountInfo<'b>,
+    pub realm_info: &'a AccountInfo<'b>,
+    pub mint_info: &'a AccountInfo<'b>,
+    pub owner_info: &'a AccountInfo<'b>,
+    pub record_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    pub record_seed: u8,
+    pub clock: Clock,
+    pub rent: Rent,
+    /// The owner's treasuries and the balances of their funds
+    pub treasuries: Vec<(WeightedTreasury, u64)>,
+}
+
+impl<'a, 'b> UpdateVoterWeightRecordContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let payer_info = next_account_info(iter)?;
+        let realm_info = next_account_info(iter)?;
+        let mint_info = next_account_info(iter)?;
+        let owner_info = next_account_info(iter)?;
+        let record_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+
+        signer(payer_info)?;
+        writable(payer_info)?;
+        writable(record_info)?;
+        let (address, record_seed) =
+            VoterWeightRecord::address(realm_info.key, mint_info.key, owner_info.key, program_id);
+        if address != *record_info.key
+            || (!record_info.data_is_empty() && *record_info.owner != *program_id)
+        {
+            return Err(TreasuryError::InvalidVoterWeightRecord.into());
+        }
+        mint(mint_info)?;
+
+        let mut infos = vec![];
+        let mut treasuries = vec![];
+        while iter.len() > 0 {
+            let treasury_info = next_account_info(iter)?;
+            let fund_info = next_account_info(iter)?;
+            let treasury = weighted_treasury(treasury_info, program_id)?;
+            if treasury.authority() != *owner_info.key {
+                return Err(TreasuryError::InvalidTreasuryOwner.into());
+            }
+            if treasury.mint() != *mint_info.key {
+                return Err(TreasuryError::MintWrongToken.into());
+            }
+
+            let (fund_authority, _) = match treasury {
+                WeightedTreasury::Simple(_) => {
+                    SimpleTreasury::fund_authority_address(treasury_info.key, program_id)
+                }
+                WeightedTreasury::Vested(_) => {
+                    VestedTreasury::fund_authority_address(treasury_info.key, program_id)
+                }
+            };
+            if get_associated_token_address(&fund_authority, mint_info.key) != *fund_info.key {
+                return Err(TreasuryError::InvalidTreasuryFundAddress.into());
+            }
+            let fund = token_account(fund_info, &fund_authority, mint_info.key)?;
+
+            infos.push(treasury_info);
+            treasuries.push((treasury, fund.amount));
+        }
+        distinct(&infos)?;
+
+        let clock = clock(clock_info)?;
+        let rent = rent(rent_info)?;
+        system_program(system_program_info)?;
+
+        Ok(UpdateVoterWeightRecordContext {
+            payer_info,
+            realm_info,
+            mint_info,
+            owner_info,
+            record_info,
+            system_program_info,
+            record_seed,
+            clock,
+            rent,
+            treasuries,
+        })
+    }
+}
+
+/// Accounts of `UpdateMaxVoterWeightRecord`
+pub struct UpdateMaxVoterWeightRecordContext<'a, 'b> {
+    pub payer_info: &'a AccountInfo<'b>,
+    pub realm_info: &'a AccountInfo<'b>,
+    pub mint_info: &'a AccountInfo<'b>,
+    pub record_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    pub record_seed: u8,
+    pub mint: Mint,
+    pub clock: Clock,
+    pub rent: Rent,
+}
+
+impl<'a, 'b> UpdateMaxVoterWeightRecordContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let payer_info = next_account_info(iter)?;
+        let realm_info = next_account_info(iter)?;
+        let mint_info = next_account_info(iter)?;
+        let record_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+
+        signer(payer_info)?;
+        writable(payer_info)?;
+        writable(record_info)?;
+        let (address, record_seed) =
+            MaxVoterWeightRecord::address(realm_info.key, mint_info.key, program_id);
+        if address != *record_info.key
+            || (!record_info.data_is_empty() && *record_info.owner != *program_id)
+        {
+            return Err(TreasuryError::InvalidVoterWeightRecord.into());
+        }
+        let mint = mint(mint_info)?;
+        let clock = clock(clock_info)?;
+        let rent = rent(rent_info)?;
+        system_program(system_program_info)?;
+
+        Ok(UpdateMaxVoterWeightRecordContext {
+            payer_info,
+            realm_info,
+            mint_info,
+            record_info,
+            system_program_info,
+            record_seed,
+            mint,
+            clock,
+            rent,
+        })
+    }
+}
+
+/// Accounts of `ExtendLock`
+pub struct ExtendLockContext<'a, 'b> {
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, SimpleTreasury>,
+    pub fund: Account,
+    pub clock: Clock,
+}
+
+impl<'a, 'b> ExtendLockContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let fund_authority_info = next_account_info(iter)?;
+        let fund_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+
+        let treasury =
+            SimpleTreasury::from_account_info(treasury_info, authority_info, program_id)?;
+        writable(treasury_info)?;
+        SimpleTreasury::verify_fund_authority_address(
+            fund_authority_info.key,
+            treasury_info.key,
+            program_id,
+        )?;
+        fund_address(fund_info, fund_authority_info, &treasury.mint)?;
+
+        let fund = token_account(fund_info, fund_authority_info.key, &treasury.mint)?;
+        let clock = clock(clock_info)?;
+
+        Ok(ExtendLockContext {
+            treasury_info,
+            treasury,
+            fund,
+            clock,
+        })
+    }
+}
+
+/// Accounts of `UpdateVoteEscrowWeight`
+pub struct UpdateVoteEscrowWeightContext<'a, 'b> {
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, SimpleTreasury>,
+    pub fund: Account,
+    pub clock: Clock,
+}
+
+impl<'a, 'b> UpdateVoteEscrowWeightContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let treasury_info = next_account_info(iter)?;
+        let fund_authority_info = next_account_info(iter)?;
+        let fund_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+
+        let treasury = SimpleTreasury::load_mut(treasury_info, program_id)?;
+        writable(treasury_info)?;
+        SimpleTreasury::verify_fund_authority_address(
+            fund_authority_info.key,
+            treasury_info.key,
+            program_id,
+        )?;
+        fund_address(fund_info, fund_authority_info, &treasury.mint)?;
+
+        let fund = token_account(fund_info, fund_authority_info.key, &treasury.mint)?;
+        let clock = clock(clock_info)?;
+
+        Ok(UpdateVoteEscrowWeightContext {
+            treasury_info,
+            treasury,
+            fund,
+            clock,
+        })
+    }
+}
+
+/// Accounts of `CreateSplitterTreasury`
+pub struct CreateSplitterTreasuryContext<'a, 'b> {
+    pub funder_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub mint_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub creation_fee: u64,
+    pub rent: Rent,
+}
+
+impl<'a, 'b> CreateSplitterTreasuryContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let funder_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let mint_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+
+        signer(funder_info)?;
+        writable(funder_info)?;
+        signer(treasury_info)?;
+        writable(treasury_info)?;
+        distinct(&[funder_info, treasury_info])?;
+
+        mint(mint_info)?;
+        let rent = rent(rent_info)?;
+        system_program(system_program_info)?;
+        let creation_fee = creation_fee(config_info, program_id)?;
+
+        if !treasury_info.data_is_empty() {
+            return Err(TreasuryError::TreasuryAlreadyExists.into());
+        }
+
+        Ok(CreateSplitterTreasuryContext {
+            funder_info,
+            treasury_info,
+            mint_info,
+            system_program_info,
+            config_info,
+            creation_fee,
+            rent,
+        })
+    }
+}
+
+/// Accounts of `ClaimSplit`
+pub struct ClaimSplitContext<'a, 'b> {
+    pub shareholder_info: &'a AccountInfo<'b>,
+    pub recipient_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub fund_authority_info: &'a AccountInfo<'b>,
+    pub fund_info: &'a AccountInfo<'b>,
+    pub token_program_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, SplitterTreasury>,
+    pub fund_authority_seed: u8,
+    pub fund: Account,
+    pub fee: WithdrawalFee<'a, 'b>,
+}
+
+impl<'a, 'b> ClaimSplitContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let shareholder_info = next_account_info(iter)?;
+        let recipient_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let fund_authority_info = next_account_info(iter)?;
+        let fund_info = next_account_info(iter)?;
+        let token_program_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+        let fee_vault_info = next_account_info(iter)?;
+
+        let treasury = SplitterTreasury::load_mut(treasury_info, program_id)?;
+        if !shareholder_info.is_signer {
+            return Err(TreasuryError::MissingAuthoritySignature.into());
+        }
+        writable(recipient_info)?;
+        writable(treasury_info)?;
+        writable(fund_info)?;
+
+        let fund_authority_seed = SplitterTreasury::verify_fund_authority_address(
+            fund_authority_info.key,
+            treasury_info.key,
+            program_id,
+        )?;
+        fund_address(fund_info, fund_authority_info, &treasury.mint)?;
+        distinct(&[recipient_info, treasury_info, fund_info])?;
+
+        let fund = token_account(fund_info, fund_authority_info.key, &treasury.mint)?;
+        token_account(recipient_info, shareholder_info.key, &treasury.mint)?;
+        token_program(token_program_info)?;
+        let fee = withdrawal_fee(config_info, fee_vault_info, program_id, &treasury.mint)?;
+
+        Ok(ClaimSplitContext {
+            shareholder_info,
+            recipient_info,
+            treasury_info,
+            fund_authority_info,
+            fund_info,
+            token_program_info,
+            treasury,
+            fund_authority_seed,
+            fund,
+            fee,
+        })
+    }
+}
+
+/// Accounts of `CreateEscrowTreasury`
+pub struct CreateEscrowTreasuryContext<'a, 'b> {
+    pub funder_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub mint_info: &'a AccountInfo<'b>,
+    pub depositor_info: &'a AccountInfo<'b>,
+    pub payee_info: &'a AccountInfo<'b>,
+    pub arbiter_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub creation_fee: u64,
+    pub rent: Rent,
+    pub clock: Clock,
+}
+
+impl<'a, 'b> CreateEscrowTreasuryContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let funder_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let mint_info = next_account_info(iter)?;
+        let depositor_info = next_account_info(iter)?;
+        let payee_info = next_account_info(iter)?;
+        let arbiter_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+
+        signer(funder_info)?;
+        writable(funder_info)?;
+        signer(treasury_info)?;
+        writable(treasury_info)?;
+        distinct(&[funder_info, treasury_info])?;
+
+        mint(mint_info)?;
+        let rent = rent(rent_info)?;
+        let clock = clock(clock_info)?;
+        system_program(system_program_info)?;
+        let creation_fee = creation_fee(config_info, program_id)?;
+
+        if !treasury_info.data_is_empty() {
+            return Err(TreasuryError::TreasuryAlreadyExists.into());
+        }
+
+        Ok(CreateEscrowTreasuryContext {
+            funder_info,
+            treasury_info,
+            mint_info,
+            depositor_info,
+            payee_info,
+            arbiter_info,
+            system_program_info,
+            config_info,
+            creation_fee,
+            rent,
+            clock,
+        })
+    }
+}
+
+/// Accounts of `ReleaseEscrow`, `RefundEscrow` and `RefundExpiredEscrow`. The first two
+/// are signed by the arbiter, the last one has no arbiter account.
+pub struct SettleEscrowContext<'a, 'b> {
+    pub recipient_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub fund_authority_info: &'a AccountInfo<'b>,
+    pub fund_info: &'a AccountInfo<'b>,
+    pub token_program_info: &'a AccountInfo<'b>,
+    pub fund_authority_seed: u8,
+    pub fund: Account,
+    pub fee: WithdrawalFee<'a, 'b>,
+}
+
+impl<'a, 'b> SettleEscrowContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+        settlement: Settlement,
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let arbiter_info = match settlement {
+            Settlement::Release | Settlement::Refund => Some(next_account_info(iter)?),
+            Settlement::Expire => None,
+        };
+        let recipient_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let fund_authority_info = next_account_info(iter)?;
+        let fund_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+        let token_program_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+        let fee_vault_info = next_account_info(iter)?;
+
+        let treasury = EscrowTreasury::load(treasury_info, program_id)?;
+        let fee = withdrawal_fee(config_info, fee_vault_info, program_id, &treasury.mint)?;
+        if let Some(arbiter_info) = arbiter_info {
+            if treasury.arbiter != *arbiter_info.key {
+                return Err(TreasuryError::InvalidArbiter.into());
+            }
+            if !arbiter_info.is_signer {
+                return Err(TreasuryError::MissingAuthoritySignature.into());
+            }
+        }
+        writable(recipient_info)?;
+        writable(fund_info)?;
+
+        let fund_authority_seed = EscrowTreasury::verify_fund_authority_address(
+            fund_authority_info.key,
+            treasury_info.key,
+            program_id,
+        )?;
+        fund_address(fund_info, fund_authority_info, &treasury.mint)?;
+        distinct(&[recipient_info, treasury_info, fund_info])?;
+
+        let clock = clock(clock_info)?;
+        let owner = treasury.settle(settlement, clock.unix_timestamp)?;
+        let fund = token_account(fund_info, fund_authority_info.key, &treasury.mint)?;
+        token_account(recipient_info, &owner, &treasury.mint)?;
+        token_program(token_program_info)?;
+
+        Ok(SettleEscrowContext {
+            recipient_info,
+            treasury_info,
+            fund_authority_info,
+            fund_info,
+            token_program_info,
+            fund_authority_seed,
+            fund,
+            fee,
+        })
+    }
+}
+
+/// Accounts of `CreateMilestoneTreasury`
+pub struct CreateMilestoneTreasuryContext<'a, 'b> {
+    pub funder_info: &'a AccountInfo<'b>,
+    pub authority_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub mint_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub creation_fee: u64,
+    pub rent: Rent,
+}
+
+impl<'a, 'b> CreateMilestoneTreasuryContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let funder_info = next_account_info(iter)?;
+        let authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let mint_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+
+        signer(funder_info)?;
+        writable(funder_info)?;
+        signer(treasury_info)?;
+        writable(treasury_info)?;
+        distinct(&[funder_info, treasury_info])?;
+
+        mint(mint_info)?;
+        let rent = rent(rent_info)?;
+        system_program(system_program_info)?;
+        let creation_fee = creation_fee(config_info, program_id)?;
+
+        if !treasury_info.data_is_empty() {
+            return Err(TreasuryError::TreasuryAlreadyExists.into());
+        }
+
+        Ok(CreateMilestoneTreasuryContext {
+            funder_info,
+            authority_info,
+            treasury_info,
+            mint_info,
+            system_program_info,
+            config_info,
+            creation_fee,
+            rent,
+        })
+    }
+}
+
+/// Accounts of `ConfirmMilestone` and `ConfirmMilestoneWithSignature`. The attestor either
+/// signs the transaction or an off-chain message verified by the previous instruction.
+pub struct ConfirmMilestoneContext<'a, 'b> {
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, MilestoneTreasury>,
+    pub attestor: Pubkey,
+}
+
+impl<'a, 'b> ConfirmMilestoneContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+        index: u8,
+        off_chain: bool,
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let attestor_info = if off_chain {
+            None
+        } else {
+            Some(next_account_info(iter)?)
+        };
+        let treasury_info = next_account_info(iter)?;
+
+        let treasury = MilestoneTreasury::load_mut(treasury_info, program_id)?;
+        writable(treasury_info)?;
+        let attestor = match attestor_info {
+            Some(attestor_info) => {
+                if !attestor_info.is_signer {
+                    return Err(TreasuryError::MissingAuthoritySignature.into());
+                }
+                *attestor_info.key
+            }
+            None => {
+                let instructions_info = next_account_info(iter)?;
+                let message = MilestoneTreasury::attestation(treasury_info.key, index);
+                ed25519_signer(instructions_info, &message)?
+            }
+        };
+
+        Ok(ConfirmMilestoneContext {
+            treasury_info,
+            treasury,
+            attestor,
+        })
+    }
+}
+
+/// Accounts of `WithdrawMilestones`
+pub struct WithdrawMilestonesContext<'a, 'b> {
+    pub recipient_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub fund_authority_info: &'a AccountInfo<'b>,
+    pub fund_info: &'a AccountInfo<'b>,
+    pub token_program_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, MilestoneTreasury>,
+    pub fund_authority_seed: u8,
+    pub fund: Account,
+    pub fee: WithdrawalFee<'a, 'b>,
+}
+
+impl<'a, 'b> WithdrawMilestonesContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let authority_info = next_account_info(iter)?;
+        let recipient_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let fund_authority_info = next_account_info(iter)?;
+        let fund_info = next_account_info(iter)?;
+        let token_program_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+        let fee_vault_info = next_account_info(iter)?;
+
+        let treasury =
+            MilestoneTreasury::from_account_info(treasury_info, authority_info, program_id)?;
+        writable(recipient_info)?;
+        writable(treasury_info)?;
+        writable(fund_info)?;
+
+        let fund_authority_seed = MilestoneTreasury::verify_fund_authority_address(
+            fund_authority_info.key,
+            treasury_info.key,
+            program_id,
+        )?;
+        fund_address(fund_info, fund_authority_info, &treasury.mint)?;
+        distinct(&[recipient_info, treasury_info, fund_info])?;
+
+        let fund = token_account(fund_info, fund_authority_info.key, &treasury.mint)?;
+        token_account(recipient_info, &treasury.authority, &treasury.mint)?;
+        token_program(token_program_info)?;
+        let fee = withdrawal_fee(config_info, fee_vault_info, program_id, &treasury.mint)?;
+
+        Ok(WithdrawMilestonesContext {
+            recipient_info,
+            treasury_info,
+            fund_authority_info,
+            fund_info,
+            token_program_info,
+            treasury,
+            fund_authority_seed,
+            fund,
+            fee,
+        })
+    }
+}
+
+/// Accounts of `CreateHurdleTreasury`
+pub struct CreateHurdleTreasuryContext<'a, 'b> {
+    pub funder_info: &'a AccountInfo<'b>,
+    pub authority_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub mint_info: &'a AccountInfo<'b>,
+    pub oracle_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub creation_fee: u64,
+    pub rent: Rent,
+}
+
+impl<'a, 'b> CreateHurdleTreasuryContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let funder_info = next_account_info(iter)?;
+        let authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let mint_info = next_account_info(iter)?;
+        let oracle_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+
+        signer(funder_info)?;
+        writable(funder_info)?;
+        signer(treasury_info)?;
+        writable(treasury_info)?;
+        distinct(&[funder_info, treasury_info, oracle_info])?;
+
+        mint(mint_info)?;
+        // catch feeds with the wrong layout early, the price itself doesn't matter yet
+        PriceFeed::unpack(&oracle_info.data.borrow())?;
+        let rent = rent(rent_info)?;
+        system_program(system_program_info)?;
+        let creation_fee = creation_fee(config_info, program_id)?;
+
+        if !treasury_info.data_is_empty() {
+            return Err(TreasuryError::TreasuryAlreadyExists.into());
+        }
+
+        Ok(CreateHurdleTreasuryContext {
+            funder_info,
+            authority_info,
+            treasury_info,
+            mint_info,
+            oracle_info,
+            system_program_info,
+            config_info,
+            creation_fee,
+            rent,
+        })
+    }
+}
+
+/// Accounts of `UpdateHurdles`
+pub struct UpdateHurdlesContext<'a, 'b> {
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, HurdleTreasury>,
+    pub price: u64,
+    pub clock: Clock,
+}
+
+impl<'a, 'b> UpdateHurdlesContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let treasury_info = next_account_info(iter)?;
+        let oracle_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+
+        let treasury = HurdleTreasury::load_mut(treasury_info, program_id)?;
+        writable(treasury_info)?;
+        if treasury.oracle != *oracle_info.key {
+            return Err(TreasuryError::InvalidPriceFeed.into());
+        }
+        let clock = clock(clock_info)?;
+        let price =
+            PriceFeed::unpack(&oracle_info.data.borrow())?.current_price(clock.unix_timestamp)?;
+
+        Ok(UpdateHurdlesContext {
+            treasury_info,
+            treasury,
+            price,
+            clock,
+        })
+    }
+}
+
+/// Accounts of `WithdrawHurdles`
+pub struct WithdrawHurdlesContext<'a, 'b> {
+    pub recipient_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub fund_authority_info: &'a AccountInfo<'b>,
+    pub fund_info: &'a AccountInfo<'b>,
+    pub token_program_info: &'a AccountInfo<'b>,
+    pub treasury: RefMut<'a, HurdleTreasury>,
+    pub fund_authority_seed: u8,
+    pub fund: Account,
+    pub fee: WithdrawalFee<'a, 'b>,
+}
+
+impl<'a, 'b> WithdrawHurdlesContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let authority_info = next_account_info(iter)?;
+        let recipient_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let fund_authority_info = next_account_info(iter)?;
+        let fund_info = next_account_info(iter)?;
+        let token_program_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+        let fee_vault_info = next_account_info(iter)?;
+
+        let treasury =
+            HurdleTreasury::from_account_info(treasury_info, authority_info, program_id)?;
+        writable(recipient_info)?;
+        writable(treasury_info)?;
+        writable(fund_info)?;
+
+        let fund_authority_seed = HurdleTreasury::verify_fund_authority_address(
+            fund_authority_info.key,
+            treasury_info.key,
+            program_id,
+        )?;
+        fund_address(fund_info, fund_authority_info, &treasury.mint)?;
+        distinct(&[recipient_info, treasury_info, fund_info])?;
+
+        let fund = token_account(fund_info, fund_authority_info.key, &treasury.mint)?;
+        token_account(recipient_info, &treasury.authority, &treasury.mint)?;
+        token_program(token_program_info)?;
+        let fee = withdrawal_fee(config_info, fee_vault_info, program_id, &treasury.mint)?;
+
+        Ok(WithdrawHurdlesContext {
+            recipient_info,
+            treasury_info,
+            fund_authority_info,
+            fund_info,
+            token_program_info,
+            treasury,
+            fund_authority_seed,
+            fund,
+            fee,
+        })
+    }
+}
+
+/// Accounts of `InitializeConfig`
+pub struct InitializeConfigContext<'a, 'b> {
+    pub funder_info: &'a AccountInfo<'b>,
+    pub admin_info: &'a AccountInfo<'b>,
+    pub config_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    pub config_seed: u8,
+    pub rent: Rent,
+}
+
+impl<'a, 'b> InitializeConfigContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let funder_info = next_account_info(iter)?;
+        let admin_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+        let program_data_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+
+        signer(funder_info)?;
+        writable(funder_info)?;
+        signer(admin_info)?;
+        writable(config_info)?;
+        if Config::load(config_info, program_id)?.is_some() {
+            return Err(TreasuryError::ConfigAlreadyExists.into());
+        }
+        if upgrade_authority(program_data_info, program_id)? != Some(*admin_info.key) {
+            return Err(TreasuryError::InvalidUpgradeAuthority.into());
+        }
+        let rent = rent(rent_info)?;
+        system_program(system_program_info)?;
+
+        let (_, config_seed) = Config::address(program_id);
+        Ok(InitializeConfigContext {
+            funder_info,
+            admin_info,
+            config_info,
+            system_program_info,
+            config_seed,
+            rent,
+        })
+    }
+}
+
+/// Accounts of `UpdateConfig`
+pub struct UpdateConfigContext<'a, 'b> {
+    pub config_info: &'a AccountInfo<'b>,
+    pub config: RefMut<'a, Config>,
+}
+
+impl<'a, 'b> UpdateConfigContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let admin_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+
+        let config =
+            Config::load_mut(config_info, program_id)?.ok_or(TreasuryError::InvalidConfig)?;
+        writable(config_info)?;
+        if !admin_info.is_signer || config.admin != *admin_info.key {
+            return Err(TreasuryError::InvalidAdmin.into());
+        }
+
+        Ok(UpdateConfigContext {
+            config_info,
+            config,
+        })
+    }
+}
+
+/// Accounts of `PauseProgram` and `UnpauseProgram`
+pub struct SetPausedContext<'a, 'b> {
+    pub config_info: &'a AccountInfo<'b>,
+    pub config: RefMut<'a, Config>,
+}
+
+impl<'a, 'b> SetPausedContext<'a, 'b> {
+    /// The guardian pauses, the admin unpauses
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+        paused: bool,
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let signer_info = next_account_info(iter)?;
+        let config_info = next_account_info(iter)?;
+
+        let config =
+            Config::load_mut(config_info, program_id)?.ok_or(TreasuryError::InvalidConfig)?;
+        writable(config_info)?;
+        if paused {
+            if !signer_info.is_signer || config.guardian != *signer_info.key {
+                return Err(TreasuryError::InvalidGuardian.into());
+            }
+        } else if !signer_info.is_signer || config.admin != *signer_info.key {
+            return Err(TreasuryError::InvalidAdmin.into());
+        }
+
+        Ok(SetPausedContext {
+            config_info,
+            config,
+        })
+    }
+}
+
+/// Accounts of `CollectFees`
+pub struct CollectFeesContext<'a, 'b> {
+    pub config_info: &'a AccountInfo<'b>,
+    pub fee_receiver_info: &'a AccountInfo<'b>,
+    pub fee_vault_info: &'a AccountInfo<'b>,
+    pub recipient_info: &'a AccountInfo<'b>,
+    pub token_program_info: &'a AccountInfo<'b>,
+    pub config_seed: u8,
+    pub fee_vault: Account,
+    pub rent: Rent,
+}
+
+impl<'a, 'b> CollectFeesContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let config_info = next_account_info(iter)?;
+        let fee_receiver_info = next_account_info(iter)?;
+        let fee_vault_info = next_account_info(iter)?;
+        let recipient_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let token_program_info = next_account_info(iter)?;
+
+        let config = Config::load(config_info, program_id)?.ok_or(TreasuryError::InvalidConfig)?;
+        if config.fee_receiver != *fee_receiver_info.key {
+            return Err(TreasuryError::InvalidConfig.into());
+        }
+        writable(config_info)?;
+        writable(fee_receiver_info)?;
+        writable(fee_vault_info)?;
+        writable(recipient_info)?;
+        distinct(&[
+            config_info,
+            fee_receiver_info,
+            fee_vault_info,
+            recipient_info,
+        ])?;
+
+        if *fee_vault_info.owner != spl_token::id() {
+            return Err(TreasuryError::InvalidFeeVault.into());
+        }
+        let fee_vault = Account::unpack(&fee_vault_info.data.borrow())
+            .map_err(|_| TreasuryError::InvalidFeeVault)?;
+        if *fee_vault_info.key != Config::fee_vault_address(&fee_vault.mint, program_id) {
+            return Err(TreasuryError::InvalidFeeVault.into());
+        }
+        token_account(recipient_info, &config.fee_receiver, &fee_vault.mint)?;
+        let rent = rent(rent_info)?;
+        token_program(token_program_info)?;
+
+        let (_, config_seed) = Config::address(program_id);
+        Ok(CollectFeesContext {
+            config_info,
+            fee_receiver_info,
+            fee_vault_info,
+            recipient_info,
+            token_program_info,
+            config_seed,
+            fee_vault,
+            rent,
+        })
+    }
+}
+
+/// Accounts of `SetRecovery`
+pub struct SetRecoveryContext<'a, 'b> {
+    pub funder_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub recovery_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    pub treasury: RecoverableTreasury<'a>,
+    /// `None` if the treasury has no recovery yet
+    pub recovery: Option<RefMut<'a, Recovery>>,
+    pub recovery_seed: u8,
+    pub clock: Clock,
+    pub rent: Rent,
+}
+
+impl<'a, 'b> SetRecoveryContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let funder_info = next_account_info(iter)?;
+        let authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let recovery_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+
+        signer(funder_info)?;
+        writable(funder_info)?;
+        let treasury = recoverable_authority(treasury_info, authority_info, program_id)?;
+        let recovery = Recovery::load_mut(recovery_info, treasury_info.key, program_id)?;
+        let (_, recovery_seed) = Recovery::address(treasury_info.key, program_id);
+        writable(recovery_info)?;
+        distinct(&[funder_info, recovery_info])?;
+
+        let clock = clock(clock_info)?;
+        let rent = rent(rent_info)?;
+        system_program(system_program_info)?;
+
+        Ok(SetRecoveryContext {
+            funder_info,
+            treasury_info,
+            recovery_info,
+            system_program_info,
+            treasury,
+            recovery,
+            recovery_seed,
+            clock,
+            rent,
+        })
+    }
+}
+
+/// Accounts of `Heartbeat`
+pub struct HeartbeatContext<'a, 'b> {
+    pub recovery_info: &'a AccountInfo<'b>,
+    pub recovery: RefMut<'a, Recovery>,
+    pub clock: Clock,
+}
+
+impl<'a, 'b> HeartbeatContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let recovery_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+
+        recoverable_authority(treasury_info, authority_info, program_id)?;
+        let recovery = Recovery::load_mut(recovery_info, treasury_info.key, program_id)?
+            .ok_or(TreasuryError::InvalidRecovery)?;
+        writable(recovery_info)?;
+        let clock = clock(clock_info)?;
+
+        Ok(HeartbeatContext {
+            recovery_info,
+            recovery,
+            clock,
+        })
+    }
+}
+
+/// Accounts of `ClaimRecovery`
+pub struct ClaimRecoveryContext<'a, 'b> {
+    pub backup_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub recovery_info: &'a AccountInfo<'b>,
+    pub treasury: RecoverableTreasury<'a>,
+    pub recovery: RefMut<'a, Recovery>,
+    pub clock: Clock,
+    /// `None` if the treasury has no guardians
+    pub social: Option<RefMut<'a, SocialRecovery>>,
+}
+
+impl<'a, 'b> ClaimRecoveryContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let backup_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let recovery_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+        let social_info = next_account_info(iter)?;
+
+        let treasury = RecoverableTreasury::load_mut(treasury_info, program_id)?;
+        writable(treasury_info)?;
+        let recovery = Recovery::load_mut(recovery_info, treasury_info.key, program_id)?
+            .ok_or(TreasuryError::InvalidRecovery)?;
+        writable(recovery_info)?;
+        if !backup_info.is_signer || *backup_info.key != recovery.backup {
+            return Err(TreasuryError::InvalidBackup.into());
+        }
+        writable(backup_info)?;
+        let clock = clock(clock_info)?;
+        let social = SocialRecovery::load_mut(social_info, treasury_info.key, program_id)?;
+        if social.is_some() {
+            writable(social_info)?;
+        }
+
+        Ok(ClaimRecoveryContext {
+            backup_info,
+            treasury_info,
+            recovery_info,
+            treasury,
+            recovery,
+            clock,
+            social,
+        })
+    }
+}
+
+/// Accounts of `SetGuardians`
+pub struct SetGuardiansContext<'a, 'b> {
+    pub funder_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub social_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    /// `None` if the treasury has no guardians yet
+    pub social: Option<RefMut<'a, SocialRecovery>>,
+    pub social_seed: u8,
+    pub rent: Rent,
+}
+
+impl<'a, 'b> SetGuardiansContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let funder_info = next_account_info(iter)?;
+        let authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let social_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+
+        signer(funder_info)?;
+        writable(funder_info)?;
+        recoverable_authority(treasury_info, authority_info, program_id)?;
+        let social = SocialRecovery::load_mut(social_info, treasury_info.key, program_id)?;
+        let (_, social_seed) = SocialRecovery::address(treasury_info.key, program_id);
+        writable(social_info)?;
+        distinct(&[funder_info, social_info])?;
+
+        let rent = rent(rent_info)?;
+        system_program(system_program_info)?;
+
+        Ok(SetGuardiansContext {
+            funder_info,
+            treasury_info,
+            social_info,
+            system_program_info,
+            social,
+            social_seed,
+            rent,
+        })
+    }
+}
+
+/// Accounts of `ApproveRotation`
+pub struct ApproveRotationContext<'a, 'b> {
+    pub social_info: &'a AccountInfo<'b>,
+    pub social: RefMut<'a, SocialRecovery>,
+    pub guardian_index: usize,
+    pub clock: Clock,
+}
+
+impl<'a, 'b> ApproveRotationContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let guardian_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let social_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+
+        RecoverableTreasury::load_mut(treasury_info, program_id)?;
+        let social = SocialRecovery::load_mut(social_info, treasury_info.key, program_id)?
+            .ok_or(TreasuryError::InvalidSocialRecovery)?;
+        writable(social_info)?;
+        let guardian_index = social
+            .guardian_index(guardian_info.key)
+            .filter(|_| guardian_info.is_signer)
+            .ok_or(TreasuryError::InvalidGuardian)?;
+        let clock = clock(clock_info)?;
+
+        Ok(ApproveRotationContext {
+            social_info,
+            social,
+            guardian_index,
+            clock,
+        })
+    }
+}
+
+/// Accounts of `CancelRotation`
+pub struct CancelRotationContext<'a, 'b> {
+    pub social_info: &'a AccountInfo<'b>,
+    pub social: RefMut<'a, SocialRecovery>,
+}
+
+impl<'a, 'b> CancelRotationContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let social_info = next_account_info(iter)?;
+
+        recoverable_authority(treasury_info, authority_info, program_id)?;
+        let social = SocialRecovery::load_mut(social_info, treasury_info.key, program_id)?
+            .ok_or(TreasuryError::InvalidSocialRecovery)?;
+        writable(social_info)?;
+
+        Ok(CancelRotationContext {
+            social_info,
+            social,
+        })
+    }
+}
+
+/// Accounts of `ExecuteRotation`
+pub struct ExecuteRotationContext<'a, 'b> {
+    pub new_authority_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub social_info: &'a AccountInfo<'b>,
+    pub recovery_info: &'a AccountInfo<'b>,
+    pub treasury: RecoverableTreasury<'a>,
+    pub social: RefMut<'a, SocialRecovery>,
+    /// Set up by the previous authority, closed by the rotation
+    pub recovery: Option<RefMut<'a, Recovery>>,
+    pub clock: Clock,
+}
+
+impl<'a, 'b> ExecuteRotationContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let new_authority_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let social_info = next_account_info(iter)?;
+        let recovery_info = next_account_info(iter)?;
+        let clock_info = next_account_info(iter)?;
+
+        let treasury = RecoverableTreasury::load_mut(treasury_info, program_id)?;
+        writable(treasury_info)?;
+        let social = SocialRecovery::load_mut(social_info, treasury_info.key, program_id)?
+            .ok_or(TreasuryError::InvalidSocialRecovery)?;
+        writable(social_info)?;
+        let recovery = Recovery::load_mut(recovery_info, treasury_info.key, program_id)?;
+        if recovery.is_some() {
+            writable(recovery_info)?;
+        }
+        if !new_authority_info.is_signer || *new_authority_info.key != social.new_authority {
+            return Err(TreasuryError::InvalidRotation.into());
+        }
+        writable(new_authority_info)?;
+        let clock = clock(clock_info)?;
+
+        Ok(ExecuteRotationContext {
+            new_authority_info,
+            treasury_info,
+            social_info,
+            recovery_info,
+            treasury,
+            social,
+            recovery,
+            clock,
+        })
+    }
+}
+
+/// Accounts of `MigrateTreasury`
+pub struct MigrateTreasuryContext<'a, 'b> {
+    pub funder_info: &'a AccountInfo<'b>,
+    pub treasury_info: &'a AccountInfo<'b>,
+    pub system_program_info: &'a AccountInfo<'b>,
+    /// The size of the current layout of the treasury
+    pub len: usize,
+    pub rent: Rent,
+}
+
+impl<'a, 'b> MigrateTreasuryContext<'a, 'b> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let iter = &mut accounts.iter();
+        let funder_info = next_account_info(iter)?;
+        let treasury_info = next_account_info(iter)?;
+        let rent_info = next_account_info(iter)?;
+        let system_program_info = next_account_info(iter)?;
+
+        signer(funder_info)?;
+        writable(funder_info)?;
+        writable(treasury_info)?;
+        distinct(&[funder_info, treasury_info])?;
+        if *treasury_info.owner != *program_id {
+            return Err(TreasuryError::InvalidTreasuryFundAccount.into());
+        }
+
+        // the size tells the kind and layout of a treasury apart
+        let len = match treasury_info.data_len() {
+            len if SimpleTreasury::LEGACY_LENS.contains(&len) => SimpleTreasury::LEN,
+            len if VestedTreasury::LEGACY_LENS.contains(&len) => VestedTreasury::LEN,
+            _ => return Err(TreasuryError::NotALegacyTreasury.into()),
+        };
+        let rent = rent(rent_info)?;
+        system_program(system_program_info)?;
+
+        Ok(MigrateTreasuryContext {
+            funder_info,
+            treasury_info,
+            system_program_info,
+            len,
+            rent,
+        })
+    }
+}
+
+/// The account signed the transaction
+pub fn signer(info: &AccountInfo) -> Result<(), ProgramError> {
+    if !info.is_signer {
+        return Err(TreasuryError::MissingSignature.into());
+    }
+    Ok(())
+}
+
+/// The account was passed as writable
+pub fn writable(info: &AccountInfo) -> Result<(), ProgramError> {
+    if !info.is_writable {
+        return Err(TreasuryError::AccountNotWritable.into());
+    }
+    Ok(())
+}
+
+/// No account appears more than once
+pub fn distinct(infos: &[&AccountInfo]) -> Result<(), ProgramError> {
+    for (i, a) in infos.iter().enumerate() {
+        if infos[i + 1..].iter().any(|b| a.key == b.key) {
+            return Err(TreasuryError::DuplicateAccount.into());
+        }
+    }
+    Ok(())
+}
+
+/// The account is the SPL Token program
+pub fn token_program(info: &AccountInfo) -> Result<(), ProgramError> {
+    if *info.key != spl_token::id() {
+        return Err(TreasuryError::InvalidTokenProgram.into());
+    }
+    Ok(())
+}
+
+/// The account is the System program
+pub fn system_program(info: &AccountInfo) -> Result<(), ProgramError> {
+    if *info.key != system_program::id() {
+        return Err(TreasuryError::InvalidSystemProgram.into());
+    }
+    Ok(())
+}
+
+/// Read the Rent sysvar
+pub fn rent(info: &AccountInfo) -> Result<Rent, ProgramError> {
+    if !sysvar::rent::check_id(info.key) {
+        return Err(TreasuryError::InvalidSysvar.into());
+    }
+    Rent::from_account_info(info)
+}
+
+/// Read the Clock sysvar
+pub fn clock(info: &AccountInfo) -> Result<Clock, ProgramError> {
+    if !sysvar::clock::check_id(info.key) {
+        return Err(TreasuryError::InvalidSysvar.into());
+    }
+    Clock::from_account_info(info)
+}
+
+/// Read an SPL Token mint
+pub fn mint(info: &AccountInfo) -> Result<Mint, ProgramError> {
+    if *info.owner != spl_token::id() {
+        return Err(TreasuryError::TokenNotSPLToken.into());
+    }
+    Mint::unpack(&info.data.borrow()).map_err(|_| TreasuryError::TokenNotSPLToken.into())
+}
+
+/// The fund is the associated token account of the fund authority
+pub fn fund_address(
+    fund_info: &AccountInfo,
+    fund_authority_info: &AccountInfo,
+    mint: &Pubkey,
+) -> Result<(), ProgramError> {
+    if get_associated_token_address(fund_authority_info.key, mint) != *fund_info.key {
+        return Err(TreasuryError::InvalidTreasuryFundAddress.into());
+    }
+    Ok(())
+}
+
+/// Read the config, failing while the program is paused
+pub fn not_paused(
+    config_info: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<Option<Config>, ProgramError> {
+    let config = Config::load(config_info, program_id)?;
+    if let Some(config) = config {
+        config.check_not_paused()?;
+    }
+    Ok(config)
+}
+
+/// The creation fee, 0 until the config is initialized. The config account receives it.
+pub fn creation_fee(config_info: &AccountInfo, program_id: &Pubkey) -> Result<u64, ProgramError> {
+    let fee = not_paused(config_info, program_id)?.map_or(0, |config| config.creation_fee);
+    if fee > 0 {
+        writable(config_info)?;
+    }
+    Ok(fee)
+}
+
+/// The withdrawal fee and the vault it's paid into
+pub struct WithdrawalFee<'a, 'b> {
+    pub vault_info: &'a AccountInfo<'b>,
+    /// `None` until the config is initialized
+    pub config: Option<Config>,
+}
+
+impl<'a, 'b> WithdrawalFee<'a, 'b> {
+    /// The part of a withdrawal of `amount` that goes to the fee vault
+    pub fn on(&self, amount: u64) -> u64 {
+        self.config.map_or(0, |config| config.fee_on(amount))
+    }
+}
+
+/// Read the withdrawal fee. The vault is only checked while there is a fee, so it
+/// doesn't have to exist before then.
+pub fn withdrawal_fee<'a, 'b>(
+    config_info: &AccountInfo,
+    vault_info: &'a AccountInfo<'b>,
+    program_id: &Pubkey,
+    mint: &Pubkey,
+) -> Result<WithdrawalFee<'a, 'b>, ProgramError> {
+    let config = not_paused(config_info, program_id)?;
+    if config.is_some_and(|config| config.withdrawal_fee > 0) {
+        if *vault_info.key != Config::fee_vault_address(mint, program_id) {
+            return Err(TreasuryError::InvalidFeeVault.into());
+        }
+        writable(vault_info)?;
+        token_account(vault_info, config_info.key, mint)?;
+    }
+    Ok(WithdrawalFee { vault_info, config })
+}
+
+/// The recovery record a treasury authority's activity is recorded in
+pub struct Activity<'a> {
+    /// `None` if the treasury has no recovery
+    pub recovery: Option<RefMut<'a, Recovery>>,
+}
+
+/// Read the recovery record of a treasury. The account only has to be writable once
+/// the treasury has a recovery.
+pub fn activity<'a>(
+    recovery_info: &'a AccountInfo,
+    treasury_info: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<Activity<'a>, ProgramError> {
+    let recovery = Recovery::load_mut(recovery_info, treasury_info.key, program_id)?;
+    if recovery.is_some() {
+        writable(recovery_info)?;
+    }
+    Ok(Activity { recovery })
+}
+
+/// Load a simple or vested treasury signed for by its authority
+pub fn recoverable_authority<'a>(
+    treasury_info: &'a AccountInfo,
+    authority_info: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<RecoverableTreasury<'a>, ProgramError> {
+    let treasury = RecoverableTreasury::load_mut(treasury_info, program_id)?;
+    if !authority_info.is_signer {
+        return Err(TreasuryError::MissingAuthoritySignature.into());
+    }
+    if treasury.authority() != *authority_info.key {
+        return Err(TreasuryError::InvalidTreasuryOwner.into());
+    }
+    Ok(treasury)
+}
+
+/// The upgrade authority of the program, read from its program data account. `None`
+/// if the program is immutable.
+pub fn upgrade_authority(
+    program_data_info: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<Option<Pubkey>, ProgramError> {
+    let (address, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if *program_data_info.key != address || *program_data_info.owner != bpf_loader_upgradeable::id()
+    {
+        return Err(TreasuryError::InvalidUpgradeAuthority.into());
+    }
+
+    // the bincode encoding of `UpgradeableLoaderState::ProgramData`: a `u32` variant
+    // index of 3, the `u64` deployment slot and an optional authority
+    let data = program_data_info.data.borrow();
+    if data.len() < 45 || data[..4] != 3u32.to_le_bytes() {
+        return Err(TreasuryError::InvalidUpgradeAuthority.into());
+    }
+    match data[12] {
+        0 => Ok(None),
+        1 => {
+            let mut bytes = [0; 32];
+            bytes.copy_from_slice(&data[13..45]);
+            Ok(Some(Pubkey::new_from_array(bytes)))
+        }
+        _ => Err(TreasuryError::InvalidUpgradeAuthority.into()),
+    }
+}
+
+/// The key whose ed25519 signature of `message` the previous instruction verified. The
+/// ed25519 program fails the transaction if a signature is invalid, so it's enough to
+/// check that it verified exactly this key and message, with all offsets pointing into
+/// its own data.
+pub fn ed25519_signer(
+    instructions_info: &AccountInfo,
+    message: &[u8],
+) -> Result<Pubkey, ProgramError> {
+    if !sysvar::instructions::check_id(instructions_info.key) {
+        return Err(TreasuryError::InvalidSysvar.into());
+    }
+    let current = sysvar::instructions::load_current_index_checked(instructions_info)?;
+    let previous = current
+        .checked_sub(1)
+        .ok_or(TreasuryError::InvalidAttestation)?;
+    let instruction =
+        sysvar::instructions::load_instruction_at_checked(previous as usize, instructions_info)?;
+    if instruction.program_id != ed25519_program::id() {
+        return Err(TreasuryError::InvalidAttestation.into());
+    }
+
+    // a signature count and padding, then seven offsets: signature, signature
+    // instruction, public key, public key instruction, message, message size and
+    // message instruction
+    let data = &instruction.data;
+    if data.len() < 16 || data[0] != 1 {
+        return Err(TreasuryError::InvalidAttestation.into());
+    }
+    let offset = |i: usize| u16::from_le_bytes([data[2 + 2 * i], data[3 + 2 * i]]);
+    if [1, 3, 6].iter().any(|i| offset(*i) != u16::MAX) {
+        return Err(TreasuryError::InvalidAttestation.into());
+    }
+    let (key, size, start) = (offset(2) as usize, offset(5) as usize, offset(4) as usize);
+    let key = data
+        .get(key..key + 32)
+        .ok_or(TreasuryError::InvalidAttestation)?;
+    if data.get(start..start + size) != Some(message) {
+        return Err(TreasuryError::InvalidAttestation.into());
+    }
+
+    let mut bytes = [0; 32];
+    bytes.copy_from_slice(key);
+    Ok(Pubkey::new_from_array(bytes))
+}
+
+/// Read a simple or vested treasury, telling them apart by their size
+pub fn weighted_treasury(
+    info: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<WeightedTreasury, ProgramError> {
+    if *info.owner != *program_id {
+        return Err(TreasuryError::InvalidTreasuryFundAccount.into());
+    }
+    let data = info.data.borrow();
+    let treasury = match data.len() {
+        SimpleTreasury::LEN => {
+            SimpleTreasury::from_bytes(&data).map(|t| WeightedTreasury::Simple(*t))
+        }
+        VestedTreasury::LEN => {
+            VestedTreasury::from_bytes(&data).map(|t| WeightedTreasury::Vested(*t))
+        }
+        _ => return Err(TreasuryError::InvalidTreasuryFundAccount.into()),
+    };
+    treasury.ok_or_else(|| TreasuryError::InvalidTreasuryFundAccount.into())
+}
+
+/// Read an SPL Token account that has to belong to a specific owner and mint
+pub fn token_account(
+    info: &AccountInfo,
+    owner: &Pubkey,
+    mint: &Pubkey,
+) -> Result<Account, ProgramError> {
+    if *info.owner != spl_token::id() {
+        return Err(TreasuryError::InvalidTokenAccountOwner.into());
+    }
+    let account = Account::unpack(&info.data.borrow())
+        .map_err(|_| TreasuryError::InvalidAssociatedAccount)?;
+    if account.mint != *mint {
+        return Err(TreasuryError::MintWrongToken.into());
+    }
+    if account.owner != *owner {
+        return Err(TreasuryError::InvalidAssociatedAccount.into());
+    }
+    Ok(account)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_distinct() {
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let owner = Pubkey::new_unique();
+        let mut lamports = [0; 2];
+        let mut data = [vec![], vec![]];
+        let mut infos = vec![];
+        for ((key, lamports), data) in keys.iter().zip(lamports.iter_mut()).zip(data.iter_mut()) {
+            infos.push(AccountInfo::new(
+                key, false, false, lamports, data, &owner, false, 0,
+            ));
+        }
+
+        assert_eq!(distinct(&[]), Ok(()));
+        assert_eq!(distinct(&[&infos[0], &infos[1]]), Ok(()));
+        assert_eq!(
+            distinct(&[&infos[0], &infos[1], &infos[0]]),
+            Err(TreasuryError::DuplicateAccount.into())
+        );
+    }
+}
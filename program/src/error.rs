@@ -81,6 +81,224 @@ pub enum TreasuryError {
     /// Invalid Associated Account
     #[error("Invalid Associated Account")]
     InvalidAssociatedAccount,
+
+    /// A required signature is missing
+    #[error("A required signature is missing")]
+    MissingSignature,
+
+    /// Account is not writable
+    #[error("Account is not writable")]
+    AccountNotWritable,
+
+    /// Invalid SPL Token Program
+    #[error("Invalid SPL Token Program")]
+    InvalidTokenProgram,
+
+    /// Invalid System Program
+    #[error("Invalid System Program")]
+    InvalidSystemProgram,
+
+    /// Invalid Sysvar
+    #[error("Invalid Sysvar")]
+    InvalidSysvar,
+
+    /// Token account is not owned by the SPL Token Program
+    #[error("Token account is not owned by the SPL Token Program")]
+    InvalidTokenAccountOwner,
+
+    /// The same account was passed more than once
+    #[error("The same account was passed more than once")]
+    DuplicateAccount,
+
+    /// Invalid Treasury Grantor
+    #[error("Invalid Treasury Grantor")]
+    InvalidTreasuryGrantor,
+
+    /// Vesting is already paused
+    #[error("Vesting is already paused")]
+    VestingPaused,
+
+    /// Vesting is not paused
+    #[error("Vesting is not paused")]
+    VestingNotPaused,
+
+    /// Invalid Acceleration (amount must be > 0, percentage between 1 and 10,000)
+    #[error("Invalid Acceleration (amount must be > 0, percentage between 1 and 10,000)")]
+    InvalidAcceleration,
+
+    /// Invalid Split Amount (must be > 0 and less than the initial amount)
+    #[error("Invalid Split Amount (must be > 0 and less than the initial amount)")]
+    InvalidSplitAmount,
+
+    /// Treasuries Can't Be Merged (mint, authority, grantor, schedule and pause state must match)
+    #[error("Treasuries Can't Be Merged (mint, authority, grantor, schedule and pause state must match)")]
+    IncompatibleTreasuries,
+
+    /// Invalid Voter Weight Record (wrong address or not owned by the program)
+    #[error("Invalid Voter Weight Record (wrong address or not owned by the program)")]
+    InvalidVoterWeightRecord,
+
+    /// Treasury Is Not A Vote Escrow
+    #[error("Treasury Is Not A Vote Escrow")]
+    NotVoteEscrow,
+
+    /// Invalid Lock End (must be later than now and the current lock end, at most four years away)
+    #[error("Invalid Lock End (must be later than now and the current lock end, at most four years away)")]
+    InvalidLockEnd,
+
+    /// Invalid Shares (1 to 10 distinct recipients with shares above 0 that add up to 10,000)
+    #[error(
+        "Invalid Shares (1 to 10 distinct recipients with shares above 0 that add up to 10,000)"
+    )]
+    InvalidShares,
+
+    /// Not A Shareholder Of The Splitter Treasury
+    #[error("Not A Shareholder Of The Splitter Treasury")]
+    NotAShareholder,
+
+    /// Invalid Escrow (expiry must be in the future, the arbiter can't be the depositor or payee)
+    #[error(
+        "Invalid Escrow (expiry must be in the future, the arbiter can't be the depositor or payee)"
+    )]
+    InvalidEscrow,
+
+    /// Invalid Escrow Arbiter
+    #[error("Invalid Escrow Arbiter")]
+    InvalidArbiter,
+
+    /// The escrow expired and can only be refunded
+    #[error("The escrow expired and can only be refunded")]
+    EscrowExpired,
+
+    /// The escrow has not expired yet
+    #[error("The escrow has not expired yet")]
+    EscrowNotExpired,
+
+    /// Invalid Milestones (1 to 8 milestones with amounts above 0)
+    #[error("Invalid Milestones (1 to 8 milestones with amounts above 0)")]
+    InvalidMilestones,
+
+    /// Invalid Milestone
+    #[error("Invalid Milestone")]
+    InvalidMilestone,
+
+    /// Invalid Milestone Attestor
+    #[error("Invalid Milestone Attestor")]
+    InvalidAttestor,
+
+    /// The milestone is already confirmed
+    #[error("The milestone is already confirmed")]
+    MilestoneConfirmed,
+
+    /// Invalid Attestation (the previous instruction has to verify the attestor's ed25519 signature)
+    #[error("Invalid Attestation (the previous instruction has to verify the attestor's ed25519 signature)")]
+    InvalidAttestation,
+
+    /// Invalid Tranches (1 to 8 tranches with amounts and prices above 0)
+    #[error("Invalid Tranches (1 to 8 tranches with amounts and prices above 0)")]
+    InvalidTranches,
+
+    /// Invalid Price Feed (wrong account or too short)
+    #[error("Invalid Price Feed (wrong account or too short)")]
+    InvalidPriceFeed,
+
+    /// The price feed is stale
+    #[error("The price feed is stale")]
+    StalePrice,
+
+    /// Invalid Config Account
+    #[error("Invalid Config Account")]
+    InvalidConfig,
+
+    /// The config already exists
+    #[error("The config already exists")]
+    ConfigAlreadyExists,
+
+    /// Invalid Fee (the withdrawal fee can't exceed 5%)
+    #[error("Invalid Fee (the withdrawal fee can't exceed 5%)")]
+    InvalidFee,
+
+    /// Only the program's upgrade authority can initialize the config
+    #[error("Only the program's upgrade authority can initialize the config")]
+    InvalidUpgradeAuthority,
+
+    /// Invalid Config Admin
+    #[error("Invalid Config Admin")]
+    InvalidAdmin,
+
+    /// Invalid Fee Vault
+    #[error("Invalid Fee Vault")]
+    InvalidFeeVault,
+
+    /// The program is paused
+    #[error("The program is paused")]
+    Paused,
+
+    /// Invalid Guardian
+    #[error("Invalid Guardian")]
+    InvalidGuardian,
+
+    /// Invalid Recovery Account
+    #[error("Invalid Recovery Account")]
+    InvalidRecovery,
+
+    /// Invalid Inactivity Period (has to be above 0)
+    #[error("Invalid Inactivity Period (has to be above 0)")]
+    InvalidInactivityPeriod,
+
+    /// The authority was active within the inactivity period
+    #[error("The authority was active within the inactivity period")]
+    AuthorityStillActive,
+
+    /// Invalid Backup Key
+    #[error("Invalid Backup Key")]
+    InvalidBackup,
+
+    /// Invalid Guardians (1 to 8 distinct guardians and a threshold between 1 and their number)
+    #[error(
+        "Invalid Guardians (1 to 8 distinct guardians and a threshold between 1 and their number)"
+    )]
+    InvalidGuardians,
+
+    /// Invalid Challenge Delay (has to be above 0)
+    #[error("Invalid Challenge Delay (has to be above 0)")]
+    InvalidChallengeDelay,
+
+    /// Invalid Social Recovery Account
+    #[error("Invalid Social Recovery Account")]
+    InvalidSocialRecovery,
+
+    /// Invalid Rotation (the new authority can't be the default key)
+    #[error("Invalid Rotation (the new authority can't be the default key)")]
+    InvalidRotation,
+
+    /// The guardians already initiated a rotation
+    #[error("The guardians already initiated a rotation")]
+    RotationPending,
+
+    /// No rotation was initiated
+    #[error("No rotation was initiated")]
+    NoRotation,
+
+    /// The rotation is still in its challenge period
+    #[error("The rotation is still in its challenge period")]
+    ChallengePeriodActive,
+
+    /// The requested amount is more than has vested and not been withdrawn yet
+    #[error("The requested amount is more than has vested and not been withdrawn yet")]
+    AmountNotAvailable,
+
+    /// The account is not a treasury with an earlier layout
+    #[error("The account is not a treasury with an earlier layout")]
+    NotALegacyTreasury,
+
+    /// The split would leave a treasury that withdrew more than it has vested
+    #[error("The split would leave a treasury that withdrew more than it has vested")]
+    SplitExceedsVested,
+
+    /// The requested amount is more than the fund holds
+    #[error("The requested amount is more than the fund holds")]
+    InsufficientFunds,
 }
 impl From<TreasuryError> for ProgramError {
     fn from(e: TreasuryError) -> Self {
@@ -0,0 +1,205 @@
+//! SPL Governance voter weight addin
+//!
+//! A realm can use the treasury program as its voter weight addin. Tokens in the funds of
+//! Locked simple treasuries, the weight of vote escrow treasuries and the locked part of
+//! vested treasury funds count toward the voting power of the treasury's authority. The
+//! record layouts mirror `spl-governance-addin-api`, governance reads them with trailing
+//! bytes ignored.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{clock::Slot, clock::UnixTimestamp, pubkey::Pubkey};
+
+use crate::account::{SimpleTreasury, SimpleTreasuryMode, VestedTreasury};
+
+/// The action a voter weight is valid for
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub enum VoterWeightAction {
+    CastVote,
+    CommentProposal,
+    CreateGovernance,
+    CreateProposal,
+    SignOffProposal,
+}
+
+/// The voting power of a governing token owner
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct VoterWeightRecord {
+    pub account_discriminator: [u8; 8],
+    pub realm: Pubkey,
+    pub governing_token_mint: Pubkey,
+    pub governing_token_owner: Pubkey,
+    pub voter_weight: u64,
+    /// The slot the weight was computed in, it's only valid in that slot
+    pub voter_weight_expiry: Option<Slot>,
+    pub weight_action: Option<VoterWeightAction>,
+    pub weight_action_target: Option<Pubkey>,
+    pub reserved: [u8; 8],
+}
+
+impl VoterWeightRecord {
+    pub const ACCOUNT_DISCRIMINATOR: [u8; 8] = *b"2ef99b4b";
+    /// The size of a serialized record with every option set
+    pub const LEN: usize = 164;
+
+    pub fn address(
+        realm: &Pubkey,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        program_id: &Pubkey,
+    ) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                b"voter-weight-record",
+                &realm.to_bytes(),
+                &mint.to_bytes(),
+                &owner.to_bytes(),
+            ],
+            program_id,
+        )
+    }
+}
+
+/// The total voting power of a governing token mint
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct MaxVoterWeightRecord {
+    pub account_discriminator: [u8; 8],
+    pub realm: Pubkey,
+    pub governing_token_mint: Pubkey,
+    pub max_voter_weight: u64,
+    /// The slot the weight was computed in, it's only valid in that slot
+    pub max_voter_weight_expiry: Option<Slot>,
+    pub reserved: [u8; 8],
+}
+
+impl MaxVoterWeightRecord {
+    pub const ACCOUNT_DISCRIMINATOR: [u8; 8] = *b"9d5a5ce9";
+    /// The size of a serialized record with every option set
+    pub const LEN: usize = 97;
+
+    pub fn address(realm: &Pubkey, mint: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[
+                b"max-voter-weight-record",
+                &realm.to_bytes(),
+                &mint.to_bytes(),
+            ],
+            program_id,
+        )
+    }
+}
+
+/// A treasury whose fund counts toward the voting power of its authority
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeightedTreasury {
+    Simple(SimpleTreasury),
+    Vested(VestedTreasury),
+}
+
+impl WeightedTreasury {
+    pub fn authority(&self) -> Pubkey {
+        match self {
+            WeightedTreasury::Simple(simple) => simple.authority,
+            WeightedTreasury::Vested(vested) => vested.authority,
+        }
+    }
+
+    pub fn mint(&self) -> Pubkey {
+        match self {
+            WeightedTreasury::Simple(simple) => simple.mint,
+            WeightedTreasury::Vested(vested) => vested.mint,
+        }
+    }
+
+    /// The part of the fund that can't be withdrawn right now. Unlocked simple
+    /// treasuries don't count since their funds are as liquid as a wallet, vote escrow
+    /// treasuries count with their decaying weight.
+    pub fn weight(&self, now: UnixTimestamp, fund_balance: u64) -> u64 {
+        match self {
+            WeightedTreasury::Simple(simple) => match simple.mode {
+                SimpleTreasuryMode::Locked => fund_balance,
+                SimpleTreasuryMode::Unlocked => 0,
+                SimpleTreasuryMode::VoteEscrow => simple.vote_escrow_weight(now, fund_balance),
+            },
+            WeightedTreasury::Vested(vested) => vested.query(now, fund_balance).locked,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_record_len() {
+        let record = VoterWeightRecord {
+            account_discriminator: VoterWeightRecord::ACCOUNT_DISCRIMINATOR,
+            realm: Pubkey::new_unique(),
+            governing_token_mint: Pubkey::new_unique(),
+            governing_token_owner: Pubkey::new_unique(),
+            voter_weight: 1,
+            voter_weight_expiry: Some(2),
+            weight_action: Some(VoterWeightAction::CastVote),
+            weight_action_target: Some(Pubkey::new_unique()),
+            reserved: [0; 8],
+        };
+        assert_eq!(record.try_to_vec().unwrap().len(), VoterWeightRecord::LEN);
+
+        let record = MaxVoterWeightRecord {
+            account_discriminator: MaxVoterWeightRecord::ACCOUNT_DISCRIMINATOR,
+            realm: Pubkey::new_unique(),
+            governing_token_mint: Pubkey::new_unique(),
+            max_voter_weight: 1,
+            max_voter_weight_expiry: Some(2),
+            reserved: [0; 8],
+        };
+        assert_eq!(
+            record.try_to_vec().unwrap().len(),
+            MaxVoterWeightRecord::LEN
+        );
+    }
+
+    #[test]
+    pub fn test_weight() {
+        let simple = SimpleTreasury {
+            mint: Pubkey::new_unique(),
+            mode: SimpleTreasuryMode::Locked,
+            authority: Pubkey::new_unique(),
+            lock_end: 0,
+            weight: 0,
+            weight_updated_at: 0,
+        };
+        assert_eq!(WeightedTreasury::Simple(simple).weight(0, 500), 500);
+        let unlocked = SimpleTreasury {
+            mode: SimpleTreasuryMode::Unlocked,
+            ..simple
+        };
+        assert_eq!(WeightedTreasury::Simple(unlocked).weight(0, 500), 0);
+        let escrow = SimpleTreasury {
+            mode: SimpleTreasuryMode::VoteEscrow,
+            lock_end: SimpleTreasury::MAX_LOCK_DURATION / 2,
+            ..simple
+        };
+        assert_eq!(WeightedTreasury::Simple(escrow).weight(0, 500), 250);
+
+        let vested = VestedTreasury {
+            mint: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            initial_amount: 1_000,
+            start: 0,
+            vestment_period: 60,
+            vestment_percentage: 1_000, // 10%
+            withdrawn: 100,
+            grantor: Pubkey::new_unique(),
+            paused_at: 0,
+            accelerated: 0,
+            delegate: Pubkey::default(),
+        };
+        // 300 vested, 200 of it claimable
+        assert_eq!(WeightedTreasury::Vested(vested).weight(180, 900), 700);
+        // underfunded
+        assert_eq!(WeightedTreasury::Vested(vested).weight(180, 150), 0);
+    }
+}
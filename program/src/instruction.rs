@@ -1,6 +1,21 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    bpf_loader_upgradeable,
+    clock::UnixTimestamp,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
 
-use crate::account::SimpleTreasuryMode;
+use crate::{
+    account::{
+        Acceleration, EscrowTreasury, HurdleTreasury, MilestoneTreasury, SimpleTreasury,
+        SimpleTreasuryMode, SplitterTreasury, VestedTreasury,
+    },
+    config::Config,
+    governance::{MaxVoterWeightRecord, VoterWeightRecord},
+    recovery::{Recovery, SocialRecovery},
+};
 
 #[repr(C)]
 #[derive(Clone, Debug, PartialEq, BorshDeserialize, BorshSerialize)]
@@ -17,19 +32,25 @@ pub enum TreasuryInstruction {
     ///   4. `[]` Rent sysvar
     ///   5. `[]` Token Program
     ///   6. `[]` System Program
+    ///   7. `[writable]` The config account, receives the creation fee
     CreateSimpleTreasury { mode: SimpleTreasuryMode },
     /// Withdraw from a Simple Treasury
     ///
-    /// With a specified amount from an Unlocked Simple Treasury
+    /// Withdraw a specified amount from an Unlocked Simple Treasury, or from a Vote Escrow
+    /// Simple Treasury whose lock has ended. Fails if the fund doesn't hold enough tokens.
     ///
     /// Accounts expected by this instruction:
     ///   0. `[signer, writable]` The account funding the instruction
     ///   1. `[signer]` The authority that controls the treasury
-    ///   2. `[writable]` The treasury account
-    ///   3. `[]` The program's simple treasury authority
-    ///   4. `[writable]` The treasury's fund account
-    ///   5. `[]` The SPL Token mint of the treasury
-    ///   5. `[]` Token Program
+    ///   2. `[writable]` The recipient token address (must be owned by authority)
+    ///   3. `[]` The treasury account
+    ///   4. `[]` The treasury's fund authority
+    ///   5. `[writable]` The treasury's fund associated account
+    ///   6. `[]` SPL Token Program
+    ///   7. `[]` Clock sysvar
+    ///   8. `[]` The config account
+    ///   9. `[writable]` The fee vault of the mint, see `config`
+    ///   10. `[writable]` The treasury's recovery account, see `recovery`
     WithdrawSimple { amount: u64 },
     /// Created Vested Treasury
     ///
@@ -43,6 +64,8 @@ pub enum TreasuryInstruction {
     ///   4. `[]` Rent sysvar
     ///   5. `[]` Clock sysvar
     ///   6. `[]` System Program
+    ///   7. `[]` The grantor that can pause and resume vesting
+    ///   8. `[writable]` The config account, receives the creation fee
     CreatedVestedTreaury {
         amount: u64,
         period: u64,
@@ -50,19 +73,1581 @@ pub enum TreasuryInstruction {
     },
     /// Withdraw from a Vested Treasury
     ///
-    /// Withdraw everything that is possible to currently withdraw from the vested treasury.
+    /// Withdraw everything that is possible to currently withdraw from the vested treasury,
+    /// or only `amount` of it. Fails if `amount` is more than has vested and not been
+    /// withdrawn yet, or more than the fund holds. The authority's delegate can withdraw as
+    /// well, the funds still go to the authority.
     ///
     /// Accounts expected by this instruction:
     ///   0. `[signer, writable]` The account funding the instruction
-    ///   1. `[signer]` The authority that controls the treasury
+    ///   1. `[signer]` The authority that controls the treasury, or its delegate
     ///   2. `[writable]` The recipient token address (must be owned by authority)
     ///   3. `[writable]` The treasury account
     ///   4. `[]` The treasury's fund authority
-    ///   5. `[]` The treasury's fund associated account
+    ///   5. `[writable]` The treasury's fund associated account
     ///   6. `[]` Clock sysvar
     ///   7. `[]` SPL Token Program
     ///   8. `[]` System Program
-    WithdrawVested,
+    ///   9. `[]` The config account
+    ///   10. `[writable]` The fee vault of the mint, see `config`
+    ///   11. `[writable]` The treasury's recovery account, see `recovery`
+    WithdrawVested { amount: Option<u64> },
+    /// Query a Vested Treasury
+    ///
+    /// Computes how much of the vested treasury is currently claimable, vested, unvested,
+    /// and locked in the fund. The result is a borsh serialized `VestedQuery` that is
+    /// returned via `set_return_data`, meant to be used with `simulateTransaction`.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[]` The treasury account
+    ///   1. `[]` The treasury's fund authority
+    ///   2. `[]` The treasury's fund associated account
+    ///   3. `[]` Clock sysvar
+    QueryVested,
+    /// Pause Vesting
+    ///
+    /// Stops the vesting clock of a vested treasury. Nothing vests while paused, funds
+    /// that vested before the pause can still be withdrawn.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The grantor of the treasury
+    ///   1. `[writable]` The treasury account
+    ///   2. `[]` Clock sysvar
+    PauseVesting,
+    /// Resume Vesting
+    ///
+    /// Restarts the vesting clock of a paused vested treasury. The schedule is moved
+    /// back by the time spent paused, so the full amount still vests.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The grantor of the treasury
+    ///   1. `[writable]` The treasury account
+    ///   2. `[]` Clock sysvar
+    ResumeVesting,
+    /// Accelerate Vesting
+    ///
+    /// Vests an amount or a share of the currently unvested amount immediately, on top
+    /// of the schedule. The total never exceeds the initial amount.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The grantor of the treasury
+    ///   1. `[writable]` The treasury account
+    ///   2. `[]` Clock sysvar
+    AccelerateVesting { acceleration: Acceleration },
+    /// Split a Vested Treasury
+    ///
+    /// Moves `amount` of the initial amount into a new vested treasury with a different
+    /// authority. The withdrawn amount is divided in the same proportion and the new
+    /// treasury's fund receives what it can still withdraw, as far as the fund covers
+    /// it. The schedule is copied so both treasuries vest in lockstep. Fails if
+    /// rounding would leave either treasury having withdrawn more than it vested. The new
+    /// treasury's fund has to exist. The tokens stay in a treasury, so no withdrawal fee
+    /// is charged. SOL fees are paid by the funder.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The account funding the instruction
+    ///   1. `[signer]` The authority that controls the treasury
+    ///   2. `[writable]` The treasury account
+    ///   3. `[]` The treasury's fund authority
+    ///   4. `[writable]` The treasury's fund associated account
+    ///   5. `[signer, writable]` The new treasury account
+    ///   6. `[]` The authority of the new treasury
+    ///   7. `[]` The new treasury's fund authority
+    ///   8. `[writable]` The new treasury's fund associated account
+    ///   9. `[]` Rent sysvar
+    ///  10. `[]` SPL Token Program
+    ///  11. `[]` System Program
+    ///  12. `[]` The config account
+    ///  13. `[]` Clock sysvar
+    SplitVested { amount: u64 },
+    /// Merge a Vested Treasury into another
+    ///
+    /// Adds the initial, withdrawn and accelerated amounts of the source treasury to the
+    /// destination and moves the source's fund balance over. Both treasuries need the
+    /// same authority, mint, grantor, schedule and pause state. The source treasury, its
+    /// fund and its recovery records are closed and their SOL goes to the recipient. No
+    /// withdrawal fee is charged on the tokens moved into the destination treasury.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The authority that controls both treasuries
+    ///   1. `[writable]` The destination treasury account
+    ///   2. `[]` The destination treasury's fund authority
+    ///   3. `[writable]` The destination treasury's fund associated account
+    ///   4. `[writable]` The source treasury account
+    ///   5. `[]` The source treasury's fund authority
+    ///   6. `[writable]` The source treasury's fund associated account
+    ///   7. `[writable]` The account receiving the SOL of the closed accounts
+    ///   8. `[]` SPL Token Program
+    ///   9. `[]` The config account
+    ///  10. `[writable]` The source treasury's recovery account, see `recovery`
+    ///  11. `[writable]` The source treasury's social recovery account
+    MergeVested,
+    /// Set the Delegate of a Vested Treasury
+    ///
+    /// The delegate can withdraw on behalf of the authority so the authority's key can
+    /// stay in cold storage. `None` revokes the delegate.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The authority that controls the treasury
+    ///   1. `[writable]` The treasury account
+    SetVestedDelegate { delegate: Option<Pubkey> },
+    /// Update a Voter Weight Record
+    ///
+    /// Sets the voting power of a governing token owner to the Locked simple treasury
+    /// funds, the weight of the Vote Escrow treasury funds and the locked part of the
+    /// vested treasury funds passed to the instruction.
+    /// The weight expires at the end of the slot, so it has to be updated in the same
+    /// transaction as the vote. The record is created if it doesn't exist yet.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The account paying for the record
+    ///   1. `[]` The realm
+    ///   2. `[]` The governing token mint
+    ///   3. `[]` The governing token owner, the authority of the treasuries
+    ///   4. `[writable]` The voter weight record
+    ///   5. `[]` Clock sysvar
+    ///   6. `[]` Rent sysvar
+    ///   7. `[]` System Program
+    ///   8. `[]` Any number of treasury accounts, each followed by its fund
+    UpdateVoterWeightRecord,
+    /// Update a Max Voter Weight Record
+    ///
+    /// Sets the maximum voting power of a governing token mint to its supply. The record
+    /// is created if it doesn't exist yet.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The account paying for the record
+    ///   1. `[]` The realm
+    ///   2. `[]` The governing token mint
+    ///   3. `[writable]` The max voter weight record
+    ///   4. `[]` Clock sysvar
+    ///   5. `[]` Rent sysvar
+    ///   6. `[]` System Program
+    UpdateMaxVoterWeightRecord,
+    /// Extend the Lock of a Vote Escrow Treasury
+    ///
+    /// Locks the fund until `lock_end`, at most `SimpleTreasury::MAX_LOCK_DURATION` from
+    /// now. The lock can only be extended, never shortened. Updates the stored weight.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The authority that controls the treasury
+    ///   1. `[writable]` The treasury account
+    ///   2. `[]` The treasury's fund authority
+    ///   3. `[]` The treasury's fund associated account
+    ///   4. `[]` Clock sysvar
+    ExtendLock { lock_end: UnixTimestamp },
+    /// Update the Weight of a Vote Escrow Treasury
+    ///
+    /// Stores the current weight of the fund in the treasury account, where other programs
+    /// can read it at `SimpleTreasury::WEIGHT_OFFSET`. The weight decays over time, so
+    /// readers should check `weight_updated_at`. The weight is also returned via
+    /// `set_return_data` for programs that invoke this instruction. Anyone can call it.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writable]` The treasury account
+    ///   1. `[]` The treasury's fund authority
+    ///   2. `[]` The treasury's fund associated account
+    ///   3. `[]` Clock sysvar
+    UpdateVoteEscrowWeight,
+    /// Create Splitter Treasury
+    ///
+    /// Initializes a treasury that splits everything deposited into its fund between
+    /// up to 10 recipients. The shares are in basis points and have to add up to 10,000.
+    /// SOL fees are paid by the funder.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The account funding the instruction
+    ///   1. `[signer, writable]` The treasury account
+    ///   2. `[]` The SPL Token mint used for this treasury
+    ///   3. `[]` Rent sysvar
+    ///   4. `[]` System Program
+    ///   5. `[writable]` The config account, receives the creation fee
+    CreateSplitterTreasury { shares: Vec<(Pubkey, u16)> },
+    /// Claim from a Splitter Treasury
+    ///
+    /// Transfers the recipient's share of everything ever deposited into the fund, less
+    /// what they have claimed before.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The recipient, one of the shareholders
+    ///   1. `[writable]` The recipient token address (must be owned by the recipient)
+    ///   2. `[writable]` The treasury account
+    ///   3. `[]` The treasury's fund authority
+    ///   4. `[writable]` The treasury's fund associated account
+    ///   5. `[]` SPL Token Program
+    ///   6. `[]` The config account
+    ///   7. `[writable]` The fee vault of the mint, see `config`
+    ClaimSplit,
+    /// Create Escrow Treasury
+    ///
+    /// Initializes a treasury that holds a deposit until the arbiter releases it to the
+    /// payee or refunds it to the depositor. After `expiry` the fund can no longer be
+    /// released and anyone can refund it. The arbiter can't be the depositor or payee.
+    /// SOL fees are paid by the funder.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The account funding the instruction
+    ///   1. `[signer, writable]` The treasury account
+    ///   2. `[]` The SPL Token mint used for this treasury
+    ///   3. `[]` The depositor
+    ///   4. `[]` The payee
+    ///   5. `[]` The arbiter
+    ///   6. `[]` Rent sysvar
+    ///   7. `[]` Clock sysvar
+    ///   8. `[]` System Program
+    ///   9. `[writable]` The config account, receives the creation fee
+    CreateEscrowTreasury { expiry: UnixTimestamp },
+    /// Release an Escrow
+    ///
+    /// Transfers the whole fund to the payee, less the withdrawal fee. Only possible
+    /// before the expiry.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The arbiter
+    ///   1. `[writable]` The recipient token address (must be owned by the payee)
+    ///   2. `[]` The treasury account
+    ///   3. `[]` The treasury's fund authority
+    ///   4. `[writable]` The treasury's fund associated account
+    ///   5. `[]` Clock sysvar
+    ///   6. `[]` SPL Token Program
+    ///   7. `[]` The config account
+    ///   8. `[writable]` The fee vault of the mint, see `config`
+    ReleaseEscrow,
+    /// Refund an Escrow
+    ///
+    /// Transfers the whole fund back to the depositor, less the withdrawal fee, for
+    /// example after a dispute.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The arbiter
+    ///   1. `[writable]` The recipient token address (must be owned by the depositor)
+    ///   2. `[]` The treasury account
+    ///   3. `[]` The treasury's fund authority
+    ///   4. `[writable]` The treasury's fund associated account
+    ///   5. `[]` Clock sysvar
+    ///   6. `[]` SPL Token Program
+    ///   7. `[]` The config account
+    ///   8. `[writable]` The fee vault of the mint, see `config`
+    RefundEscrow,
+    /// Refund an Expired Escrow
+    ///
+    /// Transfers the whole fund back to the depositor, less the withdrawal fee, once the
+    /// escrow expired. Anyone can call it.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writable]` The recipient token address (must be owned by the depositor)
+    ///   1. `[]` The treasury account
+    ///   2. `[]` The treasury's fund authority
+    ///   3. `[writable]` The treasury's fund associated account
+    ///   4. `[]` Clock sysvar
+    ///   5. `[]` SPL Token Program
+    ///   6. `[]` The config account
+    ///   7. `[writable]` The fee vault of the mint, see `config`
+    RefundExpiredEscrow,
+    /// Create Milestone Treasury
+    ///
+    /// Initializes a treasury that unlocks funds as milestones are reached instead of over
+    /// time. Every milestone has an amount and an attestor who confirms it, there can be
+    /// up to 8. SOL fees are paid by the funder.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The account funding the instruction
+    ///   1. `[]` The authority that controls the treasury
+    ///   2. `[signer, writable]` The treasury account
+    ///   3. `[]` The SPL Token mint used for this treasury
+    ///   4. `[]` Rent sysvar
+    ///   5. `[]` System Program
+    ///   6. `[writable]` The config account, receives the creation fee
+    CreateMilestoneTreasury { milestones: Vec<(u64, Pubkey)> },
+    /// Confirm a Milestone
+    ///
+    /// Unlocks the amount of a milestone, signed by its attestor. `index` counts the
+    /// treasury's milestones from 0.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The attestor of the milestone
+    ///   1. `[writable]` The treasury account
+    ConfirmMilestone { index: u8 },
+    /// Confirm a Milestone with an Off-Chain Signature
+    ///
+    /// Like `ConfirmMilestone`, but instead of signing the transaction the attestor signs
+    /// `MilestoneTreasury::attestation` off-chain, so they don't have to pay fees. The
+    /// signature is checked by an ed25519 program instruction right before this one,
+    /// which has to verify exactly that one signature. Anyone can submit it.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writable]` The treasury account
+    ///   1. `[]` Instructions sysvar
+    ConfirmMilestoneWithSignature { index: u8 },
+    /// Withdraw from a Milestone Treasury
+    ///
+    /// Transfers the amount of all confirmed milestones that hasn't been withdrawn yet,
+    /// as far as the fund covers it.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The authority that controls the treasury
+    ///   1. `[writable]` The recipient token address (must be owned by the authority)
+    ///   2. `[writable]` The treasury account
+    ///   3. `[]` The treasury's fund authority
+    ///   4. `[writable]` The treasury's fund associated account
+    ///   5. `[]` SPL Token Program
+    ///   6. `[]` The config account
+    ///   7. `[writable]` The fee vault of the mint, see `config`
+    WithdrawMilestones,
+    /// Create Hurdle Treasury
+    ///
+    /// Initializes a treasury that unlocks tranches once the price of the token stays at
+    /// or above their target price for `duration` seconds. Every tranche has an amount
+    /// and a target price in the unit of the price feed, there can be up to 8. The price
+    /// feed is fixed at creation, see `oracle` for its layout. SOL fees are paid by the
+    /// funder.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The account funding the instruction
+    ///   1. `[]` The authority that controls the treasury
+    ///   2. `[signer, writable]` The treasury account
+    ///   3. `[]` The SPL Token mint used for this treasury
+    ///   4. `[]` The price feed
+    ///   5. `[]` Rent sysvar
+    ///   6. `[]` System Program
+    ///   7. `[writable]` The config account, receives the creation fee
+    CreateHurdleTreasury {
+        duration: u64,
+        tranches: Vec<(u64, u64)>,
+    },
+    /// Update the Hurdles of a Hurdle Treasury
+    ///
+    /// Reads the current price from the price feed, records which targets are met since
+    /// when, and unlocks the tranches whose target has been met for long enough. The
+    /// price can't be older than `PriceFeed::MAX_AGE`. Anyone can call it, and someone
+    /// has to regularly, since dips between updates go unnoticed.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writable]` The treasury account
+    ///   1. `[]` The price feed
+    ///   2. `[]` Clock sysvar
+    UpdateHurdles,
+    /// Withdraw from a Hurdle Treasury
+    ///
+    /// Transfers the amount of all unlocked tranches that hasn't been withdrawn yet, as
+    /// far as the fund covers it.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The authority that controls the treasury
+    ///   1. `[writable]` The recipient token address (must be owned by the authority)
+    ///   2. `[writable]` The treasury account
+    ///   3. `[]` The treasury's fund authority
+    ///   4. `[writable]` The treasury's fund associated account
+    ///   5. `[]` SPL Token Program
+    ///   6. `[]` The config account
+    ///   7. `[writable]` The fee vault of the mint, see `config`
+    WithdrawHurdles,
+    /// Initialize the Config
+    ///
+    /// Creates the program-wide config that sets the protocol fees, see `config`. Only
+    /// the program's upgrade authority can initialize it, and only once. The upgrade
+    /// authority becomes the admin.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The account funding the instruction
+    ///   1. `[signer]` The program's upgrade authority
+    ///   2. `[writable]` The config account
+    ///   3. `[]` The program data account of the program
+    ///   4. `[]` Rent sysvar
+    ///   5. `[]` System Program
+    InitializeConfig {
+        fee_receiver: Pubkey,
+        creation_fee: u64,
+        withdrawal_fee: u16,
+        guardian: Pubkey,
+    },
+    /// Update the Config
+    ///
+    /// Replaces the admin, fee receiver, fees and guardian. The new fees apply to the
+    /// next instructions, fees that were already collected go to the new receiver. The
+    /// pause state is kept.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The admin
+    ///   1. `[writable]` The config account
+    UpdateConfig {
+        admin: Pubkey,
+        fee_receiver: Pubkey,
+        creation_fee: u64,
+        withdrawal_fee: u16,
+        guardian: Pubkey,
+    },
+    /// Collect Fees
+    ///
+    /// Sends the creation fees held by the config account to the fee receiver, and the
+    /// balance of a fee vault to a token account of the fee receiver. Anyone can call it.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[writable]` The config account
+    ///   1. `[writable]` The fee receiver
+    ///   2. `[writable]` The fee vault of a mint
+    ///   3. `[writable]` The recipient token address (must be owned by the fee receiver)
+    ///   4. `[]` Rent sysvar
+    ///   5. `[]` SPL Token Program
+    CollectFees,
+    /// Pause the Program
+    ///
+    /// Stops every instruction that creates a treasury or moves tokens out of a fund
+    /// until the admin unpauses it. Deposits still work.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The guardian
+    ///   1. `[writable]` The config account
+    PauseProgram,
+    /// Unpause the Program
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The admin
+    ///   1. `[writable]` The config account
+    UnpauseProgram,
+    /// Set the Recovery of a Simple or Vested Treasury
+    ///
+    /// Names the backup key that can claim the treasury once the authority has been
+    /// inactive for `inactivity_period` seconds. Replaces an existing recovery and counts
+    /// as activity.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The account funding the instruction
+    ///   1. `[signer]` The authority that controls the treasury
+    ///   2. `[]` The treasury account
+    ///   3. `[writable]` The treasury's recovery account
+    ///   4. `[]` Clock sysvar
+    ///   5. `[]` Rent sysvar
+    ///   6. `[]` System Program
+    SetRecovery {
+        backup: Pubkey,
+        inactivity_period: u64,
+    },
+    /// Heartbeat
+    ///
+    /// Records that the authority of a treasury with a recovery is still active.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The authority that controls the treasury
+    ///   1. `[]` The treasury account
+    ///   2. `[writable]` The treasury's recovery account
+    ///   3. `[]` Clock sysvar
+    Heartbeat,
+    /// Claim a Treasury through its Recovery
+    ///
+    /// Makes the backup the authority of the treasury once the authority has been
+    /// inactive for the inactivity period, removing a vested treasury's delegate. The
+    /// recovery account is closed and its rent goes to the backup. The guardians stay,
+    /// but a rotation they approved is dropped.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The backup
+    ///   1. `[writable]` The treasury account
+    ///   2. `[writable]` The treasury's recovery account
+    ///   3. `[]` Clock sysvar
+    ///   4. `[writable]` The treasury's social recovery account
+    ClaimRecovery,
+    /// Set the Guardians of a Simple or Vested Treasury
+    ///
+    /// Names up to 8 guardians, `threshold` of which can jointly rotate the authority of
+    /// the treasury, and the challenge delay the authority has to cancel a rotation.
+    /// Replaces existing guardians and drops a pending rotation.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The account funding the instruction
+    ///   1. `[signer]` The authority that controls the treasury
+    ///   2. `[]` The treasury account
+    ///   3. `[writable]` The treasury's social recovery account
+    ///   4. `[]` Rent sysvar
+    ///   5. `[]` System Program
+    SetGuardians {
+        guardians: Vec<Pubkey>,
+        threshold: u8,
+        challenge_delay: u64,
+    },
+    /// Approve a Rotation
+    ///
+    /// A guardian approves making `new_authority` the authority of the treasury,
+    /// replacing their earlier approval. Once `threshold` guardians approved the same
+    /// authority, the rotation is initiated and can be executed after the challenge
+    /// delay.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The guardian
+    ///   1. `[]` The treasury account
+    ///   2. `[writable]` The treasury's social recovery account
+    ///   3. `[]` Clock sysvar
+    ApproveRotation { new_authority: Pubkey },
+    /// Cancel a Rotation
+    ///
+    /// The authority drops the rotation and its approvals.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer]` The authority that controls the treasury
+    ///   1. `[]` The treasury account
+    ///   2. `[writable]` The treasury's social recovery account
+    CancelRotation,
+    /// Execute a Rotation
+    ///
+    /// Makes the new authority the authority of the treasury once the challenge delay
+    /// has passed, removing a vested treasury's delegate. The guardians stay, a recovery
+    /// set up by the previous authority is closed and its rent goes to the new authority.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The new authority
+    ///   1. `[writable]` The treasury account
+    ///   2. `[writable]` The treasury's social recovery account
+    ///   3. `[writable]` The treasury's recovery account
+    ///   4. `[]` Clock sysvar
+    ExecuteRotation,
+    /// Migrate a Treasury to the Current Layout
+    ///
+    /// Grows a simple or vested treasury stored with an earlier layout to the current
+    /// size. Fields were only ever appended, the ones added since are zero: no vote
+    /// escrow lock, no grantor, nothing accelerated and no delegate. Anyone can migrate a
+    /// treasury, the funder pays the additional rent.
+    ///
+    /// Accounts expected by this instruction:
+    ///   0. `[signer, writable]` The account funding the instruction
+    ///   1. `[writable]` The treasury account
+    ///   2. `[]` Rent sysvar
+    ///   3. `[]` System Program
+    MigrateTreasury,
+}
+
+impl TreasuryInstruction {
+    /// The name of the instruction, logged instead of its parameters which are
+    /// expensive to format
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::CreateSimpleTreasury { .. } => "CreateSimpleTreasury",
+            Self::WithdrawSimple { .. } => "WithdrawSimple",
+            Self::CreatedVestedTreaury { .. } => "CreatedVestedTreaury",
+            Self::WithdrawVested { .. } => "WithdrawVested",
+            Self::QueryVested => "QueryVested",
+            Self::PauseVesting => "PauseVesting",
+            Self::ResumeVesting => "ResumeVesting",
+            Self::AccelerateVesting { .. } => "AccelerateVesting",
+            Self::SplitVested { .. } => "SplitVested",
+            Self::MergeVested => "MergeVested",
+            Self::SetVestedDelegate { .. } => "SetVestedDelegate",
+            Self::UpdateVoterWeightRecord => "UpdateVoterWeightRecord",
+            Self::UpdateMaxVoterWeightRecord => "UpdateMaxVoterWeightRecord",
+            Self::ExtendLock { .. } => "ExtendLock",
+            Self::UpdateVoteEscrowWeight => "UpdateVoteEscrowWeight",
+            Self::CreateSplitterTreasury { .. } => "CreateSplitterTreasury",
+            Self::ClaimSplit => "ClaimSplit",
+            Self::CreateEscrowTreasury { .. } => "CreateEscrowTreasury",
+            Self::ReleaseEscrow => "ReleaseEscrow",
+            Self::RefundEscrow => "RefundEscrow",
+            Self::RefundExpiredEscrow => "RefundExpiredEscrow",
+            Self::CreateMilestoneTreasury { .. } => "CreateMilestoneTreasury",
+            Self::ConfirmMilestone { .. } => "ConfirmMilestone",
+            Self::ConfirmMilestoneWithSignature { .. } => "ConfirmMilestoneWithSignature",
+            Self::WithdrawMilestones => "WithdrawMilestones",
+            Self::CreateHurdleTreasury { .. } => "CreateHurdleTreasury",
+            Self::UpdateHurdles => "UpdateHurdles",
+            Self::WithdrawHurdles => "WithdrawHurdles",
+            Self::InitializeConfig { .. } => "InitializeConfig",
+            Self::UpdateConfig { .. } => "UpdateConfig",
+            Self::CollectFees => "CollectFees",
+            Self::PauseProgram => "PauseProgram",
+            Self::UnpauseProgram => "UnpauseProgram",
+            Self::SetRecovery { .. } => "SetRecovery",
+            Self::Heartbeat => "Heartbeat",
+            Self::ClaimRecovery => "ClaimRecovery",
+            Self::SetGuardians { .. } => "SetGuardians",
+            Self::ApproveRotation { .. } => "ApproveRotation",
+            Self::CancelRotation => "CancelRotation",
+            Self::ExecuteRotation => "ExecuteRotation",
+            Self::MigrateTreasury => "MigrateTreasury",
+        }
+    }
+}
+
+/// Creates a `CreateSimpleTreasury` instruction
+pub fn create_simple_treasury(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+    mode: SimpleTreasuryMode,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::CreateSimpleTreasury { mode }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*treasury, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(Config::address(program_id).0, false),
+        ],
+    )
+}
+
+/// Creates a `WithdrawSimple` instruction
+pub fn withdraw_simple(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let (fund_authority, _) = SimpleTreasury::fund_authority_address(treasury, program_id);
+    let fund = SimpleTreasury::fund_address(treasury, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::WithdrawSimple { amount }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(*treasury, false),
+            AccountMeta::new_readonly(fund_authority, false),
+            AccountMeta::new(fund, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(Config::address(program_id).0, false),
+            AccountMeta::new(Config::fee_vault_address(mint, program_id), false),
+            AccountMeta::new(Recovery::address(treasury, program_id).0, false),
+        ],
+    )
+}
+
+/// Creates a `CreatedVestedTreaury` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create_vested_treasury(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    authority: &Pubkey,
+    grantor: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+    period: u64,
+    percentage: u16,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::CreatedVestedTreaury {
+            amount,
+            period,
+            percentage,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new_readonly(*authority, false),
+            AccountMeta::new(*treasury, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(*grantor, false),
+            AccountMeta::new(Config::address(program_id).0, false),
+        ],
+    )
+}
+
+/// Creates a `WithdrawVested` instruction
+pub fn withdraw_vested(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    claimer: &Pubkey,
+    recipient: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+    amount: Option<u64>,
+) -> Instruction {
+    let (fund_authority, _) = VestedTreasury::fund_authority_address(treasury, program_id);
+    let fund = VestedTreasury::fund_address(treasury, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::WithdrawVested { amount }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new_readonly(*claimer, true),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(fund_authority, false),
+            AccountMeta::new(fund, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(Config::address(program_id).0, false),
+            AccountMeta::new(Config::fee_vault_address(mint, program_id), false),
+            AccountMeta::new(Recovery::address(treasury, program_id).0, false),
+        ],
+    )
+}
+
+/// Creates a `QueryVested` instruction
+pub fn query_vested(program_id: &Pubkey, treasury: &Pubkey, mint: &Pubkey) -> Instruction {
+    let (fund_authority, _) = VestedTreasury::fund_authority_address(treasury, program_id);
+    let fund = VestedTreasury::fund_address(treasury, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::QueryVested.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*treasury, false),
+            AccountMeta::new_readonly(fund_authority, false),
+            AccountMeta::new_readonly(fund, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Creates a `PauseVesting` instruction
+pub fn pause_vesting(program_id: &Pubkey, grantor: &Pubkey, treasury: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::PauseVesting.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*grantor, true),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Creates a `ResumeVesting` instruction
+pub fn resume_vesting(program_id: &Pubkey, grantor: &Pubkey, treasury: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::ResumeVesting.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*grantor, true),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Creates an `AccelerateVesting` instruction
+pub fn accelerate_vesting(
+    program_id: &Pubkey,
+    grantor: &Pubkey,
+    treasury: &Pubkey,
+    acceleration: Acceleration,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::AccelerateVesting { acceleration }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*grantor, true),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Creates a `SplitVested` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn split_vested(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    new_treasury: &Pubkey,
+    new_authority: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let (fund_authority, _) = VestedTreasury::fund_authority_address(treasury, program_id);
+    let fund = VestedTreasury::fund_address(treasury, mint, program_id);
+    let (new_fund_authority, _) = VestedTreasury::fund_authority_address(new_treasury, program_id);
+    let new_fund = VestedTreasury::fund_address(new_treasury, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::SplitVested { amount }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(fund_authority, false),
+            AccountMeta::new(fund, false),
+            AccountMeta::new(*new_treasury, true),
+            AccountMeta::new_readonly(*new_authority, false),
+            AccountMeta::new_readonly(new_fund_authority, false),
+            AccountMeta::new(new_fund, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(Config::address(program_id).0, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Creates a `MergeVested` instruction
+pub fn merge_vested(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    source: &Pubkey,
+    recipient: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let (fund_authority, _) = VestedTreasury::fund_authority_address(treasury, program_id);
+    let fund = VestedTreasury::fund_address(treasury, mint, program_id);
+    let (source_fund_authority, _) = VestedTreasury::fund_authority_address(source, program_id);
+    let source_fund = VestedTreasury::fund_address(source, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::MergeVested.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(fund_authority, false),
+            AccountMeta::new(fund, false),
+            AccountMeta::new(*source, false),
+            AccountMeta::new_readonly(source_fund_authority, false),
+            AccountMeta::new(source_fund, false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(Config::address(program_id).0, false),
+            AccountMeta::new(Recovery::address(source, program_id).0, false),
+            AccountMeta::new(SocialRecovery::address(source, program_id).0, false),
+        ],
+    )
+}
+
+/// Creates a `SetVestedDelegate` instruction
+pub fn set_vested_delegate(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    delegate: Option<Pubkey>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::SetVestedDelegate { delegate }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*treasury, false),
+        ],
+    )
+}
+
+/// Creates an `UpdateVoterWeightRecord` instruction for pairs of treasury and fund
+pub fn update_voter_weight_record(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    realm: &Pubkey,
+    mint: &Pubkey,
+    owner: &Pubkey,
+    treasuries: &[(Pubkey, Pubkey)],
+) -> Instruction {
+    let (record, _) = VoterWeightRecord::address(realm, mint, owner, program_id);
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(*realm, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*owner, false),
+        AccountMeta::new(record, false),
+        AccountMeta::new_readonly(sysvar::clock::id(), false),
+        AccountMeta::new_readonly(sysvar::rent::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    for (treasury, fund) in treasuries {
+        accounts.push(AccountMeta::new_readonly(*treasury, false));
+        accounts.push(AccountMeta::new_readonly(*fund, false));
+    }
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::UpdateVoterWeightRecord
+            .try_to_vec()
+            .unwrap(),
+        accounts,
+    )
+}
+
+/// Creates an `UpdateMaxVoterWeightRecord` instruction
+pub fn update_max_voter_weight_record(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    realm: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let (record, _) = MaxVoterWeightRecord::address(realm, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::UpdateMaxVoterWeightRecord
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*realm, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new(record, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates an `ExtendLock` instruction
+pub fn extend_lock(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+    lock_end: UnixTimestamp,
+) -> Instruction {
+    let (fund_authority, _) = SimpleTreasury::fund_authority_address(treasury, program_id);
+    let fund = SimpleTreasury::fund_address(treasury, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::ExtendLock { lock_end }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(fund_authority, false),
+            AccountMeta::new_readonly(fund, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Creates an `UpdateVoteEscrowWeight` instruction
+pub fn update_vote_escrow_weight(
+    program_id: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let (fund_authority, _) = SimpleTreasury::fund_authority_address(treasury, program_id);
+    let fund = SimpleTreasury::fund_address(treasury, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::UpdateVoteEscrowWeight
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(fund_authority, false),
+            AccountMeta::new_readonly(fund, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Creates a `CreateSplitterTreasury` instruction
+pub fn create_splitter_treasury(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+    shares: Vec<(Pubkey, u16)>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::CreateSplitterTreasury { shares }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new(*treasury, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(Config::address(program_id).0, false),
+        ],
+    )
+}
+
+/// Creates a `ClaimSplit` instruction
+pub fn claim_split(
+    program_id: &Pubkey,
+    shareholder: &Pubkey,
+    recipient: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let (fund_authority, _) = SplitterTreasury::fund_authority_address(treasury, program_id);
+    let fund = SplitterTreasury::fund_address(treasury, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::ClaimSplit.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*shareholder, true),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(fund_authority, false),
+            AccountMeta::new(fund, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(Config::address(program_id).0, false),
+            AccountMeta::new(Config::fee_vault_address(mint, program_id), false),
+        ],
+    )
+}
+
+/// Creates a `CreateEscrowTreasury` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create_escrow_treasury(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+    depositor: &Pubkey,
+    payee: &Pubkey,
+    arbiter: &Pubkey,
+    expiry: UnixTimestamp,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::CreateEscrowTreasury { expiry }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new(*treasury, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*depositor, false),
+            AccountMeta::new_readonly(*payee, false),
+            AccountMeta::new_readonly(*arbiter, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(Config::address(program_id).0, false),
+        ],
+    )
+}
+
+/// Creates a `ReleaseEscrow` instruction
+pub fn release_escrow(
+    program_id: &Pubkey,
+    arbiter: &Pubkey,
+    recipient: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    arbitrate_escrow(
+        program_id,
+        TreasuryInstruction::ReleaseEscrow,
+        arbiter,
+        recipient,
+        treasury,
+        mint,
+    )
+}
+
+/// Creates a `RefundEscrow` instruction
+pub fn refund_escrow(
+    program_id: &Pubkey,
+    arbiter: &Pubkey,
+    recipient: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    arbitrate_escrow(
+        program_id,
+        TreasuryInstruction::RefundEscrow,
+        arbiter,
+        recipient,
+        treasury,
+        mint,
+    )
+}
+
+fn arbitrate_escrow(
+    program_id: &Pubkey,
+    instruction: TreasuryInstruction,
+    arbiter: &Pubkey,
+    recipient: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let (fund_authority, _) = EscrowTreasury::fund_authority_address(treasury, program_id);
+    let fund = EscrowTreasury::fund_address(treasury, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &instruction.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*arbiter, true),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(*treasury, false),
+            AccountMeta::new_readonly(fund_authority, false),
+            AccountMeta::new(fund, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(Config::address(program_id).0, false),
+            AccountMeta::new(Config::fee_vault_address(mint, program_id), false),
+        ],
+    )
+}
+
+/// Creates a `RefundExpiredEscrow` instruction
+pub fn refund_expired_escrow(
+    program_id: &Pubkey,
+    recipient: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let (fund_authority, _) = EscrowTreasury::fund_authority_address(treasury, program_id);
+    let fund = EscrowTreasury::fund_address(treasury, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::RefundExpiredEscrow
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(*treasury, false),
+            AccountMeta::new_readonly(fund_authority, false),
+            AccountMeta::new(fund, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(Config::address(program_id).0, false),
+            AccountMeta::new(Config::fee_vault_address(mint, program_id), false),
+        ],
+    )
+}
+
+/// Creates a `CreateMilestoneTreasury` instruction
+pub fn create_milestone_treasury(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+    milestones: Vec<(u64, Pubkey)>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::CreateMilestoneTreasury { milestones }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new_readonly(*authority, false),
+            AccountMeta::new(*treasury, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(Config::address(program_id).0, false),
+        ],
+    )
+}
+
+/// Creates a `ConfirmMilestone` instruction
+pub fn confirm_milestone(
+    program_id: &Pubkey,
+    attestor: &Pubkey,
+    treasury: &Pubkey,
+    index: u8,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::ConfirmMilestone { index }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*attestor, true),
+            AccountMeta::new(*treasury, false),
+        ],
+    )
+}
+
+/// Creates a `ConfirmMilestoneWithSignature` instruction. It has to follow an ed25519
+/// program instruction that verifies the attestor's signature.
+pub fn confirm_milestone_with_signature(
+    program_id: &Pubkey,
+    treasury: &Pubkey,
+    index: u8,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::ConfirmMilestoneWithSignature { index }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        ],
+    )
+}
+
+/// Creates a `WithdrawMilestones` instruction
+pub fn withdraw_milestones(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let (fund_authority, _) = MilestoneTreasury::fund_authority_address(treasury, program_id);
+    let fund = MilestoneTreasury::fund_address(treasury, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::WithdrawMilestones
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(fund_authority, false),
+            AccountMeta::new(fund, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(Config::address(program_id).0, false),
+            AccountMeta::new(Config::fee_vault_address(mint, program_id), false),
+        ],
+    )
+}
+
+/// Creates a `CreateHurdleTreasury` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn create_hurdle_treasury(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+    oracle: &Pubkey,
+    duration: u64,
+    tranches: Vec<(u64, u64)>,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::CreateHurdleTreasury { duration, tranches }
+            .try_to_vec()
+            .unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new_readonly(*authority, false),
+            AccountMeta::new(*treasury, true),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*oracle, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(Config::address(program_id).0, false),
+        ],
+    )
+}
+
+/// Creates an `UpdateHurdles` instruction
+pub fn update_hurdles(program_id: &Pubkey, treasury: &Pubkey, oracle: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::UpdateHurdles.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(*oracle, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Creates a `WithdrawHurdles` instruction
+pub fn withdraw_hurdles(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    recipient: &Pubkey,
+    treasury: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    let (fund_authority, _) = HurdleTreasury::fund_authority_address(treasury, program_id);
+    let fund = HurdleTreasury::fund_address(treasury, mint, program_id);
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::WithdrawHurdles.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(fund_authority, false),
+            AccountMeta::new(fund, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(Config::address(program_id).0, false),
+            AccountMeta::new(Config::fee_vault_address(mint, program_id), false),
+        ],
+    )
+}
+
+/// Creates an `InitializeConfig` instruction, the upgrade authority becomes the admin
+pub fn initialize_config(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    upgrade_authority: &Pubkey,
+    fee_receiver: &Pubkey,
+    creation_fee: u64,
+    withdrawal_fee: u16,
+    guardian: &Pubkey,
+) -> Instruction {
+    let (program_data, _) =
+        Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::InitializeConfig {
+            fee_receiver: *fee_receiver,
+            creation_fee,
+            withdrawal_fee,
+            guardian: *guardian,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new_readonly(*upgrade_authority, true),
+            AccountMeta::new(Config::address(program_id).0, false),
+            AccountMeta::new_readonly(program_data, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates an `UpdateConfig` instruction
+pub fn update_config(
+    program_id: &Pubkey,
+    admin: &Pubkey,
+    new_admin: &Pubkey,
+    fee_receiver: &Pubkey,
+    creation_fee: u64,
+    withdrawal_fee: u16,
+    guardian: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::UpdateConfig {
+            admin: *new_admin,
+            fee_receiver: *fee_receiver,
+            creation_fee,
+            withdrawal_fee,
+            guardian: *guardian,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(Config::address(program_id).0, false),
+        ],
+    )
+}
+
+/// Creates a `CollectFees` instruction
+pub fn collect_fees(
+    program_id: &Pubkey,
+    fee_receiver: &Pubkey,
+    recipient: &Pubkey,
+    mint: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::CollectFees.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(Config::address(program_id).0, false),
+            AccountMeta::new(*fee_receiver, false),
+            AccountMeta::new(Config::fee_vault_address(mint, program_id), false),
+            AccountMeta::new(*recipient, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+    )
+}
+
+/// Creates a `PauseProgram` instruction
+pub fn pause_program(program_id: &Pubkey, guardian: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::PauseProgram.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*guardian, true),
+            AccountMeta::new(Config::address(program_id).0, false),
+        ],
+    )
+}
+
+/// Creates an `UnpauseProgram` instruction
+pub fn unpause_program(program_id: &Pubkey, admin: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::UnpauseProgram.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*admin, true),
+            AccountMeta::new(Config::address(program_id).0, false),
+        ],
+    )
+}
+
+/// Creates a `SetRecovery` instruction
+pub fn set_recovery(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    backup: &Pubkey,
+    inactivity_period: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::SetRecovery {
+            backup: *backup,
+            inactivity_period,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*treasury, false),
+            AccountMeta::new(Recovery::address(treasury, program_id).0, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates a `Heartbeat` instruction
+pub fn heartbeat(program_id: &Pubkey, authority: &Pubkey, treasury: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::Heartbeat.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*treasury, false),
+            AccountMeta::new(Recovery::address(treasury, program_id).0, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Creates a `ClaimRecovery` instruction
+pub fn claim_recovery(program_id: &Pubkey, backup: &Pubkey, treasury: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::ClaimRecovery.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*backup, true),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new(Recovery::address(treasury, program_id).0, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+            AccountMeta::new(SocialRecovery::address(treasury, program_id).0, false),
+        ],
+    )
+}
+
+/// Creates a `SetGuardians` instruction
+pub fn set_guardians(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    authority: &Pubkey,
+    treasury: &Pubkey,
+    guardians: &[Pubkey],
+    threshold: u8,
+    challenge_delay: u64,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::SetGuardians {
+            guardians: guardians.to_vec(),
+            threshold,
+            challenge_delay,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*treasury, false),
+            AccountMeta::new(SocialRecovery::address(treasury, program_id).0, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
+}
+
+/// Creates an `ApproveRotation` instruction
+pub fn approve_rotation(
+    program_id: &Pubkey,
+    guardian: &Pubkey,
+    treasury: &Pubkey,
+    new_authority: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::ApproveRotation {
+            new_authority: *new_authority,
+        }
+        .try_to_vec()
+        .unwrap(),
+        vec![
+            AccountMeta::new_readonly(*guardian, true),
+            AccountMeta::new_readonly(*treasury, false),
+            AccountMeta::new(SocialRecovery::address(treasury, program_id).0, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Creates a `CancelRotation` instruction
+pub fn cancel_rotation(program_id: &Pubkey, authority: &Pubkey, treasury: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::CancelRotation.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*treasury, false),
+            AccountMeta::new(SocialRecovery::address(treasury, program_id).0, false),
+        ],
+    )
+}
+
+/// Creates an `ExecuteRotation` instruction
+pub fn execute_rotation(
+    program_id: &Pubkey,
+    new_authority: &Pubkey,
+    treasury: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::ExecuteRotation.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*new_authority, true),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new(SocialRecovery::address(treasury, program_id).0, false),
+            AccountMeta::new(Recovery::address(treasury, program_id).0, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+    )
+}
+
+/// Creates a `MigrateTreasury` instruction
+pub fn migrate_treasury(program_id: &Pubkey, funder: &Pubkey, treasury: &Pubkey) -> Instruction {
+    Instruction::new_with_bytes(
+        *program_id,
+        &TreasuryInstruction::MigrateTreasury.try_to_vec().unwrap(),
+        vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+    )
 }
 
 #[cfg(test)]
@@ -70,9 +1655,9 @@ mod tests {
     use super::*;
     #[test]
     pub fn test_serialize_instruction_init() {
-        let data = vec![0];
+        let data = vec![3, 1, 100, 0, 0, 0, 0, 0, 0, 0];
 
-        let instruction = TreasuryInstruction::WithdrawVested;
+        let instruction = TreasuryInstruction::WithdrawVested { amount: Some(100) };
 
         let serialized = instruction.try_to_vec().unwrap();
         assert_eq!(data, serialized);
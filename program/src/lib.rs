@@ -1,7 +1,13 @@
 pub mod account;
+pub mod config;
+pub mod context;
 pub mod error;
+pub mod governance;
 pub mod instruction;
+pub mod oracle;
 pub mod processor;
+pub mod recovery;
+pub mod zero_copy;
 
 #[cfg(not(feature = "no-entrypoint"))]
 mod entrypoint;
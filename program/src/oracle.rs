@@ -0,0 +1,66 @@
+//! Price feeds read by hurdle treasuries
+//!
+//! A price feed is any account whose data starts with two little endian fields:
+//! * `price: u64` at offset 0, the price of one token in the smallest unit of the quote
+//!   currency. Tranche targets of a hurdle treasury use the same unit.
+//! * `publish_time: i64` at offset 8, the Unix timestamp the price was published at
+//!
+//! Anything after these 16 bytes is ignored. The program doesn't check who owns the
+//! account, a hurdle treasury trusts the feed it was created with, so an adapter that
+//! copies prices from another oracle into this layout works as well as a mock in tests.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::clock::UnixTimestamp;
+
+use crate::error::TreasuryError;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct PriceFeed {
+    pub price: u64,
+    pub publish_time: UnixTimestamp,
+}
+
+impl PriceFeed {
+    /// The size of the fields the program reads
+    pub const LEN: usize = 16;
+    /// Prices older than this many seconds are stale
+    pub const MAX_AGE: i64 = 60;
+
+    pub fn unpack(data: &[u8]) -> Result<Self, TreasuryError> {
+        Self::deserialize(&mut &data[..]).map_err(|_| TreasuryError::InvalidPriceFeed)
+    }
+
+    /// The price, unless it's older than `MAX_AGE`
+    pub fn current_price(&self, now: UnixTimestamp) -> Result<u64, TreasuryError> {
+        if now.saturating_sub(self.publish_time) > Self::MAX_AGE {
+            return Err(TreasuryError::StalePrice);
+        }
+        Ok(self.price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_price_feed() {
+        let feed = PriceFeed {
+            price: 150,
+            publish_time: 1_000,
+        };
+        let mut data = feed.try_to_vec().unwrap();
+        assert_eq!(data.len(), PriceFeed::LEN);
+        // trailing data is ignored
+        data.extend_from_slice(&[1; 8]);
+        assert_eq!(PriceFeed::unpack(&data), Ok(feed));
+        assert_eq!(
+            PriceFeed::unpack(&data[..8]),
+            Err(TreasuryError::InvalidPriceFeed)
+        );
+
+        assert_eq!(feed.current_price(1_060), Ok(150));
+        assert_eq!(feed.current_price(1_061), Err(TreasuryError::StalePrice));
+    }
+}
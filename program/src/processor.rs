@@ -1,50 +1,49 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    clock::Clock,
+    account_info::AccountInfo,
+    clock::UnixTimestamp,
     entrypoint::ProgramResult,
     msg,
-    program::{invoke, invoke_signed},
-    program_pack::Pack,
+    program::{invoke, invoke_signed, set_return_data},
+    program_error::ProgramError,
     pubkey::Pubkey,
     system_instruction::{self},
-    sysvar::{rent::Rent, Sysvar},
+    sysvar::rent::Rent,
 };
 
-use spl_token::state::{Account, Mint};
-
 use crate::{
-    account::{SimpleTreasury, SimpleTreasuryMode, VestedTreasury},
+    account::{
+        Acceleration, EscrowTreasury, HurdleTreasury, MilestoneTreasury, Settlement,
+        SimpleTreasury, SimpleTreasuryMode, SplitterTreasury, VestedTreasury,
+    },
+    config::Config,
+    context::{
+        Activity, ApproveRotationContext, CancelRotationContext, ClaimRecoveryContext,
+        ClaimSplitContext, CollectFeesContext, ConfirmMilestoneContext,
+        CreateEscrowTreasuryContext, CreateHurdleTreasuryContext, CreateMilestoneTreasuryContext,
+        CreateSimpleTreasuryContext, CreateSplitterTreasuryContext, CreateVestedTreasuryContext,
+        ExecuteRotationContext, ExtendLockContext, GrantorContext, HeartbeatContext,
+        InitializeConfigContext, MergeVestedContext, MigrateTreasuryContext, QueryVestedContext,
+        SetGuardiansContext, SetPausedContext, SetRecoveryContext, SetVestedDelegateContext,
+        SettleEscrowContext, SplitVestedContext, UpdateConfigContext, UpdateHurdlesContext,
+        UpdateMaxVoterWeightRecordContext, UpdateVoteEscrowWeightContext,
+        UpdateVoterWeightRecordContext, WithdrawHurdlesContext, WithdrawMilestonesContext,
+        WithdrawSimpleContext, WithdrawVestedContext, WithdrawalFee,
+    },
     error::TreasuryError,
+    governance::{MaxVoterWeightRecord, VoterWeightRecord},
     instruction::TreasuryInstruction,
+    recovery::{Recovery, SocialRecovery},
+    zero_copy::ZeroCopy,
 };
 
-/// Verify an Associated Account
-#[macro_export]
-macro_rules! verify_associated {
-    ($assoc:expr, $owner:expr, $mint:expr) => {
-        match Account::unpack(&$assoc.data.borrow()) {
-            Ok(account) => {
-                if account.mint != $mint {
-                    Err(TreasuryError::MintWrongToken.into())
-                } else if account.owner != $owner {
-                    Err(TreasuryError::InvalidAssociatedAccount.into())
-                } else {
-                    Ok(account)
-                }
-            }
-            _ => Err(TreasuryError::InvalidAssociatedAccount),
-        }
-    };
-}
-
 pub struct Processor {}
 impl Processor {
     pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
         let instruction = TreasuryInstruction::try_from_slice(input)
             .map_err(|_| TreasuryError::InvalidInstruction)?;
 
-        msg!("Instruction :: {:?}", instruction);
+        msg!("Instruction :: {}", instruction.name());
 
         match instruction {
             TreasuryInstruction::CreateSimpleTreasury { mode } => {
@@ -60,63 +59,184 @@ impl Processor {
             } => Self::process_create_vested_treasury(
                 program_id, accounts, amount, period, percentage,
             ),
-            TreasuryInstruction::WithdrawVested => {
-                Self::process_withdraw_vested(program_id, accounts)
+            TreasuryInstruction::WithdrawVested { amount } => {
+                Self::process_withdraw_vested(program_id, accounts, amount)
+            }
+            TreasuryInstruction::QueryVested => Self::process_query_vested(program_id, accounts),
+            TreasuryInstruction::PauseVesting => Self::process_pause_vesting(program_id, accounts),
+            TreasuryInstruction::ResumeVesting => {
+                Self::process_resume_vesting(program_id, accounts)
+            }
+            TreasuryInstruction::AccelerateVesting { acceleration } => {
+                Self::process_accelerate_vesting(program_id, accounts, acceleration)
+            }
+            TreasuryInstruction::SplitVested { amount } => {
+                Self::process_split_vested(program_id, accounts, amount)
+            }
+            TreasuryInstruction::MergeVested => Self::process_merge_vested(program_id, accounts),
+            TreasuryInstruction::SetVestedDelegate { delegate } => {
+                Self::process_set_vested_delegate(program_id, accounts, delegate)
+            }
+            TreasuryInstruction::UpdateVoterWeightRecord => {
+                Self::process_update_voter_weight_record(program_id, accounts)
+            }
+            TreasuryInstruction::UpdateMaxVoterWeightRecord => {
+                Self::process_update_max_voter_weight_record(program_id, accounts)
+            }
+            TreasuryInstruction::ExtendLock { lock_end } => {
+                Self::process_extend_lock(program_id, accounts, lock_end)
+            }
+            TreasuryInstruction::UpdateVoteEscrowWeight => {
+                Self::process_update_vote_escrow_weight(program_id, accounts)
+            }
+            TreasuryInstruction::CreateSplitterTreasury { shares } => {
+                Self::process_create_splitter_treasury(program_id, accounts, &shares)
+            }
+            TreasuryInstruction::ClaimSplit => Self::process_claim_split(program_id, accounts),
+            TreasuryInstruction::CreateEscrowTreasury { expiry } => {
+                Self::process_create_escrow_treasury(program_id, accounts, expiry)
+            }
+            TreasuryInstruction::ReleaseEscrow => {
+                Self::process_settle_escrow(program_id, accounts, Settlement::Release)
+            }
+            TreasuryInstruction::RefundEscrow => {
+                Self::process_settle_escrow(program_id, accounts, Settlement::Refund)
+            }
+            TreasuryInstruction::RefundExpiredEscrow => {
+                Self::process_settle_escrow(program_id, accounts, Settlement::Expire)
+            }
+            TreasuryInstruction::CreateMilestoneTreasury { milestones } => {
+                Self::process_create_milestone_treasury(program_id, accounts, &milestones)
+            }
+            TreasuryInstruction::ConfirmMilestone { index } => {
+                Self::process_confirm_milestone(program_id, accounts, index, false)
+            }
+            TreasuryInstruction::ConfirmMilestoneWithSignature { index } => {
+                Self::process_confirm_milestone(program_id, accounts, index, true)
+            }
+            TreasuryInstruction::WithdrawMilestones => {
+                Self::process_withdraw_milestones(program_id, accounts)
+            }
+            TreasuryInstruction::CreateHurdleTreasury { duration, tranches } => {
+                Self::process_create_hurdle_treasury(program_id, accounts, duration, &tranches)
+            }
+            TreasuryInstruction::UpdateHurdles => {
+                Self::process_update_hurdles(program_id, accounts)
+            }
+            TreasuryInstruction::WithdrawHurdles => {
+                Self::process_withdraw_hurdles(program_id, accounts)
+            }
+            TreasuryInstruction::InitializeConfig {
+                fee_receiver,
+                creation_fee,
+                withdrawal_fee,
+                guardian,
+            } => Self::process_initialize_config(
+                program_id,
+                accounts,
+                Config::new(
+                    Pubkey::default(),
+                    fee_receiver,
+                    creation_fee,
+                    withdrawal_fee,
+                    guardian,
+                )?,
+            ),
+            TreasuryInstruction::UpdateConfig {
+                admin,
+                fee_receiver,
+                creation_fee,
+                withdrawal_fee,
+                guardian,
+            } => Self::process_update_config(
+                program_id,
+                accounts,
+                Config::new(admin, fee_receiver, creation_fee, withdrawal_fee, guardian)?,
+            ),
+            TreasuryInstruction::CollectFees => Self::process_collect_fees(program_id, accounts),
+            TreasuryInstruction::PauseProgram => {
+                Self::process_set_paused(program_id, accounts, true)
+            }
+            TreasuryInstruction::UnpauseProgram => {
+                Self::process_set_paused(program_id, accounts, false)
+            }
+            TreasuryInstruction::SetRecovery {
+                backup,
+                inactivity_period,
+            } => Self::process_set_recovery(program_id, accounts, backup, inactivity_period),
+            TreasuryInstruction::Heartbeat => Self::process_heartbeat(program_id, accounts),
+            TreasuryInstruction::ClaimRecovery => {
+                Self::process_claim_recovery(program_id, accounts)
+            }
+            TreasuryInstruction::SetGuardians {
+                guardians,
+                threshold,
+                challenge_delay,
+            } => Self::process_set_guardians(
+                program_id,
+                accounts,
+                &guardians,
+                threshold,
+                challenge_delay,
+            ),
+            TreasuryInstruction::ApproveRotation { new_authority } => {
+                Self::process_approve_rotation(program_id, accounts, new_authority)
+            }
+            TreasuryInstruction::CancelRotation => {
+                Self::process_cancel_rotation(program_id, accounts)
+            }
+            TreasuryInstruction::ExecuteRotation => {
+                Self::process_execute_rotation(program_id, accounts)
+            }
+            TreasuryInstruction::MigrateTreasury => {
+                Self::process_migrate_treasury(program_id, accounts)
             }
         }
     }
 
-    // Mint::unpack(&token_info.data.borrow()).map_err(|_| TreasuryError::TokenNotSPLToken)?;
-
     pub fn process_create_simple_treasury(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
         mode: SimpleTreasuryMode,
     ) -> ProgramResult {
-        let iter = &mut accounts.iter();
-        let funder_info = next_account_info(iter)?;
-        let authority_info = next_account_info(iter)?;
-        let treasury_info = next_account_info(iter)?;
-        let mint_info = next_account_info(iter)?;
-        let rent_info = next_account_info(iter)?;
-
-        let rent = Rent::from_account_info(rent_info)?;
-
-        Mint::unpack(&mint_info.data.borrow()).map_err(|_| TreasuryError::TokenNotSPLToken)?;
+        let ctx = CreateSimpleTreasuryContext::load(program_id, accounts)?;
 
         // only allow creation of specific modes
         match mode {
             SimpleTreasuryMode::Locked => { /* ok */ }
             SimpleTreasuryMode::Unlocked => { /* ok */ }
-        }
-
-        if !treasury_info.data_is_empty() {
-            return Err(TreasuryError::TreasuryAlreadyExists.into());
+            SimpleTreasuryMode::VoteEscrow => { /* ok, unlocked until `ExtendLock` */ }
         }
 
         let user_treasury = SimpleTreasury {
-            mint: *mint_info.key,
+            mint: *ctx.mint_info.key,
             mode,
-            authority: *authority_info.key,
+            authority: *ctx.authority_info.key,
+            lock_end: 0,
+            weight: 0,
+            weight_updated_at: 0,
         };
-        let data = user_treasury.try_to_vec()?;
-
-        let lamports = rent.minimum_balance(data.len());
-        let space = data.len() as u64;
+        let lamports = ctx.rent.minimum_balance(SimpleTreasury::LEN);
+        let space = SimpleTreasury::LEN as u64;
         invoke(
             &system_instruction::create_account(
-                funder_info.key,
-                treasury_info.key,
+                ctx.funder_info.key,
+                ctx.treasury_info.key,
                 lamports,
                 space,
                 program_id,
             ),
-            &[funder_info.clone(), treasury_info.clone()],
+            &[ctx.funder_info.clone(), ctx.treasury_info.clone()],
         )?;
 
-        treasury_info.data.borrow_mut().copy_from_slice(&data);
+        user_treasury.write(ctx.treasury_info);
 
-        Ok(())
+        Self::charge_creation_fee(
+            ctx.funder_info,
+            ctx.config_info,
+            ctx.system_program_info,
+            ctx.creation_fee,
+        )
     }
 
     pub fn process_withdraw_simple(
@@ -124,200 +244,1108 @@ impl Processor {
         accounts: &[AccountInfo],
         amount: u64,
     ) -> ProgramResult {
-        let iter = &mut accounts.iter();
-        let _funder_info = next_account_info(iter)?;
-        let authority_info = next_account_info(iter)?;
-        let recipient_info = next_account_info(iter)?;
-        let treasury_info = next_account_info(iter)?;
-        let fund_authority_info = next_account_info(iter)?;
-        let fund_info = next_account_info(iter)?;
-        let token_program_info = next_account_info(iter)?;
-
-        let treasury =
-            SimpleTreasury::from_account_info(treasury_info, authority_info, program_id)?;
+        let mut ctx = WithdrawSimpleContext::load(program_id, accounts)?;
 
-        match treasury.mode {
+        match ctx.treasury.mode {
             SimpleTreasuryMode::Locked => return Err(TreasuryError::TreasuryIsLocked.into()),
             SimpleTreasuryMode::Unlocked => { /* ok */ }
+            SimpleTreasuryMode::VoteEscrow => {
+                if ctx.clock.unix_timestamp < ctx.treasury.lock_end {
+                    return Err(TreasuryError::TreasuryIsLocked.into());
+                }
+            }
         }
 
-        let fund_authority_seed = SimpleTreasury::verify_fund_authority_address(
-            fund_authority_info.key,
-            treasury_info.key,
-            program_id,
+        Self::record_activity(&mut ctx.activity, ctx.clock.unix_timestamp);
+
+        // will fail if not enough funds
+        Self::pay_out(
+            ctx.fund_info,
+            ctx.recipient_info,
+            ctx.fund_authority_info,
+            ctx.token_program_info,
+            &ctx.fee,
+            amount,
+            &[
+                b"simple authority",
+                &ctx.treasury_info.key.to_bytes(),
+                &[ctx.fund_authority_seed],
+            ],
+        )
+    }
+
+    pub fn process_create_vested_treasury(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        period: u64,
+        percentage: u16,
+    ) -> ProgramResult {
+        let ctx = CreateVestedTreasuryContext::load(program_id, accounts)?;
+
+        VestedTreasury::verify_parameters(amount, period, percentage)?;
+
+        let vested_treasury = VestedTreasury {
+            mint: *ctx.mint_info.key,
+            authority: *ctx.authority_info.key,
+            initial_amount: amount,
+            start: ctx.clock.unix_timestamp,
+            vestment_period: period,
+            vestment_percentage: percentage,
+            withdrawn: 0,
+            grantor: *ctx.grantor_info.key,
+            paused_at: 0,
+            accelerated: 0,
+            delegate: Pubkey::default(),
+        };
+        let lamports = ctx.rent.minimum_balance(VestedTreasury::LEN);
+        let space = VestedTreasury::LEN as u64;
+        invoke(
+            &system_instruction::create_account(
+                ctx.funder_info.key,
+                ctx.treasury_info.key,
+                lamports,
+                space,
+                program_id,
+            ),
+            &[ctx.funder_info.clone(), ctx.treasury_info.clone()],
         )?;
 
-        if spl_associated_token_account::get_associated_token_address(
-            fund_authority_info.key,
-            &treasury.mint,
-        ) != *fund_info.key
-        {
-            return Err(TreasuryError::InvalidTreasuryFundAddress.into());
+        vested_treasury.write(ctx.treasury_info);
+
+        Self::charge_creation_fee(
+            ctx.funder_info,
+            ctx.config_info,
+            ctx.system_program_info,
+            ctx.creation_fee,
+        )
+    }
+
+    pub fn process_withdraw_vested(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: Option<u64>,
+    ) -> ProgramResult {
+        let mut ctx = WithdrawVestedContext::load(program_id, accounts)?;
+        Self::record_activity(&mut ctx.activity, ctx.clock.unix_timestamp);
+
+        // calculate how much funds are available to be released
+        let available = ctx
+            .treasury
+            .maximum_available(ctx.clock.unix_timestamp)
+            .saturating_sub(ctx.treasury.withdrawn);
+        let payable = match amount {
+            Some(amount) if amount > available => {
+                return Err(TreasuryError::AmountNotAvailable.into())
+            }
+            Some(amount) if amount > ctx.fund.amount => {
+                return Err(TreasuryError::InsufficientFunds.into())
+            }
+            Some(amount) => amount,
+            None => available.min(ctx.fund.amount),
+        };
+        if payable > 0 {
+            ctx.treasury.withdrawn += payable;
+
+            Self::pay_out(
+                ctx.fund_info,
+                ctx.recipient_info,
+                ctx.fund_authority_info,
+                ctx.token_program_info,
+                &ctx.fee,
+                payable,
+                &[
+                    b"vested authority",
+                    &ctx.treasury_info.key.to_bytes(),
+                    &[ctx.fund_authority_seed],
+                ],
+            )
+        } else {
+            Ok(())
         }
+    }
+
+    pub fn process_query_vested(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let ctx = QueryVestedContext::load(program_id, accounts)?;
+
+        let query = ctx
+            .treasury
+            .query(ctx.clock.unix_timestamp, ctx.fund.amount);
+        set_return_data(&query.try_to_vec()?);
+
+        Ok(())
+    }
+
+    pub fn process_pause_vesting(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let mut ctx = GrantorContext::load(program_id, accounts)?;
+
+        ctx.treasury.pause(ctx.clock.unix_timestamp)?;
+
+        Ok(())
+    }
+
+    pub fn process_resume_vesting(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let mut ctx = GrantorContext::load(program_id, accounts)?;
+
+        ctx.treasury.resume(ctx.clock.unix_timestamp)?;
+
+        Ok(())
+    }
+
+    pub fn process_accelerate_vesting(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        acceleration: Acceleration,
+    ) -> ProgramResult {
+        let mut ctx = GrantorContext::load(program_id, accounts)?;
+
+        ctx.treasury
+            .accelerate(ctx.clock.unix_timestamp, acceleration)?;
+
+        Ok(())
+    }
+
+    pub fn process_split_vested(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let mut ctx = SplitVestedContext::load(program_id, accounts)?;
+
+        let (new_treasury, fund_share) = ctx.treasury.split(
+            amount,
+            *ctx.new_authority_info.key,
+            ctx.fund.amount,
+            ctx.clock.unix_timestamp,
+        )?;
+        let lamports = ctx.rent.minimum_balance(VestedTreasury::LEN);
+        let space = VestedTreasury::LEN as u64;
+        invoke(
+            &system_instruction::create_account(
+                ctx.funder_info.key,
+                ctx.new_treasury_info.key,
+                lamports,
+                space,
+                program_id,
+            ),
+            &[
+                ctx.funder_info.clone(),
+                ctx.new_treasury_info.clone(),
+                ctx.system_program_info.clone(),
+            ],
+        )?;
 
-        verify_associated!(fund_info, *fund_authority_info.key, treasury.mint)?;
-        verify_associated!(recipient_info, treasury.authority, treasury.mint)?;
+        new_treasury.write(ctx.new_treasury_info);
 
         invoke_signed(
-            // will fail if not enough funds
             &spl_token::instruction::transfer(
                 &spl_token::id(),
-                fund_info.key,
-                recipient_info.key,
-                fund_authority_info.key,
+                ctx.fund_info.key,
+                ctx.new_fund_info.key,
+                ctx.fund_authority_info.key,
                 &[],
-                amount,
+                fund_share,
             )?,
             &[
-                fund_info.clone(),
-                recipient_info.clone(),
-                treasury_info.clone(),
-                token_program_info.clone(),
+                ctx.fund_authority_info.clone(),
+                ctx.fund_info.clone(),
+                ctx.new_fund_info.clone(),
+                ctx.token_program_info.clone(),
             ],
             &[&[
-                b"simple authority",
-                &treasury_info.key.to_bytes(),
-                &[fund_authority_seed],
+                b"vested authority",
+                &ctx.treasury_info.key.to_bytes(),
+                &[ctx.fund_authority_seed],
             ]],
         )
     }
 
-    pub fn process_create_vested_treasury(
+    pub fn process_merge_vested(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let mut ctx = MergeVestedContext::load(program_id, accounts)?;
+
+        ctx.treasury.merge(&ctx.source)?;
+
+        let seeds: &[&[u8]] = &[
+            b"vested authority",
+            &ctx.source_info.key.to_bytes(),
+            &[ctx.source_fund_authority_seed],
+        ];
+        invoke_signed(
+            &spl_token::instruction::transfer(
+                &spl_token::id(),
+                ctx.source_fund_info.key,
+                ctx.fund_info.key,
+                ctx.source_fund_authority_info.key,
+                &[],
+                ctx.source_fund.amount,
+            )?,
+            &[
+                ctx.source_fund_authority_info.clone(),
+                ctx.source_fund_info.clone(),
+                ctx.fund_info.clone(),
+                ctx.token_program_info.clone(),
+            ],
+            &[seeds],
+        )?;
+        invoke_signed(
+            &spl_token::instruction::close_account(
+                &spl_token::id(),
+                ctx.source_fund_info.key,
+                ctx.recipient_info.key,
+                ctx.source_fund_authority_info.key,
+                &[],
+            )?,
+            &[
+                ctx.source_fund_authority_info.clone(),
+                ctx.source_fund_info.clone(),
+                ctx.recipient_info.clone(),
+                ctx.token_program_info.clone(),
+            ],
+            &[seeds],
+        )?;
+
+        // close the source treasury and its recovery records, which would otherwise
+        // apply again if the address is reused
+        Self::close_account(ctx.source_info, ctx.recipient_info)?;
+        if let Some(recovery) = ctx.source_recovery {
+            drop(recovery);
+            Self::close_account(ctx.source_recovery_info, ctx.recipient_info)?;
+        }
+        if let Some(social) = ctx.source_social {
+            drop(social);
+            Self::close_account(ctx.source_social_info, ctx.recipient_info)?;
+        }
+        Ok(())
+    }
+
+    pub fn process_set_vested_delegate(
         program_id: &Pubkey,
         accounts: &[AccountInfo],
-        amount: u64,
-        period: u64,
-        percentage: u16,
+        delegate: Option<Pubkey>,
     ) -> ProgramResult {
-        let iter = &mut accounts.iter();
-        let funder_info = next_account_info(iter)?;
-        let authority_info = next_account_info(iter)?;
-        let treasury_info = next_account_info(iter)?;
-        let mint_info = next_account_info(iter)?;
-        let rent_info = next_account_info(iter)?;
-        let clock_info = next_account_info(iter)?;
-
-        let rent = Rent::from_account_info(rent_info)?;
-        let clock = Clock::from_account_info(clock_info)?;
-        Mint::unpack(&mint_info.data.borrow()).map_err(|_| TreasuryError::TokenNotSPLToken)?;
+        let mut ctx = SetVestedDelegateContext::load(program_id, accounts)?;
 
-        if amount == 0 {
-            return Err(TreasuryError::InvalidVestmentAmount.into());
+        ctx.treasury.delegate = delegate.unwrap_or_default();
+        Ok(())
+    }
+
+    pub fn process_update_voter_weight_record(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let ctx = UpdateVoterWeightRecordContext::load(program_id, accounts)?;
+
+        let now = ctx.clock.unix_timestamp;
+        let voter_weight = ctx
+            .treasuries
+            .iter()
+            .fold(0u64, |total, (treasury, balance)| {
+                total.saturating_add(treasury.weight(now, *balance))
+            });
+
+        if ctx.record_info.data_is_empty() {
+            Self::create_program_account(
+                program_id,
+                ctx.payer_info,
+                ctx.record_info,
+                ctx.system_program_info,
+                &ctx.rent,
+                VoterWeightRecord::LEN,
+                &[
+                    b"voter-weight-record",
+                    &ctx.realm_info.key.to_bytes(),
+                    &ctx.mint_info.key.to_bytes(),
+                    &ctx.owner_info.key.to_bytes(),
+                    &[ctx.record_seed],
+                ],
+            )?;
         }
-        if period == 0 {
-            return Err(TreasuryError::InvalidVestmentPeriod.into());
+
+        let record = VoterWeightRecord {
+            account_discriminator: VoterWeightRecord::ACCOUNT_DISCRIMINATOR,
+            realm: *ctx.realm_info.key,
+            governing_token_mint: *ctx.mint_info.key,
+            governing_token_owner: *ctx.owner_info.key,
+            voter_weight,
+            voter_weight_expiry: Some(ctx.clock.slot),
+            weight_action: None,
+            weight_action_target: None,
+            reserved: [0; 8],
+        };
+        let mut data = ctx.record_info.data.borrow_mut();
+        data.fill(0);
+        record.serialize(&mut &mut data[..])?;
+        Ok(())
+    }
+
+    pub fn process_update_max_voter_weight_record(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let ctx = UpdateMaxVoterWeightRecordContext::load(program_id, accounts)?;
+
+        if ctx.record_info.data_is_empty() {
+            Self::create_program_account(
+                program_id,
+                ctx.payer_info,
+                ctx.record_info,
+                ctx.system_program_info,
+                &ctx.rent,
+                MaxVoterWeightRecord::LEN,
+                &[
+                    b"max-voter-weight-record",
+                    &ctx.realm_info.key.to_bytes(),
+                    &ctx.mint_info.key.to_bytes(),
+                    &[ctx.record_seed],
+                ],
+            )?;
         }
 
-        if percentage < VestedTreasury::MIN_PERCENTAGE
-            || percentage > VestedTreasury::MAX_PERCENTAGE
-        {
-            return Err(TreasuryError::InvalidVestmentPercentage.into());
+        let record = MaxVoterWeightRecord {
+            account_discriminator: MaxVoterWeightRecord::ACCOUNT_DISCRIMINATOR,
+            realm: *ctx.realm_info.key,
+            governing_token_mint: *ctx.mint_info.key,
+            max_voter_weight: ctx.mint.supply,
+            max_voter_weight_expiry: Some(ctx.clock.slot),
+            reserved: [0; 8],
+        };
+        let mut data = ctx.record_info.data.borrow_mut();
+        data.fill(0);
+        record.serialize(&mut &mut data[..])?;
+        Ok(())
+    }
+
+    pub fn process_extend_lock(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        lock_end: UnixTimestamp,
+    ) -> ProgramResult {
+        let mut ctx = ExtendLockContext::load(program_id, accounts)?;
+
+        let now = ctx.clock.unix_timestamp;
+        ctx.treasury.extend_lock(now, lock_end)?;
+        ctx.treasury.update_weight(now, ctx.fund.amount);
+        Ok(())
+    }
+
+    pub fn process_update_vote_escrow_weight(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let mut ctx = UpdateVoteEscrowWeightContext::load(program_id, accounts)?;
+        if ctx.treasury.mode != SimpleTreasuryMode::VoteEscrow {
+            return Err(TreasuryError::NotVoteEscrow.into());
         }
 
-        if !treasury_info.data_is_empty() {
-            return Err(TreasuryError::TreasuryAlreadyExists.into());
+        ctx.treasury
+            .update_weight(ctx.clock.unix_timestamp, ctx.fund.amount);
+
+        let weight = ctx.treasury.weight;
+        set_return_data(&weight.try_to_vec()?);
+        Ok(())
+    }
+
+    pub fn process_create_splitter_treasury(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        shares: &[(Pubkey, u16)],
+    ) -> ProgramResult {
+        let ctx = CreateSplitterTreasuryContext::load(program_id, accounts)?;
+
+        let splitter = SplitterTreasury::new(*ctx.mint_info.key, shares)?;
+        let lamports = ctx.rent.minimum_balance(SplitterTreasury::LEN);
+        let space = SplitterTreasury::LEN as u64;
+        invoke(
+            &system_instruction::create_account(
+                ctx.funder_info.key,
+                ctx.treasury_info.key,
+                lamports,
+                space,
+                program_id,
+            ),
+            &[
+                ctx.funder_info.clone(),
+                ctx.treasury_info.clone(),
+                ctx.system_program_info.clone(),
+            ],
+        )?;
+
+        splitter.write(ctx.treasury_info);
+
+        Self::charge_creation_fee(
+            ctx.funder_info,
+            ctx.config_info,
+            ctx.system_program_info,
+            ctx.creation_fee,
+        )
+    }
+
+    pub fn process_claim_split(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let mut ctx = ClaimSplitContext::load(program_id, accounts)?;
+
+        let amount = ctx
+            .treasury
+            .claim(ctx.shareholder_info.key, ctx.fund.amount)?;
+        if amount == 0 {
+            return Ok(());
         }
 
-        let vested_treasury = VestedTreasury {
-            mint: *mint_info.key,
-            authority: *authority_info.key,
-            initial_amount: amount,
-            start: clock.unix_timestamp,
-            vestment_period: period,
-            vestment_percentage: percentage,
-            withdrawn: 0,
-        };
-        let data = vested_treasury.try_to_vec()?;
+        Self::pay_out(
+            ctx.fund_info,
+            ctx.recipient_info,
+            ctx.fund_authority_info,
+            ctx.token_program_info,
+            &ctx.fee,
+            amount,
+            &[
+                b"splitter authority",
+                &ctx.treasury_info.key.to_bytes(),
+                &[ctx.fund_authority_seed],
+            ],
+        )
+    }
+
+    pub fn process_create_escrow_treasury(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        expiry: UnixTimestamp,
+    ) -> ProgramResult {
+        let ctx = CreateEscrowTreasuryContext::load(program_id, accounts)?;
+
+        let escrow = EscrowTreasury::new(
+            *ctx.mint_info.key,
+            *ctx.depositor_info.key,
+            *ctx.payee_info.key,
+            *ctx.arbiter_info.key,
+            expiry,
+            ctx.clock.unix_timestamp,
+        )?;
+        let lamports = ctx.rent.minimum_balance(EscrowTreasury::LEN);
+        let space = EscrowTreasury::LEN as u64;
+        invoke(
+            &system_instruction::create_account(
+                ctx.funder_info.key,
+                ctx.treasury_info.key,
+                lamports,
+                space,
+                program_id,
+            ),
+            &[
+                ctx.funder_info.clone(),
+                ctx.treasury_info.clone(),
+                ctx.system_program_info.clone(),
+            ],
+        )?;
+
+        escrow.write(ctx.treasury_info);
+
+        Self::charge_creation_fee(
+            ctx.funder_info,
+            ctx.config_info,
+            ctx.system_program_info,
+            ctx.creation_fee,
+        )
+    }
+
+    pub fn process_settle_escrow(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        settlement: Settlement,
+    ) -> ProgramResult {
+        let ctx = SettleEscrowContext::load(program_id, accounts, settlement)?;
+        if ctx.fund.amount == 0 {
+            return Ok(());
+        }
+
+        Self::pay_out(
+            ctx.fund_info,
+            ctx.recipient_info,
+            ctx.fund_authority_info,
+            ctx.token_program_info,
+            &ctx.fee,
+            ctx.fund.amount,
+            &[
+                b"escrow authority",
+                &ctx.treasury_info.key.to_bytes(),
+                &[ctx.fund_authority_seed],
+            ],
+        )
+    }
+
+    pub fn process_create_milestone_treasury(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        milestones: &[(u64, Pubkey)],
+    ) -> ProgramResult {
+        let ctx = CreateMilestoneTreasuryContext::load(program_id, accounts)?;
 
-        let lamports = rent.minimum_balance(data.len());
-        let space = data.len() as u64;
+        let treasury =
+            MilestoneTreasury::new(*ctx.mint_info.key, *ctx.authority_info.key, milestones)?;
+        let lamports = ctx.rent.minimum_balance(MilestoneTreasury::LEN);
+        let space = MilestoneTreasury::LEN as u64;
         invoke(
             &system_instruction::create_account(
-                funder_info.key,
-                treasury_info.key,
+                ctx.funder_info.key,
+                ctx.treasury_info.key,
                 lamports,
                 space,
                 program_id,
             ),
-            &[funder_info.clone(), treasury_info.clone()],
+            &[
+                ctx.funder_info.clone(),
+                ctx.treasury_info.clone(),
+                ctx.system_program_info.clone(),
+            ],
         )?;
 
-        treasury_info.data.borrow_mut().copy_from_slice(&data);
+        treasury.write(ctx.treasury_info);
+
+        Self::charge_creation_fee(
+            ctx.funder_info,
+            ctx.config_info,
+            ctx.system_program_info,
+            ctx.creation_fee,
+        )
+    }
+
+    pub fn process_confirm_milestone(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        index: u8,
+        off_chain: bool,
+    ) -> ProgramResult {
+        let mut ctx = ConfirmMilestoneContext::load(program_id, accounts, index, off_chain)?;
+
+        ctx.treasury.confirm(index, &ctx.attestor)?;
 
         Ok(())
     }
 
-    pub fn process_withdraw_vested(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
-        let iter = &mut accounts.iter();
-        let _funder_info = next_account_info(iter)?;
-        let authority_info = next_account_info(iter)?;
-        let recipient_info = next_account_info(iter)?;
-        let treasury_info = next_account_info(iter)?;
-        let fund_authority_info = next_account_info(iter)?;
-        let fund_info = next_account_info(iter)?;
-        let clock_info = next_account_info(iter)?;
-        let token_program_info = next_account_info(iter)?;
+    pub fn process_withdraw_milestones(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let mut ctx = WithdrawMilestonesContext::load(program_id, accounts)?;
 
-        let clock = Clock::from_account_info(clock_info)?;
+        let amount = ctx.treasury.available(ctx.fund.amount);
+        if amount == 0 {
+            return Ok(());
+        }
+        ctx.treasury.withdrawn += amount;
 
-        let mut treasury =
-            VestedTreasury::from_account_info(treasury_info, authority_info, program_id)?;
+        Self::pay_out(
+            ctx.fund_info,
+            ctx.recipient_info,
+            ctx.fund_authority_info,
+            ctx.token_program_info,
+            &ctx.fee,
+            amount,
+            &[
+                b"milestone authority",
+                &ctx.treasury_info.key.to_bytes(),
+                &[ctx.fund_authority_seed],
+            ],
+        )
+    }
+
+    pub fn process_create_hurdle_treasury(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        duration: u64,
+        tranches: &[(u64, u64)],
+    ) -> ProgramResult {
+        let ctx = CreateHurdleTreasuryContext::load(program_id, accounts)?;
 
-        let fund_authority_seed = VestedTreasury::verify_fund_authority_address(
-            fund_authority_info.key,
-            treasury_info.key,
+        let treasury = HurdleTreasury::new(
+            *ctx.mint_info.key,
+            *ctx.authority_info.key,
+            *ctx.oracle_info.key,
+            duration,
+            tranches,
+        )?;
+        let lamports = ctx.rent.minimum_balance(HurdleTreasury::LEN);
+        let space = HurdleTreasury::LEN as u64;
+        invoke(
+            &system_instruction::create_account(
+                ctx.funder_info.key,
+                ctx.treasury_info.key,
+                lamports,
+                space,
+                program_id,
+            ),
+            &[
+                ctx.funder_info.clone(),
+                ctx.treasury_info.clone(),
+                ctx.system_program_info.clone(),
+            ],
+        )?;
+
+        treasury.write(ctx.treasury_info);
+
+        Self::charge_creation_fee(
+            ctx.funder_info,
+            ctx.config_info,
+            ctx.system_program_info,
+            ctx.creation_fee,
+        )
+    }
+
+    pub fn process_update_hurdles(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let mut ctx = UpdateHurdlesContext::load(program_id, accounts)?;
+
+        ctx.treasury.update(ctx.clock.unix_timestamp, ctx.price);
+
+        Ok(())
+    }
+
+    pub fn process_withdraw_hurdles(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let mut ctx = WithdrawHurdlesContext::load(program_id, accounts)?;
+
+        let amount = ctx.treasury.available(ctx.fund.amount);
+        if amount == 0 {
+            return Ok(());
+        }
+        ctx.treasury.withdrawn += amount;
+
+        Self::pay_out(
+            ctx.fund_info,
+            ctx.recipient_info,
+            ctx.fund_authority_info,
+            ctx.token_program_info,
+            &ctx.fee,
+            amount,
+            &[
+                b"hurdle authority",
+                &ctx.treasury_info.key.to_bytes(),
+                &[ctx.fund_authority_seed],
+            ],
+        )
+    }
+
+    pub fn process_initialize_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        config: Config,
+    ) -> ProgramResult {
+        let ctx = InitializeConfigContext::load(program_id, accounts)?;
+
+        // the upgrade authority becomes the admin
+        let config = Config {
+            admin: *ctx.admin_info.key,
+            ..config
+        };
+        Self::create_program_account(
             program_id,
+            ctx.funder_info,
+            ctx.config_info,
+            ctx.system_program_info,
+            &ctx.rent,
+            Config::LEN,
+            &[b"config", &[ctx.config_seed]],
         )?;
+        config.write(ctx.config_info);
 
-        if spl_associated_token_account::get_associated_token_address(
-            fund_authority_info.key,
-            &treasury.mint,
-        ) != *fund_info.key
-        {
-            return Err(TreasuryError::InvalidTreasuryFundAddress.into());
+        Ok(())
+    }
+
+    pub fn process_update_config(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        config: Config,
+    ) -> ProgramResult {
+        let mut ctx = UpdateConfigContext::load(program_id, accounts)?;
+
+        let config = Config {
+            paused: ctx.config.paused,
+            ..config
+        };
+        // only what changed, the whole config is expensive to format
+        let previous = *ctx.config;
+        if previous.admin != config.admin {
+            msg!("Admin :: {} -> {}", previous.admin, config.admin);
         }
-        let fund = verify_associated!(fund_info, *fund_authority_info.key, treasury.mint)?;
-        verify_associated!(recipient_info, treasury.authority, treasury.mint)?;
+        if previous.fee_receiver != config.fee_receiver {
+            msg!(
+                "Fee receiver :: {} -> {}",
+                previous.fee_receiver,
+                config.fee_receiver
+            );
+        }
+        let (before, after) = (previous.creation_fee, config.creation_fee);
+        if before != after {
+            msg!("Creation fee :: {} -> {}", before, after);
+        }
+        let (before, after) = (previous.withdrawal_fee, config.withdrawal_fee);
+        if before != after {
+            msg!("Withdrawal fee :: {} -> {}", before, after);
+        }
+        if previous.guardian != config.guardian {
+            msg!("Guardian :: {} -> {}", previous.guardian, config.guardian);
+        }
+        *ctx.config = config;
 
-        // calculate how much funds are available to be released
-        let available = treasury.maximum_available(clock.unix_timestamp) - treasury.withdrawn;
-        if available > 0 {
-            let payable = if available > fund.amount {
-                fund.amount
-            } else {
-                available
-            };
-
-            treasury.withdrawn += payable;
-            treasury_info
-                .data
-                .borrow_mut()
-                .copy_from_slice(&treasury.try_to_vec()?);
+        Ok(())
+    }
 
+    pub fn process_set_paused(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        paused: bool,
+    ) -> ProgramResult {
+        let mut ctx = SetPausedContext::load(program_id, accounts, paused)?;
+
+        msg!("Paused :: {} -> {}", ctx.config.paused, paused);
+        ctx.config.paused = paused;
+
+        Ok(())
+    }
+
+    pub fn process_collect_fees(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let ctx = CollectFeesContext::load(program_id, accounts)?;
+
+        // tokens first, the runtime doesn't allow changing the balance of an account
+        // that is passed to another program afterwards
+        if ctx.fee_vault.amount > 0 {
+            invoke_signed(
+                &spl_token::instruction::transfer(
+                    &spl_token::id(),
+                    ctx.fee_vault_info.key,
+                    ctx.recipient_info.key,
+                    ctx.config_info.key,
+                    &[],
+                    ctx.fee_vault.amount,
+                )?,
+                &[
+                    ctx.config_info.clone(),
+                    ctx.fee_vault_info.clone(),
+                    ctx.recipient_info.clone(),
+                    ctx.token_program_info.clone(),
+                ],
+                &[&[b"config", &[ctx.config_seed]]],
+            )?;
+        }
+
+        // creation fees, the config stays rent exempt
+        let lamports = ctx
+            .config_info
+            .lamports()
+            .saturating_sub(ctx.rent.minimum_balance(Config::LEN));
+        let balance = ctx
+            .fee_receiver_info
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::InvalidArgument)?;
+        **ctx.fee_receiver_info.lamports.borrow_mut() = balance;
+        **ctx.config_info.lamports.borrow_mut() -= lamports;
+        Ok(())
+    }
+
+    pub fn process_set_recovery(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        backup: Pubkey,
+        inactivity_period: u64,
+    ) -> ProgramResult {
+        let mut ctx = SetRecoveryContext::load(program_id, accounts)?;
+
+        if backup == Pubkey::default() || backup == ctx.treasury.authority() {
+            return Err(TreasuryError::InvalidBackup.into());
+        }
+        let recovery = Recovery::new(
+            *ctx.treasury_info.key,
+            backup,
+            inactivity_period,
+            ctx.clock.unix_timestamp,
+        )?;
+        if let Some(existing) = &mut ctx.recovery {
+            **existing = recovery;
+        } else {
+            Self::create_program_account(
+                program_id,
+                ctx.funder_info,
+                ctx.recovery_info,
+                ctx.system_program_info,
+                &ctx.rent,
+                Recovery::LEN,
+                &[
+                    b"recovery",
+                    &ctx.treasury_info.key.to_bytes(),
+                    &[ctx.recovery_seed],
+                ],
+            )?;
+            recovery.write(ctx.recovery_info);
+        }
+
+        Ok(())
+    }
+
+    pub fn process_heartbeat(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let mut ctx = HeartbeatContext::load(program_id, accounts)?;
+
+        ctx.recovery.last_active = ctx.clock.unix_timestamp;
+
+        Ok(())
+    }
+
+    pub fn process_claim_recovery(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let mut ctx = ClaimRecoveryContext::load(program_id, accounts)?;
+
+        ctx.recovery.check_claimable(ctx.clock.unix_timestamp)?;
+        msg!(
+            "Authority :: {} -> {}",
+            ctx.treasury.authority(),
+            ctx.recovery.backup
+        );
+        ctx.treasury.set_authority(ctx.recovery.backup);
+        // the guardians stay, but not what they approved under the previous authority
+        if let Some(social) = &mut ctx.social {
+            social.reset();
+        }
+
+        // close the recovery, a new one has to be set up by the new authority
+        drop(ctx.recovery);
+        Self::close_account(ctx.recovery_info, ctx.backup_info)
+    }
+
+    pub fn process_set_guardians(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        guardians: &[Pubkey],
+        threshold: u8,
+        challenge_delay: u64,
+    ) -> ProgramResult {
+        let mut ctx = SetGuardiansContext::load(program_id, accounts)?;
+
+        let social = SocialRecovery::new(
+            *ctx.treasury_info.key,
+            guardians,
+            threshold,
+            challenge_delay,
+        )?;
+        if let Some(existing) = &mut ctx.social {
+            **existing = social;
+        } else {
+            Self::create_program_account(
+                program_id,
+                ctx.funder_info,
+                ctx.social_info,
+                ctx.system_program_info,
+                &ctx.rent,
+                SocialRecovery::LEN,
+                &[
+                    b"social recovery",
+                    &ctx.treasury_info.key.to_bytes(),
+                    &[ctx.social_seed],
+                ],
+            )?;
+            social.write(ctx.social_info);
+        }
+
+        Ok(())
+    }
+
+    pub fn process_approve_rotation(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        new_authority: Pubkey,
+    ) -> ProgramResult {
+        let mut ctx = ApproveRotationContext::load(program_id, accounts)?;
+
+        ctx.social
+            .approve(ctx.guardian_index, new_authority, ctx.clock.unix_timestamp)?;
+
+        Ok(())
+    }
+
+    pub fn process_cancel_rotation(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let mut ctx = CancelRotationContext::load(program_id, accounts)?;
+
+        ctx.social.cancel()?;
+
+        Ok(())
+    }
+
+    pub fn process_execute_rotation(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let mut ctx = ExecuteRotationContext::load(program_id, accounts)?;
+
+        let new_authority = ctx.social.executable(ctx.clock.unix_timestamp)?;
+        msg!(
+            "Authority :: {} -> {}",
+            ctx.treasury.authority(),
+            new_authority
+        );
+        ctx.treasury.set_authority(new_authority);
+        ctx.social.cancel()?;
+
+        // the backup was chosen by the previous authority
+        if let Some(recovery) = ctx.recovery {
+            drop(recovery);
+            Self::close_account(ctx.recovery_info, ctx.new_authority_info)?;
+        }
+        Ok(())
+    }
+
+    pub fn process_migrate_treasury(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let ctx = MigrateTreasuryContext::load(program_id, accounts)?;
+
+        let top_up = ctx
+            .rent
+            .minimum_balance(ctx.len)
+            .saturating_sub(ctx.treasury_info.lamports());
+        if top_up > 0 {
+            invoke(
+                &system_instruction::transfer(ctx.funder_info.key, ctx.treasury_info.key, top_up),
+                &[
+                    ctx.funder_info.clone(),
+                    ctx.treasury_info.clone(),
+                    ctx.system_program_info.clone(),
+                ],
+            )?;
+        }
+
+        // the fields added since the earlier layout are zero
+        ctx.treasury_info.realloc(ctx.len, true)
+    }
+
+    /// Record that the authority of a treasury with a recovery is active
+    fn record_activity(activity: &mut Activity, now: UnixTimestamp) {
+        if let Some(recovery) = &mut activity.recovery {
+            recovery.last_active = now;
+        }
+    }
+
+    /// Close a program account, its SOL goes to `recipient_info`
+    fn close_account(account_info: &AccountInfo, recipient_info: &AccountInfo) -> ProgramResult {
+        let lamports = recipient_info
+            .lamports()
+            .checked_add(account_info.lamports())
+            .ok_or(ProgramError::InvalidArgument)?;
+        **recipient_info.lamports.borrow_mut() = lamports;
+        **account_info.lamports.borrow_mut() = 0;
+        account_info.data.borrow_mut().fill(0);
+        Ok(())
+    }
+
+    /// Charge the funder of a new treasury the creation fee
+    fn charge_creation_fee<'a>(
+        funder_info: &AccountInfo<'a>,
+        config_info: &AccountInfo<'a>,
+        system_program_info: &AccountInfo<'a>,
+        fee: u64,
+    ) -> ProgramResult {
+        if fee == 0 {
+            return Ok(());
+        }
+        invoke(
+            &system_instruction::transfer(funder_info.key, config_info.key, fee),
+            &[
+                funder_info.clone(),
+                config_info.clone(),
+                system_program_info.clone(),
+            ],
+        )
+    }
+
+    /// Transfer `amount` out of a treasury's fund. The withdrawal fee is taken out of it
+    /// and goes to the fee vault, the recipient gets the rest. `SplitVested` and
+    /// `MergeVested` don't use it: they move tokens into the fund of another treasury,
+    /// which pays the fee once they are withdrawn from there.
+    #[allow(clippy::too_many_arguments)]
+    fn pay_out<'a>(
+        fund_info: &AccountInfo<'a>,
+        recipient_info: &AccountInfo<'a>,
+        fund_authority_info: &AccountInfo<'a>,
+        token_program_info: &AccountInfo<'a>,
+        fee: &WithdrawalFee<'_, 'a>,
+        amount: u64,
+        seeds: &[&[u8]],
+    ) -> ProgramResult {
+        let fee_amount = fee.on(amount);
+        for (destination_info, amount) in [
+            (fee.vault_info, fee_amount),
+            (recipient_info, amount - fee_amount),
+        ] {
+            if amount == 0 {
+                continue;
+            }
             invoke_signed(
                 &spl_token::instruction::transfer(
                     &spl_token::id(),
                     fund_info.key,
-                    recipient_info.key,
+                    destination_info.key,
                     fund_authority_info.key,
                     &[],
-                    payable,
+                    amount,
                 )?,
                 &[
                     fund_authority_info.clone(),
                     fund_info.clone(),
-                    recipient_info.clone(),
-                    treasury_info.clone(),
+                    destination_info.clone(),
                     token_program_info.clone(),
                 ],
-                &[&[
-                    b"vested authority",
-                    &treasury_info.key.to_bytes(),
-                    &[fund_authority_seed],
-                ]],
-            )
-        } else {
-            Ok(())
+                &[seeds],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Create a program owned account at a program address. Anyone can send lamports
+    /// to the address beforehand, so an account that already has some is topped up,
+    /// allocated and assigned instead.
+    fn create_program_account<'a>(
+        program_id: &Pubkey,
+        payer_info: &AccountInfo<'a>,
+        account_info: &AccountInfo<'a>,
+        system_program_info: &AccountInfo<'a>,
+        rent: &Rent,
+        space: usize,
+        seeds: &[&[u8]],
+    ) -> ProgramResult {
+        let lamports = rent.minimum_balance(space);
+        let accounts = [
+            payer_info.clone(),
+            account_info.clone(),
+            system_program_info.clone(),
+        ];
+        if account_info.lamports() == 0 {
+            return invoke_signed(
+                &system_instruction::create_account(
+                    payer_info.key,
+                    account_info.key,
+                    lamports,
+                    space as u64,
+                    program_id,
+                ),
+                &accounts,
+                &[seeds],
+            );
+        }
+
+        let top_up = lamports.saturating_sub(account_info.lamports());
+        if top_up > 0 {
+            invoke(
+                &system_instruction::transfer(payer_info.key, account_info.key, top_up),
+                &accounts,
+            )?;
         }
+        invoke_signed(
+            &system_instruction::allocate(account_info.key, space as u64),
+            &accounts,
+            &[seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(account_info.key, program_id),
+            &accounts,
+            &[seeds],
+        )
     }
 }
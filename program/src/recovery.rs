@@ -0,0 +1,403 @@
+//! Inactivity recovery for simple and vested treasuries
+//!
+//! The authority of a simple or vested treasury can name a backup key and an inactivity
+//! period. The recovery record at `Recovery::address` keeps track of when the authority
+//! was last active: `SetRecovery`, `Heartbeat` and withdrawals signed by the authority
+//! all count. Once the authority has been inactive for the whole period, the backup can
+//! take over the treasury with `ClaimRecovery`, which makes it the authority and closes
+//! the record.
+//!
+//! Separately, the authority can name guardians and a threshold in the social recovery
+//! record at `SocialRecovery::address`. Every guardian approves one new authority at a
+//! time. Once enough guardians approved the same one, the rotation is initiated and can
+//! be executed after the challenge delay. Until then the authority can cancel it.
+//!
+//! Whichever way the authority changes, what the previous authority set up for the
+//! other way is dropped: a claim drops the guardians' approvals, a rotation closes the
+//! recovery record.
+
+use std::cell::RefMut;
+
+use bytemuck::{CheckedBitPattern, NoUninit};
+use solana_program::{
+    account_info::AccountInfo, clock::UnixTimestamp, program_error::ProgramError, pubkey::Pubkey,
+};
+
+use crate::{
+    account::{SimpleTreasury, VestedTreasury},
+    error::TreasuryError,
+    zero_copy::{impl_borsh_as_bytes, ZeroCopy},
+};
+
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, CheckedBitPattern, NoUninit)]
+pub struct Recovery {
+    pub treasury: Pubkey,
+    /// The key that can claim the treasury once the authority is inactive
+    pub backup: Pubkey,
+    /// How long the authority has to be inactive, in seconds
+    pub inactivity_period: u64,
+    pub last_active: UnixTimestamp,
+}
+
+impl ZeroCopy for Recovery {}
+impl_borsh_as_bytes!(Recovery);
+
+impl Recovery {
+    /// The size of a serialized recovery record
+    pub const LEN: usize = 80;
+
+    pub fn new(
+        treasury: Pubkey,
+        backup: Pubkey,
+        inactivity_period: u64,
+        now: UnixTimestamp,
+    ) -> Result<Self, TreasuryError> {
+        if inactivity_period == 0 || inactivity_period > i64::MAX as u64 {
+            return Err(TreasuryError::InvalidInactivityPeriod);
+        }
+        Ok(Recovery {
+            treasury,
+            backup,
+            inactivity_period,
+            last_active: now,
+        })
+    }
+
+    pub fn address(treasury: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"recovery", &treasury.to_bytes()], program_id)
+    }
+
+    /// Borrow the recovery record of a treasury, `None` if none was set up
+    pub fn load_mut<'a>(
+        recovery_info: &'a AccountInfo,
+        treasury: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Result<Option<RefMut<'a, Self>>, ProgramError> {
+        if *recovery_info.key != Self::address(treasury, program_id).0 {
+            return Err(TreasuryError::InvalidRecovery.into());
+        }
+        // closed records are removed at the end of the transaction
+        if recovery_info.data_is_empty() || recovery_info.lamports() == 0 {
+            return Ok(None);
+        }
+        if *recovery_info.owner != *program_id {
+            return Err(TreasuryError::InvalidRecovery.into());
+        }
+        Self::borrow_mut(recovery_info)
+            .map(Some)
+            .ok_or_else(|| TreasuryError::InvalidRecovery.into())
+    }
+
+    /// When the backup can claim the treasury
+    pub fn claimable_at(&self) -> UnixTimestamp {
+        self.last_active
+            .saturating_add(self.inactivity_period as UnixTimestamp)
+    }
+
+    pub fn check_claimable(&self, now: UnixTimestamp) -> Result<(), TreasuryError> {
+        if now < self.claimable_at() {
+            return Err(TreasuryError::AuthorityStillActive);
+        }
+        Ok(())
+    }
+}
+
+/// Guardians that can jointly rotate the authority of a treasury
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, CheckedBitPattern, NoUninit)]
+pub struct SocialRecovery {
+    pub treasury: Pubkey,
+    /// Unused slots hold the default key
+    pub guardians: [Pubkey; SocialRecovery::MAX_GUARDIANS],
+    /// How many guardians have to approve a rotation
+    pub threshold: u8,
+    /// How long the authority has to cancel an initiated rotation, in seconds
+    pub challenge_delay: u64,
+    /// The authority guardian `n` approved, the default key if none
+    pub approvals: [Pubkey; SocialRecovery::MAX_GUARDIANS],
+    /// The authority the guardians are rotating to, the default key until enough
+    /// guardians approved it
+    pub new_authority: Pubkey,
+    /// When the rotation can be executed, 0 until enough guardians approved it
+    pub effective_at: UnixTimestamp,
+}
+
+impl ZeroCopy for SocialRecovery {}
+impl_borsh_as_bytes!(SocialRecovery);
+
+impl SocialRecovery {
+    pub const MAX_GUARDIANS: usize = 8;
+
+    /// The size of a serialized social recovery record
+    pub const LEN: usize = 593;
+
+    pub fn new(
+        treasury: Pubkey,
+        guardians: &[Pubkey],
+        threshold: u8,
+        challenge_delay: u64,
+    ) -> Result<Self, TreasuryError> {
+        if guardians.is_empty()
+            || guardians.len() > Self::MAX_GUARDIANS
+            || threshold == 0
+            || threshold as usize > guardians.len()
+            || guardians.contains(&Pubkey::default())
+            || (1..guardians.len()).any(|i| guardians[..i].contains(&guardians[i]))
+        {
+            return Err(TreasuryError::InvalidGuardians);
+        }
+        if challenge_delay == 0 || challenge_delay > i64::MAX as u64 {
+            return Err(TreasuryError::InvalidChallengeDelay);
+        }
+
+        let mut social = SocialRecovery {
+            treasury,
+            guardians: [Pubkey::default(); Self::MAX_GUARDIANS],
+            threshold,
+            challenge_delay,
+            approvals: [Pubkey::default(); Self::MAX_GUARDIANS],
+            new_authority: Pubkey::default(),
+            effective_at: 0,
+        };
+        social.guardians[..guardians.len()].copy_from_slice(guardians);
+        Ok(social)
+    }
+
+    pub fn address(treasury: &Pubkey, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[b"social recovery", &treasury.to_bytes()], program_id)
+    }
+
+    /// Borrow the social recovery record of a treasury, `None` if none was set up
+    pub fn load_mut<'a>(
+        social_info: &'a AccountInfo,
+        treasury: &Pubkey,
+        program_id: &Pubkey,
+    ) -> Result<Option<RefMut<'a, Self>>, ProgramError> {
+        if *social_info.key != Self::address(treasury, program_id).0 {
+            return Err(TreasuryError::InvalidSocialRecovery.into());
+        }
+        if social_info.data_is_empty() || social_info.lamports() == 0 {
+            return Ok(None);
+        }
+        if *social_info.owner != *program_id {
+            return Err(TreasuryError::InvalidSocialRecovery.into());
+        }
+        Self::borrow_mut(social_info)
+            .map(Some)
+            .ok_or_else(|| TreasuryError::InvalidSocialRecovery.into())
+    }
+
+    /// The slot of a guardian
+    pub fn guardian_index(&self, key: &Pubkey) -> Option<usize> {
+        if *key == Pubkey::default() {
+            return None;
+        }
+        self.guardians.iter().position(|guardian| guardian == key)
+    }
+
+    /// How many guardians approved rotating to `new_authority`
+    pub fn approvals_of(&self, new_authority: &Pubkey) -> usize {
+        self.approvals
+            .iter()
+            .filter(|approval| *approval == new_authority)
+            .count()
+    }
+
+    /// A guardian approves rotating to `new_authority`, replacing their earlier approval.
+    /// The approvals of the other guardians stay. The rotation is initiated once enough
+    /// guardians approved the same authority.
+    pub fn approve(
+        &mut self,
+        index: usize,
+        new_authority: Pubkey,
+        now: UnixTimestamp,
+    ) -> Result<(), TreasuryError> {
+        if self.effective_at != 0 {
+            return Err(TreasuryError::RotationPending);
+        }
+        if new_authority == Pubkey::default() {
+            return Err(TreasuryError::InvalidRotation);
+        }
+        self.approvals[index] = new_authority;
+        if self.approvals_of(&new_authority) >= self.threshold as usize {
+            self.new_authority = new_authority;
+            self.effective_at = now.saturating_add(self.challenge_delay as UnixTimestamp);
+        }
+        Ok(())
+    }
+
+    /// Drop the rotation and all approvals
+    pub fn cancel(&mut self) -> Result<(), TreasuryError> {
+        if self.new_authority == Pubkey::default()
+            && self
+                .approvals
+                .iter()
+                .all(|approval| *approval == Pubkey::default())
+        {
+            return Err(TreasuryError::NoRotation);
+        }
+        self.reset();
+        Ok(())
+    }
+
+    /// Drop the rotation and all approvals, if there are any
+    pub fn reset(&mut self) {
+        self.approvals = [Pubkey::default(); Self::MAX_GUARDIANS];
+        self.new_authority = Pubkey::default();
+        self.effective_at = 0;
+    }
+
+    /// The new authority once the rotation can be executed
+    pub fn executable(&self, now: UnixTimestamp) -> Result<Pubkey, TreasuryError> {
+        if self.effective_at == 0 {
+            return Err(TreasuryError::NoRotation);
+        }
+        if now < self.effective_at {
+            return Err(TreasuryError::ChallengePeriodActive);
+        }
+        Ok(self.new_authority)
+    }
+}
+
+/// A treasury that can have a recovery record
+#[derive(Debug)]
+pub enum RecoverableTreasury<'a> {
+    Simple(RefMut<'a, SimpleTreasury>),
+    Vested(RefMut<'a, VestedTreasury>),
+}
+
+impl<'a> RecoverableTreasury<'a> {
+    /// Borrow a simple or vested treasury, telling them apart by size
+    pub fn load_mut(
+        treasury_info: &'a AccountInfo,
+        program_id: &Pubkey,
+    ) -> Result<Self, ProgramError> {
+        match treasury_info.data_len() {
+            SimpleTreasury::LEN => Ok(Self::Simple(SimpleTreasury::load_mut(
+                treasury_info,
+                program_id,
+            )?)),
+            VestedTreasury::LEN => Ok(Self::Vested(VestedTreasury::load_mut(
+                treasury_info,
+                program_id,
+            )?)),
+            _ => Err(TreasuryError::InvalidTreasuryFundAccount.into()),
+        }
+    }
+
+    pub fn authority(&self) -> Pubkey {
+        match self {
+            Self::Simple(simple) => simple.authority,
+            Self::Vested(vested) => vested.authority,
+        }
+    }
+
+    /// Hand the treasury to a new authority. A vested treasury's delegate was chosen
+    /// by the previous authority and is removed.
+    pub fn set_authority(&mut self, authority: Pubkey) {
+        match self {
+            Self::Simple(simple) => simple.authority = authority,
+            Self::Vested(vested) => {
+                vested.authority = authority;
+                vested.delegate = Pubkey::default();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::BorshSerialize;
+
+    #[test]
+    pub fn test_recovery() {
+        let recovery =
+            Recovery::new(Pubkey::new_unique(), Pubkey::new_unique(), 100, 1_000).unwrap();
+        assert_eq!(recovery.try_to_vec().unwrap().len(), Recovery::LEN);
+
+        assert_eq!(recovery.claimable_at(), 1_100);
+        assert_eq!(
+            recovery.check_claimable(1_099),
+            Err(TreasuryError::AuthorityStillActive)
+        );
+        assert_eq!(recovery.check_claimable(1_100), Ok(()));
+
+        assert_eq!(
+            Recovery::new(recovery.treasury, recovery.backup, 0, 1_000),
+            Err(TreasuryError::InvalidInactivityPeriod)
+        );
+        assert_eq!(
+            Recovery::new(recovery.treasury, recovery.backup, u64::MAX, 1_000),
+            Err(TreasuryError::InvalidInactivityPeriod)
+        );
+    }
+
+    #[test]
+    pub fn test_social_recovery() {
+        let guardians = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let mut social = SocialRecovery::new(Pubkey::new_unique(), &guardians, 2, 100).unwrap();
+        assert_eq!(social.try_to_vec().unwrap().len(), SocialRecovery::LEN);
+        assert_eq!(social.guardian_index(&guardians[2]), Some(2));
+        assert_eq!(social.guardian_index(&Pubkey::default()), None);
+
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        assert_eq!(social.executable(1_000), Err(TreasuryError::NoRotation));
+        social.approve(0, first, 1_000).unwrap();
+        assert_eq!({ social.effective_at }, 0);
+        // approving a different authority doesn't drop the other guardians' approvals
+        social.approve(1, second, 1_000).unwrap();
+        assert_eq!(social.approvals_of(&first), 1);
+        assert_eq!(social.approvals_of(&second), 1);
+        assert_eq!({ social.effective_at }, 0);
+        social.approve(1, second, 1_000).unwrap();
+        assert_eq!({ social.effective_at }, 0);
+        // a guardian can change their approval
+        social.approve(0, second, 1_000).unwrap();
+        assert_eq!(social.approvals_of(&first), 0);
+        assert_eq!({ social.new_authority }, second);
+        assert_eq!({ social.effective_at }, 1_100);
+        assert_eq!(
+            social.approve(0, first, 1_000),
+            Err(TreasuryError::RotationPending)
+        );
+
+        assert_eq!(
+            social.executable(1_099),
+            Err(TreasuryError::ChallengePeriodActive)
+        );
+        assert_eq!(social.executable(1_100), Ok(second));
+
+        social.cancel().unwrap();
+        assert_eq!(social.executable(1_100), Err(TreasuryError::NoRotation));
+        assert_eq!(social.cancel(), Err(TreasuryError::NoRotation));
+    }
+
+    #[test]
+    pub fn test_invalid_guardians() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let treasury = Pubkey::new_unique();
+        for (guardians, threshold) in [
+            (vec![], 1),
+            (vec![a, b], 0),
+            (vec![a, b], 3),
+            (vec![a, a], 1),
+            (vec![a, Pubkey::default()], 1),
+            (vec![a; SocialRecovery::MAX_GUARDIANS + 1], 1),
+        ] {
+            assert_eq!(
+                SocialRecovery::new(treasury, &guardians, threshold, 100),
+                Err(TreasuryError::InvalidGuardians)
+            );
+        }
+        assert_eq!(
+            SocialRecovery::new(treasury, &[a, b], 1, 0),
+            Err(TreasuryError::InvalidChallengeDelay)
+        );
+    }
+}
@@ -0,0 +1,193 @@
+//! Zero-copy account state
+//!
+//! Program accounts are `#[repr(C, packed)]` structs that instructions read and modify
+//! in place in the account data, instead of deserializing the whole account and
+//! serializing it back. Packed structs have an alignment of 1, so any account data can
+//! be viewed as one without copying it first. Casting validates the fields that don't
+//! accept every bit pattern, such as enums and `bool`, so a corrupt account is rejected
+//! the same way a failed deserialization was.
+//!
+//! The layout of every struct is the borsh encoding of its fields. Accounts created
+//! before, the JS decoders and `try_from_slice` read them unchanged.
+
+use std::{
+    cell::{Ref, RefMut},
+    mem::size_of,
+};
+
+use bytemuck::{CheckedBitPattern, NoUninit, Pod, Zeroable};
+use solana_program::account_info::AccountInfo;
+
+/// A fixed-layout account read and modified in place
+pub trait ZeroCopy: CheckedBitPattern + NoUninit {
+    /// View bytes as `Self`. Fails unless the length matches and every field holds a
+    /// valid value.
+    fn from_bytes(data: &[u8]) -> Option<&Self> {
+        bytemuck::checked::try_from_bytes(data).ok()
+    }
+
+    fn from_bytes_mut(data: &mut [u8]) -> Option<&mut Self> {
+        bytemuck::checked::try_from_bytes_mut(data).ok()
+    }
+
+    /// Borrow the data of an account as `Self`
+    fn borrow<'a>(info: &'a AccountInfo) -> Option<Ref<'a, Self>> {
+        let data = info.try_borrow_data().ok()?;
+        Ref::filter_map(data, |data| Self::from_bytes(data)).ok()
+    }
+
+    /// Borrow the data of an account as `Self`, changes are written to the account
+    fn borrow_mut<'a>(info: &'a AccountInfo) -> Option<RefMut<'a, Self>> {
+        let data = info.try_borrow_mut_data().ok()?;
+        RefMut::filter_map(data, |data| Self::from_bytes_mut(data)).ok()
+    }
+
+    /// Overwrite the data of an account, used when it's created
+    fn write(&self, info: &AccountInfo) {
+        info.data
+            .borrow_mut()
+            .copy_from_slice(bytemuck::bytes_of(self));
+    }
+}
+
+/// A `bool` that can be part of a `Pod` struct, any value other than 0 is true
+#[repr(transparent)]
+#[derive(Clone, Copy, Default, PartialEq, Eq, Pod, Zeroable)]
+pub struct PodBool(u8);
+
+impl PodBool {
+    pub fn get(self) -> bool {
+        self.0 != 0
+    }
+}
+
+impl From<bool> for PodBool {
+    fn from(value: bool) -> Self {
+        PodBool(value as u8)
+    }
+}
+
+impl std::fmt::Debug for PodBool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.get().fmt(f)
+    }
+}
+
+/// Borsh (de)serializes a `ZeroCopy` type as its raw bytes, for clients that read
+/// accounts with `try_from_slice`
+macro_rules! impl_borsh_as_bytes {
+    ($($ty:ty),* $(,)?) => {$(
+        impl borsh::BorshSerialize for $ty {
+            fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                writer.write_all(bytemuck::bytes_of(self))
+            }
+        }
+
+        impl borsh::BorshDeserialize for $ty {
+            fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+                let (value, rest) = $crate::zero_copy::read_unaligned(buf)?;
+                *buf = rest;
+                Ok(value)
+            }
+        }
+    )*};
+}
+pub(crate) use impl_borsh_as_bytes;
+
+/// Copy a `ZeroCopy` value out of the start of `data`, returning it and the rest of the
+/// data
+pub fn read_unaligned<T: ZeroCopy>(data: &[u8]) -> std::io::Result<(T, &[u8])> {
+    if data.len() < size_of::<T>() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Unexpected length of input",
+        ));
+    }
+    let (value, rest) = data.split_at(size_of::<T>());
+    let value = bytemuck::checked::try_pod_read_unaligned(value).map_err(|_| {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid account data")
+    })?;
+    Ok((value, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use borsh::{BorshDeserialize, BorshSerialize};
+    use solana_program::pubkey::Pubkey;
+
+    #[repr(u8)]
+    #[derive(Clone, Copy, Debug, PartialEq, CheckedBitPattern, NoUninit)]
+    enum Kind {
+        A,
+        B,
+    }
+
+    #[repr(C, packed)]
+    #[derive(Clone, Copy, Debug, PartialEq, CheckedBitPattern, NoUninit)]
+    struct Example {
+        key: Pubkey,
+        kind: Kind,
+        amount: u64,
+        flag: PodBool,
+    }
+    impl ZeroCopy for Example {}
+    impl_borsh_as_bytes!(Example);
+
+    #[test]
+    pub fn test_zero_copy() {
+        let example = Example {
+            key: Pubkey::new_unique(),
+            kind: Kind::B,
+            amount: 7,
+            flag: true.into(),
+        };
+        let mut data = example.try_to_vec().unwrap();
+        assert_eq!(data.len(), 42);
+        assert_eq!(data[32], 1);
+        assert_eq!(data[33..41], 7u64.to_le_bytes());
+        assert_eq!(Example::try_from_slice(&data).unwrap(), example);
+
+        // modified in place, at any alignment
+        let mut unaligned = vec![0; 1];
+        unaligned.extend_from_slice(&data);
+        Example::from_bytes_mut(&mut unaligned[1..]).unwrap().amount += 1;
+        assert_eq!(unaligned[34..42], 8u64.to_le_bytes());
+
+        data[32] = 0;
+        assert_eq!(Example::from_bytes(&data).unwrap().kind, Kind::A);
+
+        // invalid enum values and lengths are rejected
+        data[32] = 2;
+        assert!(Example::from_bytes(&data).is_none());
+        assert!(Example::try_from_slice(&data).is_err());
+        assert!(Example::from_bytes(&data[1..]).is_none());
+        assert!(Example::try_from_slice(&data[1..]).is_err());
+    }
+
+    #[test]
+    pub fn test_borrow() {
+        let (key, owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut lamports = 0;
+        let mut data = vec![0; 42];
+        let info = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+
+        Example::borrow_mut(&info).unwrap().amount = 5;
+        assert_eq!({ Example::borrow(&info).unwrap().amount }, 5);
+
+        // the data can't be borrowed twice mutably
+        let example = Example::borrow_mut(&info).unwrap();
+        assert!(Example::borrow(&info).is_none());
+        drop(example);
+        assert!(Example::borrow(&info).is_some());
+    }
+}
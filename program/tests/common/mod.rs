@@ -0,0 +1,515 @@
+#![allow(dead_code)]
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    bpf_loader_upgradeable,
+    clock::{Clock, UnixTimestamp},
+    ed25519_program,
+    instruction::{Instruction, InstructionError},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account,
+};
+use treasury::{
+    account::{
+        EscrowTreasury, HurdleTreasury, MilestoneTreasury, SimpleTreasury, SimpleTreasuryMode,
+        SplitterTreasury, VestedTreasury,
+    },
+    config::Config,
+    error::TreasuryError,
+    instruction,
+    oracle::PriceFeed,
+    processor::Processor,
+};
+
+pub struct TestContext {
+    pub context: ProgramTestContext,
+    pub program_id: Pubkey,
+    pub mint: Keypair,
+    pub mint_authority: Keypair,
+}
+
+impl TestContext {
+    pub async fn new() -> TestContext {
+        let program_id = Pubkey::new_unique();
+        let program_test = ProgramTest::new("treasury", program_id, processor!(Processor::process));
+        let context = program_test.start_with_context().await;
+
+        let mut test = TestContext {
+            context,
+            program_id,
+            mint: Keypair::new(),
+            mint_authority: Keypair::new(),
+        };
+        let mint = test.mint.insecure_clone();
+        test.create_mint(&mint).await;
+        test
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    /// Process a transaction paid for by the context's payer
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), BanksClientError> {
+        let transaction = self.transaction(instructions, signers).await?;
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+    }
+
+    /// Process a transaction that has to succeed and return the compute units it
+    /// consumed. The program only consumes compute units when it runs as SBF, under
+    /// `cargo test-sbf`.
+    pub async fn compute_units(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> u64 {
+        let transaction = self.transaction(instructions, signers).await.unwrap();
+        let result = self
+            .context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await
+            .unwrap();
+        result.result.unwrap();
+        result.metadata.unwrap().compute_units_consumed
+    }
+
+    async fn transaction(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<Transaction, BanksClientError> {
+        let blockhash = self.context.get_new_latest_blockhash().await?;
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+
+        Ok(Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        ))
+    }
+
+    pub async fn create_mint(&mut self, mint: &Keypair) {
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let instructions = [
+            system_instruction::create_account(
+                &self.payer(),
+                &mint.pubkey(),
+                rent.minimum_balance(spl_token::state::Mint::LEN),
+                spl_token::state::Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &self.mint_authority.pubkey(),
+                None,
+                0,
+            )
+            .unwrap(),
+        ];
+        self.process(&instructions, &[mint]).await.unwrap();
+    }
+
+    /// Create the associated token account of `owner` for a mint
+    pub async fn create_associated(&mut self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        let instruction =
+            create_associated_token_account(&self.payer(), owner, mint, &spl_token::id());
+        self.process(&[instruction], &[]).await.unwrap();
+        get_associated_token_address(owner, mint)
+    }
+
+    pub async fn mint_to(&mut self, account: &Pubkey, amount: u64) {
+        let instruction = spl_token::instruction::mint_to(
+            &spl_token::id(),
+            &self.mint.pubkey(),
+            account,
+            &self.mint_authority.pubkey(),
+            &[],
+            amount,
+        )
+        .unwrap();
+        let mint_authority = self.mint_authority.insecure_clone();
+        self.process(&[instruction], &[&mint_authority])
+            .await
+            .unwrap();
+    }
+
+    pub async fn token_balance(&mut self, account: &Pubkey) -> u64 {
+        let account = self
+            .context
+            .banks_client
+            .get_account(*account)
+            .await
+            .unwrap()
+            .unwrap();
+        spl_token::state::Account::unpack(&account.data)
+            .unwrap()
+            .amount
+    }
+
+    pub async fn now(&mut self) -> UnixTimestamp {
+        self.context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+            .unix_timestamp
+    }
+
+    /// Move the clock forward by a number of seconds
+    pub async fn warp(&mut self, seconds: i64) {
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp += seconds;
+        self.context.set_sysvar(&clock);
+    }
+
+    /// Create a simple treasury with its fund account
+    pub async fn create_simple(
+        &mut self,
+        authority: &Keypair,
+        mode: SimpleTreasuryMode,
+    ) -> Result<Keypair, BanksClientError> {
+        let treasury = Keypair::new();
+        let (fund_authority, _) =
+            SimpleTreasury::fund_authority_address(&treasury.pubkey(), &self.program_id);
+        let mint = self.mint.pubkey();
+        self.create_associated(&fund_authority, &mint).await;
+
+        let instruction = instruction::create_simple_treasury(
+            &self.program_id,
+            &self.payer(),
+            &authority.pubkey(),
+            &treasury.pubkey(),
+            &mint,
+            mode,
+        );
+        self.process(&[instruction], &[authority, &treasury])
+            .await?;
+        Ok(treasury)
+    }
+
+    /// Create a vested treasury with its fund account, the payer is the grantor
+    pub async fn create_vested(
+        &mut self,
+        authority: &Pubkey,
+        amount: u64,
+        period: u64,
+        percentage: u16,
+    ) -> Result<Keypair, BanksClientError> {
+        let treasury = Keypair::new();
+        let (fund_authority, _) =
+            VestedTreasury::fund_authority_address(&treasury.pubkey(), &self.program_id);
+        let mint = self.mint.pubkey();
+        self.create_associated(&fund_authority, &mint).await;
+
+        let instruction = instruction::create_vested_treasury(
+            &self.program_id,
+            &self.payer(),
+            authority,
+            &self.payer(),
+            &treasury.pubkey(),
+            &mint,
+            amount,
+            period,
+            percentage,
+        );
+        self.process(&[instruction], &[&treasury]).await?;
+        Ok(treasury)
+    }
+
+    /// Create a splitter treasury with its fund account
+    pub async fn create_splitter(
+        &mut self,
+        shares: Vec<(Pubkey, u16)>,
+    ) -> Result<Keypair, BanksClientError> {
+        let treasury = Keypair::new();
+        let (fund_authority, _) =
+            SplitterTreasury::fund_authority_address(&treasury.pubkey(), &self.program_id);
+        let mint = self.mint.pubkey();
+        self.create_associated(&fund_authority, &mint).await;
+
+        let instruction = instruction::create_splitter_treasury(
+            &self.program_id,
+            &self.payer(),
+            &treasury.pubkey(),
+            &mint,
+            shares,
+        );
+        self.process(&[instruction], &[&treasury]).await?;
+        Ok(treasury)
+    }
+
+    /// Create an escrow treasury with its fund account that expires `duration` seconds
+    /// from now
+    pub async fn create_escrow(
+        &mut self,
+        depositor: &Pubkey,
+        payee: &Pubkey,
+        arbiter: &Pubkey,
+        duration: i64,
+    ) -> Result<Keypair, BanksClientError> {
+        let treasury = Keypair::new();
+        let (fund_authority, _) =
+            EscrowTreasury::fund_authority_address(&treasury.pubkey(), &self.program_id);
+        let mint = self.mint.pubkey();
+        self.create_associated(&fund_authority, &mint).await;
+
+        let expiry = self.now().await + duration;
+        let instruction = instruction::create_escrow_treasury(
+            &self.program_id,
+            &self.payer(),
+            &treasury.pubkey(),
+            &mint,
+            depositor,
+            payee,
+            arbiter,
+            expiry,
+        );
+        self.process(&[instruction], &[&treasury]).await?;
+        Ok(treasury)
+    }
+
+    /// Create a milestone treasury with its fund account
+    pub async fn create_milestone(
+        &mut self,
+        authority: &Pubkey,
+        milestones: Vec<(u64, Pubkey)>,
+    ) -> Result<Keypair, BanksClientError> {
+        let treasury = Keypair::new();
+        let (fund_authority, _) =
+            MilestoneTreasury::fund_authority_address(&treasury.pubkey(), &self.program_id);
+        let mint = self.mint.pubkey();
+        self.create_associated(&fund_authority, &mint).await;
+
+        let instruction = instruction::create_milestone_treasury(
+            &self.program_id,
+            &self.payer(),
+            authority,
+            &treasury.pubkey(),
+            &mint,
+            milestones,
+        );
+        self.process(&[instruction], &[&treasury]).await?;
+        Ok(treasury)
+    }
+
+    /// Create a hurdle treasury with its fund account
+    pub async fn create_hurdle(
+        &mut self,
+        authority: &Pubkey,
+        oracle: &Pubkey,
+        duration: u64,
+        tranches: Vec<(u64, u64)>,
+    ) -> Result<Keypair, BanksClientError> {
+        let treasury = Keypair::new();
+        let (fund_authority, _) =
+            HurdleTreasury::fund_authority_address(&treasury.pubkey(), &self.program_id);
+        let mint = self.mint.pubkey();
+        self.create_associated(&fund_authority, &mint).await;
+
+        let instruction = instruction::create_hurdle_treasury(
+            &self.program_id,
+            &self.payer(),
+            authority,
+            &treasury.pubkey(),
+            &mint,
+            oracle,
+            duration,
+            tranches,
+        );
+        self.process(&[instruction], &[&treasury]).await?;
+        Ok(treasury)
+    }
+
+    /// Write a price feed published now to `oracle`
+    pub async fn set_price(&mut self, oracle: &Pubkey, price: u64) {
+        let feed = PriceFeed {
+            price,
+            publish_time: self.now().await,
+        };
+        let account = Account {
+            lamports: 1_000_000_000,
+            data: feed.try_to_vec().unwrap(),
+            owner: Pubkey::new_unique(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.context.set_account(oracle, &account.into());
+    }
+
+    /// Make `authority` the upgrade authority of the program by writing its program
+    /// data account, which programs added with `processor!` don't have
+    pub fn set_upgrade_authority(&mut self, authority: &Pubkey) {
+        let (address, _) = Pubkey::find_program_address(
+            &[self.program_id.as_ref()],
+            &bpf_loader_upgradeable::id(),
+        );
+        // `UpgradeableLoaderState::ProgramData` without any program bytes
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&authority.to_bytes());
+        let account = Account {
+            lamports: 1_000_000_000,
+            data,
+            owner: bpf_loader_upgradeable::id(),
+            executable: false,
+            rent_epoch: 0,
+        };
+        self.context.set_account(&address, &account.into());
+    }
+
+    /// Initialize the config with a new upgrade authority as the admin, and create the
+    /// fee vault of the mint
+    pub async fn initialize_config(
+        &mut self,
+        fee_receiver: &Pubkey,
+        creation_fee: u64,
+        withdrawal_fee: u16,
+        guardian: &Pubkey,
+    ) -> Keypair {
+        let admin = Keypair::new();
+        self.set_upgrade_authority(&admin.pubkey());
+        let instruction = instruction::initialize_config(
+            &self.program_id,
+            &self.payer(),
+            &admin.pubkey(),
+            fee_receiver,
+            creation_fee,
+            withdrawal_fee,
+            guardian,
+        );
+        self.process(&[instruction], &[&admin]).await.unwrap();
+
+        let (config, _) = Config::address(&self.program_id);
+        let mint = self.mint.pubkey();
+        self.create_associated(&config, &mint).await;
+        admin
+    }
+
+    pub async fn lamports(&mut self, key: &Pubkey) -> u64 {
+        self.context.banks_client.get_balance(*key).await.unwrap()
+    }
+
+    /// Cut an account down to its first `len` bytes with the rent for that size, the
+    /// way an earlier version of the program stored it
+    pub async fn truncate_account(&mut self, key: &Pubkey, len: usize) {
+        let mut account = self
+            .context
+            .banks_client
+            .get_account(*key)
+            .await
+            .unwrap()
+            .unwrap();
+        account.data.truncate(len);
+        account.lamports = Rent::default().minimum_balance(len);
+        self.context.set_account(key, &account.into());
+    }
+
+    pub async fn get_account_data<T: BorshDeserialize>(&mut self, key: &Pubkey) -> T {
+        let account = self
+            .context
+            .banks_client
+            .get_account(*key)
+            .await
+            .unwrap()
+            .unwrap();
+        T::try_from_slice(&account.data).unwrap()
+    }
+
+    pub fn simple_fund(&self, treasury: &Pubkey) -> Pubkey {
+        SimpleTreasury::fund_address(treasury, &self.mint.pubkey(), &self.program_id)
+    }
+
+    pub fn vested_fund(&self, treasury: &Pubkey) -> Pubkey {
+        VestedTreasury::fund_address(treasury, &self.mint.pubkey(), &self.program_id)
+    }
+
+    pub fn escrow_fund(&self, treasury: &Pubkey) -> Pubkey {
+        EscrowTreasury::fund_address(treasury, &self.mint.pubkey(), &self.program_id)
+    }
+
+    pub fn milestone_fund(&self, treasury: &Pubkey) -> Pubkey {
+        MilestoneTreasury::fund_address(treasury, &self.mint.pubkey(), &self.program_id)
+    }
+
+    pub fn hurdle_fund(&self, treasury: &Pubkey) -> Pubkey {
+        HurdleTreasury::fund_address(treasury, &self.mint.pubkey(), &self.program_id)
+    }
+
+    pub fn fee_vault(&self) -> Pubkey {
+        Config::fee_vault_address(&self.mint.pubkey(), &self.program_id)
+    }
+
+    pub fn splitter_fund(&self, treasury: &Pubkey) -> Pubkey {
+        SplitterTreasury::fund_address(treasury, &self.mint.pubkey(), &self.program_id)
+    }
+}
+
+/// An ed25519 program instruction that verifies one signature of `message`
+pub fn ed25519_instruction(signer: &Keypair, message: &[u8]) -> Instruction {
+    // the offsets, then the public key, signature and message
+    const HEADER: u16 = 16;
+    let public_key = HEADER;
+    let signature = public_key + 32;
+    let message_offset = signature + 64;
+
+    let mut data = vec![1, 0];
+    for offset in [
+        signature,
+        u16::MAX,
+        public_key,
+        u16::MAX,
+        message_offset,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(&signer.pubkey().to_bytes());
+    data.extend_from_slice(signer.sign_message(message).as_ref());
+    data.extend_from_slice(message);
+
+    Instruction::new_with_bytes(ed25519_program::id(), &data, vec![])
+}
+
+/// Assert that a transaction failed with a specific treasury error
+pub fn assert_treasury_error<T: std::fmt::Debug>(
+    result: Result<T, BanksClientError>,
+    error: TreasuryError,
+) {
+    assert_instruction_error(result, InstructionError::Custom(error as u32));
+}
+
+pub fn assert_instruction_error<T: std::fmt::Debug>(
+    result: Result<T, BanksClientError>,
+    error: InstructionError,
+) {
+    match result.unwrap_err() {
+        BanksClientError::TransactionError(TransactionError::InstructionError(_, e)) => {
+            assert_eq!(e, error)
+        }
+        e => panic!("unexpected error: {:?}", e),
+    }
+}
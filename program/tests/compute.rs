@@ -0,0 +1,205 @@
+#![cfg(feature = "test-bpf")]
+
+//! Compute units consumed by the instructions that run most often, checked against an
+//! upper bound. The numbers are only meaningful when the program runs as SBF:
+//!
+//! ```text
+//! cargo test-sbf --features test-bpf --test compute -- --nocapture
+//! ```
+//!
+//! Natively only the token program CPIs are counted, so the bounds always hold there.
+
+mod common;
+
+use common::TestContext;
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use treasury::{account::SimpleTreasuryMode, instruction};
+
+/// Instructions that only update a treasury account
+const UPDATE_BUDGET: u64 = 15_000;
+/// Instructions that also derive the fund addresses and transfer out of the fund
+const WITHDRAWAL_BUDGET: u64 = 50_000;
+
+fn report(name: &str, units: u64, budget: u64) {
+    println!("{:<24} {:>7} CU", name, units);
+    assert!(
+        units <= budget,
+        "{} consumed {} CU, more than {}",
+        name,
+        units,
+        budget
+    );
+}
+
+#[tokio::test]
+async fn test_compute_simple_and_vested() {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+
+    let simple = test
+        .create_simple(&authority, SimpleTreasuryMode::Unlocked)
+        .await
+        .unwrap()
+        .pubkey();
+    test.mint_to(&test.simple_fund(&simple), 1_000).await;
+    let withdraw = instruction::withdraw_simple(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &recipient,
+        &simple,
+        &mint,
+        100,
+    );
+    report(
+        "WithdrawSimple",
+        test.compute_units(&[withdraw], &[&authority]).await,
+        WITHDRAWAL_BUDGET,
+    );
+
+    let vested = test
+        .create_vested(&authority.pubkey(), 1_000, 100, 1_000)
+        .await
+        .unwrap()
+        .pubkey();
+    test.mint_to(&test.vested_fund(&vested), 1_000).await;
+    test.warp(100).await;
+    let withdraw = instruction::withdraw_vested(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &recipient,
+        &vested,
+        &mint,
+        None,
+    );
+    report(
+        "WithdrawVested",
+        test.compute_units(&[withdraw], &[&authority]).await,
+        WITHDRAWAL_BUDGET,
+    );
+
+    // the payer is the grantor
+    let pause = instruction::pause_vesting(&test.program_id, &test.payer(), &vested);
+    report(
+        "PauseVesting",
+        test.compute_units(&[pause], &[]).await,
+        UPDATE_BUDGET,
+    );
+    let resume = instruction::resume_vesting(&test.program_id, &test.payer(), &vested);
+    report(
+        "ResumeVesting",
+        test.compute_units(&[resume], &[]).await,
+        UPDATE_BUDGET,
+    );
+}
+
+#[tokio::test]
+async fn test_compute_splitter() {
+    let mut test = TestContext::new().await;
+    let shareholders: Vec<Keypair> = (0..10).map(|_| Keypair::new()).collect();
+    let shares = shareholders
+        .iter()
+        .map(|holder| (holder.pubkey(), 1_000))
+        .collect();
+    let treasury = test.create_splitter(shares).await.unwrap().pubkey();
+    test.mint_to(&test.splitter_fund(&treasury), 1_000).await;
+
+    // the last shareholder is furthest into the list
+    let holder = &shareholders[9];
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&holder.pubkey(), &mint).await;
+    let claim = instruction::claim_split(
+        &test.program_id,
+        &holder.pubkey(),
+        &recipient,
+        &treasury,
+        &mint,
+    );
+    report(
+        "ClaimSplit",
+        test.compute_units(&[claim], &[holder]).await,
+        WITHDRAWAL_BUDGET,
+    );
+}
+
+#[tokio::test]
+async fn test_compute_milestone_and_hurdle() {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let attestor = Keypair::new();
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+
+    let milestone = test
+        .create_milestone(
+            &authority.pubkey(),
+            (0..8).map(|_| (100, attestor.pubkey())).collect(),
+        )
+        .await
+        .unwrap()
+        .pubkey();
+    test.mint_to(&test.milestone_fund(&milestone), 800).await;
+    let confirm =
+        instruction::confirm_milestone(&test.program_id, &attestor.pubkey(), &milestone, 7);
+    report(
+        "ConfirmMilestone",
+        test.compute_units(&[confirm], &[&attestor]).await,
+        UPDATE_BUDGET,
+    );
+    let withdraw = instruction::withdraw_milestones(
+        &test.program_id,
+        &authority.pubkey(),
+        &recipient,
+        &milestone,
+        &mint,
+    );
+    report(
+        "WithdrawMilestones",
+        test.compute_units(&[withdraw], &[&authority]).await,
+        WITHDRAWAL_BUDGET,
+    );
+
+    let oracle = Pubkey::new_unique();
+    test.set_price(&oracle, 10).await;
+    let hurdle = test
+        .create_hurdle(
+            &authority.pubkey(),
+            &oracle,
+            100,
+            (1..=8).map(|price| (100, price)).collect(),
+        )
+        .await
+        .unwrap()
+        .pubkey();
+    test.mint_to(&test.hurdle_fund(&hurdle), 800).await;
+    let update = instruction::update_hurdles(&test.program_id, &hurdle, &oracle);
+    report(
+        "UpdateHurdles",
+        test.compute_units(&[update], &[]).await,
+        UPDATE_BUDGET,
+    );
+    test.warp(100).await;
+    test.set_price(&oracle, 10).await;
+    let update = instruction::update_hurdles(&test.program_id, &hurdle, &oracle);
+    report(
+        "UpdateHurdles (unlock)",
+        test.compute_units(&[update], &[]).await,
+        UPDATE_BUDGET,
+    );
+    let withdraw = instruction::withdraw_hurdles(
+        &test.program_id,
+        &authority.pubkey(),
+        &recipient,
+        &hurdle,
+        &mint,
+    );
+    report(
+        "WithdrawHurdles",
+        test.compute_units(&[withdraw], &[&authority]).await,
+        WITHDRAWAL_BUDGET,
+    );
+}
@@ -0,0 +1,356 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{assert_treasury_error, TestContext};
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+use treasury::{account::SimpleTreasuryMode, config::Config, error::TreasuryError, instruction};
+
+const CREATION_FEE: u64 = 1_000_000;
+/// 2.5%
+const WITHDRAWAL_FEE: u16 = 250;
+
+/// An unlocked simple treasury holding 1,000 and the authority's token account
+async fn setup_simple(test: &mut TestContext) -> (Keypair, Pubkey, Pubkey) {
+    let authority = Keypair::new();
+    let treasury = test
+        .create_simple(&authority, SimpleTreasuryMode::Unlocked)
+        .await
+        .unwrap()
+        .pubkey();
+    test.mint_to(&test.simple_fund(&treasury), 1_000).await;
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+    (authority, treasury, recipient)
+}
+
+#[tokio::test]
+async fn test_initialize_config() {
+    let mut test = TestContext::new().await;
+    let admin = Keypair::new();
+    let (fee_receiver, guardian) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (program_id, payer) = (test.program_id, test.payer());
+    let initialize = |admin: &Pubkey| {
+        instruction::initialize_config(
+            &program_id,
+            &payer,
+            admin,
+            &fee_receiver,
+            CREATION_FEE,
+            WITHDRAWAL_FEE,
+            &guardian,
+        )
+    };
+
+    // only the upgrade authority
+    let instruction = initialize(&admin.pubkey());
+    let result = test.process(&[instruction], &[&admin]).await;
+    assert_treasury_error(result, TreasuryError::InvalidUpgradeAuthority);
+
+    test.set_upgrade_authority(&admin.pubkey());
+    let instruction = initialize(&admin.pubkey());
+    test.process(&[instruction], &[&admin]).await.unwrap();
+
+    let (address, _) = Config::address(&program_id);
+    let config: Config = test.get_account_data(&address).await;
+    assert_eq!(
+        config,
+        Config {
+            admin: admin.pubkey(),
+            fee_receiver,
+            creation_fee: CREATION_FEE,
+            withdrawal_fee: WITHDRAWAL_FEE,
+            guardian,
+            paused: false,
+        }
+    );
+
+    // only once
+    let instruction = initialize(&admin.pubkey());
+    let result = test.process(&[instruction], &[&admin]).await;
+    assert_treasury_error(result, TreasuryError::ConfigAlreadyExists);
+}
+
+#[tokio::test]
+async fn test_update_config() {
+    let mut test = TestContext::new().await;
+    let admin = test
+        .initialize_config(
+            &Pubkey::new_unique(),
+            CREATION_FEE,
+            WITHDRAWAL_FEE,
+            &Pubkey::new_unique(),
+        )
+        .await;
+    let program_id = test.program_id;
+    let (new_admin, fee_receiver, guardian) =
+        (Keypair::new(), Pubkey::new_unique(), Pubkey::new_unique());
+
+    let attacker = Keypair::new();
+    let instruction = instruction::update_config(
+        &program_id,
+        &attacker.pubkey(),
+        &attacker.pubkey(),
+        &attacker.pubkey(),
+        0,
+        0,
+        &attacker.pubkey(),
+    );
+    let result = test.process(&[instruction], &[&attacker]).await;
+    assert_treasury_error(result, TreasuryError::InvalidAdmin);
+
+    let instruction = instruction::update_config(
+        &program_id,
+        &admin.pubkey(),
+        &new_admin.pubkey(),
+        &fee_receiver,
+        0,
+        Config::MAX_WITHDRAWAL_FEE + 1,
+        &guardian,
+    );
+    let result = test.process(&[instruction], &[&admin]).await;
+    assert_treasury_error(result, TreasuryError::InvalidFee);
+
+    let instruction = instruction::update_config(
+        &program_id,
+        &admin.pubkey(),
+        &new_admin.pubkey(),
+        &fee_receiver,
+        0,
+        Config::MAX_WITHDRAWAL_FEE,
+        &guardian,
+    );
+    test.process(&[instruction], &[&admin]).await.unwrap();
+    let config: Config = test.get_account_data(&Config::address(&program_id).0).await;
+    assert_eq!(config.admin, new_admin.pubkey());
+    assert_eq!(config.fee_receiver, fee_receiver);
+    assert_eq!({ config.creation_fee }, 0);
+    assert_eq!({ config.withdrawal_fee }, Config::MAX_WITHDRAWAL_FEE);
+    assert_eq!(config.guardian, guardian);
+}
+
+#[tokio::test]
+async fn test_fees() {
+    let mut test = TestContext::new().await;
+    let fee_receiver = Keypair::new().pubkey();
+    test.initialize_config(
+        &fee_receiver,
+        CREATION_FEE,
+        WITHDRAWAL_FEE,
+        &Pubkey::new_unique(),
+    )
+    .await;
+    let program_id = test.program_id;
+    let (config, _) = Config::address(&program_id);
+
+    let before = test.lamports(&config).await;
+    let (authority, treasury, recipient) = setup_simple(&mut test).await;
+    assert_eq!(test.lamports(&config).await, before + CREATION_FEE);
+
+    let mint = test.mint.pubkey();
+    let withdraw = instruction::withdraw_simple(
+        &program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &recipient,
+        &treasury,
+        &mint,
+        400,
+    );
+    test.process(&[withdraw], &[&authority]).await.unwrap();
+    assert_eq!(test.token_balance(&recipient).await, 390);
+    assert_eq!(test.token_balance(&test.fee_vault()).await, 10);
+
+    let fee_account = test.create_associated(&fee_receiver, &mint).await;
+    let collect = instruction::collect_fees(&program_id, &fee_receiver, &fee_account, &mint);
+    test.process(&[collect], &[]).await.unwrap();
+    assert_eq!(test.token_balance(&fee_account).await, 10);
+    assert_eq!(test.token_balance(&test.fee_vault()).await, 0);
+    assert_eq!(test.lamports(&fee_receiver).await, CREATION_FEE);
+    assert_eq!(test.lamports(&config).await, before);
+}
+
+#[tokio::test]
+async fn test_escrow_fee() {
+    let mut test = TestContext::new().await;
+    test.initialize_config(
+        &Pubkey::new_unique(),
+        0,
+        WITHDRAWAL_FEE,
+        &Pubkey::new_unique(),
+    )
+    .await;
+    let (depositor, payee, arbiter) = (Pubkey::new_unique(), Pubkey::new_unique(), Keypair::new());
+    let treasury = test
+        .create_escrow(&depositor, &payee, &arbiter.pubkey(), 1_000)
+        .await
+        .unwrap()
+        .pubkey();
+    test.mint_to(&test.escrow_fund(&treasury), 1_000).await;
+    let mint = test.mint.pubkey();
+    let payee_account = test.create_associated(&payee, &mint).await;
+
+    let release = instruction::release_escrow(
+        &test.program_id,
+        &arbiter.pubkey(),
+        &payee_account,
+        &treasury,
+        &mint,
+    );
+    test.process(&[release], &[&arbiter]).await.unwrap();
+    assert_eq!(test.token_balance(&payee_account).await, 975);
+    assert_eq!(test.token_balance(&test.fee_vault()).await, 25);
+}
+
+#[tokio::test]
+async fn test_no_config() {
+    // nothing is charged before the config is initialized
+    let mut test = TestContext::new().await;
+    let (authority, treasury, recipient) = setup_simple(&mut test).await;
+    let withdraw = instruction::withdraw_simple(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &recipient,
+        &treasury,
+        &test.mint.pubkey(),
+        400,
+    );
+    test.process(&[withdraw], &[&authority]).await.unwrap();
+    assert_eq!(test.token_balance(&recipient).await, 400);
+}
+
+#[tokio::test]
+async fn test_wrong_fee_vault() {
+    let mut test = TestContext::new().await;
+    test.initialize_config(
+        &Pubkey::new_unique(),
+        0,
+        WITHDRAWAL_FEE,
+        &Pubkey::new_unique(),
+    )
+    .await;
+    let (authority, treasury, recipient) = setup_simple(&mut test).await;
+    let mut withdraw = instruction::withdraw_simple(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &recipient,
+        &treasury,
+        &test.mint.pubkey(),
+        400,
+    );
+    withdraw.accounts[9] = AccountMeta::new(recipient, false);
+    let result = test.process(&[withdraw], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidFeeVault);
+}
+
+#[tokio::test]
+async fn test_pause() {
+    let mut test = TestContext::new().await;
+    let guardian = Keypair::new();
+    let admin = test
+        .initialize_config(&Pubkey::new_unique(), 0, 0, &guardian.pubkey())
+        .await;
+    let (authority, treasury, recipient) = setup_simple(&mut test).await;
+    let (program_id, mint) = (test.program_id, test.mint.pubkey());
+    let withdraw = instruction::withdraw_simple(
+        &program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &recipient,
+        &treasury,
+        &mint,
+        400,
+    );
+
+    // only the guardian can pause
+    let pause = instruction::pause_program(&program_id, &admin.pubkey());
+    let result = test.process(&[pause], &[&admin]).await;
+    assert_treasury_error(result, TreasuryError::InvalidGuardian);
+    let pause = instruction::pause_program(&program_id, &guardian.pubkey());
+    test.process(&[pause], &[&guardian]).await.unwrap();
+    let config: Config = test.get_account_data(&Config::address(&program_id).0).await;
+    assert!(config.paused);
+
+    let result = test
+        .process(std::slice::from_ref(&withdraw), &[&authority])
+        .await;
+    assert_treasury_error(result, TreasuryError::Paused);
+    let result = test
+        .create_simple(&authority, SimpleTreasuryMode::Unlocked)
+        .await;
+    assert_treasury_error(result, TreasuryError::Paused);
+
+    // deposits still work
+    test.mint_to(&test.simple_fund(&treasury), 1_000).await;
+    assert_eq!(
+        test.token_balance(&test.simple_fund(&treasury)).await,
+        2_000
+    );
+
+    // only the admin can unpause
+    let unpause = instruction::unpause_program(&program_id, &guardian.pubkey());
+    let result = test.process(&[unpause], &[&guardian]).await;
+    assert_treasury_error(result, TreasuryError::InvalidAdmin);
+    let unpause = instruction::unpause_program(&program_id, &admin.pubkey());
+    test.process(&[unpause], &[&admin]).await.unwrap();
+
+    test.process(&[withdraw], &[&authority]).await.unwrap();
+    assert_eq!(test.token_balance(&recipient).await, 400);
+}
+
+#[tokio::test]
+async fn test_pause_escrow() {
+    let mut test = TestContext::new().await;
+    let guardian = Keypair::new();
+    test.initialize_config(&Pubkey::new_unique(), 0, 0, &guardian.pubkey())
+        .await;
+    let (depositor, payee, arbiter) = (Pubkey::new_unique(), Pubkey::new_unique(), Keypair::new());
+    let treasury = test
+        .create_escrow(&depositor, &payee, &arbiter.pubkey(), 1_000)
+        .await
+        .unwrap()
+        .pubkey();
+    test.mint_to(&test.escrow_fund(&treasury), 1_000).await;
+    let mint = test.mint.pubkey();
+    let payee_account = test.create_associated(&payee, &mint).await;
+
+    let pause = instruction::pause_program(&test.program_id, &guardian.pubkey());
+    test.process(&[pause], &[&guardian]).await.unwrap();
+    let release = instruction::release_escrow(
+        &test.program_id,
+        &arbiter.pubkey(),
+        &payee_account,
+        &treasury,
+        &mint,
+    );
+    let result = test.process(&[release], &[&arbiter]).await;
+    assert_treasury_error(result, TreasuryError::Paused);
+}
+
+#[tokio::test]
+async fn test_update_config_keeps_pause() {
+    let mut test = TestContext::new().await;
+    let guardian = Keypair::new();
+    let admin = test
+        .initialize_config(&Pubkey::new_unique(), 0, 0, &guardian.pubkey())
+        .await;
+    let program_id = test.program_id;
+    let pause = instruction::pause_program(&program_id, &guardian.pubkey());
+    test.process(&[pause], &[&guardian]).await.unwrap();
+
+    let update = instruction::update_config(
+        &program_id,
+        &admin.pubkey(),
+        &admin.pubkey(),
+        &Pubkey::new_unique(),
+        0,
+        0,
+        &Pubkey::new_unique(),
+    );
+    test.process(&[update], &[&admin]).await.unwrap();
+    let config: Config = test.get_account_data(&Config::address(&program_id).0).await;
+    assert!(config.paused);
+}
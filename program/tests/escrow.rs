@@ -0,0 +1,188 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{assert_treasury_error, TestContext};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use treasury::{account::EscrowTreasury, error::TreasuryError, instruction};
+
+const DURATION: i64 = 1_000;
+
+struct Escrow {
+    test: TestContext,
+    arbiter: Keypair,
+    treasury: Pubkey,
+    fund: Pubkey,
+    /// Token accounts of the depositor and the payee
+    depositor_account: Pubkey,
+    payee_account: Pubkey,
+}
+
+impl Escrow {
+    /// Create an escrow with 1,000 in its fund
+    async fn new() -> Escrow {
+        let mut test = TestContext::new().await;
+        let (depositor, payee, arbiter) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Keypair::new());
+        let treasury = test
+            .create_escrow(&depositor, &payee, &arbiter.pubkey(), DURATION)
+            .await
+            .unwrap()
+            .pubkey();
+        let fund = test.escrow_fund(&treasury);
+        test.mint_to(&fund, 1_000).await;
+
+        let mint = test.mint.pubkey();
+        let depositor_account = test.create_associated(&depositor, &mint).await;
+        let payee_account = test.create_associated(&payee, &mint).await;
+
+        Escrow {
+            test,
+            arbiter,
+            treasury,
+            fund,
+            depositor_account,
+            payee_account,
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_create_escrow() {
+    let mut test = TestContext::new().await;
+    let (depositor, payee, arbiter) = (
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    );
+    let now = test.now().await;
+    let treasury = test
+        .create_escrow(&depositor, &payee, &arbiter, DURATION)
+        .await
+        .unwrap();
+
+    let data: EscrowTreasury = test.get_account_data(&treasury.pubkey()).await;
+    assert_eq!(
+        data,
+        EscrowTreasury {
+            mint: test.mint.pubkey(),
+            depositor,
+            payee,
+            arbiter,
+            expiry: now + DURATION,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_create_escrow_invalid() {
+    let mut test = TestContext::new().await;
+    let (depositor, payee) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let result = test
+        .create_escrow(&depositor, &payee, &payee, DURATION)
+        .await;
+    assert_treasury_error(result, TreasuryError::InvalidEscrow);
+
+    let result = test
+        .create_escrow(&depositor, &payee, &Pubkey::new_unique(), 0)
+        .await;
+    assert_treasury_error(result, TreasuryError::InvalidEscrow);
+}
+
+#[tokio::test]
+async fn test_release_escrow() {
+    let mut e = Escrow::new().await;
+    let instruction = instruction::release_escrow(
+        &e.test.program_id,
+        &e.arbiter.pubkey(),
+        &e.payee_account,
+        &e.treasury,
+        &e.test.mint.pubkey(),
+    );
+    let arbiter = e.arbiter.insecure_clone();
+    e.test.process(&[instruction], &[&arbiter]).await.unwrap();
+
+    assert_eq!(e.test.token_balance(&e.payee_account).await, 1_000);
+    assert_eq!(e.test.token_balance(&e.fund).await, 0);
+}
+
+#[tokio::test]
+async fn test_release_escrow_to_depositor() {
+    let mut e = Escrow::new().await;
+    let instruction = instruction::release_escrow(
+        &e.test.program_id,
+        &e.arbiter.pubkey(),
+        &e.depositor_account,
+        &e.treasury,
+        &e.test.mint.pubkey(),
+    );
+    let arbiter = e.arbiter.insecure_clone();
+    let result = e.test.process(&[instruction], &[&arbiter]).await;
+    assert_treasury_error(result, TreasuryError::InvalidAssociatedAccount);
+}
+
+#[tokio::test]
+async fn test_release_escrow_expired() {
+    let mut e = Escrow::new().await;
+    e.test.warp(DURATION).await;
+    let instruction = instruction::release_escrow(
+        &e.test.program_id,
+        &e.arbiter.pubkey(),
+        &e.payee_account,
+        &e.treasury,
+        &e.test.mint.pubkey(),
+    );
+    let arbiter = e.arbiter.insecure_clone();
+    let result = e.test.process(&[instruction], &[&arbiter]).await;
+    assert_treasury_error(result, TreasuryError::EscrowExpired);
+}
+
+#[tokio::test]
+async fn test_release_escrow_wrong_arbiter() {
+    let mut e = Escrow::new().await;
+    let attacker = Keypair::new();
+    let instruction = instruction::release_escrow(
+        &e.test.program_id,
+        &attacker.pubkey(),
+        &e.payee_account,
+        &e.treasury,
+        &e.test.mint.pubkey(),
+    );
+    let result = e.test.process(&[instruction], &[&attacker]).await;
+    assert_treasury_error(result, TreasuryError::InvalidArbiter);
+}
+
+#[tokio::test]
+async fn test_refund_escrow() {
+    let mut e = Escrow::new().await;
+    let instruction = instruction::refund_escrow(
+        &e.test.program_id,
+        &e.arbiter.pubkey(),
+        &e.depositor_account,
+        &e.treasury,
+        &e.test.mint.pubkey(),
+    );
+    let arbiter = e.arbiter.insecure_clone();
+    e.test.process(&[instruction], &[&arbiter]).await.unwrap();
+
+    assert_eq!(e.test.token_balance(&e.depositor_account).await, 1_000);
+    assert_eq!(e.test.token_balance(&e.fund).await, 0);
+}
+
+#[tokio::test]
+async fn test_refund_expired_escrow() {
+    let mut e = Escrow::new().await;
+    let (program_id, mint) = (e.test.program_id, e.test.mint.pubkey());
+    let (recipient, treasury) = (e.depositor_account, e.treasury);
+    let refund = || instruction::refund_expired_escrow(&program_id, &recipient, &treasury, &mint);
+    let result = e.test.process(&[refund()], &[]).await;
+    assert_treasury_error(result, TreasuryError::EscrowNotExpired);
+
+    // anyone can refund once the escrow expired
+    e.test.warp(DURATION).await;
+    e.test.process(&[refund()], &[]).await.unwrap();
+    assert_eq!(e.test.token_balance(&e.depositor_account).await, 1_000);
+    assert_eq!(e.test.token_balance(&e.fund).await, 0);
+}
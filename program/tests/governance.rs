@@ -0,0 +1,214 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use borsh::BorshDeserialize;
+use common::{assert_treasury_error, TestContext};
+use solana_program::{pubkey::Pubkey, rent::Rent, system_instruction};
+use solana_sdk::signature::{Keypair, Signer};
+use treasury::{
+    account::SimpleTreasuryMode,
+    error::TreasuryError,
+    governance::{MaxVoterWeightRecord, VoterWeightRecord},
+    instruction,
+};
+
+const PERIOD: u64 = 100;
+/// 10% per period
+const PERCENTAGE: u16 = 1_000;
+
+struct Governance {
+    test: TestContext,
+    realm: Pubkey,
+    owner: Pubkey,
+    /// Pairs of treasury and fund: locked simple, unlocked simple, vested
+    treasuries: Vec<(Pubkey, Pubkey)>,
+}
+
+impl Governance {
+    /// Create and fund a locked simple treasury with 500, an unlocked one with 300 and
+    /// a vested treasury of 1,000
+    async fn new() -> Governance {
+        let mut test = TestContext::new().await;
+        let owner = Keypair::new();
+
+        let mut treasuries = vec![];
+        for (mode, amount) in [
+            (SimpleTreasuryMode::Locked, 500),
+            (SimpleTreasuryMode::Unlocked, 300),
+        ] {
+            let treasury = test.create_simple(&owner, mode).await.unwrap().pubkey();
+            let fund = test.simple_fund(&treasury);
+            test.mint_to(&fund, amount).await;
+            treasuries.push((treasury, fund));
+        }
+        let treasury = test
+            .create_vested(&owner.pubkey(), 1_000, PERIOD, PERCENTAGE)
+            .await
+            .unwrap()
+            .pubkey();
+        let fund = test.vested_fund(&treasury);
+        test.mint_to(&fund, 1_000).await;
+        treasuries.push((treasury, fund));
+
+        Governance {
+            test,
+            realm: Pubkey::new_unique(),
+            owner: owner.pubkey(),
+            treasuries,
+        }
+    }
+
+    async fn record<T: BorshDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self
+            .test
+            .context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(account.owner, self.test.program_id);
+        // governance ignores the bytes of unset options at the end
+        T::deserialize(&mut &account.data[..]).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record() {
+    let mut g = Governance::new().await;
+    let program_id = g.test.program_id;
+    let mint = g.test.mint.pubkey();
+    let (address, _) = VoterWeightRecord::address(&g.realm, &mint, &g.owner, &program_id);
+
+    // two periods vested, nothing withdrawn
+    g.test.warp(2 * PERIOD as i64).await;
+    let instruction = instruction::update_voter_weight_record(
+        &program_id,
+        &g.test.payer(),
+        &g.realm,
+        &mint,
+        &g.owner,
+        &g.treasuries,
+    );
+    g.test.process(&[instruction], &[]).await.unwrap();
+
+    let record: VoterWeightRecord = g.record(&address).await;
+    assert_eq!(
+        record.account_discriminator,
+        VoterWeightRecord::ACCOUNT_DISCRIMINATOR
+    );
+    assert_eq!(record.realm, g.realm);
+    assert_eq!(record.governing_token_mint, mint);
+    assert_eq!(record.governing_token_owner, g.owner);
+    assert_eq!(record.voter_weight, 500 + 800);
+    assert!(record.voter_weight_expiry.is_some());
+    assert_eq!(record.weight_action, None);
+
+    // only the treasuries passed count
+    g.test.warp(PERIOD as i64).await;
+    let instruction = instruction::update_voter_weight_record(
+        &program_id,
+        &g.test.payer(),
+        &g.realm,
+        &mint,
+        &g.owner,
+        &g.treasuries[1..],
+    );
+    g.test.process(&[instruction], &[]).await.unwrap();
+    let record: VoterWeightRecord = g.record(&address).await;
+    assert_eq!(record.voter_weight, 700);
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_prefunded() {
+    let mut g = Governance::new().await;
+    let program_id = g.test.program_id;
+    let mint = g.test.mint.pubkey();
+    let (address, _) = VoterWeightRecord::address(&g.realm, &mint, &g.owner, &program_id);
+
+    // anyone can fund the address before the record exists
+    let lamports = Rent::default().minimum_balance(0);
+    let transfer = system_instruction::transfer(&g.test.payer(), &address, lamports);
+    g.test.process(&[transfer], &[]).await.unwrap();
+
+    let instruction = instruction::update_voter_weight_record(
+        &program_id,
+        &g.test.payer(),
+        &g.realm,
+        &mint,
+        &g.owner,
+        &g.treasuries,
+    );
+    g.test.process(&[instruction], &[]).await.unwrap();
+    let record: VoterWeightRecord = g.record(&address).await;
+    assert_eq!(record.voter_weight, 500 + 1_000);
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_wrong_owner() {
+    let mut g = Governance::new().await;
+    let instruction = instruction::update_voter_weight_record(
+        &g.test.program_id,
+        &g.test.payer(),
+        &g.realm,
+        &g.test.mint.pubkey(),
+        &Pubkey::new_unique(),
+        &g.treasuries,
+    );
+    let result = g.test.process(&[instruction], &[]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_duplicate_treasury() {
+    let mut g = Governance::new().await;
+    let treasuries = [g.treasuries[0], g.treasuries[0]];
+    let instruction = instruction::update_voter_weight_record(
+        &g.test.program_id,
+        &g.test.payer(),
+        &g.realm,
+        &g.test.mint.pubkey(),
+        &g.owner,
+        &treasuries,
+    );
+    let result = g.test.process(&[instruction], &[]).await;
+    assert_treasury_error(result, TreasuryError::DuplicateAccount);
+}
+
+#[tokio::test]
+async fn test_update_voter_weight_record_wrong_fund() {
+    let mut g = Governance::new().await;
+    let treasuries = [(g.treasuries[0].0, g.treasuries[1].1)];
+    let instruction = instruction::update_voter_weight_record(
+        &g.test.program_id,
+        &g.test.payer(),
+        &g.realm,
+        &g.test.mint.pubkey(),
+        &g.owner,
+        &treasuries,
+    );
+    let result = g.test.process(&[instruction], &[]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryFundAddress);
+}
+
+#[tokio::test]
+async fn test_update_max_voter_weight_record() {
+    let mut g = Governance::new().await;
+    let program_id = g.test.program_id;
+    let mint = g.test.mint.pubkey();
+    let (address, _) = MaxVoterWeightRecord::address(&g.realm, &mint, &program_id);
+
+    let instruction =
+        instruction::update_max_voter_weight_record(&program_id, &g.test.payer(), &g.realm, &mint);
+    g.test.process(&[instruction], &[]).await.unwrap();
+
+    let record: MaxVoterWeightRecord = g.record(&address).await;
+    assert_eq!(
+        record.account_discriminator,
+        MaxVoterWeightRecord::ACCOUNT_DISCRIMINATOR
+    );
+    assert_eq!(record.realm, g.realm);
+    assert_eq!(record.governing_token_mint, mint);
+    assert_eq!(record.max_voter_weight, 500 + 300 + 1_000);
+}
@@ -0,0 +1,191 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{assert_treasury_error, TestContext};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use treasury::{
+    account::{HurdleTreasury, Tranche},
+    error::TreasuryError,
+    instruction,
+    oracle::PriceFeed,
+};
+
+const DURATION: u64 = 100;
+
+struct Hurdles {
+    test: TestContext,
+    authority: Keypair,
+    oracle: Pubkey,
+    treasury: Pubkey,
+    recipient: Pubkey,
+}
+
+impl Hurdles {
+    /// Create and fully fund a hurdle treasury with 300 unlocking at a price of 10 and
+    /// 700 at 20, the price starts at 5
+    async fn new() -> Hurdles {
+        let mut test = TestContext::new().await;
+        let authority = Keypair::new();
+        let oracle = Pubkey::new_unique();
+        test.set_price(&oracle, 5).await;
+        let treasury = test
+            .create_hurdle(
+                &authority.pubkey(),
+                &oracle,
+                DURATION,
+                vec![(300, 10), (700, 20)],
+            )
+            .await
+            .unwrap()
+            .pubkey();
+        test.mint_to(&test.hurdle_fund(&treasury), 1_000).await;
+        let mint = test.mint.pubkey();
+        let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+
+        Hurdles {
+            test,
+            authority,
+            oracle,
+            treasury,
+            recipient,
+        }
+    }
+
+    /// Publish a price and update the treasury with it
+    async fn update(&mut self, price: u64) {
+        self.test.set_price(&self.oracle, price).await;
+        let instruction =
+            instruction::update_hurdles(&self.test.program_id, &self.treasury, &self.oracle);
+        self.test.process(&[instruction], &[]).await.unwrap();
+    }
+
+    async fn withdraw(&mut self) -> u64 {
+        let instruction = instruction::withdraw_hurdles(
+            &self.test.program_id,
+            &self.authority.pubkey(),
+            &self.recipient,
+            &self.treasury,
+            &self.test.mint.pubkey(),
+        );
+        let authority = self.authority.insecure_clone();
+        self.test
+            .process(&[instruction], &[&authority])
+            .await
+            .unwrap();
+        self.test.token_balance(&self.recipient).await
+    }
+}
+
+#[tokio::test]
+async fn test_create_hurdle() {
+    let mut test = TestContext::new().await;
+    let (authority, oracle) = (Pubkey::new_unique(), Pubkey::new_unique());
+    test.set_price(&oracle, 5).await;
+    let treasury = test
+        .create_hurdle(&authority, &oracle, DURATION, vec![(1_000, 10)])
+        .await
+        .unwrap();
+
+    let data: HurdleTreasury = test.get_account_data(&treasury.pubkey()).await;
+    assert_eq!(data.mint, test.mint.pubkey());
+    assert_eq!(data.authority, authority);
+    assert_eq!(data.oracle, oracle);
+    assert_eq!({ data.duration }, DURATION);
+    assert_eq!(
+        data.tranches().copied().collect::<Vec<_>>(),
+        vec![Tranche {
+            amount: 1_000,
+            price: 10,
+            met_since: 0,
+            unlocked: false.into(),
+        }]
+    );
+
+    let result = test
+        .create_hurdle(&authority, &oracle, DURATION, vec![(1_000, 0)])
+        .await;
+    assert_treasury_error(result, TreasuryError::InvalidTranches);
+
+    // the feed has to exist
+    let result = test
+        .create_hurdle(
+            &authority,
+            &Pubkey::new_unique(),
+            DURATION,
+            vec![(1_000, 10)],
+        )
+        .await;
+    assert_treasury_error(result, TreasuryError::InvalidPriceFeed);
+}
+
+#[tokio::test]
+async fn test_update_hurdles() {
+    let mut h = Hurdles::new().await;
+    h.update(5).await;
+    assert_eq!(h.withdraw().await, 0);
+
+    // the first target is met, but not for long enough yet
+    h.update(15).await;
+    h.test.warp(DURATION as i64 - 1).await;
+    h.update(12).await;
+    assert_eq!(h.withdraw().await, 0);
+
+    h.test.warp(1).await;
+    h.update(25).await;
+    assert_eq!(h.withdraw().await, 300);
+
+    // a dip below the second target starts its period over
+    h.test.warp(DURATION as i64 / 2).await;
+    h.update(15).await;
+    h.update(25).await;
+    h.test.warp(DURATION as i64 / 2).await;
+    h.update(25).await;
+    assert_eq!(h.withdraw().await, 300);
+
+    // unlocked tranches stay unlocked when the price drops
+    h.test.warp(DURATION as i64 / 2).await;
+    h.update(1).await;
+    h.test.warp(DURATION as i64).await;
+    h.update(1).await;
+    let data: HurdleTreasury = h.test.get_account_data(&h.treasury).await;
+    assert!(data.tranches[0].unlocked.get());
+    assert!(!data.tranches[1].unlocked.get());
+    assert_eq!({ data.withdrawn }, 300);
+}
+
+#[tokio::test]
+async fn test_update_hurdles_stale_price() {
+    let mut h = Hurdles::new().await;
+    h.test.set_price(&h.oracle, 25).await;
+    h.test.warp(PriceFeed::MAX_AGE + 1).await;
+    let instruction = instruction::update_hurdles(&h.test.program_id, &h.treasury, &h.oracle);
+    let result = h.test.process(&[instruction], &[]).await;
+    assert_treasury_error(result, TreasuryError::StalePrice);
+}
+
+#[tokio::test]
+async fn test_update_hurdles_wrong_oracle() {
+    let mut h = Hurdles::new().await;
+    let oracle = Pubkey::new_unique();
+    h.test.set_price(&oracle, 25).await;
+    let instruction = instruction::update_hurdles(&h.test.program_id, &h.treasury, &oracle);
+    let result = h.test.process(&[instruction], &[]).await;
+    assert_treasury_error(result, TreasuryError::InvalidPriceFeed);
+}
+
+#[tokio::test]
+async fn test_withdraw_hurdles_wrong_authority() {
+    let mut h = Hurdles::new().await;
+    let attacker = Keypair::new();
+    let instruction = instruction::withdraw_hurdles(
+        &h.test.program_id,
+        &attacker.pubkey(),
+        &h.recipient,
+        &h.treasury,
+        &h.test.mint.pubkey(),
+    );
+    let result = h.test.process(&[instruction], &[&attacker]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+}
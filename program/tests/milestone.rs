@@ -0,0 +1,178 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{assert_treasury_error, ed25519_instruction, TestContext};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use treasury::{
+    account::{Milestone, MilestoneTreasury},
+    error::TreasuryError,
+    instruction,
+};
+
+struct Milestones {
+    test: TestContext,
+    authority: Keypair,
+    /// The attestors of the two milestones, of 300 and 700
+    attestors: [Keypair; 2],
+    treasury: Pubkey,
+    recipient: Pubkey,
+}
+
+impl Milestones {
+    /// Create and fully fund a milestone treasury
+    async fn new() -> Milestones {
+        let mut test = TestContext::new().await;
+        let authority = Keypair::new();
+        let attestors = [Keypair::new(), Keypair::new()];
+        let treasury = test
+            .create_milestone(
+                &authority.pubkey(),
+                vec![(300, attestors[0].pubkey()), (700, attestors[1].pubkey())],
+            )
+            .await
+            .unwrap()
+            .pubkey();
+        test.mint_to(&test.milestone_fund(&treasury), 1_000).await;
+        let mint = test.mint.pubkey();
+        let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+
+        Milestones {
+            test,
+            authority,
+            attestors,
+            treasury,
+            recipient,
+        }
+    }
+
+    async fn withdraw(&mut self) -> u64 {
+        let instruction = instruction::withdraw_milestones(
+            &self.test.program_id,
+            &self.authority.pubkey(),
+            &self.recipient,
+            &self.treasury,
+            &self.test.mint.pubkey(),
+        );
+        let authority = self.authority.insecure_clone();
+        self.test
+            .process(&[instruction], &[&authority])
+            .await
+            .unwrap();
+        self.test.token_balance(&self.recipient).await
+    }
+}
+
+#[tokio::test]
+async fn test_create_milestone() {
+    let mut test = TestContext::new().await;
+    let (authority, attestor) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let treasury = test
+        .create_milestone(&authority, vec![(1_000, attestor)])
+        .await
+        .unwrap();
+
+    let data: MilestoneTreasury = test.get_account_data(&treasury.pubkey()).await;
+    assert_eq!(data.mint, test.mint.pubkey());
+    assert_eq!(data.authority, authority);
+    assert_eq!({ data.withdrawn }, 0);
+    assert_eq!(
+        data.milestones().copied().collect::<Vec<_>>(),
+        vec![Milestone {
+            amount: 1_000,
+            attestor,
+            confirmed: false.into(),
+        }]
+    );
+
+    let result = test.create_milestone(&authority, vec![(0, attestor)]).await;
+    assert_treasury_error(result, TreasuryError::InvalidMilestones);
+}
+
+#[tokio::test]
+async fn test_confirm_milestone() {
+    let mut m = Milestones::new().await;
+    assert_eq!(m.withdraw().await, 0);
+
+    let program_id = m.test.program_id;
+    let attestor = m.attestors[1].insecure_clone();
+    let confirm = instruction::confirm_milestone(&program_id, &attestor.pubkey(), &m.treasury, 1);
+    m.test.process(&[confirm], &[&attestor]).await.unwrap();
+    assert_eq!(m.withdraw().await, 700);
+
+    // only once
+    let confirm = instruction::confirm_milestone(&program_id, &attestor.pubkey(), &m.treasury, 1);
+    let result = m.test.process(&[confirm], &[&attestor]).await;
+    assert_treasury_error(result, TreasuryError::MilestoneConfirmed);
+
+    // not by the attestor of another milestone
+    let confirm = instruction::confirm_milestone(&program_id, &attestor.pubkey(), &m.treasury, 0);
+    let result = m.test.process(&[confirm], &[&attestor]).await;
+    assert_treasury_error(result, TreasuryError::InvalidAttestor);
+
+    let confirm = instruction::confirm_milestone(&program_id, &attestor.pubkey(), &m.treasury, 2);
+    let result = m.test.process(&[confirm], &[&attestor]).await;
+    assert_treasury_error(result, TreasuryError::InvalidMilestone);
+}
+
+#[tokio::test]
+async fn test_confirm_milestone_with_signature() {
+    let mut m = Milestones::new().await;
+    let message = MilestoneTreasury::attestation(&m.treasury, 0);
+    let instructions = [
+        ed25519_instruction(&m.attestors[0], &message),
+        instruction::confirm_milestone_with_signature(&m.test.program_id, &m.treasury, 0),
+    ];
+    // the fee payer submits it, the attestor never signs the transaction
+    m.test.process(&instructions, &[]).await.unwrap();
+    assert_eq!(m.withdraw().await, 300);
+
+    let data: MilestoneTreasury = m.test.get_account_data(&m.treasury).await;
+    assert!(data.milestones[0].confirmed.get());
+    assert_eq!({ data.withdrawn }, 300);
+}
+
+#[tokio::test]
+async fn test_confirm_milestone_with_wrong_signature() {
+    let mut m = Milestones::new().await;
+    let program_id = m.test.program_id;
+
+    // a signature for another milestone
+    let message = MilestoneTreasury::attestation(&m.treasury, 1);
+    let instructions = [
+        ed25519_instruction(&m.attestors[0], &message),
+        instruction::confirm_milestone_with_signature(&program_id, &m.treasury, 0),
+    ];
+    let result = m.test.process(&instructions, &[]).await;
+    assert_treasury_error(result, TreasuryError::InvalidAttestation);
+
+    // the attestor of another milestone
+    let message = MilestoneTreasury::attestation(&m.treasury, 0);
+    let instructions = [
+        ed25519_instruction(&m.attestors[1], &message),
+        instruction::confirm_milestone_with_signature(&program_id, &m.treasury, 0),
+    ];
+    let result = m.test.process(&instructions, &[]).await;
+    assert_treasury_error(result, TreasuryError::InvalidAttestor);
+
+    // no signature at all
+    let instruction = instruction::confirm_milestone_with_signature(&program_id, &m.treasury, 0);
+    let result = m.test.process(&[instruction], &[]).await;
+    assert_treasury_error(result, TreasuryError::InvalidAttestation);
+}
+
+#[tokio::test]
+async fn test_withdraw_milestones_wrong_authority() {
+    let mut m = Milestones::new().await;
+    let attacker = Keypair::new();
+    let instruction = instruction::withdraw_milestones(
+        &m.test.program_id,
+        &attacker.pubkey(),
+        &m.recipient,
+        &m.treasury,
+        &m.test.mint.pubkey(),
+    );
+    let result = m.test.process(&[instruction], &[&attacker]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+}
@@ -0,0 +1,553 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{assert_treasury_error, TestContext};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::BanksClientError;
+use solana_sdk::signature::{Keypair, Signer};
+use treasury::{
+    account::{SimpleTreasury, SimpleTreasuryMode, VestedTreasury},
+    error::TreasuryError,
+    instruction,
+    recovery::{Recovery, SocialRecovery},
+};
+
+const PERIOD: u64 = 1_000;
+
+struct Recoverable {
+    test: TestContext,
+    authority: Keypair,
+    backup: Keypair,
+    treasury: Pubkey,
+    recovery: Pubkey,
+}
+
+impl Recoverable {
+    /// A locked simple treasury whose authority named a backup
+    async fn simple() -> Recoverable {
+        let mut test = TestContext::new().await;
+        let authority = Keypair::new();
+        let treasury = test
+            .create_simple(&authority, SimpleTreasuryMode::Locked)
+            .await
+            .unwrap()
+            .pubkey();
+        Self::set_up(test, authority, treasury).await
+    }
+
+    /// A vested treasury whose authority named a backup
+    async fn vested() -> Recoverable {
+        let mut test = TestContext::new().await;
+        let authority = Keypair::new();
+        let treasury = test
+            .create_vested(&authority.pubkey(), 1_000, 100, 1_000)
+            .await
+            .unwrap()
+            .pubkey();
+        Self::set_up(test, authority, treasury).await
+    }
+
+    async fn set_up(mut test: TestContext, authority: Keypair, treasury: Pubkey) -> Recoverable {
+        let backup = Keypair::new();
+        let instruction = instruction::set_recovery(
+            &test.program_id,
+            &test.payer(),
+            &authority.pubkey(),
+            &treasury,
+            &backup.pubkey(),
+            PERIOD,
+        );
+        test.process(&[instruction], &[&authority]).await.unwrap();
+        let (recovery, _) = Recovery::address(&treasury, &test.program_id);
+
+        Recoverable {
+            test,
+            authority,
+            backup,
+            treasury,
+            recovery,
+        }
+    }
+
+    async fn claim(&mut self) -> Result<(), BanksClientError> {
+        let instruction = instruction::claim_recovery(
+            &self.test.program_id,
+            &self.backup.pubkey(),
+            &self.treasury,
+        );
+        self.test.process(&[instruction], &[&self.backup]).await
+    }
+}
+
+#[tokio::test]
+async fn test_set_recovery() {
+    let mut r = Recoverable::simple().await;
+    let now = r.test.now().await;
+    let recovery: Recovery = r.test.get_account_data(&r.recovery).await;
+    assert_eq!(
+        recovery,
+        Recovery {
+            treasury: r.treasury,
+            backup: r.backup.pubkey(),
+            inactivity_period: PERIOD,
+            last_active: now,
+        }
+    );
+
+    // only the authority
+    let attacker = Keypair::new();
+    let instruction = instruction::set_recovery(
+        &r.test.program_id,
+        &r.test.payer(),
+        &attacker.pubkey(),
+        &r.treasury,
+        &attacker.pubkey(),
+        PERIOD,
+    );
+    let result = r.test.process(&[instruction], &[&attacker]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+
+    let instruction = instruction::set_recovery(
+        &r.test.program_id,
+        &r.test.payer(),
+        &r.authority.pubkey(),
+        &r.treasury,
+        &r.backup.pubkey(),
+        0,
+    );
+    let result = r.test.process(&[instruction], &[&r.authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidInactivityPeriod);
+
+    let instruction = instruction::set_recovery(
+        &r.test.program_id,
+        &r.test.payer(),
+        &r.authority.pubkey(),
+        &r.treasury,
+        &r.authority.pubkey(),
+        PERIOD,
+    );
+    let result = r.test.process(&[instruction], &[&r.authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidBackup);
+
+    // replaced
+    let backup = Pubkey::new_unique();
+    let instruction = instruction::set_recovery(
+        &r.test.program_id,
+        &r.test.payer(),
+        &r.authority.pubkey(),
+        &r.treasury,
+        &backup,
+        2 * PERIOD,
+    );
+    r.test
+        .process(&[instruction], &[&r.authority])
+        .await
+        .unwrap();
+    let recovery: Recovery = r.test.get_account_data(&r.recovery).await;
+    assert_eq!(recovery.backup, backup);
+    assert_eq!({ recovery.inactivity_period }, 2 * PERIOD);
+}
+
+#[tokio::test]
+async fn test_claim_recovery() {
+    let mut r = Recoverable::simple().await;
+
+    r.test.warp(PERIOD as i64 - 1).await;
+    let result = r.claim().await;
+    assert_treasury_error(result, TreasuryError::AuthorityStillActive);
+
+    // only the backup
+    r.test.warp(1).await;
+    let attacker = Keypair::new();
+    let instruction =
+        instruction::claim_recovery(&r.test.program_id, &attacker.pubkey(), &r.treasury);
+    let result = r.test.process(&[instruction], &[&attacker]).await;
+    assert_treasury_error(result, TreasuryError::InvalidBackup);
+
+    let rent = r.test.lamports(&r.recovery).await;
+    r.claim().await.unwrap();
+    let treasury: SimpleTreasury = r.test.get_account_data(&r.treasury).await;
+    assert_eq!(treasury.authority, r.backup.pubkey());
+    assert_eq!(r.test.lamports(&r.recovery).await, 0);
+    assert_eq!(r.test.lamports(&r.backup.pubkey()).await, rent);
+
+    // the recovery is used up
+    let result = r.claim().await;
+    assert_treasury_error(result, TreasuryError::InvalidRecovery);
+}
+
+#[tokio::test]
+async fn test_claim_recovery_drops_rotation() {
+    let mut r = Recoverable::simple().await;
+    let guardian = Keypair::new();
+    let instruction = instruction::set_guardians(
+        &r.test.program_id,
+        &r.test.payer(),
+        &r.authority.pubkey(),
+        &r.treasury,
+        &[guardian.pubkey()],
+        1,
+        PERIOD,
+    );
+    r.test
+        .process(&[instruction], &[&r.authority])
+        .await
+        .unwrap();
+    let new_authority = Keypair::new();
+    approve(&mut r.test, &guardian, &r.treasury, &new_authority.pubkey())
+        .await
+        .unwrap();
+
+    r.test.warp(PERIOD as i64).await;
+    r.claim().await.unwrap();
+    let (address, _) = SocialRecovery::address(&r.treasury, &r.test.program_id);
+    let social: SocialRecovery = r.test.get_account_data(&address).await;
+    assert_eq!(social.new_authority, Pubkey::default());
+    assert_eq!(social.approvals_of(&new_authority.pubkey()), 0);
+
+    // the rotation approved under the previous authority can't take the treasury
+    let instruction =
+        instruction::execute_rotation(&r.test.program_id, &new_authority.pubkey(), &r.treasury);
+    let result = r.test.process(&[instruction], &[&new_authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidRotation);
+    let treasury: SimpleTreasury = r.test.get_account_data(&r.treasury).await;
+    assert_eq!(treasury.authority, r.backup.pubkey());
+}
+
+#[tokio::test]
+async fn test_heartbeat() {
+    let mut r = Recoverable::simple().await;
+
+    r.test.warp(PERIOD as i64 - 1).await;
+    let attacker = Keypair::new();
+    let instruction = instruction::heartbeat(&r.test.program_id, &attacker.pubkey(), &r.treasury);
+    let result = r.test.process(&[instruction], &[&attacker]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+
+    let instruction =
+        instruction::heartbeat(&r.test.program_id, &r.authority.pubkey(), &r.treasury);
+    r.test
+        .process(&[instruction], &[&r.authority])
+        .await
+        .unwrap();
+    let recovery: Recovery = r.test.get_account_data(&r.recovery).await;
+    assert_eq!({ recovery.last_active }, r.test.now().await);
+
+    r.test.warp(PERIOD as i64 - 1).await;
+    let result = r.claim().await;
+    assert_treasury_error(result, TreasuryError::AuthorityStillActive);
+    r.test.warp(1).await;
+    r.claim().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_heartbeat_without_recovery() {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let treasury = test
+        .create_simple(&authority, SimpleTreasuryMode::Locked)
+        .await
+        .unwrap()
+        .pubkey();
+    let instruction = instruction::heartbeat(&test.program_id, &authority.pubkey(), &treasury);
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidRecovery);
+}
+
+#[tokio::test]
+async fn test_withdraw_resets_recovery() {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let treasury = test
+        .create_simple(&authority, SimpleTreasuryMode::Unlocked)
+        .await
+        .unwrap()
+        .pubkey();
+    test.mint_to(&test.simple_fund(&treasury), 1_000).await;
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+    let mut r = Recoverable::set_up(test, authority, treasury).await;
+
+    r.test.warp(PERIOD as i64 - 1).await;
+    let withdraw = instruction::withdraw_simple(
+        &r.test.program_id,
+        &r.test.payer(),
+        &r.authority.pubkey(),
+        &recipient,
+        &r.treasury,
+        &mint,
+        100,
+    );
+    r.test.process(&[withdraw], &[&r.authority]).await.unwrap();
+    let recovery: Recovery = r.test.get_account_data(&r.recovery).await;
+    assert_eq!({ recovery.last_active }, r.test.now().await);
+
+    r.test.warp(1).await;
+    let result = r.claim().await;
+    assert_treasury_error(result, TreasuryError::AuthorityStillActive);
+}
+
+#[tokio::test]
+async fn test_claim_vested_recovery() {
+    let mut r = Recoverable::vested().await;
+    let program_id = r.test.program_id;
+    let mint = r.test.mint.pubkey();
+    r.test
+        .mint_to(&r.test.vested_fund(&r.treasury), 1_000)
+        .await;
+    let recipient = r.test.create_associated(&r.authority.pubkey(), &mint).await;
+
+    let delegate = Keypair::new();
+    let instruction = instruction::set_vested_delegate(
+        &program_id,
+        &r.authority.pubkey(),
+        &r.treasury,
+        Some(delegate.pubkey()),
+    );
+    r.test
+        .process(&[instruction], &[&r.authority])
+        .await
+        .unwrap();
+
+    // the delegate's withdrawals don't count as activity
+    r.test.warp(PERIOD as i64).await;
+    let withdraw = instruction::withdraw_vested(
+        &program_id,
+        &r.test.payer(),
+        &delegate.pubkey(),
+        &recipient,
+        &r.treasury,
+        &mint,
+        None,
+    );
+    r.test.process(&[withdraw], &[&delegate]).await.unwrap();
+    assert_eq!(r.test.token_balance(&recipient).await, 1_000);
+
+    r.claim().await.unwrap();
+    let treasury: VestedTreasury = r.test.get_account_data(&r.treasury).await;
+    assert_eq!(treasury.authority, r.backup.pubkey());
+    assert_eq!(treasury.delegate, Pubkey::default());
+
+    // the previous authority is locked out
+    let instruction = instruction::heartbeat(&program_id, &r.authority.pubkey(), &r.treasury);
+    let result = r.test.process(&[instruction], &[&r.authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+}
+
+const DELAY: u64 = 3_600;
+
+/// A locked simple treasury with three guardians, two of which can rotate its authority
+async fn guarded() -> (TestContext, Keypair, Pubkey, Vec<Keypair>) {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let treasury = test
+        .create_simple(&authority, SimpleTreasuryMode::Locked)
+        .await
+        .unwrap()
+        .pubkey();
+    let guardians = vec![Keypair::new(), Keypair::new(), Keypair::new()];
+    let keys: Vec<Pubkey> = guardians.iter().map(|guardian| guardian.pubkey()).collect();
+    let instruction = instruction::set_guardians(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &treasury,
+        &keys,
+        2,
+        DELAY,
+    );
+    test.process(&[instruction], &[&authority]).await.unwrap();
+    (test, authority, treasury, guardians)
+}
+
+async fn approve(
+    test: &mut TestContext,
+    guardian: &Keypair,
+    treasury: &Pubkey,
+    new_authority: &Pubkey,
+) -> Result<(), BanksClientError> {
+    let instruction = instruction::approve_rotation(
+        &test.program_id,
+        &guardian.pubkey(),
+        treasury,
+        new_authority,
+    );
+    test.process(&[instruction], &[guardian]).await
+}
+
+#[tokio::test]
+async fn test_set_guardians() {
+    let (mut test, authority, treasury, guardians) = guarded().await;
+    let (address, _) = SocialRecovery::address(&treasury, &test.program_id);
+    let social: SocialRecovery = test.get_account_data(&address).await;
+    assert_eq!(social.threshold, 2);
+    assert_eq!({ social.challenge_delay }, DELAY);
+    assert_eq!(social.guardian_index(&guardians[2].pubkey()), Some(2));
+
+    let instruction = instruction::set_guardians(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &treasury,
+        &[guardians[0].pubkey()],
+        2,
+        DELAY,
+    );
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidGuardians);
+
+    // only the authority
+    let instruction = instruction::set_guardians(
+        &test.program_id,
+        &test.payer(),
+        &guardians[0].pubkey(),
+        &treasury,
+        &[guardians[0].pubkey()],
+        1,
+        DELAY,
+    );
+    let result = test.process(&[instruction], &[&guardians[0]]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+}
+
+#[tokio::test]
+async fn test_rotation() {
+    let (mut test, authority, treasury, guardians) = guarded().await;
+    let new_authority = Keypair::new();
+    let execute = |test: &TestContext| {
+        instruction::execute_rotation(&test.program_id, &new_authority.pubkey(), &treasury)
+    };
+
+    let attacker = Keypair::new();
+    let result = approve(&mut test, &attacker, &treasury, &attacker.pubkey()).await;
+    assert_treasury_error(result, TreasuryError::InvalidGuardian);
+
+    // one approval doesn't initiate the rotation
+    approve(&mut test, &guardians[0], &treasury, &new_authority.pubkey())
+        .await
+        .unwrap();
+    let result = test.process(&[execute(&test)], &[&new_authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidRotation);
+
+    approve(&mut test, &guardians[2], &treasury, &new_authority.pubkey())
+        .await
+        .unwrap();
+    let result = approve(&mut test, &guardians[1], &treasury, &attacker.pubkey()).await;
+    assert_treasury_error(result, TreasuryError::RotationPending);
+
+    test.warp(DELAY as i64 - 1).await;
+    let result = test.process(&[execute(&test)], &[&new_authority]).await;
+    assert_treasury_error(result, TreasuryError::ChallengePeriodActive);
+
+    test.warp(1).await;
+    test.process(&[execute(&test)], &[&new_authority])
+        .await
+        .unwrap();
+    let data: SimpleTreasury = test.get_account_data(&treasury).await;
+    assert_eq!(data.authority, new_authority.pubkey());
+
+    // the guardians stay for the new authority, the previous one is locked out
+    let (address, _) = SocialRecovery::address(&treasury, &test.program_id);
+    let social: SocialRecovery = test.get_account_data(&address).await;
+    assert_eq!(social.new_authority, Pubkey::default());
+    let instruction =
+        instruction::cancel_rotation(&test.program_id, &authority.pubkey(), &treasury);
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+}
+
+#[tokio::test]
+async fn test_rotation_competing_approval() {
+    let (mut test, _, treasury, guardians) = guarded().await;
+    let (new_authority, attacker) = (Keypair::new(), Keypair::new());
+
+    // a guardian approving another key doesn't drop the other approvals
+    approve(&mut test, &guardians[0], &treasury, &new_authority.pubkey())
+        .await
+        .unwrap();
+    approve(&mut test, &guardians[1], &treasury, &attacker.pubkey())
+        .await
+        .unwrap();
+    approve(&mut test, &guardians[2], &treasury, &new_authority.pubkey())
+        .await
+        .unwrap();
+
+    let (address, _) = SocialRecovery::address(&treasury, &test.program_id);
+    let social: SocialRecovery = test.get_account_data(&address).await;
+    assert_eq!(social.new_authority, new_authority.pubkey());
+    assert_eq!(social.approvals_of(&attacker.pubkey()), 1);
+
+    test.warp(DELAY as i64).await;
+    let instruction =
+        instruction::execute_rotation(&test.program_id, &new_authority.pubkey(), &treasury);
+    test.process(&[instruction], &[&new_authority])
+        .await
+        .unwrap();
+    let data: SimpleTreasury = test.get_account_data(&treasury).await;
+    assert_eq!(data.authority, new_authority.pubkey());
+}
+
+#[tokio::test]
+async fn test_cancel_rotation() {
+    let (mut test, authority, treasury, guardians) = guarded().await;
+    let new_authority = Keypair::new();
+    for guardian in &guardians[..2] {
+        approve(&mut test, guardian, &treasury, &new_authority.pubkey())
+            .await
+            .unwrap();
+    }
+
+    test.warp(DELAY as i64 - 1).await;
+    let instruction =
+        instruction::cancel_rotation(&test.program_id, &authority.pubkey(), &treasury);
+    test.process(&[instruction], &[&authority]).await.unwrap();
+
+    test.warp(1).await;
+    let instruction =
+        instruction::execute_rotation(&test.program_id, &new_authority.pubkey(), &treasury);
+    let result = test.process(&[instruction], &[&new_authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidRotation);
+    let data: SimpleTreasury = test.get_account_data(&treasury).await;
+    assert_eq!(data.authority, authority.pubkey());
+}
+
+#[tokio::test]
+async fn test_rotation_closes_recovery() {
+    let mut r = Recoverable::vested().await;
+    let guardian = Keypair::new();
+    let instruction = instruction::set_guardians(
+        &r.test.program_id,
+        &r.test.payer(),
+        &r.authority.pubkey(),
+        &r.treasury,
+        &[guardian.pubkey()],
+        1,
+        DELAY,
+    );
+    r.test
+        .process(&[instruction], &[&r.authority])
+        .await
+        .unwrap();
+
+    let new_authority = Keypair::new();
+    approve(&mut r.test, &guardian, &r.treasury, &new_authority.pubkey())
+        .await
+        .unwrap();
+    r.test.warp(DELAY as i64).await;
+    let rent = r.test.lamports(&r.recovery).await;
+    let instruction =
+        instruction::execute_rotation(&r.test.program_id, &new_authority.pubkey(), &r.treasury);
+    r.test
+        .process(&[instruction], &[&new_authority])
+        .await
+        .unwrap();
+    let data: VestedTreasury = r.test.get_account_data(&r.treasury).await;
+    assert_eq!(data.authority, new_authority.pubkey());
+    assert_eq!(r.test.lamports(&r.recovery).await, 0);
+    assert_eq!(r.test.lamports(&new_authority.pubkey()).await, rent);
+
+    // the previous authority's backup can't claim it anymore
+    let result = r.claim().await;
+    assert_treasury_error(result, TreasuryError::InvalidRecovery);
+}
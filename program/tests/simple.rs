@@ -0,0 +1,475 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{assert_instruction_error, assert_treasury_error, TestContext};
+use solana_program::{
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+};
+use solana_sdk::signature::{Keypair, Signer};
+use spl_token::error::TokenError;
+use treasury::{
+    account::{SimpleTreasury, SimpleTreasuryMode},
+    error::TreasuryError,
+    instruction,
+};
+
+#[tokio::test]
+async fn test_create_simple() {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+
+    for mode in [
+        SimpleTreasuryMode::Locked,
+        SimpleTreasuryMode::Unlocked,
+        SimpleTreasuryMode::VoteEscrow,
+    ] {
+        let treasury = test.create_simple(&authority, mode).await.unwrap();
+        let data: SimpleTreasury = test.get_account_data(&treasury.pubkey()).await;
+        assert_eq!(
+            data,
+            SimpleTreasury {
+                mint: test.mint.pubkey(),
+                mode,
+                authority: authority.pubkey(),
+                lock_end: 0,
+                weight: 0,
+                weight_updated_at: 0,
+            }
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_create_simple_already_exists() {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let treasury = test
+        .create_simple(&authority, SimpleTreasuryMode::Locked)
+        .await
+        .unwrap();
+
+    let instruction = instruction::create_simple_treasury(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &treasury.pubkey(),
+        &test.mint.pubkey(),
+        SimpleTreasuryMode::Locked,
+    );
+    let result = test.process(&[instruction], &[&authority, &treasury]).await;
+    assert_treasury_error(result, TreasuryError::TreasuryAlreadyExists);
+}
+
+#[tokio::test]
+async fn test_create_simple_invalid_mint() {
+    let mut test = TestContext::new().await;
+    let (authority, treasury) = (Keypair::new(), Keypair::new());
+
+    let instruction = instruction::create_simple_treasury(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &treasury.pubkey(),
+        &Pubkey::new_unique(),
+        SimpleTreasuryMode::Locked,
+    );
+    let result = test.process(&[instruction], &[&authority, &treasury]).await;
+    assert_treasury_error(result, TreasuryError::TokenNotSPLToken);
+}
+
+#[tokio::test]
+async fn test_create_simple_missing_authority_signature() {
+    let mut test = TestContext::new().await;
+    let (authority, treasury) = (Keypair::new(), Keypair::new());
+
+    let mut instruction = instruction::create_simple_treasury(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &treasury.pubkey(),
+        &test.mint.pubkey(),
+        SimpleTreasuryMode::Locked,
+    );
+    instruction.accounts[1] = AccountMeta::new_readonly(authority.pubkey(), false);
+
+    let result = test.process(&[instruction], &[&treasury]).await;
+    assert_treasury_error(result, TreasuryError::MissingSignature);
+}
+
+#[tokio::test]
+async fn test_withdraw_simple_locked() {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let treasury = test
+        .create_simple(&authority, SimpleTreasuryMode::Locked)
+        .await
+        .unwrap();
+    let fund = test.simple_fund(&treasury.pubkey());
+    test.mint_to(&fund, 1_000).await;
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+
+    let instruction = instruction::withdraw_simple(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &recipient,
+        &treasury.pubkey(),
+        &mint,
+        500,
+    );
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::TreasuryIsLocked);
+    assert_eq!(test.token_balance(&fund).await, 1_000);
+}
+
+/// Set up an unlocked treasury and a withdrawal instruction that tests can tamper with
+async fn setup_withdraw() -> (TestContext, Keypair, Instruction) {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let treasury = test
+        .create_simple(&authority, SimpleTreasuryMode::Unlocked)
+        .await
+        .unwrap();
+    let fund = test.simple_fund(&treasury.pubkey());
+    test.mint_to(&fund, 1_000).await;
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+
+    let instruction = instruction::withdraw_simple(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &recipient,
+        &treasury.pubkey(),
+        &mint,
+        500,
+    );
+    (test, authority, instruction)
+}
+
+#[tokio::test]
+async fn test_withdraw_simple() {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let treasury = test
+        .create_simple(&authority, SimpleTreasuryMode::Unlocked)
+        .await
+        .unwrap();
+    let fund = test.simple_fund(&treasury.pubkey());
+    test.mint_to(&fund, 1_000).await;
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+
+    let program_id = test.program_id;
+    let payer = test.payer();
+    let withdraw = |amount| {
+        instruction::withdraw_simple(
+            &program_id,
+            &payer,
+            &authority.pubkey(),
+            &recipient,
+            &treasury.pubkey(),
+            &mint,
+            amount,
+        )
+    };
+
+    // partial
+    let instruction = withdraw(300);
+    test.process(&[instruction], &[&authority]).await.unwrap();
+    assert_eq!(test.token_balance(&recipient).await, 300);
+    assert_eq!(test.token_balance(&fund).await, 700);
+
+    // more than the fund holds
+    let instruction = withdraw(701);
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_instruction_error(
+        result,
+        InstructionError::Custom(TokenError::InsufficientFunds as u32),
+    );
+    assert_eq!(test.token_balance(&fund).await, 700);
+
+    // the rest
+    let instruction = withdraw(700);
+    test.process(&[instruction], &[&authority]).await.unwrap();
+    assert_eq!(test.token_balance(&recipient).await, 1_000);
+    assert_eq!(test.token_balance(&fund).await, 0);
+}
+
+#[tokio::test]
+async fn test_withdraw_simple_wrong_owner() {
+    let (mut test, _, mut instruction) = setup_withdraw().await;
+    let impostor = Keypair::new();
+    instruction.accounts[1] = AccountMeta::new_readonly(impostor.pubkey(), true);
+
+    let result = test.process(&[instruction], &[&impostor]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+}
+
+#[tokio::test]
+async fn test_withdraw_simple_missing_signature() {
+    let (mut test, authority, mut instruction) = setup_withdraw().await;
+    instruction.accounts[1] = AccountMeta::new_readonly(authority.pubkey(), false);
+
+    let result = test.process(&[instruction], &[]).await;
+    assert_treasury_error(result, TreasuryError::MissingAuthoritySignature);
+}
+
+#[tokio::test]
+async fn test_withdraw_simple_not_a_treasury() {
+    let (mut test, authority, mut instruction) = setup_withdraw().await;
+    // the recipient is a token account and not owned by the treasury program
+    instruction.accounts[3] = AccountMeta::new(instruction.accounts[2].pubkey, false);
+
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryFundAccount);
+}
+
+#[tokio::test]
+async fn test_withdraw_simple_wrong_fund_authority() {
+    let (mut test, authority, mut instruction) = setup_withdraw().await;
+    instruction.accounts[4] = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryFundAuthorityAddress);
+}
+
+#[tokio::test]
+async fn test_withdraw_simple_wrong_fund_address() {
+    let (mut test, authority, mut instruction) = setup_withdraw().await;
+    instruction.accounts[5] = AccountMeta::new(instruction.accounts[2].pubkey, false);
+
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryFundAddress);
+}
+
+#[tokio::test]
+async fn test_withdraw_simple_wrong_mint() {
+    let (mut test, authority, mut instruction) = setup_withdraw().await;
+    let other_mint = Keypair::new();
+    test.create_mint(&other_mint).await;
+    let recipient = test
+        .create_associated(&authority.pubkey(), &other_mint.pubkey())
+        .await;
+    instruction.accounts[2] = AccountMeta::new(recipient, false);
+
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::MintWrongToken);
+}
+
+#[tokio::test]
+async fn test_withdraw_simple_wrong_recipient() {
+    let (mut test, authority, mut instruction) = setup_withdraw().await;
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&Pubkey::new_unique(), &mint).await;
+    instruction.accounts[2] = AccountMeta::new(recipient, false);
+
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidAssociatedAccount);
+}
+
+#[tokio::test]
+async fn test_create_simple_wrong_system_program() {
+    let mut test = TestContext::new().await;
+    let (authority, treasury) = (Keypair::new(), Keypair::new());
+
+    let mut instruction = instruction::create_simple_treasury(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &treasury.pubkey(),
+        &test.mint.pubkey(),
+        SimpleTreasuryMode::Locked,
+    );
+    instruction.accounts[6] = AccountMeta::new_readonly(spl_token::id(), false);
+
+    let result = test.process(&[instruction], &[&authority, &treasury]).await;
+    assert_treasury_error(result, TreasuryError::InvalidSystemProgram);
+}
+
+#[tokio::test]
+async fn test_withdraw_simple_fund_not_writable() {
+    let (mut test, authority, mut instruction) = setup_withdraw().await;
+    instruction.accounts[5].is_writable = false;
+
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::AccountNotWritable);
+}
+
+#[tokio::test]
+async fn test_withdraw_simple_wrong_token_program() {
+    let (mut test, authority, mut instruction) = setup_withdraw().await;
+    instruction.accounts[6] = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTokenProgram);
+}
+
+#[tokio::test]
+async fn test_withdraw_simple_without_config() {
+    // the clock used to be optional, the config accounts after it no longer allow that
+    let (mut test, authority, mut instruction) = setup_withdraw().await;
+    instruction.accounts.truncate(7);
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_instruction_error(result, InstructionError::NotEnoughAccountKeys);
+}
+
+#[tokio::test]
+async fn test_vote_escrow() {
+    const MAX: i64 = SimpleTreasury::MAX_LOCK_DURATION;
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let treasury = test
+        .create_simple(&authority, SimpleTreasuryMode::VoteEscrow)
+        .await
+        .unwrap()
+        .pubkey();
+    let fund = test.simple_fund(&treasury);
+    test.mint_to(&fund, 1_000).await;
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+
+    let program_id = test.program_id;
+    let payer = test.payer();
+    let withdraw = |amount| {
+        instruction::withdraw_simple(
+            &program_id,
+            &payer,
+            &authority.pubkey(),
+            &recipient,
+            &treasury,
+            &mint,
+            amount,
+        )
+    };
+    let extend = |lock_end| {
+        instruction::extend_lock(&program_id, &authority.pubkey(), &treasury, &mint, lock_end)
+    };
+
+    // unlocked until the first lock
+    test.process(&[withdraw(100)], &[&authority]).await.unwrap();
+
+    let now = test.now().await;
+    let lock_end = now + MAX / 2;
+    test.process(&[extend(lock_end)], &[&authority])
+        .await
+        .unwrap();
+    let data: SimpleTreasury = test.get_account_data(&treasury).await;
+    assert_eq!({ data.lock_end }, lock_end);
+    assert_eq!({ data.weight }, 450);
+    assert_eq!({ data.weight_updated_at }, now);
+
+    let result = test.process(&[withdraw(100)], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::TreasuryIsLocked);
+
+    // the lock can't be shortened
+    let result = test.process(&[extend(lock_end - 1)], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidLockEnd);
+    // or go past the maximum
+    let result = test.process(&[extend(now + MAX + 1)], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidLockEnd);
+
+    // anyone can update the weight as it decays
+    test.warp(MAX / 4).await;
+    test.mint_to(&fund, 100).await;
+    let instruction = instruction::update_vote_escrow_weight(&program_id, &treasury, &mint);
+    test.process(&[instruction], &[]).await.unwrap();
+    let data: SimpleTreasury = test.get_account_data(&treasury).await;
+    assert_eq!({ data.weight }, 250);
+    assert_eq!({ data.weight_updated_at }, now + MAX / 4);
+
+    test.warp(MAX / 4).await;
+    test.process(&[withdraw(1_000)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&recipient).await, 1_100);
+}
+
+#[tokio::test]
+async fn test_vote_escrow_withdraw_without_clock() {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let treasury = test
+        .create_simple(&authority, SimpleTreasuryMode::VoteEscrow)
+        .await
+        .unwrap();
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+
+    let mut instruction = instruction::withdraw_simple(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &recipient,
+        &treasury.pubkey(),
+        &mint,
+        0,
+    );
+    instruction.accounts.pop();
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_instruction_error(result, InstructionError::NotEnoughAccountKeys);
+}
+
+#[tokio::test]
+async fn test_extend_lock_not_vote_escrow() {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let treasury = test
+        .create_simple(&authority, SimpleTreasuryMode::Locked)
+        .await
+        .unwrap();
+    let mint = test.mint.pubkey();
+    let lock_end = test.now().await + 100;
+
+    let instruction = instruction::extend_lock(
+        &test.program_id,
+        &authority.pubkey(),
+        &treasury.pubkey(),
+        &mint,
+        lock_end,
+    );
+    let result = test.process(&[instruction], &[&authority]).await;
+    assert_treasury_error(result, TreasuryError::NotVoteEscrow);
+
+    let instruction =
+        instruction::update_vote_escrow_weight(&test.program_id, &treasury.pubkey(), &mint);
+    let result = test.process(&[instruction], &[]).await;
+    assert_treasury_error(result, TreasuryError::NotVoteEscrow);
+}
+
+#[tokio::test]
+async fn test_migrate_simple() {
+    let (mut test, authority, instruction) = setup_withdraw().await;
+    let treasury = instruction.accounts[3].pubkey;
+    let before: SimpleTreasury = test.get_account_data(&treasury).await;
+    test.truncate_account(&treasury, SimpleTreasury::LEGACY_LENS[0])
+        .await;
+
+    let result = test
+        .process(std::slice::from_ref(&instruction), &[&authority])
+        .await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryFundAccount);
+
+    let migrate = instruction::migrate_treasury(&test.program_id, &test.payer(), &treasury);
+    test.process(std::slice::from_ref(&migrate), &[])
+        .await
+        .unwrap();
+    let data: SimpleTreasury = test.get_account_data(&treasury).await;
+    assert_eq!(data, before);
+    assert_eq!(
+        test.lamports(&treasury).await,
+        Rent::default().minimum_balance(SimpleTreasury::LEN)
+    );
+
+    test.process(&[instruction], &[&authority]).await.unwrap();
+    assert_eq!(test.token_balance(&test.simple_fund(&treasury)).await, 500);
+
+    // only earlier layouts can be migrated
+    test.warp(1).await;
+    let result = test.process(&[migrate], &[]).await;
+    assert_treasury_error(result, TreasuryError::NotALegacyTreasury);
+}
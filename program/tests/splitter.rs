@@ -0,0 +1,165 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use common::{assert_treasury_error, TestContext};
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey};
+use solana_sdk::signature::{Keypair, Signer};
+use treasury::{
+    account::{Shareholder, SplitterTreasury},
+    error::TreasuryError,
+    instruction,
+};
+
+#[tokio::test]
+async fn test_create_splitter() {
+    let mut test = TestContext::new().await;
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let treasury = test
+        .create_splitter(vec![(alice, 7_500), (bob, 2_500)])
+        .await
+        .unwrap();
+
+    let data: SplitterTreasury = test.get_account_data(&treasury.pubkey()).await;
+    assert_eq!(data.mint, test.mint.pubkey());
+    assert_eq!({ data.total_released }, 0);
+    assert_eq!(
+        data.shareholders().copied().collect::<Vec<_>>(),
+        vec![
+            Shareholder {
+                recipient: alice,
+                share: 7_500,
+                released: 0,
+            },
+            Shareholder {
+                recipient: bob,
+                share: 2_500,
+                released: 0,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_create_splitter_invalid_shares() {
+    let mut test = TestContext::new().await;
+    let result = test
+        .create_splitter(vec![(Pubkey::new_unique(), 5_000)])
+        .await;
+    assert_treasury_error(result, TreasuryError::InvalidShares);
+}
+
+#[tokio::test]
+async fn test_claim_split() {
+    let mut test = TestContext::new().await;
+    let (alice, bob) = (Keypair::new(), Keypair::new());
+    let treasury = test
+        .create_splitter(vec![(alice.pubkey(), 6_000), (bob.pubkey(), 4_000)])
+        .await
+        .unwrap()
+        .pubkey();
+    let fund = test.splitter_fund(&treasury);
+    let mint = test.mint.pubkey();
+    let alice_account = test.create_associated(&alice.pubkey(), &mint).await;
+    let bob_account = test.create_associated(&bob.pubkey(), &mint).await;
+
+    let program_id = test.program_id;
+    let claim = |shareholder: &Keypair, recipient: &Pubkey| {
+        instruction::claim_split(
+            &program_id,
+            &shareholder.pubkey(),
+            recipient,
+            &treasury,
+            &mint,
+        )
+    };
+
+    test.mint_to(&fund, 1_000).await;
+    test.process(&[claim(&alice, &alice_account)], &[&alice])
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&alice_account).await, 600);
+
+    // a late deposit is split as well
+    test.mint_to(&fund, 500).await;
+    test.process(&[claim(&bob, &bob_account)], &[&bob])
+        .await
+        .unwrap();
+    test.process(&[claim(&alice, &alice_account)], &[&alice])
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&alice_account).await, 900);
+    assert_eq!(test.token_balance(&bob_account).await, 600);
+    assert_eq!(test.token_balance(&fund).await, 0);
+
+    let data: SplitterTreasury = test.get_account_data(&treasury).await;
+    assert_eq!({ data.total_released }, 1_500);
+    assert_eq!({ data.shareholders[0].released }, 900);
+    assert_eq!({ data.shareholders[1].released }, 600);
+}
+
+#[tokio::test]
+async fn test_claim_split_not_a_shareholder() {
+    let mut test = TestContext::new().await;
+    let treasury = test
+        .create_splitter(vec![(Pubkey::new_unique(), 10_000)])
+        .await
+        .unwrap()
+        .pubkey();
+    test.mint_to(&test.splitter_fund(&treasury), 1_000).await;
+    let attacker = Keypair::new();
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&attacker.pubkey(), &mint).await;
+
+    let instruction = instruction::claim_split(
+        &test.program_id,
+        &attacker.pubkey(),
+        &recipient,
+        &treasury,
+        &mint,
+    );
+    let result = test.process(&[instruction], &[&attacker]).await;
+    assert_treasury_error(result, TreasuryError::NotAShareholder);
+}
+
+#[tokio::test]
+async fn test_claim_split_wrong_recipient() {
+    let mut test = TestContext::new().await;
+    let alice = Keypair::new();
+    let treasury = test
+        .create_splitter(vec![(alice.pubkey(), 10_000)])
+        .await
+        .unwrap()
+        .pubkey();
+    let mint = test.mint.pubkey();
+    let other = test.create_associated(&Pubkey::new_unique(), &mint).await;
+
+    let instruction =
+        instruction::claim_split(&test.program_id, &alice.pubkey(), &other, &treasury, &mint);
+    let result = test.process(&[instruction], &[&alice]).await;
+    assert_treasury_error(result, TreasuryError::InvalidAssociatedAccount);
+}
+
+#[tokio::test]
+async fn test_claim_split_missing_signature() {
+    let mut test = TestContext::new().await;
+    let alice = Keypair::new();
+    let treasury = test
+        .create_splitter(vec![(alice.pubkey(), 10_000)])
+        .await
+        .unwrap()
+        .pubkey();
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&alice.pubkey(), &mint).await;
+
+    let mut instruction = instruction::claim_split(
+        &test.program_id,
+        &alice.pubkey(),
+        &recipient,
+        &treasury,
+        &mint,
+    );
+    instruction.accounts[0] = AccountMeta::new_readonly(alice.pubkey(), false);
+    let result = test.process(&[instruction], &[]).await;
+    assert_treasury_error(result, TreasuryError::MissingAuthoritySignature);
+}
@@ -0,0 +1,909 @@
+#![cfg(feature = "test-bpf")]
+
+mod common;
+
+use borsh::BorshDeserialize;
+use common::{assert_treasury_error, TestContext};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use treasury::{
+    account::{Acceleration, VestedQuery, VestedTreasury},
+    error::TreasuryError,
+    instruction,
+    recovery::{Recovery, SocialRecovery},
+};
+
+const AMOUNT: u64 = 1_000;
+const PERIOD: u64 = 100;
+/// 10% per period
+const PERCENTAGE: u16 = 1_000;
+
+struct Vested {
+    test: TestContext,
+    authority: Keypair,
+    treasury: Pubkey,
+    fund: Pubkey,
+    recipient: Pubkey,
+}
+
+impl Vested {
+    /// Create a funded vested treasury and the authority's token account
+    async fn new() -> Vested {
+        let mut test = TestContext::new().await;
+        let authority = Keypair::new();
+        let treasury = test
+            .create_vested(&authority.pubkey(), AMOUNT, PERIOD, PERCENTAGE)
+            .await
+            .unwrap()
+            .pubkey();
+        let fund = test.vested_fund(&treasury);
+        test.mint_to(&fund, AMOUNT).await;
+        let mint = test.mint.pubkey();
+        let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+
+        Vested {
+            test,
+            authority,
+            treasury,
+            fund,
+            recipient,
+        }
+    }
+
+    fn withdraw(&self) -> Instruction {
+        self.withdraw_amount(None)
+    }
+
+    fn withdraw_amount(&self, amount: Option<u64>) -> Instruction {
+        instruction::withdraw_vested(
+            &self.test.program_id,
+            &self.test.payer(),
+            &self.authority.pubkey(),
+            &self.recipient,
+            &self.treasury,
+            &self.test.mint.pubkey(),
+            amount,
+        )
+    }
+
+    async fn query(&mut self) -> VestedQuery {
+        let instruction = instruction::query_vested(
+            &self.test.program_id,
+            &self.treasury,
+            &self.test.mint.pubkey(),
+        );
+        let blockhash = self.test.context.get_new_latest_blockhash().await.unwrap();
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&self.test.payer()),
+            &[&self.test.context.payer],
+            blockhash,
+        );
+        let simulation = self
+            .test
+            .context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+        assert_eq!(return_data.program_id, self.test.program_id);
+        VestedQuery::try_from_slice(&return_data.data).unwrap()
+    }
+}
+
+#[tokio::test]
+async fn test_create_vested() {
+    let mut test = TestContext::new().await;
+    let authority = Pubkey::new_unique();
+    let treasury = test
+        .create_vested(&authority, AMOUNT, PERIOD, PERCENTAGE)
+        .await
+        .unwrap();
+    let now = test.now().await;
+
+    let data: VestedTreasury = test.get_account_data(&treasury.pubkey()).await;
+    assert_eq!(
+        data,
+        VestedTreasury {
+            mint: test.mint.pubkey(),
+            authority,
+            initial_amount: AMOUNT,
+            start: now,
+            vestment_period: PERIOD,
+            vestment_percentage: PERCENTAGE,
+            withdrawn: 0,
+            grantor: test.payer(),
+            paused_at: 0,
+            accelerated: 0,
+            delegate: Pubkey::default(),
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_create_vested_invalid_parameters() {
+    let mut test = TestContext::new().await;
+    let authority = Pubkey::new_unique();
+
+    let cases = [
+        (0, PERIOD, PERCENTAGE, TreasuryError::InvalidVestmentAmount),
+        (AMOUNT, 0, PERCENTAGE, TreasuryError::InvalidVestmentPeriod),
+        (AMOUNT, PERIOD, 0, TreasuryError::InvalidVestmentPercentage),
+        (
+            AMOUNT,
+            PERIOD,
+            10_001,
+            TreasuryError::InvalidVestmentPercentage,
+        ),
+    ];
+    for (amount, period, percentage, error) in cases {
+        let result = test
+            .create_vested(&authority, amount, period, percentage)
+            .await;
+        assert_treasury_error(result, error);
+    }
+}
+
+#[tokio::test]
+async fn test_create_vested_already_exists() {
+    let mut test = TestContext::new().await;
+    let authority = Pubkey::new_unique();
+    let treasury = test
+        .create_vested(&authority, AMOUNT, PERIOD, PERCENTAGE)
+        .await
+        .unwrap();
+
+    let instruction = instruction::create_vested_treasury(
+        &test.program_id,
+        &test.payer(),
+        &authority,
+        &test.payer(),
+        &treasury.pubkey(),
+        &test.mint.pubkey(),
+        AMOUNT,
+        PERIOD,
+        PERCENTAGE,
+    );
+    let result = test.process(&[instruction], &[&treasury]).await;
+    assert_treasury_error(result, TreasuryError::TreasuryAlreadyExists);
+}
+
+#[tokio::test]
+async fn test_withdraw_vested() {
+    let mut v = Vested::new().await;
+
+    // nothing has vested yet
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, 0);
+
+    // two and a half periods
+    v.test.warp(250).await;
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, 200);
+    assert_eq!(v.test.token_balance(&v.fund).await, 800);
+
+    // withdrawing again in the same period releases nothing
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, 200);
+
+    // long after the vesting finished
+    v.test.warp(10 * PERIOD as i64).await;
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, AMOUNT);
+    assert_eq!(v.test.token_balance(&v.fund).await, 0);
+
+    let data: VestedTreasury = v.test.get_account_data(&v.treasury).await;
+    assert_eq!({ data.withdrawn }, AMOUNT);
+}
+
+#[tokio::test]
+async fn test_withdraw_vested_amount() {
+    let mut v = Vested::new().await;
+
+    // 300 have vested
+    v.test.warp(3 * PERIOD as i64).await;
+    let instruction = v.withdraw_amount(Some(120));
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, 120);
+
+    // only 180 are left
+    let instruction = v.withdraw_amount(Some(181));
+    let result = v.test.process(&[instruction], &[&v.authority]).await;
+    assert_treasury_error(result, TreasuryError::AmountNotAvailable);
+
+    let instruction = v.withdraw_amount(Some(180));
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, 300);
+
+    let data: VestedTreasury = v.test.get_account_data(&v.treasury).await;
+    assert_eq!({ data.withdrawn }, 300);
+}
+
+#[tokio::test]
+async fn test_withdraw_vested_amount_underfunded() {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let treasury = test
+        .create_vested(&authority.pubkey(), AMOUNT, PERIOD, PERCENTAGE)
+        .await
+        .unwrap()
+        .pubkey();
+    let fund = test.vested_fund(&treasury);
+    test.mint_to(&fund, 100).await;
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+    let mut v = Vested {
+        test,
+        authority,
+        treasury,
+        fund,
+        recipient,
+    };
+
+    // 300 have vested but the fund only holds 100
+    v.test.warp(3 * PERIOD as i64).await;
+    let instruction = v.withdraw_amount(Some(101));
+    let result = v.test.process(&[instruction], &[&v.authority]).await;
+    assert_treasury_error(result, TreasuryError::InsufficientFunds);
+
+    let instruction = v.withdraw_amount(Some(100));
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, 100);
+}
+
+#[tokio::test]
+async fn test_withdraw_vested_underfunded() {
+    let mut test = TestContext::new().await;
+    let authority = Keypair::new();
+    let treasury = test
+        .create_vested(&authority.pubkey(), AMOUNT, PERIOD, PERCENTAGE)
+        .await
+        .unwrap()
+        .pubkey();
+    let fund = test.vested_fund(&treasury);
+    test.mint_to(&fund, 150).await;
+    let mint = test.mint.pubkey();
+    let recipient = test.create_associated(&authority.pubkey(), &mint).await;
+
+    // 500 have vested but only 150 are in the fund
+    test.warp(5 * PERIOD as i64).await;
+    let instruction = instruction::withdraw_vested(
+        &test.program_id,
+        &test.payer(),
+        &authority.pubkey(),
+        &recipient,
+        &treasury,
+        &mint,
+        None,
+    );
+    test.process(&[instruction], &[&authority]).await.unwrap();
+    assert_eq!(test.token_balance(&recipient).await, 150);
+
+    let data: VestedTreasury = test.get_account_data(&treasury).await;
+    assert_eq!({ data.withdrawn }, 150);
+}
+
+#[tokio::test]
+async fn test_query_vested() {
+    let mut v = Vested::new().await;
+    assert_eq!(
+        v.query().await,
+        VestedQuery {
+            claimable: 0,
+            vested: 0,
+            unvested: AMOUNT,
+            locked: AMOUNT,
+        }
+    );
+
+    v.test.warp(3 * PERIOD as i64).await;
+    assert_eq!(
+        v.query().await,
+        VestedQuery {
+            claimable: 300,
+            vested: 300,
+            unvested: 700,
+            locked: 700,
+        }
+    );
+
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(
+        v.query().await,
+        VestedQuery {
+            claimable: 0,
+            vested: 300,
+            unvested: 700,
+            locked: 700,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_withdraw_vested_wrong_owner() {
+    let mut v = Vested::new().await;
+    let impostor = Keypair::new();
+    let mut instruction = v.withdraw();
+    instruction.accounts[1] = AccountMeta::new_readonly(impostor.pubkey(), true);
+
+    let result = v.test.process(&[instruction], &[&impostor]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+}
+
+#[tokio::test]
+async fn test_withdraw_vested_missing_signature() {
+    let mut v = Vested::new().await;
+    let mut instruction = v.withdraw();
+    instruction.accounts[1] = AccountMeta::new_readonly(v.authority.pubkey(), false);
+
+    let result = v.test.process(&[instruction], &[]).await;
+    assert_treasury_error(result, TreasuryError::MissingAuthoritySignature);
+}
+
+#[tokio::test]
+async fn test_withdraw_vested_wrong_fund_authority() {
+    let mut v = Vested::new().await;
+    let mut instruction = v.withdraw();
+    instruction.accounts[4] = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+
+    let result = v.test.process(&[instruction], &[&v.authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryFundAuthorityAddress);
+}
+
+#[tokio::test]
+async fn test_withdraw_vested_wrong_fund_address() {
+    let mut v = Vested::new().await;
+    let mut instruction = v.withdraw();
+    instruction.accounts[5] = AccountMeta::new(v.recipient, false);
+
+    let result = v.test.process(&[instruction], &[&v.authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryFundAddress);
+}
+
+#[tokio::test]
+async fn test_withdraw_vested_wrong_mint() {
+    let mut v = Vested::new().await;
+    let other_mint = Keypair::new();
+    v.test.create_mint(&other_mint).await;
+    let recipient = v
+        .test
+        .create_associated(&v.authority.pubkey(), &other_mint.pubkey())
+        .await;
+    let mut instruction = v.withdraw();
+    instruction.accounts[2] = AccountMeta::new(recipient, false);
+
+    let result = v.test.process(&[instruction], &[&v.authority]).await;
+    assert_treasury_error(result, TreasuryError::MintWrongToken);
+}
+
+#[tokio::test]
+async fn test_withdraw_vested_wrong_recipient() {
+    let mut v = Vested::new().await;
+    let mint = v.test.mint.pubkey();
+    let recipient = v.test.create_associated(&Pubkey::new_unique(), &mint).await;
+    let mut instruction = v.withdraw();
+    instruction.accounts[2] = AccountMeta::new(recipient, false);
+
+    let result = v.test.process(&[instruction], &[&v.authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidAssociatedAccount);
+}
+
+#[tokio::test]
+async fn test_withdraw_vested_recipient_is_fund() {
+    let mut v = Vested::new().await;
+    let mut instruction = v.withdraw();
+    instruction.accounts[2] = AccountMeta::new(v.fund, false);
+
+    let result = v.test.process(&[instruction], &[&v.authority]).await;
+    assert_treasury_error(result, TreasuryError::DuplicateAccount);
+}
+
+#[tokio::test]
+async fn test_withdraw_vested_wrong_clock() {
+    let mut v = Vested::new().await;
+    let mut instruction = v.withdraw();
+    instruction.accounts[6] = AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false);
+
+    let result = v.test.process(&[instruction], &[&v.authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidSysvar);
+}
+
+#[tokio::test]
+async fn test_pause_vesting() {
+    let mut v = Vested::new().await;
+    let program_id = v.test.program_id;
+    let grantor = v.test.payer();
+
+    // pause after two periods
+    v.test.warp(2 * PERIOD as i64).await;
+    let instruction = instruction::pause_vesting(&program_id, &grantor, &v.treasury);
+    v.test.process(&[instruction], &[]).await.unwrap();
+    let paused_at = v.test.now().await;
+
+    let instruction = instruction::pause_vesting(&program_id, &grantor, &v.treasury);
+    let result = v.test.process(&[instruction], &[]).await;
+    assert_treasury_error(result, TreasuryError::VestingPaused);
+
+    // nothing vests while paused
+    v.test.warp(5 * PERIOD as i64).await;
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, 200);
+
+    let instruction = instruction::resume_vesting(&program_id, &grantor, &v.treasury);
+    v.test.process(&[instruction], &[]).await.unwrap();
+    let data: VestedTreasury = v.test.get_account_data(&v.treasury).await;
+    assert_eq!({ data.paused_at }, 0);
+    assert_eq!(
+        { data.start },
+        paused_at - 2 * PERIOD as i64 + 5 * PERIOD as i64
+    );
+
+    // the rest of the schedule vests in full
+    v.test.warp(8 * PERIOD as i64).await;
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, AMOUNT);
+
+    let instruction = instruction::resume_vesting(&program_id, &grantor, &v.treasury);
+    let result = v.test.process(&[instruction], &[]).await;
+    assert_treasury_error(result, TreasuryError::VestingNotPaused);
+}
+
+#[tokio::test]
+async fn test_pause_vesting_wrong_grantor() {
+    let mut v = Vested::new().await;
+    let impostor = Keypair::new();
+    let instruction =
+        instruction::pause_vesting(&v.test.program_id, &impostor.pubkey(), &v.treasury);
+
+    let result = v.test.process(&[instruction], &[&impostor]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryGrantor);
+}
+
+#[tokio::test]
+async fn test_accelerate_vesting() {
+    let mut v = Vested::new().await;
+    let program_id = v.test.program_id;
+    let grantor = v.test.payer();
+
+    // a quarter of the 800 unvested after two periods
+    v.test.warp(2 * PERIOD as i64).await;
+    let instruction = instruction::accelerate_vesting(
+        &program_id,
+        &grantor,
+        &v.treasury,
+        Acceleration::Percentage(2_500),
+    );
+    v.test.process(&[instruction], &[]).await.unwrap();
+
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, 400);
+
+    // everything else
+    let instruction = instruction::accelerate_vesting(
+        &program_id,
+        &grantor,
+        &v.treasury,
+        Acceleration::Amount(AMOUNT),
+    );
+    v.test.process(&[instruction], &[]).await.unwrap();
+    let data: VestedTreasury = v.test.get_account_data(&v.treasury).await;
+    assert_eq!({ data.accelerated }, 800);
+
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, AMOUNT);
+}
+
+#[tokio::test]
+async fn test_accelerate_vesting_wrong_grantor() {
+    let mut v = Vested::new().await;
+    let impostor = Keypair::new();
+    let instruction = instruction::accelerate_vesting(
+        &v.test.program_id,
+        &impostor.pubkey(),
+        &v.treasury,
+        Acceleration::Amount(AMOUNT),
+    );
+
+    let result = v.test.process(&[instruction], &[&impostor]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryGrantor);
+}
+
+#[tokio::test]
+async fn test_split_vested() {
+    let mut v = Vested::new().await;
+    let program_id = v.test.program_id;
+    let mint = v.test.mint.pubkey();
+    let new_treasury = Keypair::new();
+    let new_authority = Keypair::new();
+    let (new_fund_authority, _) =
+        VestedTreasury::fund_authority_address(&new_treasury.pubkey(), &program_id);
+    let new_fund = v.test.create_associated(&new_fund_authority, &mint).await;
+    let new_recipient = v
+        .test
+        .create_associated(&new_authority.pubkey(), &mint)
+        .await;
+
+    v.test.warp(2 * PERIOD as i64).await;
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+
+    let instruction = instruction::split_vested(
+        &program_id,
+        &v.test.payer(),
+        &v.authority.pubkey(),
+        &v.treasury,
+        &new_treasury.pubkey(),
+        &new_authority.pubkey(),
+        &mint,
+        400,
+    );
+    v.test
+        .process(&[instruction], &[&v.authority, &new_treasury])
+        .await
+        .unwrap();
+
+    let original: VestedTreasury = v.test.get_account_data(&v.treasury).await;
+    assert_eq!({ original.initial_amount }, 600);
+    assert_eq!({ original.withdrawn }, 120);
+    let split: VestedTreasury = v.test.get_account_data(&new_treasury.pubkey()).await;
+    assert_eq!(split.authority, new_authority.pubkey());
+    assert_eq!({ split.initial_amount }, 400);
+    assert_eq!({ split.withdrawn }, 80);
+    assert_eq!({ split.start }, { original.start });
+    assert_eq!(v.test.token_balance(&v.fund).await, 480);
+    assert_eq!(v.test.token_balance(&new_fund).await, 320);
+
+    // both halves keep vesting on the same schedule
+    v.test.warp(PERIOD as i64).await;
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, 260);
+
+    let instruction = instruction::withdraw_vested(
+        &program_id,
+        &v.test.payer(),
+        &new_authority.pubkey(),
+        &new_recipient,
+        &new_treasury.pubkey(),
+        &mint,
+        None,
+    );
+    v.test
+        .process(&[instruction], &[&new_authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&new_recipient).await, 40);
+}
+
+#[tokio::test]
+async fn test_split_vested_invalid_amount() {
+    let mut v = Vested::new().await;
+    let program_id = v.test.program_id;
+    let mint = v.test.mint.pubkey();
+    let new_treasury = Keypair::new();
+    let (new_fund_authority, _) =
+        VestedTreasury::fund_authority_address(&new_treasury.pubkey(), &program_id);
+    v.test.create_associated(&new_fund_authority, &mint).await;
+
+    for amount in [0, AMOUNT] {
+        let instruction = instruction::split_vested(
+            &program_id,
+            &v.test.payer(),
+            &v.authority.pubkey(),
+            &v.treasury,
+            &new_treasury.pubkey(),
+            &Pubkey::new_unique(),
+            &mint,
+            amount,
+        );
+        let result = v
+            .test
+            .process(&[instruction], &[&v.authority, &new_treasury])
+            .await;
+        assert_treasury_error(result, TreasuryError::InvalidSplitAmount);
+    }
+}
+
+#[tokio::test]
+async fn test_merge_vested() {
+    let mut v = Vested::new().await;
+    let program_id = v.test.program_id;
+    let mint = v.test.mint.pubkey();
+    let source = Keypair::new();
+    let (source_fund_authority, _) =
+        VestedTreasury::fund_authority_address(&source.pubkey(), &program_id);
+    let source_fund = v
+        .test
+        .create_associated(&source_fund_authority, &mint)
+        .await;
+
+    // split off a part with the same authority and schedule
+    v.test.warp(2 * PERIOD as i64).await;
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    let instruction = instruction::split_vested(
+        &program_id,
+        &v.test.payer(),
+        &v.authority.pubkey(),
+        &v.treasury,
+        &source.pubkey(),
+        &v.authority.pubkey(),
+        &mint,
+        400,
+    );
+    v.test
+        .process(&[instruction], &[&v.authority, &source])
+        .await
+        .unwrap();
+
+    // the source's recovery records are closed with it
+    let set_recovery = instruction::set_recovery(
+        &program_id,
+        &v.test.payer(),
+        &v.authority.pubkey(),
+        &source.pubkey(),
+        &Pubkey::new_unique(),
+        PERIOD,
+    );
+    let set_guardians = instruction::set_guardians(
+        &program_id,
+        &v.test.payer(),
+        &v.authority.pubkey(),
+        &source.pubkey(),
+        &[Pubkey::new_unique()],
+        1,
+        PERIOD,
+    );
+    v.test
+        .process(&[set_recovery, set_guardians], &[&v.authority])
+        .await
+        .unwrap();
+    let (recovery, _) = Recovery::address(&source.pubkey(), &program_id);
+    let (social, _) = SocialRecovery::address(&source.pubkey(), &program_id);
+
+    let mut rent = 0;
+    for key in [source.pubkey(), source_fund, recovery, social] {
+        let account = v.test.context.banks_client.get_account(key).await.unwrap();
+        rent += account.unwrap().lamports;
+    }
+
+    let recipient = Pubkey::new_unique();
+    let instruction = instruction::merge_vested(
+        &program_id,
+        &v.authority.pubkey(),
+        &v.treasury,
+        &source.pubkey(),
+        &recipient,
+        &mint,
+    );
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+
+    let data: VestedTreasury = v.test.get_account_data(&v.treasury).await;
+    assert_eq!({ data.initial_amount }, AMOUNT);
+    assert_eq!({ data.withdrawn }, 200);
+    assert_eq!(v.test.token_balance(&v.fund).await, 800);
+    let banks_client = &mut v.test.context.banks_client;
+    for key in [source.pubkey(), source_fund, recovery, social] {
+        assert_eq!(banks_client.get_account(key).await.unwrap(), None);
+    }
+    let recipient = banks_client.get_account(recipient).await.unwrap().unwrap();
+    assert_eq!(recipient.lamports, rent);
+
+    v.test.warp(PERIOD as i64).await;
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, 300);
+}
+
+#[tokio::test]
+async fn test_merge_vested_incompatible() {
+    let mut v = Vested::new().await;
+    let source = v
+        .test
+        .create_vested(&v.authority.pubkey(), AMOUNT, PERIOD + 1, PERCENTAGE)
+        .await
+        .unwrap()
+        .pubkey();
+
+    let instruction = instruction::merge_vested(
+        &v.test.program_id,
+        &v.authority.pubkey(),
+        &v.treasury,
+        &source,
+        &v.test.payer(),
+        &v.test.mint.pubkey(),
+    );
+    let result = v.test.process(&[instruction], &[&v.authority]).await;
+    assert_treasury_error(result, TreasuryError::IncompatibleTreasuries);
+}
+
+#[tokio::test]
+async fn test_merge_vested_into_itself() {
+    let mut v = Vested::new().await;
+    let instruction = instruction::merge_vested(
+        &v.test.program_id,
+        &v.authority.pubkey(),
+        &v.treasury,
+        &v.treasury,
+        &v.test.payer(),
+        &v.test.mint.pubkey(),
+    );
+    let result = v.test.process(&[instruction], &[&v.authority]).await;
+    assert_treasury_error(result, TreasuryError::DuplicateAccount);
+}
+
+#[tokio::test]
+async fn test_withdraw_vested_delegate() {
+    let mut v = Vested::new().await;
+    let program_id = v.test.program_id;
+    let mint = v.test.mint.pubkey();
+    let payer = v.test.payer();
+    let (treasury, recipient) = (v.treasury, v.recipient);
+    let delegate = Keypair::new();
+    let withdraw = || {
+        instruction::withdraw_vested(
+            &program_id,
+            &payer,
+            &delegate.pubkey(),
+            &recipient,
+            &treasury,
+            &mint,
+            None,
+        )
+    };
+
+    // not a delegate yet
+    v.test.warp(PERIOD as i64).await;
+    let instruction = withdraw();
+    let result = v.test.process(&[instruction], &[&delegate]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+
+    let instruction = instruction::set_vested_delegate(
+        &program_id,
+        &v.authority.pubkey(),
+        &v.treasury,
+        Some(delegate.pubkey()),
+    );
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    let data: VestedTreasury = v.test.get_account_data(&v.treasury).await;
+    assert_eq!(data.delegate, delegate.pubkey());
+
+    let instruction = withdraw();
+    v.test.process(&[instruction], &[&delegate]).await.unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, 100);
+
+    // funds only go to the authority
+    let delegate_account = v.test.create_associated(&delegate.pubkey(), &mint).await;
+    v.test.warp(PERIOD as i64).await;
+    let instruction = instruction::withdraw_vested(
+        &program_id,
+        &v.test.payer(),
+        &delegate.pubkey(),
+        &delegate_account,
+        &v.treasury,
+        &mint,
+        None,
+    );
+    let result = v.test.process(&[instruction], &[&delegate]).await;
+    assert_treasury_error(result, TreasuryError::InvalidAssociatedAccount);
+
+    // the delegate can't do anything else
+    let instruction =
+        instruction::set_vested_delegate(&program_id, &delegate.pubkey(), &v.treasury, None);
+    let result = v.test.process(&[instruction], &[&delegate]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+
+    let instruction =
+        instruction::set_vested_delegate(&program_id, &v.authority.pubkey(), &v.treasury, None);
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    let instruction = withdraw();
+    let result = v.test.process(&[instruction], &[&delegate]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryOwner);
+}
+
+#[tokio::test]
+async fn test_migrate_vested() {
+    let mut v = Vested::new().await;
+    let before: VestedTreasury = v.test.get_account_data(&v.treasury).await;
+
+    // the layout before grantors, acceleration and delegates
+    v.test
+        .truncate_account(&v.treasury, VestedTreasury::LEGACY_LENS[0])
+        .await;
+    v.test.warp(PERIOD as i64).await;
+    let instruction = v.withdraw();
+    let result = v.test.process(&[instruction], &[&v.authority]).await;
+    assert_treasury_error(result, TreasuryError::InvalidTreasuryFundAccount);
+
+    let instruction =
+        instruction::migrate_treasury(&v.test.program_id, &v.test.payer(), &v.treasury);
+    v.test.process(&[instruction], &[]).await.unwrap();
+    let data: VestedTreasury = v.test.get_account_data(&v.treasury).await;
+    assert_eq!(
+        data,
+        VestedTreasury {
+            grantor: Pubkey::default(),
+            ..before
+        }
+    );
+
+    let instruction = v.withdraw();
+    v.test
+        .process(&[instruction], &[&v.authority])
+        .await
+        .unwrap();
+    assert_eq!(v.test.token_balance(&v.recipient).await, 100);
+}
@@ -123,6 +123,7 @@ const treasury = new Treasury(connection, programId);
                 funder.publicKey,
                 vested_treasury.publicKey,
                 vested_authority.publicKey,
+                funder.publicKey,
                 100_000n,
                 10n,
                 1000
@@ -299,6 +300,7 @@ async function launch_treasury(
 
     const sig = await sendAndConfirmTransaction(connection, tx, [
         funder,
+        authority,
         treasury
     ]);
     console.log(`Treasury launched: ${sig}`);